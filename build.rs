@@ -0,0 +1,9 @@
+//! Generates the Tauri plugin's command permissions (feature `tauri`).
+
+#[cfg(feature = "tauri")]
+const COMMANDS: &[&str] = &["validate_license", "license_status", "clear_license"];
+
+fn main() {
+    #[cfg(feature = "tauri")]
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
@@ -17,7 +17,7 @@
 //! This prevents attackers from simply setting environment variables to
 //! point at their own Keygen account.
 
-use gatewarden::{GatewardenConfig, GatewardenError, LicenseManager};
+use gatewarden::{FallbackPolicy, GatewardenConfig, GatewardenError, LicenseManager, ProxyConfig};
 use std::time::Duration;
 
 // These would be your actual Keygen credentials in production.
@@ -35,10 +35,29 @@ fn main() {
         feature_name: "pro",
         account_id: KEYGEN_ACCOUNT_ID,
         public_key_hex: KEYGEN_PUBLIC_KEY,
+        public_key_id: None,
+        trusted_keys: &[],
         required_entitlements: &["PRO_FEATURE"],
         user_agent_product: "example-app",
         cache_namespace: "example-app",
         offline_grace: Duration::from_secs(24 * 60 * 60), // 24 hours
+        feature_grace: &[],
+        max_cache_age: Duration::from_secs(90 * 24 * 60 * 60), // 90 days
+        fallback_policy: FallbackPolicy::default(),
+        online_budget: None,
+        request_timeout: Duration::from_secs(30),
+        connect_timeout: Duration::from_secs(10),
+        proxy: ProxyConfig::System,
+        tls_pins: &[],
+        require_digest: false,
+        memo_ttl: Duration::from_secs(60),
+        negative_cache_ttl: Duration::ZERO,
+        validation_rate_limit: None,
+        app_version: None,
+        key_format: Default::default(),
+        user_agent_extras: Default::default(),
+        connection_pool: Default::default(),
+        ip_strategy: Default::default(),
     };
 
     // Create the license manager
@@ -81,11 +100,11 @@ fn main() {
                 GatewardenError::EntitlementMissing { code } => {
                     eprintln!("License missing required entitlement: {}", code);
                 }
-                GatewardenError::SignatureInvalid => {
+                GatewardenError::SignatureInvalid { .. } => {
                     // Security: someone may be tampering with responses
                     eprintln!("SECURITY: Response signature verification failed!");
                 }
-                GatewardenError::KeygenTransport(_) => {
+                GatewardenError::KeygenTransport { .. } => {
                     eprintln!("Network error - trying offline cache...");
                     // You could call check_access() here to use cached validation
                 }
@@ -0,0 +1,77 @@
+//! Attribute macros for gatewarden.
+//!
+//! Use through the `gatewarden` crate with feature `macros`, which
+//! re-exports [`licensed`]; the expansion refers to `::gatewarden`.
+
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Expr, ItemFn, LitStr, ReturnType};
+
+/// Run the function only while the globally registered license is valid.
+///
+/// A check is inserted at the start of the body that validates the key
+/// registered with `gatewarden::global::register` against the default
+/// manager from `gatewarden::init`:
+///
+/// - `#[licensed]` requires a valid license;
+/// - `#[licensed(entitlement = "PRO")]` also requires entitlement `PRO`.
+///
+/// The function must return a `Result`. When the check fails, it returns
+/// `Err(From::from(error))` with the `GatewardenError`, or
+/// `Err(f(error))` given `error = f` for any `FnOnce(GatewardenError) -> E`:
+///
+/// ```ignore
+/// #[gatewarden::licensed(entitlement = "PRO", error = |_| AppError::Unlicensed)]
+/// fn export_pdf(path: &str) -> Result<(), AppError> {
+///     Ok(())
+/// }
+/// ```
+///
+/// The check blocks, so in `async fn`s it may stall the executor on a
+/// cache miss; prefer gating async code at its entry point.
+#[proc_macro_attribute]
+pub fn licensed(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut entitlement: Option<LitStr> = None;
+    let mut error: Option<Expr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("entitlement") {
+            entitlement = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("error") {
+            error = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported property; expected `entitlement` or `error`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    let mut function = parse_macro_input!(item as ItemFn);
+    if let ReturnType::Default = function.sig.output {
+        return syn::Error::new_spanned(
+            &function.sig,
+            "#[licensed] functions must return a Result",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let check = match &entitlement {
+        Some(code) => quote!(::gatewarden::global::require_entitlement(#code)),
+        None => quote!(::gatewarden::global::require_license()),
+    };
+    let denied = match &error {
+        Some(map) => quote!((#map)(denied)),
+        None => quote!(::core::convert::From::from(denied)),
+    };
+    let guard = syn::parse_quote! {
+        if let ::core::result::Result::Err(denied) = #check {
+            return ::core::result::Result::Err(#denied);
+        }
+    };
+    function.block.stmts.insert(0, guard);
+
+    quote!(#function).into()
+}
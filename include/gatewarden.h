@@ -0,0 +1,95 @@
+/*
+ * gatewarden C ABI (Rust feature `ffi`).
+ *
+ * Build: cargo rustc --release --lib --features ffi --crate-type cdylib
+ *
+ * Every function returning int returns GATEWARDEN_OK, a positive status
+ * matching GatewardenError::code(), or a negative status for API misuse.
+ * Details of the last failure are available per thread from
+ * gatewarden_last_error_code() and gatewarden_last_error_message().
+ */
+
+#ifndef GATEWARDEN_H
+#define GATEWARDEN_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define GATEWARDEN_OK                     0
+#define GATEWARDEN_ERR_INVALID_ARGUMENT (-1) /* null pointer or non-UTF-8 string */
+#define GATEWARDEN_ERR_PANIC            (-2) /* internal panic; do not reuse the handle */
+#define GATEWARDEN_ERR_OTHER            (-3) /* error code newer than this header */
+
+/* Statuses never change once released; new codes are appended. */
+#define GATEWARDEN_CONFIG_ERROR         1
+#define GATEWARDEN_SIGNATURE_MISSING    2
+#define GATEWARDEN_SIGNATURE_INVALID    3
+#define GATEWARDEN_DIGEST_MISMATCH      4
+#define GATEWARDEN_RESPONSE_TOO_OLD     5
+#define GATEWARDEN_RESPONSE_FROM_FUTURE 6
+#define GATEWARDEN_PROTOCOL_ERROR       7
+#define GATEWARDEN_KEYGEN_TRANSPORT     8
+#define GATEWARDEN_RATE_LIMITED         9
+#define GATEWARDEN_SERVER_ERROR         10
+#define GATEWARDEN_CACHE_IO             11
+#define GATEWARDEN_CACHE_TAMPERED       12
+#define GATEWARDEN_CLOCK_ROLLBACK       13
+#define GATEWARDEN_LINK_EXPIRED         14
+#define GATEWARDEN_CACHE_EXPIRED        15
+#define GATEWARDEN_MISSING_LICENSE      16
+#define GATEWARDEN_INVALID_LICENSE      17
+#define GATEWARDEN_LICENSE_EXPIRED      18
+#define GATEWARDEN_ENTITLEMENT_MISSING  19
+#define GATEWARDEN_USAGE_LIMIT_EXCEEDED 20
+#define GATEWARDEN_METER_IO             21
+#define GATEWARDEN_METER_TAMPERED       22
+#define GATEWARDEN_ENTROPY              23
+#define GATEWARDEN_TLS_PIN_MISMATCH     24
+#define GATEWARDEN_LICENSE_SUSPENDED    25
+#define GATEWARDEN_LICENSE_BANNED       26
+#define GATEWARDEN_LICENSE_OVERDUE      27
+#define GATEWARDEN_VERSION_NOT_ENTITLED 28
+#define GATEWARDEN_MALFORMED_LICENSE_KEY 29
+#define GATEWARDEN_NO_VALID_KEY         30
+#define GATEWARDEN_NO_SEAT_AVAILABLE    31
+#define GATEWARDEN_COMPONENTS_MISMATCH  32
+#define GATEWARDEN_USER_MISMATCH        33
+
+/* Opaque license manager handle; safe to share between threads. */
+typedef struct GatewardenManager GatewardenManager;
+
+/* Create a manager; free it with gatewarden_manager_free. */
+int gatewarden_manager_new(const char *app_name,
+                           const char *account_id,
+                           const char *public_key_hex,
+                           GatewardenManager **out);
+
+/* Free a manager. NULL is ignored. */
+void gatewarden_manager_free(GatewardenManager *manager);
+
+/*
+ * Validate a license key online, falling back to the verified cache.
+ * On success, if out_json is not NULL, *out_json receives the
+ * ValidationResult as JSON; free it with gatewarden_string_free.
+ */
+int gatewarden_validate_key(const GatewardenManager *manager,
+                            const char *license_key,
+                            char **out_json);
+
+/* Free a string returned by gatewarden. NULL is ignored. */
+void gatewarden_string_free(char *s);
+
+/*
+ * Code (e.g. "INVALID_LICENSE") and message of the last failure on this
+ * thread, or NULL after a success. Valid until the next gatewarden call
+ * on this thread.
+ */
+const char *gatewarden_last_error_code(void);
+const char *gatewarden_last_error_message(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GATEWARDEN_H */
@@ -0,0 +1,513 @@
+//! Append-only audit log of validation attempts (feature `audit`).
+//!
+//! An [`AuditLog`] registered as an event sink records one entry per
+//! validation outcome: valid, invalid, or failed with an error code, with
+//! a flag for security errors and Keygen's request ID when one is known.
+//! Entries never contain the license key.
+//!
+//! ```ignore
+//! let audit = Arc::new(AuditLog::for_namespace(config.cache_namespace)?);
+//! let manager = LicenseManager::new(config)?.with_event_sink(audit.clone());
+//! // ...
+//! for entry in audit.read()? {
+//!     println!("{} {:?} {:?}", entry.at, entry.outcome, entry.error_code);
+//! }
+//! ```
+//!
+//! The log is JSON lines at `dirs::data_dir()/<namespace>/audit.log`.
+//! Each entry carries an HMAC-SHA256 over its fields and the previous
+//! entry's MAC, keyed from the namespace and the machine fingerprint, so
+//! editing, reordering, or deleting entries inside a file breaks the chain
+//! and [`AuditLog::read`] fails with `CacheTampered`. As with the usage
+//! meter, truncating the newest entries or deleting the whole file cannot
+//! be detected locally.
+//!
+//! [`AuditLog::rotate`] moves the current file aside; each rotated file
+//! is a chain of its own and can be read with [`AuditLog::with_path`].
+
+use crate::clock::{Clock, SystemClock};
+use crate::crypto::mac::new_hmac;
+use crate::events::{is_security_error, EventSink};
+use crate::fingerprint::machine_fingerprint;
+use crate::policy::fallback::FallbackReason;
+use crate::{GatewardenError, ValidationResult};
+use chrono::{DateTime, Utc};
+use fs2::FileExt;
+use hmac::Mac;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File name for the audit log within the cache namespace.
+const AUDIT_FILE: &str = "audit.log";
+
+/// Domain-separation key for deriving audit keys.
+const AUDIT_KEY_CONTEXT: &[u8] = b"gatewarden-audit-log-v1";
+
+/// Key authenticating an audit log's entries.
+#[derive(Clone)]
+pub struct AuditKey([u8; 32]);
+
+impl AuditKey {
+    /// Derive the key for a cache namespace on a machine.
+    ///
+    /// Pass an empty `machine_fingerprint` if it is unavailable (the key
+    /// is then bound to the namespace only).
+    pub fn derive(namespace: &str, machine_fingerprint: &str) -> Self {
+        let mut mac = new_hmac(AUDIT_KEY_CONTEXT);
+        mac.update(namespace.as_bytes());
+        mac.update(&[0]);
+        mac.update(machine_fingerprint.as_bytes());
+        Self(mac.finalize().into_bytes().into())
+    }
+
+    /// Hex-encoded HMAC-SHA256 of `entry` chained to `previous`.
+    fn sign(&self, previous: &str, entry: &[u8]) -> String {
+        let mut mac = new_hmac(&self.0);
+        mac.update(previous.as_bytes());
+        mac.update(&[0]);
+        mac.update(entry);
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+impl std::fmt::Debug for AuditKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AuditKey(..)")
+    }
+}
+
+/// How a validation attempt ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The license was valid.
+    Valid,
+    /// Keygen answered that the license is not valid.
+    Invalid,
+    /// Validation failed with an error.
+    Failed,
+}
+
+/// One recorded validation attempt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the file, starting at 1.
+    pub seq: u64,
+
+    /// When the attempt finished.
+    pub at: DateTime<Utc>,
+
+    /// How the attempt ended.
+    pub outcome: AuditOutcome,
+
+    /// Keygen license ID, for answered attempts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license_id: Option<String>,
+
+    /// Whether the answer came from the cache.
+    #[serde(default)]
+    pub from_cache: bool,
+
+    /// Why the cache answered instead of Keygen, if it did after a failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_reason: Option<FallbackReason>,
+
+    /// `GatewardenError::code` of a failed attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+
+    /// Whether the failure was a security error (failed authenticity check).
+    #[serde(default)]
+    pub security: bool,
+
+    /// Keygen's request ID for the failing response, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// HMAC over the entry and the previous entry's MAC.
+    #[serde(default)]
+    mac: String,
+}
+
+impl AuditEntry {
+    /// Unsigned entry describing `outcome`.
+    fn of(
+        seq: u64,
+        at: DateTime<Utc>,
+        outcome: Result<&ValidationResult, &GatewardenError>,
+    ) -> Self {
+        let mut entry = Self {
+            seq,
+            at,
+            outcome: AuditOutcome::Failed,
+            license_id: None,
+            from_cache: false,
+            fallback_reason: None,
+            error_code: None,
+            security: false,
+            request_id: None,
+            mac: String::new(),
+        };
+        match outcome {
+            Ok(result) => {
+                entry.outcome = if result.valid {
+                    AuditOutcome::Valid
+                } else {
+                    AuditOutcome::Invalid
+                };
+                entry.license_id = result.state.license_id.clone();
+                entry.from_cache = result.from_cache;
+                entry.fallback_reason = result.fallback_reason;
+            }
+            Err(error) => {
+                entry.error_code = Some(error.code().to_string());
+                entry.security = is_security_error(error);
+                entry.request_id = error.request_id().map(String::from);
+            }
+        }
+        entry
+    }
+
+    /// Bytes covered by the entry's HMAC: the entry with an empty MAC.
+    fn signed_bytes(&self) -> Result<Vec<u8>, GatewardenError> {
+        let unsigned = Self {
+            mac: String::new(),
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to serialize audit entry".to_string(),
+            source: Some(e.into()),
+        })
+    }
+}
+
+/// HMAC-chained, append-only log of validation attempts.
+pub struct AuditLog {
+    path: PathBuf,
+    key: AuditKey,
+    clock: Arc<dyn Clock>,
+}
+
+impl AuditLog {
+    /// Log stored in a cache namespace, keyed to this machine.
+    ///
+    /// Stored at `dirs::data_dir()/<namespace>/audit.log`.
+    ///
+    /// # Errors
+    /// - `CacheIO` - The data directory cannot be determined
+    pub fn for_namespace(namespace: &str) -> Result<Self, GatewardenError> {
+        let base_dir = dirs::data_dir().ok_or_else(|| GatewardenError::CacheIO {
+            message: "Could not determine data directory".to_string(),
+            source: None,
+        })?;
+        let fingerprint = machine_fingerprint().unwrap_or_default();
+        Ok(Self::with_path(
+            base_dir.join(namespace).join(AUDIT_FILE),
+            AuditKey::derive(namespace, &fingerprint),
+        ))
+    }
+
+    /// Log stored at a specific path, e.g. a rotated file.
+    pub fn with_path(path: PathBuf, key: AuditKey) -> Self {
+        Self {
+            path,
+            key,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Timestamp entries with `clock` (for testing).
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a validation outcome.
+    ///
+    /// Registered as an event sink, the log calls this for every attempt;
+    /// call it directly to record outcomes from elsewhere.
+    ///
+    /// # Errors
+    /// - `CacheIO` - The log could not be read or written
+    /// - `CacheTampered` - The last entry is not a valid entry
+    pub fn append(
+        &self,
+        outcome: Result<&ValidationResult, &GatewardenError>,
+    ) -> Result<AuditEntry, GatewardenError> {
+        let _lock = self.lock()?;
+        let last = self.read_entries(false)?.pop();
+        let (seq, previous) = last.map_or((1, String::new()), |entry| (entry.seq + 1, entry.mac));
+
+        let mut entry = AuditEntry::of(seq, self.clock.now_utc(), outcome);
+        entry.mac = self.key.sign(&previous, &entry.signed_bytes()?);
+        let line = serde_json::to_string(&entry).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to serialize audit entry".to_string(),
+            source: Some(e.into()),
+        })?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to open audit log".to_string(),
+                source: Some(e.into()),
+            })?;
+        writeln!(file, "{}", line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to write audit log".to_string(),
+                source: Some(e.into()),
+            })?;
+        Ok(entry)
+    }
+
+    /// All entries, oldest first, after verifying the chain.
+    ///
+    /// A missing file reads as empty.
+    ///
+    /// # Errors
+    /// - `CacheIO` - The log could not be read
+    /// - `CacheTampered` - An entry was edited, reordered, or removed
+    pub fn read(&self) -> Result<Vec<AuditEntry>, GatewardenError> {
+        self.read_entries(true)
+    }
+
+    /// Move the current file aside and start a new chain.
+    ///
+    /// The file is renamed to `audit-<timestamp>.log` next to it. Returns
+    /// the new path, or `None` if there was nothing to rotate.
+    ///
+    /// # Errors
+    /// - `CacheIO` - The file could not be renamed
+    pub fn rotate(&self) -> Result<Option<PathBuf>, GatewardenError> {
+        let _lock = self.lock()?;
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let stem = self
+            .path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("audit");
+        let timestamp = self.clock.now_utc().format("%Y%m%dT%H%M%S%.3fZ");
+        let rotated = self.path.with_file_name(format!("{stem}-{timestamp}.log"));
+        fs::rename(&self.path, &rotated).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to rotate audit log".to_string(),
+            source: Some(e.into()),
+        })?;
+        Ok(Some(rotated))
+    }
+
+    /// Parse the file's entries, checking every MAC if `verify` is set.
+    fn read_entries(&self, verify: bool) -> Result<Vec<AuditEntry>, GatewardenError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(GatewardenError::CacheIO {
+                    message: "Failed to read audit log".to_string(),
+                    source: Some(e.into()),
+                })
+            }
+        };
+
+        let mut entries: Vec<AuditEntry> = Vec::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let entry: AuditEntry =
+                serde_json::from_str(line).map_err(|_| GatewardenError::CacheTampered)?;
+            if verify {
+                let previous = entries.last().map_or("", |last| last.mac.as_str());
+                let expected_seq = entries.last().map_or(1, |last| last.seq + 1);
+                let expected_mac = self.key.sign(previous, &entry.signed_bytes()?);
+                if entry.seq != expected_seq || !macs_equal(&entry.mac, &expected_mac) {
+                    return Err(GatewardenError::CacheTampered);
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Take the cross-process write lock, held until the file is dropped.
+    fn lock(&self) -> Result<File, GatewardenError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to create dir".to_string(),
+                source: Some(e.into()),
+            })?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("lock"))
+            .map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to open lock".to_string(),
+                source: Some(e.into()),
+            })?;
+        file.lock_exclusive()
+            .map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to lock audit log".to_string(),
+                source: Some(e.into()),
+            })?;
+        Ok(file)
+    }
+}
+
+impl EventSink for AuditLog {
+    fn on_attempt(&self, outcome: Result<&ValidationResult, &GatewardenError>) {
+        // Sinks cannot fail the validation; a lost entry is only logged
+        if let Err(_e) = self.append(outcome) {
+            trace_warn!(error = %_e, "failed to write audit log");
+        }
+    }
+}
+
+/// Constant-time comparison of two hex MACs.
+fn macs_equal(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::mock::{MockKeygen, MockLicense};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::LicenseManager;
+    use chrono::TimeZone;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn test_log(dir: &TempDir, clock: Arc<dyn Clock>) -> AuditLog {
+        AuditLog::with_path(
+            dir.path().join("audit.log"),
+            AuditKey::derive("audit-test", "machine"),
+        )
+        .with_clock(clock)
+    }
+
+    #[test]
+    fn test_records_attempts_from_manager() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("good-key", MockLicense::new("lic-1"));
+        let audit = Arc::new(test_log(&temp_dir, clock.clone()));
+        let config = crate::GatewardenConfig::builder()
+            .app_name("audit-test")
+            .feature_name("audit")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                crate::cache::file::FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen)
+            .with_event_sink(audit.clone());
+
+        manager.validate_key("good-key").unwrap();
+        assert!(manager.validate_key("unknown-key").is_err());
+
+        let entries = audit.read().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].seq, 1);
+        assert_eq!(entries[0].outcome, AuditOutcome::Valid);
+        assert_eq!(entries[0].license_id.as_deref(), Some("lic-1"));
+        assert_eq!(entries[1].outcome, AuditOutcome::Failed);
+        assert_eq!(entries[1].error_code.as_deref(), Some("INVALID_LICENSE"));
+        assert!(!entries[1].security);
+
+        let contents = fs::read_to_string(audit.path()).unwrap();
+        assert!(!contents.contains("good-key"));
+    }
+
+    #[test]
+    fn test_chain_detects_edits_and_removals() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let log = test_log(&temp_dir, clock);
+        let security = GatewardenError::SignatureInvalid {
+            request_id: Some("req-1".to_string()),
+        };
+        log.append(Err(&security)).unwrap();
+        log.append(Err(&GatewardenError::InvalidLicense)).unwrap();
+        log.append(Err(&GatewardenError::CacheExpired)).unwrap();
+
+        let entries = log.read().unwrap();
+        assert!(entries[0].security);
+        assert_eq!(entries[0].request_id.as_deref(), Some("req-1"));
+
+        let original = fs::read_to_string(log.path()).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        // Dropping a middle entry breaks the chain
+        fs::write(log.path(), format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(matches!(log.read(), Err(GatewardenError::CacheTampered)));
+
+        // So does editing one
+        let edited = original.replace("INVALID_LICENSE", "CACHE_EXPIRED");
+        fs::write(log.path(), edited).unwrap();
+        assert!(matches!(log.read(), Err(GatewardenError::CacheTampered)));
+
+        // A different machine's key cannot verify the chain
+        fs::write(log.path(), &original).unwrap();
+        let elsewhere = AuditLog::with_path(
+            log.path().to_path_buf(),
+            AuditKey::derive("audit-test", "other-machine"),
+        );
+        assert!(matches!(
+            elsewhere.read(),
+            Err(GatewardenError::CacheTampered)
+        ));
+    }
+
+    #[test]
+    fn test_rotate_starts_new_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let log = test_log(&temp_dir, clock);
+        assert!(log.rotate().unwrap().is_none());
+
+        log.append(Err(&GatewardenError::InvalidLicense)).unwrap();
+        let rotated = log.rotate().unwrap().unwrap();
+        assert_eq!(
+            rotated.file_name().unwrap(),
+            "audit-20250115T120000.000Z.log"
+        );
+        assert!(log.read().unwrap().is_empty());
+
+        let entry = log.append(Err(&GatewardenError::InvalidLicense)).unwrap();
+        assert_eq!(entry.seq, 1);
+
+        let archived =
+            AuditLog::with_path(rotated, AuditKey::derive("audit-test", "machine")).read();
+        assert_eq!(archived.unwrap().len(), 1);
+    }
+}
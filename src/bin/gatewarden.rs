@@ -0,0 +1,311 @@
+//! `gatewarden` diagnostics CLI (feature `cli`).
+//!
+//! Support tool for debugging customer installs: runs the library's own
+//! validation, cache, and meter code against the same cache namespace as
+//! the application, so results match what the application sees.
+//!
+//! ```bash
+//! export GATEWARDEN_APP_NAME=myapp GATEWARDEN_ACCOUNT_ID=... GATEWARDEN_PUBLIC_KEY=...
+//! export GATEWARDEN_LICENSE_KEY=...
+//! gatewarden doctor
+//! gatewarden cache inspect
+//! ```
+//!
+//! The license key is read from `--key` or `GATEWARDEN_LICENSE_KEY` and
+//! never printed.
+
+use clap::{Args, Parser, Subcommand};
+use gatewarden::fingerprint::machine_fingerprint;
+use gatewarden::{GatewardenConfig, GatewardenError, LicenseManager};
+use serde::Serialize;
+use std::process::ExitCode;
+
+/// Local clock offsets above this are reported by `doctor`.
+const SKEW_WARNING_SECONDS: i64 = 60;
+
+/// Keygen API token prefixes, often pasted in place of a license key.
+const TOKEN_PREFIXES: &[&str] = &["admin-", "prod-", "activ-", "user-", "env-"];
+
+#[derive(Parser)]
+#[command(
+    name = "gatewarden",
+    version,
+    about = "Diagnose gatewarden license installs"
+)]
+struct Cli {
+    #[command(flatten)]
+    app: AppArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// The application's gatewarden configuration.
+#[derive(Args)]
+struct AppArgs {
+    /// Application name, as passed to `GatewardenConfig`.
+    #[arg(long, env = "GATEWARDEN_APP_NAME", global = true)]
+    app_name: Option<String>,
+
+    /// Keygen account ID.
+    #[arg(long, env = "GATEWARDEN_ACCOUNT_ID", global = true)]
+    account_id: Option<String>,
+
+    /// Keygen Ed25519 public key (hex).
+    #[arg(long, env = "GATEWARDEN_PUBLIC_KEY", global = true)]
+    public_key: Option<String>,
+
+    /// Cache namespace, if the application overrides it.
+    #[arg(long, env = "GATEWARDEN_CACHE_NAMESPACE", global = true)]
+    cache_namespace: Option<String>,
+
+    /// License key.
+    #[arg(
+        long,
+        env = "GATEWARDEN_LICENSE_KEY",
+        hide_env_values = true,
+        global = true
+    )]
+    key: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Validate the key (online, falling back to the cache) and print the result.
+    Validate,
+
+    /// Inspect or clear cached results.
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// Inspect the local usage meter.
+    #[command(subcommand)]
+    Meter(MeterCommand),
+
+    /// Check the key format, connectivity to Keygen, and clock skew.
+    Doctor,
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Verify the key's cached result offline and print it.
+    Inspect,
+
+    /// Delete the key's cached result.
+    Clear {
+        /// Delete cached results for every key instead.
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MeterCommand {
+    /// Print the key's per-day usage, attributed to this machine's fingerprint.
+    Show,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}: {}", e.code(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, GatewardenError> {
+    let manager = manager(&cli.app)?;
+    let key = cli.app.key.as_deref();
+
+    match cli.command {
+        Command::Validate => print_json(&manager.validate_key(require_key(key)?)?)?,
+        Command::Cache(CacheCommand::Inspect) => {
+            print_json(&manager.check_access(require_key(key)?)?)?;
+        }
+        Command::Cache(CacheCommand::Clear { all: true }) => {
+            manager.clear_cache(None)?;
+            println!("cleared all cached results");
+        }
+        Command::Cache(CacheCommand::Clear { all: false }) => {
+            manager.clear_cache(Some(require_key(key)?))?;
+            println!("cleared cached result");
+        }
+        Command::Meter(MeterCommand::Show) => {
+            let source = machine_fingerprint().unwrap_or_default();
+            print_json(&manager.usage_report(require_key(key)?, &source)?)?;
+        }
+        Command::Doctor => return Ok(doctor(&manager, require_key(key)?)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Build a manager from the application's configuration.
+fn manager(app: &AppArgs) -> Result<LicenseManager, GatewardenError> {
+    let required = |value: &Option<String>, name: &str| {
+        value
+            .clone()
+            .map(leak)
+            .ok_or_else(|| GatewardenError::ConfigError(format!("{} is required", name)))
+    };
+    let mut builder = GatewardenConfig::builder()
+        .app_name(required(&app.app_name, "--app-name")?)
+        .account_id(required(&app.account_id, "--account-id")?)
+        .public_key_hex(required(&app.public_key, "--public-key")?);
+    if let Some(namespace) = &app.cache_namespace {
+        builder = builder.cache_namespace(leak(namespace.clone()));
+    }
+    LicenseManager::new(builder.build()?)
+}
+
+/// Config fields are `&'static str`; the CLI builds one config per run.
+fn leak(value: String) -> &'static str {
+    Box::leak(value.into_boxed_str())
+}
+
+fn require_key(key: Option<&str>) -> Result<&str, GatewardenError> {
+    key.ok_or(GatewardenError::MissingLicense)
+}
+
+fn print_json(value: &impl Serialize) -> Result<(), GatewardenError> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| GatewardenError::ConfigError(format!("Failed to serialize output: {}", e)))?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Run every check, print one line per check, and fail if any failed.
+fn doctor(manager: &LicenseManager, key: &str) -> ExitCode {
+    let mut failed = false;
+
+    let problems = key_format_problems(key);
+    if problems.is_empty() {
+        println!("ok    key format");
+    } else {
+        failed = true;
+        for problem in problems {
+            println!("FAIL  key format: {}", problem);
+        }
+    }
+
+    match manager.config().proxy.proxy_for("api.keygen.sh") {
+        Some(proxy) => println!("ok    proxy: {}", proxy),
+        None => println!("ok    proxy: none (direct connection)"),
+    }
+
+    // Skew is only measured from a verified response
+    let mut reached = true;
+    match manager.force_refresh(key) {
+        Ok(result) => println!(
+            "ok    connectivity: verified Keygen response ({})",
+            result.state.code
+        ),
+        Err(e @ GatewardenError::InvalidLicense)
+        | Err(e @ GatewardenError::LicenseSuspended)
+        | Err(e @ GatewardenError::LicenseBanned)
+        | Err(e @ GatewardenError::LicenseOverdue)
+        | Err(e @ GatewardenError::LicenseExpired { .. }) => {
+            // Keygen answered and the response verified; the key itself is the problem
+            println!("ok    connectivity: verified Keygen response");
+            println!("FAIL  license: {}: {}", e.code(), e);
+            failed = true;
+        }
+        Err(GatewardenError::ResponseTooOld { .. }) => {
+            println!("FAIL  clock skew: responses look stale; the local clock may be ahead");
+            failed = true;
+            reached = false;
+        }
+        Err(GatewardenError::KeygenTransport { kind, .. }) => {
+            let kind = format!("{:?}", kind).to_lowercase();
+            println!("FAIL  connectivity: cannot reach Keygen ({} failure)", kind);
+            failed = true;
+            reached = false;
+        }
+        Err(e) => {
+            println!("FAIL  connectivity: {}: {}", e.code(), e);
+            failed = true;
+            reached = false;
+        }
+    }
+
+    let skew = manager.clock_skew().num_seconds();
+    if !reached {
+        println!("skip  clock skew: Keygen not reached");
+    } else if skew > SKEW_WARNING_SECONDS {
+        println!(
+            "warn  clock skew: local clock is {}s behind Keygen (corrected automatically)",
+            skew
+        );
+    } else {
+        println!("ok    clock skew: {}s", skew);
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Obvious copy-paste damage to a license key.
+///
+/// Keygen key formats are configurable, so anything else is left to
+/// Keygen to judge.
+fn key_format_problems(key: &str) -> Vec<&'static str> {
+    if key.is_empty() {
+        return vec!["key is empty"];
+    }
+    let mut problems = Vec::new();
+    let trimmed = key.trim();
+    if trimmed != key {
+        problems.push("leading or trailing whitespace");
+    }
+    if trimmed.contains(char::is_whitespace) {
+        problems.push("whitespace inside the key");
+    }
+    if key.chars().any(char::is_control) {
+        problems.push("control characters");
+    }
+    if !key.is_ascii() {
+        problems.push("non-ASCII characters (e.g. typographic dashes)");
+    }
+    if TOKEN_PREFIXES
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+    {
+        problems.push("looks like a Keygen API token, not a license key");
+    }
+    if trimmed.starts_with("key/") && !trimmed.contains('.') {
+        problems.push("signed key is missing its signature");
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_format_problems() {
+        assert!(key_format_problems("ABCD-1234-EFGH-5678").is_empty());
+        assert_eq!(key_format_problems(""), ["key is empty"]);
+        assert_eq!(
+            key_format_problems(" ABCD-1234\n"),
+            ["leading or trailing whitespace", "control characters"]
+        );
+        assert_eq!(
+            key_format_problems("ABCD\u{2013}1234"),
+            ["non-ASCII characters (e.g. typographic dashes)"]
+        );
+        assert_eq!(
+            key_format_problems("prod-0123456789abcdef"),
+            ["looks like a Keygen API token, not a license key"]
+        );
+        assert_eq!(
+            key_format_problems("key/eyJhY2NvdW50Ijp7fX0"),
+            ["signed key is missing its signature"]
+        );
+    }
+}
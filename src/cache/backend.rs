@@ -0,0 +1,39 @@
+//! Cache storage backend abstraction.
+//!
+//! Backends only persist records. Authenticity is always re-checked via
+//! [`CacheRecord::verify`] after load, so a backend never has to be trusted.
+
+use crate::cache::format::CacheRecord;
+use crate::GatewardenError;
+
+/// Storage backend for authenticated cache records.
+///
+/// Records are keyed by the SHA-256 hash of the license key
+/// (see [`hash_license_key`]).
+pub trait CacheBackend: Send + Sync {
+    /// Persist a cache record, replacing any existing record for the key.
+    fn save(&self, license_key_hash: &str, record: &CacheRecord) -> Result<(), GatewardenError>;
+
+    /// Load a cache record, returning `None` if none is stored.
+    fn load(&self, license_key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError>;
+
+    /// Delete a cache record. Deleting a missing record is not an error.
+    fn delete(&self, license_key_hash: &str) -> Result<(), GatewardenError>;
+
+    /// Remove all cache records held by this backend.
+    fn clear(&self) -> Result<(), GatewardenError>;
+
+    /// Whether records are encrypted at rest (reported by `security_posture`).
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+}
+
+/// Compute a SHA-256 hash of the license key for use as cache key.
+///
+/// This avoids storing the raw license key in filenames and storage keys.
+pub fn hash_license_key(license_key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let hash = Sha256::digest(license_key.as_bytes());
+    hex::encode(hash)
+}
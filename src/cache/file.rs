@@ -1,12 +1,37 @@
 //! File-based cache backend with atomic writes.
 //!
 //! Stores authenticated cache records under `dirs::data_dir()/<namespace>/`.
-//! Uses temp file + rename for atomic writes.
+//! Uses temp file + rename for atomic writes, under an advisory lock per
+//! record (`<name>.lock`) so processes sharing a namespace neither
+//! interleave writes nor read a record mid-replacement.
+//!
+//! [`FileCache::export`] and [`FileCache::import`] move records between
+//! machines as a single bundle file, e.g. from a connected machine to an
+//! air-gapped one. Records keep their Keygen signatures and original
+//! `cached_at`, so they are re-verified on every load and the offline
+//! grace window still counts from the online validation.
 
+use crate::cache::backend::CacheBackend;
 use crate::cache::format::CacheRecord;
 use crate::GatewardenError;
-use std::fs;
-use std::path::PathBuf;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+pub use crate::cache::backend::hash_license_key;
+
+/// Format tag of an exported cache bundle.
+const BUNDLE_FORMAT: &str = "gatewarden-cache-bundle-v1";
+
+/// Cache records exported for transfer to another machine.
+#[derive(Serialize, Deserialize)]
+struct CacheBundle {
+    format: String,
+    /// Record file name (without `.json`) to record.
+    records: BTreeMap<String, CacheRecord>,
+}
 
 /// File-based cache backend.
 pub struct FileCache {
@@ -19,14 +44,18 @@ impl FileCache {
     ///
     /// Cache files are stored under `dirs::data_dir()/<namespace>/`.
     pub fn new(namespace: &str) -> Result<Self, GatewardenError> {
-        let base_dir = dirs::data_dir()
-            .ok_or_else(|| GatewardenError::CacheIO("Could not find data directory".to_string()))?;
+        let base_dir = dirs::data_dir().ok_or_else(|| GatewardenError::CacheIO {
+            message: "Could not find data directory".to_string(),
+            source: None,
+        })?;
 
         let cache_dir = base_dir.join(namespace);
 
         // Ensure directory exists
-        fs::create_dir_all(&cache_dir)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to create cache dir: {}", e)))?;
+        fs::create_dir_all(&cache_dir).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to create cache dir".to_string(),
+            source: Some(e.into()),
+        })?;
 
         Ok(Self { cache_dir })
     }
@@ -34,8 +63,10 @@ impl FileCache {
     /// Create a file cache at a specific path (for testing).
     #[cfg(test)]
     pub fn with_path(cache_dir: PathBuf) -> Result<Self, GatewardenError> {
-        fs::create_dir_all(&cache_dir)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to create cache dir: {}", e)))?;
+        fs::create_dir_all(&cache_dir).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to create cache dir".to_string(),
+            source: Some(e.into()),
+        })?;
         Ok(Self { cache_dir })
     }
 
@@ -46,14 +77,43 @@ impl FileCache {
         self.cache_dir.join(format!("{}.json", safe_name))
     }
 
+    /// Lock a record against other processes, until the returned file is
+    /// dropped.
+    ///
+    /// Writers take the lock exclusively, readers shared.
+    fn lock(&self, license_key_hash: &str, exclusive: bool) -> Result<File, GatewardenError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.license_path(license_key_hash).with_extension("lock"))
+            .map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to open cache lock".to_string(),
+                source: Some(e.into()),
+            })?;
+        // fs2's methods, not std's newer inherent ones (MSRV)
+        let locked = if exclusive {
+            FileExt::lock_exclusive(&file)
+        } else {
+            FileExt::lock_shared(&file)
+        };
+        locked.map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to lock cache".to_string(),
+            source: Some(e.into()),
+        })?;
+        Ok(file)
+    }
+
     /// Save a cache record atomically.
     ///
-    /// Uses temp file + rename for atomic write.
+    /// Uses temp file + rename for atomic write, holding the record's
+    /// lock exclusively.
     pub fn save(
         &self,
         license_key_hash: &str,
         record: &CacheRecord,
     ) -> Result<(), GatewardenError> {
+        let _lock = self.lock(license_key_hash, true)?;
         let target_path = self.license_path(license_key_hash);
         // Avoid using the full hash in filenames.
         let safe_name = &license_key_hash[..16.min(license_key_hash.len())];
@@ -62,8 +122,10 @@ impl FileCache {
         let json = record.to_json()?;
 
         // Write to temp file
-        fs::write(&temp_path, &json)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to write temp file: {}", e)))?;
+        fs::write(&temp_path, &json).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to write temp file".to_string(),
+            source: Some(e.into()),
+        })?;
 
         // Best-effort restrict permissions on Unix-like systems.
         #[cfg(unix)]
@@ -75,67 +137,195 @@ impl FileCache {
 
         // Atomic rename on Unix; on Windows rename-over-existing fails.
         if target_path.exists() {
-            fs::remove_file(&target_path).map_err(|e| {
-                GatewardenError::CacheIO(format!("Failed to remove old cache file: {}", e))
+            fs::remove_file(&target_path).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to remove old cache file".to_string(),
+                source: Some(e.into()),
             })?;
         }
-        fs::rename(&temp_path, &target_path)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to rename cache file: {}", e)))?;
+        fs::rename(&temp_path, &target_path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to rename cache file".to_string(),
+            source: Some(e.into()),
+        })?;
 
+        trace_debug!("cache record saved");
         Ok(())
     }
 
     /// Load a cache record.
     pub fn load(&self, license_key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        let _lock = self.lock(license_key_hash, false)?;
         let path = self.license_path(license_key_hash);
 
         if !path.exists() {
+            trace_debug!(hit = false, "cache lookup");
             return Ok(None);
         }
 
-        let json = fs::read_to_string(&path)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to read cache file: {}", e)))?;
+        let json = fs::read_to_string(&path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to read cache file".to_string(),
+            source: Some(e.into()),
+        })?;
 
         let record = CacheRecord::from_json(&json)?;
+        trace_debug!(hit = true, "cache lookup");
         Ok(Some(record))
     }
 
     /// Delete a cache record.
     pub fn delete(&self, license_key_hash: &str) -> Result<(), GatewardenError> {
+        let _lock = self.lock(license_key_hash, true)?;
         let path = self.license_path(license_key_hash);
 
         if path.exists() {
-            fs::remove_file(&path)
-                .map_err(|e| GatewardenError::CacheIO(format!("Failed to delete cache: {}", e)))?;
+            fs::remove_file(&path).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to delete cache".to_string(),
+                source: Some(e.into()),
+            })?;
         }
 
         Ok(())
     }
 
-    /// Clear all cache files.
+    /// Clear all cache records.
+    ///
+    /// Only record files are deleted; other state kept in the same
+    /// directory (the clock high-water mark, usage meters) survives.
     pub fn clear(&self) -> Result<(), GatewardenError> {
-        for entry in fs::read_dir(&self.cache_dir)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to read cache dir: {}", e)))?
-        {
-            let entry = entry
-                .map_err(|e| GatewardenError::CacheIO(format!("Failed to read entry: {}", e)))?;
+        for name in self.record_names()? {
+            let path = self.cache_dir.join(format!("{}.json", name));
+            fs::remove_file(&path).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to delete".to_string(),
+                source: Some(e.into()),
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl FileCache {
+    /// Write every cache record to a single bundle file at `path`.
+    ///
+    /// Returns the number of records exported.
+    pub fn export(&self, path: &Path) -> Result<usize, GatewardenError> {
+        let mut records = BTreeMap::new();
+        for name in self.record_names()? {
+            if let Some(record) = self.load(&name)? {
+                records.insert(name, record);
+            }
+        }
+        let count = records.len();
+
+        let bundle = CacheBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            records,
+        };
+        let json = serde_json::to_string_pretty(&bundle).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to serialize cache bundle".to_string(),
+            source: Some(e.into()),
+        })?;
+        fs::write(path, json).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to write cache bundle".to_string(),
+            source: Some(e.into()),
+        })?;
+        Ok(count)
+    }
+
+    /// Import the records of a bundle written by [`export`](Self::export).
+    ///
+    /// Records are not verified here; like any cached record they are
+    /// verified against the trusted keys and offline grace on every load.
+    /// A record older than the one already cached under its name is
+    /// skipped, so importing a stale bundle never rolls the cache back.
+    /// Returns the number of records imported.
+    ///
+    /// # Errors
+    /// - `CacheIO` - The bundle cannot be read, is not a cache bundle, or
+    ///   names a record file that is not a cache key hash prefix
+    pub fn import(&self, path: &Path) -> Result<usize, GatewardenError> {
+        let json = fs::read_to_string(path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to read cache bundle".to_string(),
+            source: Some(e.into()),
+        })?;
+        let bundle: CacheBundle =
+            serde_json::from_str(&json).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to parse cache bundle".to_string(),
+                source: Some(e.into()),
+            })?;
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(GatewardenError::CacheIO {
+                message: format!("Unsupported cache bundle format: {}", bundle.format),
+                source: None,
+            });
+        }
+        // Names become file names; never let one escape the cache dir
+        if let Some(name) = bundle.records.keys().find(|name| !is_record_name(name)) {
+            return Err(GatewardenError::CacheIO {
+                message: format!("Invalid record name in cache bundle: {}", name),
+                source: None,
+            });
+        }
+
+        let mut imported = 0;
+        for (name, record) in &bundle.records {
+            let newer = match self.load(name) {
+                Ok(Some(existing)) => record.cached_at > existing.cached_at,
+                // A missing or unreadable record is replaced
+                _ => true,
+            };
+            if newer {
+                self.save(name, record)?;
+                imported += 1;
+            }
+        }
+        Ok(imported)
+    }
+
+    /// Names (file stems) of the records in the cache directory.
+    fn record_names(&self) -> Result<Vec<String>, GatewardenError> {
+        let entries = fs::read_dir(&self.cache_dir).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to read cache dir".to_string(),
+            source: Some(e.into()),
+        })?;
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to read entry".to_string(),
+                source: Some(e.into()),
+            })?;
             let path = entry.path();
             if path.extension().is_some_and(|ext| ext == "json") {
-                fs::remove_file(&path)
-                    .map_err(|e| GatewardenError::CacheIO(format!("Failed to delete: {}", e)))?;
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    if is_record_name(name) {
+                        names.push(name.to_string());
+                    }
+                }
             }
         }
-        Ok(())
+        Ok(names)
     }
 }
 
-/// Compute a SHA-256 hash of the license key for use as cache key.
-///
-/// This avoids storing the raw license key in filenames.
-pub fn hash_license_key(license_key: &str) -> String {
-    use sha2::{Digest, Sha256};
-    let hash = Sha256::digest(license_key.as_bytes());
-    hex::encode(hash)
+/// Whether `name` is a record file stem: a 16-character hex hash prefix.
+fn is_record_name(name: &str) -> bool {
+    name.len() == 16 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+impl CacheBackend for FileCache {
+    fn save(&self, license_key_hash: &str, record: &CacheRecord) -> Result<(), GatewardenError> {
+        FileCache::save(self, license_key_hash, record)
+    }
+
+    fn load(&self, license_key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        FileCache::load(self, license_key_hash)
+    }
+
+    fn delete(&self, license_key_hash: &str) -> Result<(), GatewardenError> {
+        FileCache::delete(self, license_key_hash)
+    }
+
+    fn clear(&self) -> Result<(), GatewardenError> {
+        FileCache::clear(self)
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +412,23 @@ mod tests {
         assert!(cache.load(&hash_license_key("key2")).unwrap().is_none());
     }
 
+    #[test]
+    fn test_file_cache_as_backend() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache: Box<dyn CacheBackend> =
+            Box::new(FileCache::with_path(temp_dir.path().to_path_buf()).unwrap());
+
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        let record = make_test_record(&clock);
+        let key_hash = hash_license_key("test-license-key");
+
+        cache.save(&key_hash, &record).unwrap();
+        assert_eq!(cache.load(&key_hash).unwrap().unwrap().body, record.body);
+
+        cache.delete(&key_hash).unwrap();
+        assert!(cache.load(&key_hash).unwrap().is_none());
+    }
+
     #[test]
     fn test_hash_license_key() {
         let hash1 = hash_license_key("test-key-1");
@@ -259,4 +466,75 @@ mod tests {
         let loaded = cache.load(&key_hash).unwrap().unwrap();
         assert_eq!(loaded.body, record2.body);
     }
+
+    #[test]
+    fn test_file_cache_export_import() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = FileCache::with_path(source_dir.path().to_path_buf()).unwrap();
+        let target = FileCache::with_path(target_dir.path().to_path_buf()).unwrap();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        let key_hash = hash_license_key("test-key");
+        let record = make_test_record(&clock);
+        source.save(&key_hash, &record).unwrap();
+        source
+            .save(&hash_license_key("other-key"), &record)
+            .unwrap();
+
+        let bundle_path = source_dir.path().join("bundle.json");
+        assert_eq!(source.export(&bundle_path).unwrap(), 2);
+        assert_eq!(target.import(&bundle_path).unwrap(), 2);
+        let loaded = target.load(&key_hash).unwrap().unwrap();
+        assert_eq!(loaded.body, record.body);
+        assert_eq!(loaded.cached_at, record.cached_at);
+
+        // Re-importing the same bundle changes nothing
+        assert_eq!(target.import(&bundle_path).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_file_cache_import_rejects_bad_names() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().join("cache")).unwrap();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        let bundle = CacheBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            records: BTreeMap::from([("../escape".to_string(), make_test_record(&clock))]),
+        };
+        let bundle_path = temp_dir.path().join("bundle.json");
+        fs::write(&bundle_path, serde_json::to_string(&bundle).unwrap()).unwrap();
+
+        assert!(matches!(
+            cache.import(&bundle_path),
+            Err(GatewardenError::CacheIO { .. })
+        ));
+        assert!(!temp_dir.path().join("escape.json").exists());
+    }
+
+    #[test]
+    fn test_file_cache_concurrent_writers() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        let key_hash = hash_license_key("test-key");
+
+        // Separate handles stand in for separate processes
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+                let mut record = make_test_record(&clock);
+                record.body = format!(r#"{{"writer":{}}}"#, i);
+                let key_hash = key_hash.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        cache.save(&key_hash, &record).unwrap();
+                        let loaded = cache.load(&key_hash).unwrap().unwrap();
+                        assert!(loaded.body.starts_with(r#"{"writer":"#));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
 }
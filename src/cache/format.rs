@@ -4,18 +4,25 @@
 //! - Original response body
 //! - HTTP headers needed for signature verification (Date, Keygen-Signature, Digest)
 //! - Timestamp when the record was cached
+//! - The license state parsed at write time (optional summary)
+//! - Keygen's request ID and rate-limit headers (optional, for support)
 //!
 //! On load, we:
 //! 1. Re-verify the signature (required)
 //! 2. Compare digest if present
 //! 3. Check `now - cached_at <= offline_grace`
+//!
+//! The summary is not covered by Keygen's signature. Readers may use it
+//! instead of parsing the body only after checking it against the body
+//! once, remembering the check by [`CacheRecord::summary_fingerprint`].
 
+use crate::client::response::RateLimit;
 use crate::clock::Clock;
 use crate::crypto::{
-    digest::verify_digest,
-    signing::build_signing_string,
-    verify::{decode_public_key, parse_signature_header, verify_ed25519},
+    digest::verify_digest, keys::KeyRing, signing::build_signing_string_for,
+    verify::parse_signature_header,
 };
+use crate::protocol::models::LicenseState;
 use crate::GatewardenError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -46,6 +53,26 @@ pub struct CacheRecord {
 
     /// Host used for signing string reconstruction.
     pub host: String,
+
+    /// License state parsed from `body` when the record was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<LicenseState>,
+
+    /// Keygen's `X-Request-Id` for the cached response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// Keygen's rate-limit window as of the cached response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+
+    /// Keygen's `uses` from a verified usage increment made after the
+    /// response was cached.
+    ///
+    /// Not covered by the signature; readers take the larger of this and
+    /// the signed `uses`, so an edit can only tighten usage caps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_uses: Option<u64>,
 }
 
 impl CacheRecord {
@@ -67,19 +94,63 @@ impl CacheRecord {
             cached_at: clock.now_utc(),
             request_path,
             host,
+            summary: None,
+            request_id: None,
+            rate_limit: None,
+            synced_uses: None,
         }
     }
 
+    /// Attach the license state parsed from the body.
+    pub fn with_summary(mut self, state: LicenseState) -> Self {
+        self.summary = Some(state);
+        self
+    }
+
+    /// Attach the request ID and rate limit Keygen sent with the response.
+    ///
+    /// Like the summary, these are not covered by the signature; they are
+    /// kept only to quote in support requests.
+    pub fn with_request(
+        mut self,
+        request_id: Option<String>,
+        rate_limit: Option<RateLimit>,
+    ) -> Self {
+        self.request_id = request_id;
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// SHA-256 over the signature, body, and summary, or `None` without a
+    /// summary.
+    ///
+    /// Identifies a summary that has been checked against this exact
+    /// signed body; any edit to either changes the fingerprint.
+    pub fn summary_fingerprint(&self) -> Option<[u8; 32]> {
+        use sha2::{Digest, Sha256};
+        let summary = serde_json::to_vec(self.summary.as_ref()?).ok()?;
+        let mut hasher = Sha256::new();
+        for part in [self.signature.as_bytes(), self.body.as_bytes(), &summary] {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        Some(hasher.finalize().into())
+    }
+
     /// Serialize the cache record to JSON.
     pub fn to_json(&self) -> Result<String, GatewardenError> {
-        serde_json::to_string_pretty(self)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to serialize cache: {}", e)))
+        serde_json::to_string_pretty(self).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to serialize cache".to_string(),
+            source: Some(e.into()),
+        })
     }
 
     /// Deserialize a cache record from JSON.
     pub fn from_json(json: &str) -> Result<Self, GatewardenError> {
-        serde_json::from_str(json)
-            .map_err(|e| GatewardenError::CacheIO(format!("Failed to deserialize cache: {}", e)))
+        serde_json::from_str(json).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to deserialize cache".to_string(),
+            source: Some(e.into()),
+        })
     }
 
     /// Verify the cached record is authentic and within offline grace.
@@ -91,31 +162,31 @@ impl CacheRecord {
     ///
     /// Note: We do NOT apply the 5-minute replay window to cached records.
     /// The `offline_grace` parameter controls how long cached data is valid.
-    pub fn verify(
+    pub fn verify<'a>(
         &self,
-        public_key_hex: &str,
+        keys: impl Into<KeyRing<'a>>,
         offline_grace: Duration,
         clock: &dyn Clock,
     ) -> Result<(), GatewardenError> {
         // 1. Parse signature header
         let parsed_sig = parse_signature_header(&self.signature)?;
 
-        // 2. Decode public key
-        let verifying_key = decode_public_key(public_key_hex)?;
-
-        // 3. Reconstruct signing string
+        // 2. Reconstruct signing string
         // For POST validate requests, Keygen signs: (request-target), host, date, digest
-        let signing_string = build_signing_string(
+        let signing_string = build_signing_string_for(
+            &parsed_sig.headers,
             "post",
             &self.request_path,
             &self.host,
             &self.date,
             self.digest.as_deref(),
-        );
+        )
+        .map_err(|_| GatewardenError::CacheTampered)?;
 
-        // 4. Verify Ed25519 signature
-        verify_ed25519(&parsed_sig.signature, &signing_string, &verifying_key)
-            .map_err(|_| GatewardenError::CacheTampered)?;
+        // 3. Verify Ed25519 signature with the first eligible trusted key
+        keys.into()
+            .verify(&parsed_sig, &signing_string)
+            .map_err(tampered_unless_config)?;
 
         // 5. Verify digest if present
         if let Some(ref digest_header) = self.digest {
@@ -140,40 +211,35 @@ impl CacheRecord {
         Ok(())
     }
 
+    /// Whether this record is older than `max_age`.
+    pub fn is_older_than(&self, max_age: Duration, clock: &dyn Clock) -> bool {
+        let age = clock.now_utc().signed_duration_since(self.cached_at);
+        age.num_seconds() > i64::try_from(max_age.as_secs()).unwrap_or(i64::MAX)
+    }
+
     /// Extract the cached response body.
     pub fn body(&self) -> &str {
         &self.body
     }
 }
 
+/// Report a failed signature check on stored data as tampering, keeping
+/// configuration errors (a malformed trusted key) as they are.
+pub(crate) fn tampered_unless_config(error: GatewardenError) -> GatewardenError {
+    match error {
+        GatewardenError::ConfigError(_) => error,
+        _ => GatewardenError::CacheTampered,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::clock::MockClock;
     use crate::crypto::digest::format_digest_header;
-    use base64::{engine::general_purpose::STANDARD, Engine};
+    use crate::crypto::signing::build_signing_string;
+    use crate::mock::{test_signature_header, TEST_PUBLIC_KEY_HEX};
     use chrono::TimeZone;
-    use ed25519_dalek::{Signer, SigningKey};
-
-    // Test signing seed + verifying key (DO NOT USE IN PRODUCTION)
-    // This is a well-known Ed25519 test vector seed.
-    const TEST_SIGNING_SEED_BYTES: [u8; 32] = [
-        0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c,
-        0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae,
-        0x7f, 0x60,
-    ];
-    const TEST_VERIFY_KEY_HEX: &str =
-        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
-
-    fn get_test_signing_key() -> SigningKey {
-        SigningKey::from_bytes(&TEST_SIGNING_SEED_BYTES)
-    }
-
-    fn sign_test_data(signing_string: &str) -> String {
-        let signing_key = get_test_signing_key();
-        let signature = signing_key.sign(signing_string.as_bytes());
-        STANDARD.encode(signature.to_bytes())
-    }
 
     fn create_test_record(
         body: &str,
@@ -184,8 +250,7 @@ mod tests {
     ) -> CacheRecord {
         let digest = format_digest_header(body.as_bytes());
         let signing_string = build_signing_string("post", path, host, date, Some(&digest));
-        let signature_b64 = sign_test_data(&signing_string);
-        let signature_header = format!(r#"algorithm="ed25519", signature="{}""#, signature_b64);
+        let signature_header = test_signature_header(&signing_string);
 
         CacheRecord::new(
             date.to_string(),
@@ -233,7 +298,7 @@ mod tests {
 
         // Verify immediately - should pass
         let result = record.verify(
-            TEST_VERIFY_KEY_HEX,
+            TEST_PUBLIC_KEY_HEX,
             Duration::from_secs(86400), // 24 hours grace
             &clock,
         );
@@ -255,7 +320,7 @@ mod tests {
         // Advance 23 hours (within 24-hour grace)
         let later_clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 16, 11, 0, 0).unwrap());
         let result = record.verify(
-            TEST_VERIFY_KEY_HEX,
+            TEST_PUBLIC_KEY_HEX,
             Duration::from_secs(86400), // 24 hours grace
             &later_clock,
         );
@@ -277,7 +342,7 @@ mod tests {
         // Advance 25 hours (beyond 24-hour grace)
         let later_clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 16, 13, 0, 0).unwrap());
         let result = record.verify(
-            TEST_VERIFY_KEY_HEX,
+            TEST_PUBLIC_KEY_HEX,
             Duration::from_secs(86400), // 24 hours grace
             &later_clock,
         );
@@ -299,7 +364,7 @@ mod tests {
         // Tamper with body
         record.body = r#"{"data":{"type":"licenses","attributes":{"valid":false}}}"#.to_string();
 
-        let result = record.verify(TEST_VERIFY_KEY_HEX, Duration::from_secs(86400), &clock);
+        let result = record.verify(TEST_PUBLIC_KEY_HEX, Duration::from_secs(86400), &clock);
         assert!(matches!(result, Err(GatewardenError::CacheTampered)));
     }
 
@@ -318,7 +383,7 @@ mod tests {
         // Tamper with date
         record.date = "Thu, 16 Jan 2025 12:00:00 GMT".to_string();
 
-        let result = record.verify(TEST_VERIFY_KEY_HEX, Duration::from_secs(86400), &clock);
+        let result = record.verify(TEST_PUBLIC_KEY_HEX, Duration::from_secs(86400), &clock);
         assert!(matches!(result, Err(GatewardenError::CacheTampered)));
     }
 
@@ -337,7 +402,7 @@ mod tests {
         // Tamper with signature by using a completely different base64 value
         record.signature = r#"algorithm="ed25519", signature="AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA==""#.to_string();
 
-        let result = record.verify(TEST_VERIFY_KEY_HEX, Duration::from_secs(86400), &clock);
+        let result = record.verify(TEST_PUBLIC_KEY_HEX, Duration::from_secs(86400), &clock);
         assert!(matches!(result, Err(GatewardenError::CacheTampered)));
     }
 
@@ -355,7 +420,7 @@ mod tests {
 
         // Verify with a clock that's BEFORE the cached_at time
         let past_clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 11, 0, 0).unwrap());
-        let result = record.verify(TEST_VERIFY_KEY_HEX, Duration::from_secs(86400), &past_clock);
+        let result = record.verify(TEST_PUBLIC_KEY_HEX, Duration::from_secs(86400), &past_clock);
         assert!(matches!(result, Err(GatewardenError::CacheTampered)));
     }
 
@@ -369,8 +434,7 @@ mod tests {
 
         // Sign without digest
         let signing_string = build_signing_string("post", path, host, date, None);
-        let signature_b64 = sign_test_data(&signing_string);
-        let signature_header = format!(r#"algorithm="ed25519", signature="{}""#, signature_b64);
+        let signature_header = test_signature_header(&signing_string);
 
         let record = CacheRecord::new(
             date.to_string(),
@@ -382,7 +446,7 @@ mod tests {
             &clock,
         );
 
-        let result = record.verify(TEST_VERIFY_KEY_HEX, Duration::from_secs(86400), &clock);
+        let result = record.verify(TEST_PUBLIC_KEY_HEX, Duration::from_secs(86400), &clock);
         assert!(result.is_ok());
     }
 
@@ -401,7 +465,7 @@ mod tests {
         // Exactly at grace boundary (should pass)
         let boundary_clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 16, 12, 0, 0).unwrap());
         let result = record.verify(
-            TEST_VERIFY_KEY_HEX,
+            TEST_PUBLIC_KEY_HEX,
             Duration::from_secs(86400), // 24 hours
             &boundary_clock,
         );
@@ -409,7 +473,7 @@ mod tests {
 
         // One second over (should fail)
         let over_clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 16, 12, 0, 1).unwrap());
-        let result = record.verify(TEST_VERIFY_KEY_HEX, Duration::from_secs(86400), &over_clock);
+        let result = record.verify(TEST_PUBLIC_KEY_HEX, Duration::from_secs(86400), &over_clock);
         assert!(matches!(result, Err(GatewardenError::CacheExpired)));
     }
 }
@@ -0,0 +1,308 @@
+//! Anti-rollback high-water mark of verified server time.
+//!
+//! `CacheRecord::verify` measures offline grace from `cached_at`, which is
+//! local time. Setting the clock back after caching makes the record look
+//! younger and stretches the grace period. To close that hole we persist
+//! the latest verified Keygen `Date` and refuse cached validations while
+//! the clock reads earlier than it.
+//!
+//! The mark is not protected by a local secret (anything the process can
+//! read, the user can too). Instead it keeps the headers Keygen signed —
+//! `Date`, `Digest`, host, and request path — so it is re-verified with
+//! Keygen's public key on every load and cannot be forged. Deleting the
+//! file only falls back to the date of the cached record itself, which is
+//! signed the same way.
+
+use crate::cache::format::{tampered_unless_config, CacheRecord};
+use crate::crypto::freshness::parse_rfc2822_date;
+use crate::crypto::{
+    keys::KeyRing, signing::build_signing_string_for, verify::parse_signature_header,
+};
+use crate::GatewardenError;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Clock may read this far behind the mark before it counts as rollback.
+pub const ROLLBACK_TOLERANCE_SECONDS: i64 = 5 * 60;
+
+/// File name for the persisted mark within the cache namespace.
+const HIGH_WATER_FILE: &str = "time_high_water.json";
+
+/// Keygen-signed evidence of a server time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeProof {
+    /// The signed HTTP Date header value.
+    pub date: String,
+
+    /// The Keygen-Signature header value.
+    pub signature: String,
+
+    /// The Digest header value (optional).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+
+    /// Request path used for signing string reconstruction.
+    pub request_path: String,
+
+    /// Host used for signing string reconstruction.
+    pub host: String,
+}
+
+impl TimeProof {
+    /// Take the signed headers from a cache record.
+    pub fn from_record(record: &CacheRecord) -> Self {
+        Self {
+            date: record.date.clone(),
+            signature: record.signature.clone(),
+            digest: record.digest.clone(),
+            request_path: record.request_path.clone(),
+            host: record.host.clone(),
+        }
+    }
+
+    /// Verify the signature and return the proven server time.
+    ///
+    /// # Errors
+    /// - `CacheTampered` - Signature does not match the headers
+    /// - `ConfigError` - Public key is malformed
+    /// - `ProtocolError` - Date header cannot be parsed
+    pub fn verify<'a>(
+        &self,
+        keys: impl Into<KeyRing<'a>>,
+    ) -> Result<DateTime<Utc>, GatewardenError> {
+        let parsed_sig = parse_signature_header(&self.signature)?;
+        let signing_string = build_signing_string_for(
+            &parsed_sig.headers,
+            "post",
+            &self.request_path,
+            &self.host,
+            &self.date,
+            self.digest.as_deref(),
+        )
+        .map_err(|_| GatewardenError::CacheTampered)?;
+
+        keys.into()
+            .verify(&parsed_sig, &signing_string)
+            .map_err(tampered_unless_config)?;
+
+        parse_rfc2822_date(&self.date)
+    }
+}
+
+/// Persisted high-water mark of verified server time.
+#[derive(Debug)]
+pub struct HighWaterMark {
+    path: Option<PathBuf>,
+    latest: Mutex<Option<TimeProof>>,
+}
+
+impl HighWaterMark {
+    /// Mark stored in a cache namespace.
+    ///
+    /// Stored at `dirs::data_dir()/<namespace>/time_high_water.json`.
+    pub fn for_namespace(namespace: &str) -> Self {
+        Self::with_path(dirs::data_dir().map(|d| d.join(namespace).join(HIGH_WATER_FILE)))
+    }
+
+    /// Mark stored at a specific path, or kept in memory only if `None`.
+    pub fn with_path(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            latest: Mutex::new(None),
+        }
+    }
+
+    /// Whether the mark survives restarts.
+    pub fn is_persistent(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Current mark, re-verified against Keygen's public key.
+    ///
+    /// Returns `Ok(None)` if no mark has been recorded.
+    pub fn read<'a>(
+        &self,
+        keys: impl Into<KeyRing<'a>>,
+    ) -> Result<Option<DateTime<Utc>>, GatewardenError> {
+        match self.proof()? {
+            Some(proof) => proof.verify(keys).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Raise the mark to `proof` if it is verified and newer.
+    ///
+    /// Best-effort: a failed write leaves the previous mark in place.
+    pub fn advance<'a>(&self, proof: TimeProof, keys: impl Into<KeyRing<'a>>) {
+        let keys = keys.into();
+        let Ok(candidate) = proof.verify(keys) else {
+            return;
+        };
+        if matches!(self.read(keys), Ok(Some(current)) if current >= candidate) {
+            return;
+        }
+
+        if let Some(path) = &self.path {
+            if let Ok(json) = serde_json::to_string_pretty(&proof) {
+                if let Err(_e) = fs::write(path, json) {
+                    trace_debug!(error = %_e, "failed to persist time high-water mark");
+                }
+            }
+        }
+        if let Ok(mut latest) = self.latest.lock() {
+            *latest = Some(proof);
+        }
+    }
+
+    /// Reject `now` if it is earlier than the mark or the record's own
+    /// signed date, beyond [`ROLLBACK_TOLERANCE_SECONDS`].
+    ///
+    /// # Errors
+    /// - `ClockRollback` - The clock was set back
+    /// - `CacheTampered` - The persisted mark failed verification
+    pub fn check<'a>(
+        &self,
+        record: &CacheRecord,
+        now: DateTime<Utc>,
+        keys: impl Into<KeyRing<'a>>,
+    ) -> Result<(), GatewardenError> {
+        let keys = keys.into();
+        let record_date = TimeProof::from_record(record).verify(keys)?;
+        let mark = match self.read(keys)? {
+            Some(mark) => mark.max(record_date),
+            None => record_date,
+        };
+
+        if now < mark - Duration::seconds(ROLLBACK_TOLERANCE_SECONDS) {
+            return Err(GatewardenError::ClockRollback {
+                high_water_mark: mark,
+            });
+        }
+        Ok(())
+    }
+
+    /// Latest proof: the in-memory copy, else the persisted file.
+    fn proof(&self) -> Result<Option<TimeProof>, GatewardenError> {
+        if let Ok(latest) = self.latest.lock() {
+            if latest.is_some() {
+                return Ok(latest.clone());
+            }
+        }
+
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map(Some)
+                .map_err(|_| GatewardenError::CacheTampered),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(GatewardenError::CacheIO {
+                message: "Failed to read time high-water mark".to_string(),
+                source: Some(e.into()),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{signed_test_record, TEST_PUBLIC_KEY_HEX};
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn signed_record(date: &str) -> CacheRecord {
+        signed_test_record(
+            r#"{"meta":{"valid":true,"code":"VALID"}}"#,
+            date,
+            Utc::now(),
+        )
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rollback_below_mark_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HIGH_WATER_FILE);
+        let old = signed_record("Wed, 15 Jan 2025 12:00:00 GMT");
+        let newer = signed_record("Mon, 20 Jan 2025 12:00:00 GMT");
+
+        HighWaterMark::with_path(Some(path.clone()))
+            .advance(TimeProof::from_record(&newer), TEST_PUBLIC_KEY_HEX);
+
+        // A fresh handle reads the persisted mark
+        let mark = HighWaterMark::with_path(Some(path));
+        assert_eq!(mark.read(TEST_PUBLIC_KEY_HEX).unwrap(), Some(at(20, 12)));
+        assert!(matches!(
+            mark.check(&old, at(16, 12), TEST_PUBLIC_KEY_HEX),
+            Err(GatewardenError::ClockRollback { .. })
+        ));
+        assert!(mark.check(&old, at(20, 13), TEST_PUBLIC_KEY_HEX).is_ok());
+    }
+
+    #[test]
+    fn test_record_date_is_a_mark() {
+        let mark = HighWaterMark::with_path(None);
+        let record = signed_record("Wed, 15 Jan 2025 12:00:00 GMT");
+
+        assert!(matches!(
+            mark.check(&record, at(15, 11), TEST_PUBLIC_KEY_HEX),
+            Err(GatewardenError::ClockRollback { .. })
+        ));
+        assert!(mark.check(&record, at(15, 12), TEST_PUBLIC_KEY_HEX).is_ok());
+    }
+
+    #[test]
+    fn test_mark_never_moves_back() {
+        let mark = HighWaterMark::with_path(None);
+        mark.advance(
+            TimeProof::from_record(&signed_record("Mon, 20 Jan 2025 12:00:00 GMT")),
+            TEST_PUBLIC_KEY_HEX,
+        );
+        mark.advance(
+            TimeProof::from_record(&signed_record("Wed, 15 Jan 2025 12:00:00 GMT")),
+            TEST_PUBLIC_KEY_HEX,
+        );
+        assert_eq!(mark.read(TEST_PUBLIC_KEY_HEX).unwrap(), Some(at(20, 12)));
+    }
+
+    #[test]
+    fn test_mark_survives_cache_clear() {
+        use crate::cache::file::{hash_license_key, FileCache};
+
+        let temp_dir = TempDir::new().unwrap();
+        let record = signed_record("Mon, 20 Jan 2025 12:00:00 GMT");
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache.save(&hash_license_key("key"), &record).unwrap();
+        let path = temp_dir.path().join(HIGH_WATER_FILE);
+        HighWaterMark::with_path(Some(path.clone()))
+            .advance(TimeProof::from_record(&record), TEST_PUBLIC_KEY_HEX);
+
+        cache.clear().unwrap();
+        assert!(cache.load(&hash_license_key("key")).unwrap().is_none());
+        let mark = HighWaterMark::with_path(Some(path));
+        assert_eq!(mark.read(TEST_PUBLIC_KEY_HEX).unwrap(), Some(at(20, 12)));
+    }
+
+    #[test]
+    fn test_forged_mark_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(HIGH_WATER_FILE);
+        let mut proof = TimeProof::from_record(&signed_record("Wed, 15 Jan 2025 12:00:00 GMT"));
+        proof.date = "Sat, 01 Jan 2000 00:00:00 GMT".to_string();
+        fs::write(&path, serde_json::to_string(&proof).unwrap()).unwrap();
+
+        let mark = HighWaterMark::with_path(Some(path));
+        assert!(matches!(
+            mark.read(TEST_PUBLIC_KEY_HEX),
+            Err(GatewardenError::CacheTampered)
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+//! License key storage.
+//!
+//! The cache never stores raw license keys; records are keyed by hash.
+//! Applications that want gatewarden to remember the user's key between
+//! runs use a [`KeyStore`] instead. A key store also holds the license
+//! token from `LicenseManager::exchange_license_token`, if one was issued.
+
+use crate::GatewardenError;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Persistent storage for the user's license key.
+pub trait KeyStore: Send + Sync {
+    /// Store the license key, replacing any existing one.
+    fn save_license_key(&self, license_key: &str) -> Result<(), GatewardenError>;
+
+    /// Load the stored license key, if any.
+    fn load_license_key(&self) -> Result<Option<String>, GatewardenError>;
+
+    /// Delete the stored license key. Deleting a missing key is not an error.
+    fn delete_license_key(&self) -> Result<(), GatewardenError>;
+
+    /// Store a serialized license token, replacing any existing one.
+    ///
+    /// The default implementation fails with `ConfigError`; stores that
+    /// can hold secrets should override all three token methods.
+    fn save_license_token(&self, _token: &str) -> Result<(), GatewardenError> {
+        Err(GatewardenError::ConfigError(
+            "Key store does not support license tokens".to_string(),
+        ))
+    }
+
+    /// Load the stored license token, if any.
+    fn load_license_token(&self) -> Result<Option<String>, GatewardenError> {
+        Ok(None)
+    }
+
+    /// Delete the stored license token. Deleting a missing token is not an error.
+    fn delete_license_token(&self) -> Result<(), GatewardenError> {
+        Ok(())
+    }
+}
+
+/// File-based key store with owner-only permissions on Unix.
+pub struct FileKeyStore {
+    path: PathBuf,
+}
+
+impl FileKeyStore {
+    /// Create a key store with the given namespace.
+    ///
+    /// The key is stored at `dirs::data_dir()/<namespace>/license.key`,
+    /// and any license token next to it in `license.token`.
+    pub fn new(namespace: &str) -> Result<Self, GatewardenError> {
+        let base_dir = dirs::data_dir().ok_or_else(|| GatewardenError::CacheIO {
+            message: "Could not find data directory".to_string(),
+            source: None,
+        })?;
+        Self::with_path(base_dir.join(namespace).join("license.key"))
+    }
+
+    /// Create a key store at a specific file path.
+    ///
+    /// The license token is stored alongside, with a `.token` extension.
+    pub fn with_path(path: PathBuf) -> Result<Self, GatewardenError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to create key store dir".to_string(),
+                source: Some(e.into()),
+            })?;
+        }
+        Ok(Self { path })
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn save_license_key(&self, license_key: &str) -> Result<(), GatewardenError> {
+        write_secret(&self.path, license_key)
+    }
+
+    fn load_license_key(&self) -> Result<Option<String>, GatewardenError> {
+        read_secret(&self.path)
+    }
+
+    fn delete_license_key(&self) -> Result<(), GatewardenError> {
+        delete_secret(&self.path)
+    }
+
+    fn save_license_token(&self, token: &str) -> Result<(), GatewardenError> {
+        write_secret(&self.path.with_extension("token"), token)
+    }
+
+    fn load_license_token(&self) -> Result<Option<String>, GatewardenError> {
+        read_secret(&self.path.with_extension("token"))
+    }
+
+    fn delete_license_token(&self) -> Result<(), GatewardenError> {
+        delete_secret(&self.path.with_extension("token"))
+    }
+}
+
+/// Atomically write a secret with owner-only permissions where supported.
+///
+/// On Unix the temp file is created with mode `0600`, so the secret is
+/// never readable by others, not even between the write and a chmod.
+fn write_secret(path: &Path, secret: &str) -> Result<(), GatewardenError> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    // A temp file left by an interrupted write keeps its old mode
+    delete_secret(&temp_path)?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&temp_path)
+        .and_then(|mut file| file.write_all(secret.as_bytes()))
+        .map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to write key store".to_string(),
+            source: Some(e.into()),
+        })?;
+
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to replace key store".to_string(),
+            source: Some(e.into()),
+        })?;
+    }
+    fs::rename(&temp_path, path).map_err(|e| GatewardenError::CacheIO {
+        message: "Failed to rename key store".to_string(),
+        source: Some(e.into()),
+    })
+}
+
+/// Read a secret, treating a missing or blank file as absent.
+fn read_secret(path: &Path) -> Result<Option<String>, GatewardenError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let secret = fs::read_to_string(path).map_err(|e| GatewardenError::CacheIO {
+        message: "Failed to read key store".to_string(),
+        source: Some(e.into()),
+    })?;
+    let secret = secret.trim();
+    Ok((!secret.is_empty()).then(|| secret.to_string()))
+}
+
+/// Delete a secret. Deleting a missing file is not an error.
+fn delete_secret(path: &Path) -> Result<(), GatewardenError> {
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to delete key store".to_string(),
+            source: Some(e.into()),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_key_store_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileKeyStore::with_path(temp_dir.path().join("license.key")).unwrap();
+
+        assert!(store.load_license_key().unwrap().is_none());
+
+        store.save_license_key("KEY-1234").unwrap();
+        assert_eq!(
+            store.load_license_key().unwrap().as_deref(),
+            Some("KEY-1234")
+        );
+
+        store.save_license_token("activ-5678").unwrap();
+        assert_eq!(
+            store.load_license_token().unwrap().as_deref(),
+            Some("activ-5678")
+        );
+
+        store.delete_license_key().unwrap();
+        assert!(store.load_license_key().unwrap().is_none());
+        assert!(store.load_license_token().unwrap().is_some());
+
+        store.delete_license_token().unwrap();
+        assert!(store.load_license_token().unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_key_store_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("license.key");
+        let store = FileKeyStore::with_path(path.clone()).unwrap();
+
+        // A stale world-readable temp file is not reused
+        fs::write(temp_dir.path().join("license.key.tmp"), "stale").unwrap();
+        store.save_license_key("KEY-1234").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!temp_dir.path().join("license.key.tmp").exists());
+    }
+}
@@ -1,4 +1,12 @@
 //! Authenticated license cache.
 
+pub mod backend;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod file;
 pub mod format;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod high_water;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod keystore;
+#[cfg(all(windows, feature = "windows-registry"))]
+pub mod registry;
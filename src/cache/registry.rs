@@ -0,0 +1,354 @@
+//! Windows registry cache backend with DPAPI-protected values.
+//!
+//! Stores authenticated cache records under
+//! `HKEY_CURRENT_USER\Software\<namespace>\Cache` and, optionally, the
+//! license key itself as `HKEY_CURRENT_USER\Software\<namespace>\LicenseKey`
+//! (and any license token as `LicenseToken` beside it).
+//!
+//! Every value is encrypted with DPAPI (`CryptProtectData`) scoped to the
+//! current user before it is written. DPAPI only adds confidentiality;
+//! cache records are still signature-verified on load like any other backend.
+//!
+//! Requires the `windows-registry` feature and a Windows target.
+
+use crate::cache::backend::CacheBackend;
+use crate::cache::format::CacheRecord;
+use crate::cache::keystore::KeyStore;
+use crate::GatewardenError;
+use std::ptr;
+use windows_sys::Win32::Foundation::{LocalFree, ERROR_FILE_NOT_FOUND, ERROR_SUCCESS, WIN32_ERROR};
+use windows_sys::Win32::Security::Cryptography::{
+    CryptProtectData, CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+};
+use windows_sys::Win32::System::Registry::{
+    RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegDeleteValueW, RegQueryValueExW,
+    RegSetValueExW, HKEY, HKEY_CURRENT_USER, KEY_ALL_ACCESS, REG_BINARY, REG_OPTION_NON_VOLATILE,
+};
+
+/// Name of the registry value holding the stored license key.
+const LICENSE_KEY_VALUE: &str = "LicenseKey";
+
+/// Name of the registry value holding the stored license token.
+const LICENSE_TOKEN_VALUE: &str = "LicenseToken";
+
+/// Subkey (relative to the namespace key) holding cache records.
+const CACHE_SUBKEY: &str = "Cache";
+
+/// Registry-backed cache for GPO-managed Windows deployments.
+pub struct RegistryCache {
+    /// Key path relative to `HKEY_CURRENT_USER`.
+    key_path: String,
+}
+
+impl RegistryCache {
+    /// Create a registry cache with the given namespace.
+    ///
+    /// Values are stored under `HKEY_CURRENT_USER\Software\<namespace>`.
+    pub fn new(namespace: &str) -> Result<Self, GatewardenError> {
+        let cache = Self {
+            key_path: format!("Software\\{}", namespace),
+        };
+
+        // Ensure the key exists so permission problems surface early.
+        let key = RegKey::create(&cache.cache_key_path())?;
+        drop(key);
+
+        Ok(cache)
+    }
+
+    /// Full key path for cache records.
+    fn cache_key_path(&self) -> String {
+        format!("{}\\{}", self.key_path, CACHE_SUBKEY)
+    }
+
+    /// Encrypt and store a secret string under the namespace key.
+    fn save_secret(&self, name: &str, secret: &str) -> Result<(), GatewardenError> {
+        let key = RegKey::create(&self.key_path)?;
+        let encrypted = dpapi_protect(secret.as_bytes())?;
+        key.set_binary(name, &encrypted)
+    }
+
+    /// Load and decrypt a secret string, if present.
+    fn load_secret(&self, name: &str) -> Result<Option<String>, GatewardenError> {
+        let key = RegKey::create(&self.key_path)?;
+        let Some(encrypted) = key.get_binary(name)? else {
+            return Ok(None);
+        };
+
+        let plain = dpapi_unprotect(&encrypted)?;
+        String::from_utf8(plain)
+            .map(Some)
+            .map_err(|_| GatewardenError::CacheTampered)
+    }
+
+    /// Delete a secret. Deleting a missing value is not an error.
+    fn delete_secret(&self, name: &str) -> Result<(), GatewardenError> {
+        let key = RegKey::create(&self.key_path)?;
+        key.delete_value(name)
+    }
+}
+
+/// License key and token storage, DPAPI-encrypted.
+impl KeyStore for RegistryCache {
+    fn save_license_key(&self, license_key: &str) -> Result<(), GatewardenError> {
+        self.save_secret(LICENSE_KEY_VALUE, license_key)
+    }
+
+    fn load_license_key(&self) -> Result<Option<String>, GatewardenError> {
+        self.load_secret(LICENSE_KEY_VALUE)
+    }
+
+    fn delete_license_key(&self) -> Result<(), GatewardenError> {
+        self.delete_secret(LICENSE_KEY_VALUE)
+    }
+
+    fn save_license_token(&self, token: &str) -> Result<(), GatewardenError> {
+        self.save_secret(LICENSE_TOKEN_VALUE, token)
+    }
+
+    fn load_license_token(&self) -> Result<Option<String>, GatewardenError> {
+        self.load_secret(LICENSE_TOKEN_VALUE)
+    }
+
+    fn delete_license_token(&self) -> Result<(), GatewardenError> {
+        self.delete_secret(LICENSE_TOKEN_VALUE)
+    }
+}
+
+impl CacheBackend for RegistryCache {
+    fn save(&self, license_key_hash: &str, record: &CacheRecord) -> Result<(), GatewardenError> {
+        let key = RegKey::create(&self.cache_key_path())?;
+        let json = record.to_json()?;
+        let encrypted = dpapi_protect(json.as_bytes())?;
+        key.set_binary(value_name(license_key_hash), &encrypted)
+    }
+
+    fn load(&self, license_key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        let key = RegKey::create(&self.cache_key_path())?;
+        let Some(encrypted) = key.get_binary(value_name(license_key_hash))? else {
+            return Ok(None);
+        };
+
+        let plain = dpapi_unprotect(&encrypted)?;
+        let json = std::str::from_utf8(&plain).map_err(|_| GatewardenError::CacheTampered)?;
+        CacheRecord::from_json(json).map(Some)
+    }
+
+    fn delete(&self, license_key_hash: &str) -> Result<(), GatewardenError> {
+        let key = RegKey::create(&self.cache_key_path())?;
+        key.delete_value(value_name(license_key_hash))
+    }
+
+    fn clear(&self) -> Result<(), GatewardenError> {
+        let parent = RegKey::create(&self.key_path)?;
+        let subkey = wide(CACHE_SUBKEY);
+        // SAFETY: `parent` is an open key and `subkey` is NUL-terminated.
+        let status = unsafe { RegDeleteTreeW(parent.0, subkey.as_ptr()) };
+        check_status(status, "Failed to clear registry cache")
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+}
+
+/// Use first 16 chars of hash as value name to avoid exposing full key.
+fn value_name(license_key_hash: &str) -> &str {
+    &license_key_hash[..16.min(license_key_hash.len())]
+}
+
+/// Encode a string as a NUL-terminated UTF-16 buffer.
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Map a registry status code to a `CacheIO` error.
+fn check_status(status: WIN32_ERROR, context: &str) -> Result<(), GatewardenError> {
+    if status == ERROR_SUCCESS || status == ERROR_FILE_NOT_FOUND {
+        Ok(())
+    } else {
+        Err(GatewardenError::CacheIO {
+            message: format!("{} (error {})", context, status),
+            source: None,
+        })
+    }
+}
+
+/// Owned registry key handle, closed on drop.
+struct RegKey(HKEY);
+
+impl RegKey {
+    /// Open (creating if needed) a key under `HKEY_CURRENT_USER`.
+    fn create(path: &str) -> Result<Self, GatewardenError> {
+        let path = wide(path);
+        let mut hkey: HKEY = ptr::null_mut();
+        // SAFETY: all pointers are valid for the duration of the call.
+        let status = unsafe {
+            RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                path.as_ptr(),
+                0,
+                ptr::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_ALL_ACCESS,
+                ptr::null(),
+                &mut hkey,
+                ptr::null_mut(),
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return Err(GatewardenError::CacheIO {
+                message: format!("Failed to open registry key (error {})", status),
+                source: None,
+            });
+        }
+        Ok(Self(hkey))
+    }
+
+    fn set_binary(&self, name: &str, data: &[u8]) -> Result<(), GatewardenError> {
+        let name = wide(name);
+        let len = u32::try_from(data.len()).map_err(|_| GatewardenError::CacheIO {
+            message: "Registry value too large".to_string(),
+            source: None,
+        })?;
+        // SAFETY: `data` is valid for `len` bytes and `name` is NUL-terminated.
+        let status =
+            unsafe { RegSetValueExW(self.0, name.as_ptr(), 0, REG_BINARY, data.as_ptr(), len) };
+        if status != ERROR_SUCCESS {
+            return Err(GatewardenError::CacheIO {
+                message: format!("Failed to write registry value (error {})", status),
+                source: None,
+            });
+        }
+        Ok(())
+    }
+
+    fn get_binary(&self, name: &str) -> Result<Option<Vec<u8>>, GatewardenError> {
+        let name = wide(name);
+        let mut len: u32 = 0;
+        // SAFETY: a null data pointer queries the required size.
+        let status = unsafe {
+            RegQueryValueExW(
+                self.0,
+                name.as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut len,
+            )
+        };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        if status != ERROR_SUCCESS {
+            return Err(GatewardenError::CacheIO {
+                message: format!("Failed to read registry value (error {})", status),
+                source: None,
+            });
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        // SAFETY: `buf` has room for `len` bytes.
+        let status = unsafe {
+            RegQueryValueExW(
+                self.0,
+                name.as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+                buf.as_mut_ptr(),
+                &mut len,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return Err(GatewardenError::CacheIO {
+                message: format!("Failed to read registry value (error {})", status),
+                source: None,
+            });
+        }
+        buf.truncate(len as usize);
+        Ok(Some(buf))
+    }
+
+    fn delete_value(&self, name: &str) -> Result<(), GatewardenError> {
+        let name = wide(name);
+        // SAFETY: `name` is NUL-terminated.
+        let status = unsafe { RegDeleteValueW(self.0, name.as_ptr()) };
+        check_status(status, "Failed to delete registry value")
+    }
+}
+
+impl Drop for RegKey {
+    fn drop(&mut self) {
+        // SAFETY: the handle was opened by `RegCreateKeyExW` and is closed once.
+        unsafe {
+            RegCloseKey(self.0);
+        }
+    }
+}
+
+/// Encrypt bytes with DPAPI for the current user.
+fn dpapi_protect(data: &[u8]) -> Result<Vec<u8>, GatewardenError> {
+    dpapi_call(data, true)
+}
+
+/// Decrypt DPAPI-protected bytes. Failure indicates tampering or a foreign user.
+fn dpapi_unprotect(data: &[u8]) -> Result<Vec<u8>, GatewardenError> {
+    dpapi_call(data, false)
+}
+
+fn dpapi_call(data: &[u8], protect: bool) -> Result<Vec<u8>, GatewardenError> {
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: u32::try_from(data.len()).map_err(|_| GatewardenError::CacheIO {
+            message: "DPAPI input too large".to_string(),
+            source: None,
+        })?,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+
+    // SAFETY: `input` borrows `data` for the call; DPAPI allocates `output`,
+    // which is freed with `LocalFree` below.
+    let ok = unsafe {
+        if protect {
+            CryptProtectData(
+                &input,
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                CRYPTPROTECT_UI_FORBIDDEN,
+                &mut output,
+            )
+        } else {
+            CryptUnprotectData(
+                &input,
+                ptr::null_mut(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                CRYPTPROTECT_UI_FORBIDDEN,
+                &mut output,
+            )
+        }
+    };
+
+    if ok == 0 {
+        return Err(if protect {
+            GatewardenError::CacheIO {
+                message: "DPAPI encryption failed".to_string(),
+                source: None,
+            }
+        } else {
+            GatewardenError::CacheTampered
+        });
+    }
+
+    // SAFETY: on success DPAPI returns a valid buffer of `cbData` bytes.
+    let bytes =
+        unsafe { std::slice::from_raw_parts(output.pbData, output.cbData as usize) }.to_vec();
+    unsafe {
+        LocalFree(output.pbData as _);
+    }
+
+    Ok(bytes)
+}
@@ -0,0 +1,298 @@
+//! Fault injection for resilience testing (`test-seams` only).
+//!
+//! A [`FaultInjector`] is shared between a test and a [`LicenseManager`]
+//! (see `LicenseManager::with_fault_injector`). The test arms faults; the
+//! cache and client layers consume them at well-defined points:
+//!
+//! - cache writes/reads fail with `CacheIO`
+//! - requests fail with `KeygenTransport` before being sent
+//! - responses are delayed (timing out past the request's deadline)
+//!   and/or have a header corrupted after receipt
+//!
+//! [`LicenseManager`]: crate::LicenseManager
+
+use crate::cache::backend::CacheBackend;
+use crate::cache::format::CacheRecord;
+use crate::client::response::KeygenResponse;
+use crate::{GatewardenError, TransportFailure};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Header corruption applied to received responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFault {
+    /// Remove the `Keygen-Signature` header.
+    DropSignature,
+    /// Remove the `Date` header.
+    DropDate,
+    /// Replace the signature with a well-formed but wrong value.
+    CorruptSignature,
+    /// Replace the `Digest` header with a wrong value.
+    CorruptDigest,
+}
+
+/// Shared, thread-safe set of armed faults.
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    cache_write_failures: AtomicU32,
+    cache_read_failures: AtomicU32,
+    request_failures: AtomicU32,
+    response_delay: Mutex<Option<Duration>>,
+    header_fault: Mutex<Option<HeaderFault>>,
+}
+
+impl FaultInjector {
+    /// Create an injector with no faults armed.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Fail the next `n` cache writes.
+    pub fn fail_next_cache_writes(&self, n: u32) {
+        self.cache_write_failures.store(n, Ordering::SeqCst);
+    }
+
+    /// Fail the next `n` cache reads.
+    pub fn fail_next_cache_reads(&self, n: u32) {
+        self.cache_read_failures.store(n, Ordering::SeqCst);
+    }
+
+    /// Fail the next `n` Keygen requests with a transport error.
+    pub fn fail_next_requests(&self, n: u32) {
+        self.request_failures.store(n, Ordering::SeqCst);
+    }
+
+    /// Delay every response by `delay` (`None` to disable).
+    pub fn delay_responses(&self, delay: Option<Duration>) {
+        if let Ok(mut guard) = self.response_delay.lock() {
+            *guard = delay;
+        }
+    }
+
+    /// Corrupt a header on every response (`None` to disable).
+    pub fn corrupt_header(&self, fault: Option<HeaderFault>) {
+        if let Ok(mut guard) = self.header_fault.lock() {
+            *guard = fault;
+        }
+    }
+
+    /// Disarm all faults.
+    pub fn reset(&self) {
+        self.fail_next_cache_writes(0);
+        self.fail_next_cache_reads(0);
+        self.fail_next_requests(0);
+        self.delay_responses(None);
+        self.corrupt_header(None);
+    }
+
+    /// Consume one armed failure from a counter, if any.
+    fn take(counter: &AtomicU32) -> bool {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Client hook: called before a request is sent.
+    pub(crate) fn before_request(&self) -> Result<(), GatewardenError> {
+        if Self::take(&self.request_failures) {
+            return Err(GatewardenError::KeygenTransport {
+                message: "Injected request failure".to_string(),
+                kind: TransportFailure::Connect,
+                source: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Client hook: called after a response is received.
+    ///
+    /// A delay longer than the request's `deadline` times out at the
+    /// deadline, as a slow Keygen would.
+    pub(crate) fn after_response(
+        &self,
+        response: &mut KeygenResponse,
+        deadline: Option<Duration>,
+    ) -> Result<(), GatewardenError> {
+        let delay = self.response_delay.lock().ok().and_then(|g| *g);
+        if let Some(delay) = delay {
+            match deadline {
+                Some(deadline) if deadline < delay => {
+                    std::thread::sleep(deadline);
+                    return Err(GatewardenError::KeygenTransport {
+                        message: "Injected delay exceeded the deadline".to_string(),
+                        kind: TransportFailure::Timeout,
+                        source: None,
+                    });
+                }
+                _ => std::thread::sleep(delay),
+            }
+        }
+
+        let fault = self.header_fault.lock().ok().and_then(|g| *g);
+        match fault {
+            Some(HeaderFault::DropSignature) => response.signature = None,
+            Some(HeaderFault::DropDate) => response.date = None,
+            Some(HeaderFault::CorruptSignature) => {
+                response.signature = Some(format!(
+                    r#"algorithm="ed25519", signature="{}""#,
+                    "A".repeat(86) + "=="
+                ));
+            }
+            Some(HeaderFault::CorruptDigest) => {
+                response.digest =
+                    Some("sha-256=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".to_string());
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Cache backend decorator that consumes cache faults.
+pub(crate) struct FaultyCache {
+    inner: Arc<dyn CacheBackend>,
+    faults: Arc<FaultInjector>,
+}
+
+impl FaultyCache {
+    pub(crate) fn new(inner: Arc<dyn CacheBackend>, faults: Arc<FaultInjector>) -> Self {
+        Self { inner, faults }
+    }
+}
+
+impl CacheBackend for FaultyCache {
+    fn save(&self, license_key_hash: &str, record: &CacheRecord) -> Result<(), GatewardenError> {
+        if FaultInjector::take(&self.faults.cache_write_failures) {
+            return Err(GatewardenError::CacheIO {
+                message: "Injected cache write failure".to_string(),
+                source: None,
+            });
+        }
+        self.inner.save(license_key_hash, record)
+    }
+
+    fn load(&self, license_key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        if FaultInjector::take(&self.faults.cache_read_failures) {
+            return Err(GatewardenError::CacheIO {
+                message: "Injected cache read failure".to_string(),
+                source: None,
+            });
+        }
+        self.inner.load(license_key_hash)
+    }
+
+    fn delete(&self, license_key_hash: &str) -> Result<(), GatewardenError> {
+        self.inner.delete(license_key_hash)
+    }
+
+    fn clear(&self) -> Result<(), GatewardenError> {
+        self.inner.clear()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.inner.is_encrypted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::file::FileCache;
+    use crate::clock::MockClock;
+    use chrono::{TimeZone, Utc};
+    use tempfile::TempDir;
+
+    fn make_record() -> CacheRecord {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        CacheRecord::new(
+            "Wed, 15 Jan 2025 12:00:00 GMT".to_string(),
+            r#"algorithm="ed25519", signature="test""#.to_string(),
+            None,
+            "{}".to_string(),
+            "/v1/test".to_string(),
+            "api.keygen.sh".to_string(),
+            &clock,
+        )
+    }
+
+    fn make_response() -> KeygenResponse {
+        KeygenResponse {
+            status: 200,
+            date: Some("Wed, 15 Jan 2025 12:00:00 GMT".to_string()),
+            signature: Some(r#"algorithm="ed25519", signature="x""#.to_string()),
+            digest: Some("sha-256=abc".to_string()),
+            body: b"{}".to_vec(),
+            method: "post".to_string(),
+            request_path: "/v1/test".to_string(),
+            host: "api.keygen.sh".to_string(),
+            request_id: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_fail_next_cache_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let faults = FaultInjector::new();
+        let cache = FaultyCache::new(
+            Arc::new(FileCache::with_path(temp_dir.path().to_path_buf()).unwrap()),
+            faults.clone(),
+        );
+
+        faults.fail_next_cache_writes(2);
+        let record = make_record();
+        assert!(matches!(
+            cache.save("abcdef0123456789", &record),
+            Err(GatewardenError::CacheIO { .. })
+        ));
+        assert!(cache.save("abcdef0123456789", &record).is_err());
+        assert!(cache.save("abcdef0123456789", &record).is_ok());
+    }
+
+    #[test]
+    fn test_fail_next_requests() {
+        let faults = FaultInjector::new();
+        faults.fail_next_requests(1);
+        assert!(matches!(
+            faults.before_request(),
+            Err(GatewardenError::KeygenTransport { .. })
+        ));
+        assert!(faults.before_request().is_ok());
+    }
+
+    #[test]
+    fn test_header_faults() {
+        let faults = FaultInjector::new();
+
+        faults.corrupt_header(Some(HeaderFault::DropSignature));
+        let mut response = make_response();
+        faults.after_response(&mut response, None).unwrap();
+        assert!(response.signature.is_none());
+
+        faults.corrupt_header(Some(HeaderFault::CorruptDigest));
+        let mut response = make_response();
+        faults.after_response(&mut response, None).unwrap();
+        assert_ne!(response.digest.as_deref(), Some("sha-256=abc"));
+
+        faults.reset();
+        let mut response = make_response();
+        faults.after_response(&mut response, None).unwrap();
+        assert!(response.signature.is_some());
+    }
+
+    #[test]
+    fn test_delay_past_deadline_times_out() {
+        let faults = FaultInjector::new();
+        faults.delay_responses(Some(Duration::from_secs(5)));
+
+        let mut response = make_response();
+        assert!(matches!(
+            faults.after_response(&mut response, Some(Duration::from_millis(10))),
+            Err(GatewardenError::KeygenTransport {
+                kind: TransportFailure::Timeout,
+                ..
+            })
+        ));
+    }
+}
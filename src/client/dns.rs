@@ -0,0 +1,94 @@
+//! Address resolution for the Keygen HTTP client.
+
+use crate::GatewardenError;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::blocking::ClientBuilder;
+use std::net::IpAddr;
+#[cfg(not(target_arch = "wasm32"))]
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// How the Keygen client finds `api.keygen.sh`.
+///
+/// For locked-down networks where default resolution fails: restrict
+/// connections to one address family, or pin Keygen to a known address.
+/// Requests still name `api.keygen.sh` in the `Host` header and TLS
+/// server name, so certificates and response signatures verify as usual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IpStrategy {
+    /// Resolve with the system resolver and connect over either family.
+    #[default]
+    System,
+
+    /// Connect over IPv4 only, ignoring IPv6 addresses.
+    Ipv4Only,
+
+    /// Connect over IPv6 only, ignoring IPv4 addresses.
+    Ipv6Only,
+
+    /// Skip resolution and connect to this address on port 443.
+    Static(IpAddr),
+}
+
+impl IpStrategy {
+    /// Check that this strategy is supported by the platform.
+    ///
+    /// # Errors
+    /// - `ConfigError` - A strategy other than `System` on `wasm32`, or a
+    ///   static address that cannot be connected to (unspecified or
+    ///   multicast)
+    pub fn validate(&self) -> Result<(), GatewardenError> {
+        if *self != Self::System && cfg!(target_arch = "wasm32") {
+            return Err(GatewardenError::ConfigError(
+                "ip_strategy is not supported on wasm32; the browser resolves hosts".to_string(),
+            ));
+        }
+        if let Self::Static(ip) = self {
+            if ip.is_unspecified() || ip.is_multicast() {
+                return Err(GatewardenError::ConfigError(format!(
+                    "ip_strategy static address {} cannot be connected to",
+                    ip
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply this strategy to a client builder for requests to `host`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn apply(&self, builder: ClientBuilder, host: &str) -> ClientBuilder {
+        // Binding the unspecified address of a family makes the connector
+        // skip resolved addresses of the other family
+        match self {
+            Self::System => builder,
+            Self::Ipv4Only => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            Self::Ipv6Only => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            Self::Static(ip) => builder.resolve(host, SocketAddr::new(*ip, 443)),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use reqwest::blocking::Client;
+
+    #[test]
+    fn test_apply_ip_strategy() {
+        for strategy in [
+            IpStrategy::System,
+            IpStrategy::Ipv4Only,
+            IpStrategy::Ipv6Only,
+            IpStrategy::Static(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10))),
+        ] {
+            assert!(strategy.validate().is_ok());
+            let builder = strategy.apply(Client::builder(), "api.keygen.sh");
+            assert!(builder.build().is_ok());
+        }
+
+        let unspecified = IpStrategy::Static(IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+        assert!(matches!(
+            unspecified.validate(),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+}
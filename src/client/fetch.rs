@@ -0,0 +1,180 @@
+//! Async Keygen client, fetch-based on `wasm32`.
+//!
+//! On `wasm32` targets reqwest sends requests through the browser's
+//! `fetch` API; elsewhere it needs a Tokio runtime. Responses are captured
+//! exactly as by the blocking client, so they go through the same
+//! verification pipeline.
+//!
+//! In a browser, Keygen's CORS response must expose the `Date`,
+//! `Keygen-Signature`, and `Digest` headers to the page. If they are
+//! hidden, verification fails closed with `SignatureMissing`.
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::client::request::build_user_agent;
+use crate::client::request::{validate_key_path, zeroize_body, LicenseCredential, KEYGEN_HOST};
+use crate::client::response::{KeygenResponse, RateLimit};
+use crate::config::GatewardenConfig;
+use crate::crypto::digest::format_digest_header;
+#[cfg(any(test, feature = "test-seams"))]
+use crate::mock::MockKeygen;
+use crate::redact::RedactedKey;
+use crate::{GatewardenError, TransportFailure};
+use reqwest::header::{CONTENT_TYPE, DATE};
+use reqwest::{Client, Response};
+#[cfg(any(test, feature = "test-seams"))]
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Async Keygen HTTP client.
+#[derive(Clone)]
+pub struct FetchClient {
+    client: Client,
+    #[cfg(not(target_arch = "wasm32"))]
+    user_agent: String,
+    account_id: String,
+    host: String,
+    timeout: Duration,
+    #[cfg(any(test, feature = "test-seams"))]
+    mock: Option<Arc<MockKeygen>>,
+}
+
+impl FetchClient {
+    /// Create a new client from config.
+    ///
+    /// Proxy and TLS settings are left to the browser on `wasm32`.
+    pub fn new(config: &GatewardenConfig) -> Result<Self, GatewardenError> {
+        let client = Client::builder().build().map_err(|e| {
+            GatewardenError::ConfigError(format!("Failed to build HTTP client: {}", e))
+        })?;
+
+        Ok(Self {
+            client,
+            #[cfg(not(target_arch = "wasm32"))]
+            user_agent: build_user_agent(config),
+            account_id: config.account_id.to_string(),
+            host: KEYGEN_HOST.to_string(),
+            timeout: config.request_timeout,
+            #[cfg(any(test, feature = "test-seams"))]
+            mock: None,
+        })
+    }
+
+    /// Answer requests from a mock Keygen instead of the network.
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn set_mock_keygen(&mut self, mock: Arc<MockKeygen>) {
+        self.mock = Some(mock);
+    }
+
+    /// Validate a license key with entitlement scope.
+    ///
+    /// As `KeygenClient::validate_key`; the response is not verified.
+    pub async fn validate_key(
+        &self,
+        license_key: &RedactedKey,
+        scope_entitlements: &[&str],
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let mut document = LicenseCredential::Key(license_key).validation_body(scope_entitlements);
+        let body = serde_json::to_vec(&document).map_err(|e| GatewardenError::ProtocolError {
+            message: "Failed to serialize".to_string(),
+            source: Some(e.into()),
+        });
+        zeroize_body(&mut document);
+        // The serialized bytes are handed to the fetch API and cannot be
+        // cleared afterwards
+        self.post(validate_key_path(&self.account_id), body?).await
+    }
+
+    /// Send a JSON POST and capture the signed response.
+    async fn post(&self, path: String, body: Vec<u8>) -> Result<KeygenResponse, GatewardenError> {
+        #[cfg(any(test, feature = "test-seams"))]
+        if let Some(mock) = &self.mock {
+            return Ok(mock.respond("POST", &self.host, &path, &body, None));
+        }
+
+        let url = format!("https://{}{}", self.host, path);
+        let request = self
+            .client
+            .post(&url)
+            .header("Accept", "application/vnd.api+json")
+            .header(CONTENT_TYPE, "application/vnd.api+json")
+            .header("Digest", format_digest_header(&body))
+            .timeout(self.timeout)
+            .body(body);
+        // Browsers set these themselves and refuse them from scripts
+        #[cfg(not(target_arch = "wasm32"))]
+        let request = request
+            .header(reqwest::header::USER_AGENT, &self.user_agent)
+            .header(reqwest::header::HOST, &self.host);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| GatewardenError::KeygenTransport {
+                message: "Request failed".to_string(),
+                kind: transport_failure(&e),
+                source: Some(e.into()),
+            })?;
+        capture(response, path, self.host.clone()).await
+    }
+
+    /// Get the configured host.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+/// Capture the status, signed headers, and body of a POST response.
+async fn capture(
+    response: Response,
+    request_path: String,
+    host: String,
+) -> Result<KeygenResponse, GatewardenError> {
+    let status = response.status().as_u16();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let date = header(DATE.as_str());
+    let signature = header("Keygen-Signature");
+    let digest = header("Digest");
+    let request_id = header("X-Request-Id");
+    let rate_limit = RateLimit::from_headers(header);
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| GatewardenError::KeygenTransport {
+            message: "Failed to read body".to_string(),
+            kind: transport_failure(&e),
+            source: Some(e.into()),
+        })?
+        .to_vec();
+
+    Ok(KeygenResponse {
+        status,
+        date,
+        signature,
+        digest,
+        body,
+        method: "post".to_string(),
+        request_path,
+        host,
+        request_id,
+        rate_limit,
+    })
+}
+
+/// Classify a failed request; `fetch` reports little beyond timeouts.
+fn transport_failure(error: &reqwest::Error) -> TransportFailure {
+    if error.is_timeout() {
+        return TransportFailure::Timeout;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if error.is_connect() {
+        return TransportFailure::Connect;
+    }
+    TransportFailure::Other
+}
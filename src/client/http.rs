@@ -3,66 +3,67 @@
 //! This module handles the raw HTTP communication with Keygen,
 //! capturing all headers needed for signature verification.
 
+#[cfg(any(test, feature = "test-seams"))]
+use crate::chaos::FaultInjector;
+use crate::client::dns::IpStrategy;
+use crate::client::pool::ConnectionPool;
+use crate::client::proxy::ProxyConfig;
+use crate::client::request::{
+    artifact_path, group_path, increment_usage_path, license_entitlements_path, licenses_path,
+    machine_path, machine_ping_path, machines_path, release_artifacts_path, release_upgrade_path,
+    tokens_path, user_path, validate_key_path, validate_license_path, zeroize_body, Auth,
+    LicenseCredential, KEYGEN_HOST,
+};
+use crate::client::response::RateLimit;
 use crate::config::GatewardenConfig;
 use crate::crypto::digest::format_digest_header;
-use crate::GatewardenError;
-use reqwest::blocking::{Client, Response};
-use reqwest::header::{CONTENT_TYPE, DATE, HOST, USER_AGENT};
+use crate::fingerprint::Component;
+#[cfg(any(test, feature = "test-seams"))]
+use crate::mock::MockKeygen;
+use crate::redact::RedactedKey;
+use crate::{GatewardenError, TransportFailure};
+use reqwest::blocking::{Body, Client, Response};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, DATE, HOST, USER_AGENT};
+use reqwest::Method;
+use std::io::Cursor;
+#[cfg(any(test, feature = "test-seams"))]
+use std::sync::Arc;
 use std::time::Duration;
+use zeroize::Zeroizing;
 
-/// HTTP response with captured headers and body.
-#[derive(Debug)]
-pub struct KeygenResponse {
-    /// HTTP status code.
-    pub status: u16,
-
-    /// Date header value.
-    pub date: Option<String>,
-
-    /// Keygen-Signature header value.
-    pub signature: Option<String>,
-
-    /// Digest header value.
-    pub digest: Option<String>,
-
-    /// Raw response body.
-    pub body: Vec<u8>,
-
-    /// Request path used (for signing string reconstruction).
-    pub request_path: String,
-
-    /// Host used (for signing string reconstruction).
-    pub host: String,
-}
+pub use crate::client::request::build_user_agent;
+pub use crate::client::response::KeygenResponse;
 
 impl KeygenResponse {
     /// Extract headers from a reqwest Response.
     fn from_response(
         response: Response,
+        method: &str,
         request_path: String,
         host: String,
     ) -> Result<Self, GatewardenError> {
         let status = response.status().as_u16();
         let headers = response.headers().clone();
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
 
-        let date = headers
-            .get(DATE)
-            .and_then(|v| v.to_str().ok())
-            .map(String::from);
-
-        let signature = headers
-            .get("Keygen-Signature")
-            .and_then(|v| v.to_str().ok())
-            .map(String::from);
-
-        let digest = headers
-            .get("Digest")
-            .and_then(|v| v.to_str().ok())
-            .map(String::from);
+        let date = header(DATE.as_str());
+        let signature = header("Keygen-Signature");
+        let digest = header("Digest");
+        let request_id = header("X-Request-Id");
+        let rate_limit = RateLimit::from_headers(header);
 
         let body = response
             .bytes()
-            .map_err(|e| GatewardenError::KeygenTransport(format!("Failed to read body: {}", e)))?
+            .map_err(|e| GatewardenError::KeygenTransport {
+                message: "Failed to read body".to_string(),
+                kind: transport_failure(&e),
+                source: Some(e.without_url().into()),
+            })?
             .to_vec();
 
         Ok(Self {
@@ -71,36 +72,47 @@ impl KeygenResponse {
             signature,
             digest,
             body,
+            method: method.to_lowercase(),
             request_path,
             host,
+            request_id,
+            rate_limit,
         })
     }
-
-    /// Get the body as a UTF-8 string.
-    pub fn body_str(&self) -> Result<&str, GatewardenError> {
-        std::str::from_utf8(&self.body)
-            .map_err(|e| GatewardenError::ProtocolError(format!("Invalid UTF-8 in body: {}", e)))
-    }
 }
 
 /// Keygen HTTP client.
+#[derive(Clone)]
 pub struct KeygenClient {
     client: Client,
     user_agent: String,
     account_id: String,
     host: String,
     timeout: Duration,
+    connect_timeout: Duration,
+    proxy: ProxyConfig,
+    tls_pins: &'static [&'static str],
+    pool: ConnectionPool,
+    ip_strategy: IpStrategy,
+    product_scope: Option<String>,
+    policy_scope: Option<String>,
+    #[cfg(any(test, feature = "test-seams"))]
+    faults: Option<Arc<FaultInjector>>,
+    #[cfg(any(test, feature = "test-seams"))]
+    mock: Option<Arc<MockKeygen>>,
 }
 
 impl KeygenClient {
     /// Create a new Keygen client from config.
     pub fn new(config: &GatewardenConfig) -> Result<Self, GatewardenError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .map_err(|e| {
-                GatewardenError::KeygenTransport(format!("Failed to create client: {}", e))
-            })?;
+        let client = build_http_client(
+            config.request_timeout,
+            config.connect_timeout,
+            config.proxy,
+            config.tls_pins,
+            config.connection_pool,
+            config.ip_strategy,
+        )?;
 
         let user_agent = build_user_agent(config);
 
@@ -108,8 +120,50 @@ impl KeygenClient {
             client,
             user_agent,
             account_id: config.account_id.to_string(),
-            host: "api.keygen.sh".to_string(),
-            timeout: Duration::from_secs(30),
+            host: KEYGEN_HOST.to_string(),
+            timeout: config.request_timeout,
+            connect_timeout: config.connect_timeout,
+            proxy: config.proxy,
+            tls_pins: config.tls_pins,
+            pool: config.connection_pool,
+            ip_strategy: config.ip_strategy,
+            product_scope: None,
+            policy_scope: None,
+            #[cfg(any(test, feature = "test-seams"))]
+            faults: None,
+            #[cfg(any(test, feature = "test-seams"))]
+            mock: None,
+        })
+    }
+
+    /// A client for `config` that shares this client's connection pool.
+    ///
+    /// The account and User-Agent come from `config`; the mock, fault
+    /// injector and host carry over, the product and policy scope do not.
+    ///
+    /// # Errors
+    /// - `ConfigError` - `config` uses different timeouts, proxy, TLS
+    ///   pins, connection pool settings or IP strategy, which are fixed when
+    ///   the pool is built
+    pub(crate) fn for_config(&self, config: &GatewardenConfig) -> Result<Self, GatewardenError> {
+        if config.request_timeout != self.timeout
+            || config.connect_timeout != self.connect_timeout
+            || config.proxy != self.proxy
+            || config.tls_pins != self.tls_pins
+            || config.connection_pool != self.pool
+            || config.ip_strategy != self.ip_strategy
+        {
+            return Err(GatewardenError::ConfigError(format!(
+                "{} uses different transport settings than the shared HTTP client",
+                config.feature_name
+            )));
+        }
+        Ok(Self {
+            user_agent: build_user_agent(config),
+            account_id: config.account_id.to_string(),
+            product_scope: None,
+            policy_scope: None,
+            ..self.clone()
         })
     }
 
@@ -121,12 +175,31 @@ impl KeygenClient {
         Ok(client)
     }
 
+    /// Attach a fault injector (for resilience testing).
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn set_fault_injector(&mut self, faults: Arc<FaultInjector>) {
+        self.faults = Some(faults);
+    }
+
+    /// Answer requests from a mock Keygen instead of the network.
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn set_mock_keygen(&mut self, mock: Arc<MockKeygen>) {
+        self.mock = Some(mock);
+    }
+
     /// Set request timeout.
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
 
         // Best-effort rebuild; use `try_with_timeout` if you need fallible construction.
-        if let Ok(client) = Client::builder().timeout(timeout).build() {
+        if let Ok(client) = build_http_client(
+            timeout,
+            self.connect_timeout,
+            self.proxy,
+            self.tls_pins,
+            self.pool,
+            self.ip_strategy,
+        ) {
             self.client = client;
         }
         self
@@ -135,66 +208,699 @@ impl KeygenClient {
     /// Set request timeout with fallible construction.
     pub fn try_with_timeout(mut self, timeout: Duration) -> Result<Self, GatewardenError> {
         self.timeout = timeout;
-        self.client = Client::builder().timeout(timeout).build().map_err(|e| {
-            GatewardenError::ConfigError(format!("Failed to build HTTP client: {}", e))
-        })?;
+        self.client = build_http_client(
+            timeout,
+            self.connect_timeout,
+            self.proxy,
+            self.tls_pins,
+            self.pool,
+            self.ip_strategy,
+        )?;
+        Ok(self)
+    }
+
+    /// Route requests through `proxy` instead of the configured one.
+    ///
+    /// # Errors
+    /// - `ConfigError` - The proxy URL is malformed or the client cannot be built
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Self, GatewardenError> {
+        self.proxy = proxy;
+        self.client = build_http_client(
+            self.timeout,
+            self.connect_timeout,
+            proxy,
+            self.tls_pins,
+            self.pool,
+            self.ip_strategy,
+        )?;
+        Ok(self)
+    }
+
+    /// Reuse connections as configured by `pool`.
+    ///
+    /// # Errors
+    /// - `ConfigError` - The pool settings are invalid or the client cannot
+    ///   be built
+    pub fn with_connection_pool(mut self, pool: ConnectionPool) -> Result<Self, GatewardenError> {
+        pool.validate()?;
+        self.pool = pool;
+        self.client = build_http_client(
+            self.timeout,
+            self.connect_timeout,
+            self.proxy,
+            self.tls_pins,
+            pool,
+            self.ip_strategy,
+        )?;
         Ok(self)
     }
 
+    /// Resolve `api.keygen.sh` with `strategy`.
+    ///
+    /// # Errors
+    /// - `ConfigError` - The strategy is invalid or the client cannot be
+    ///   built
+    pub fn with_ip_strategy(mut self, strategy: IpStrategy) -> Result<Self, GatewardenError> {
+        strategy.validate()?;
+        self.ip_strategy = strategy;
+        self.client = build_http_client(
+            self.timeout,
+            self.connect_timeout,
+            self.proxy,
+            self.tls_pins,
+            self.pool,
+            strategy,
+        )?;
+        Ok(self)
+    }
+
+    /// Scope validations to product `product_id`.
+    ///
+    /// For accounts with several products: Keygen answers
+    /// `PRODUCT_SCOPE_MISMATCH` (`InvalidLicense`) for keys that belong to
+    /// a sibling product.
+    pub fn with_product_scope(mut self, product_id: impl Into<String>) -> Self {
+        self.product_scope = Some(product_id.into());
+        self
+    }
+
+    /// Scope validations to policy `policy_id`.
+    ///
+    /// Keygen answers `POLICY_SCOPE_MISMATCH` (`InvalidLicense`) for keys
+    /// issued under any other policy.
+    pub fn with_policy_scope(mut self, policy_id: impl Into<String>) -> Self {
+        self.policy_scope = Some(policy_id.into());
+        self
+    }
+
+    /// The product validations are scoped to, if any.
+    pub(crate) fn product_scope(&self) -> Option<&str> {
+        self.product_scope.as_deref()
+    }
+
+    /// The policy validations are scoped to, if any.
+    pub(crate) fn policy_scope(&self) -> Option<&str> {
+        self.policy_scope.as_deref()
+    }
+
+    /// Add the product and policy scope to a validation `body`.
+    fn scope_body(&self, body: &mut serde_json::Value) {
+        if let Some(product) = &self.product_scope {
+            body["meta"]["scope"]["product"] = product.as_str().into();
+        }
+        if let Some(policy) = &self.policy_scope {
+            body["meta"]["scope"]["policy"] = policy.as_str().into();
+        }
+    }
+
     /// Validate a license key with entitlement scope.
     ///
     /// The `scope_entitlements` parameter specifies which entitlements to assert.
     /// Keygen will echo these back in the response if the license has them,
-    /// enabling entitlement-based access control.
+    /// enabling entitlement-based access control. The product and policy
+    /// scope, if set, are sent along.
     pub fn validate_key(
         &self,
-        license_key: &str,
+        license_key: &RedactedKey,
         scope_entitlements: &[&str],
     ) -> Result<KeygenResponse, GatewardenError> {
-        let path = format!(
-            "/v1/accounts/{}/licenses/actions/validate-key",
-            self.account_id
-        );
+        self.validate(
+            LicenseCredential::Key(license_key),
+            scope_entitlements,
+            None,
+            None,
+        )
+    }
 
-        let url = format!("https://{}{}", self.host, path);
+    /// Validate a license by ID, authenticating with a license token.
+    ///
+    /// Calls the license's validate action with `Authorization: Bearer`,
+    /// for deployments that distribute tokens rather than raw keys.
+    /// Scoped entitlements are echoed back as for `validate_key`.
+    pub fn validate_license(
+        &self,
+        license_id: &str,
+        token: &str,
+        scope_entitlements: &[&str],
+    ) -> Result<KeygenResponse, GatewardenError> {
+        self.validate(
+            LicenseCredential::Token { license_id, token },
+            scope_entitlements,
+            None,
+            None,
+        )
+    }
 
-        // Build request body
-        // Include scope.entitlements to get entitlements echoed back in response
-        let body = if scope_entitlements.is_empty() {
-            serde_json::json!({
-                "meta": {
-                    "key": license_key
-                }
-            })
-        } else {
-            serde_json::json!({
-                "meta": {
-                    "key": license_key,
-                    "scope": {
-                        "entitlements": scope_entitlements
+    /// Validate a license key, giving up after `deadline`.
+    ///
+    /// Like `validate_key`, but the request fails with `KeygenTransport`
+    /// if it does not complete within `deadline` (instead of the client's
+    /// full timeout).
+    pub fn validate_key_within(
+        &self,
+        license_key: &RedactedKey,
+        scope_entitlements: &[&str],
+        deadline: Duration,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        self.validate(
+            LicenseCredential::Key(license_key),
+            scope_entitlements,
+            None,
+            Some(deadline),
+        )
+    }
+
+    /// Validate by key or token, scoped to `user` (an email or user ID) if
+    /// given; `deadline` overrides the client timeout.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn validate(
+        &self,
+        credential: LicenseCredential<'_>,
+        scope_entitlements: &[&str],
+        user: Option<&str>,
+        deadline: Option<Duration>,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let (path, auth) = match credential {
+            LicenseCredential::Key(_) => (validate_key_path(&self.account_id), None),
+            LicenseCredential::Token { license_id, token } => (
+                validate_license_path(&self.account_id, license_id),
+                Some(Auth::Bearer(token)),
+            ),
+        };
+
+        let mut body = credential.validation_body(scope_entitlements);
+        if let Some(user) = user {
+            body["meta"]["scope"]["user"] = user.into();
+        }
+        self.scope_body(&mut body);
+        let response = self.post(path, &body, auth, deadline);
+        zeroize_body(&mut body);
+        response
+    }
+
+    /// Exchange a license key for a license token.
+    ///
+    /// Authenticates with the license key itself (`Authorization: License`).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "create-token"),
+            err(level = "warn")
+        )
+    )]
+    pub fn create_token(
+        &self,
+        license_key: &RedactedKey,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let body = serde_json::json!({
+            "data": {
+                "type": "tokens"
+            }
+        });
+
+        self.post(
+            tokens_path(&self.account_id),
+            &body,
+            Some(Auth::License(license_key)),
+            None,
+        )
+    }
+
+    /// Create a trial license under `policy_id`, marked `"trial": true` in
+    /// its metadata.
+    ///
+    /// Authenticates as a Keygen user (`Authorization: Bearer`); Keygen
+    /// only lets users create licenses for unprotected policies.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "create-license"),
+            err(level = "warn")
+        )
+    )]
+    pub fn create_trial_license(
+        &self,
+        policy_id: &str,
+        user_token: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let body = serde_json::json!({
+            "data": {
+                "type": "licenses",
+                "attributes": {
+                    "metadata": { "trial": true }
+                },
+                "relationships": {
+                    "policy": {
+                        "data": { "type": "policies", "id": policy_id }
                     }
                 }
+            }
+        });
+
+        self.post(
+            licenses_path(&self.account_id),
+            &body,
+            Some(Auth::Bearer(user_token)),
+            None,
+        )
+    }
+
+    /// Report `increment` uses of a license to Keygen.
+    ///
+    /// Authenticates with the license key itself (`Authorization: License`).
+    pub fn increment_usage(
+        &self,
+        license_key: &RedactedKey,
+        license_id: &str,
+        increment: u64,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        self.increment_usage_as(Auth::License(license_key), license_id, increment)
+    }
+
+    /// Report uses of a license, authenticating with `auth`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "increment-usage"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn increment_usage_as(
+        &self,
+        auth: Auth<'_>,
+        license_id: &str,
+        increment: u64,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = increment_usage_path(&self.account_id, license_id);
+        let body = serde_json::json!({
+            "meta": {
+                "increment": increment
+            }
+        });
+
+        self.post(path, &body, Some(auth), None)
+    }
+
+    /// Fetch one page (1-based) of a license's entitlements.
+    ///
+    /// Authenticates with the license key itself (`Authorization: License`).
+    pub fn list_entitlements(
+        &self,
+        license_key: &RedactedKey,
+        page: u32,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        self.list_entitlements_as(Auth::License(license_key), license_key.expose(), page, None)
+    }
+
+    /// Fetch a page of the entitlements of `license` (ID or key),
+    /// authenticating with `auth`; `deadline` overrides the client timeout.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "list-entitlements", page),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn list_entitlements_as(
+        &self,
+        auth: Auth<'_>,
+        license: &str,
+        page: u32,
+        deadline: Option<Duration>,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = license_entitlements_path(&self.account_id, license, page);
+        self.send(Method::GET, path, None, Some(auth), deadline)
+    }
+
+    /// Ask for the newest release after `current_version` on `channel`
+    /// that the authenticated license may download.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "upgrade-release"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn release_upgrade_as(
+        &self,
+        auth: Auth<'_>,
+        current_version: &str,
+        channel: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = release_upgrade_path(&self.account_id, current_version, channel);
+        self.send(Method::GET, path, None, Some(auth), None)
+    }
+
+    /// Fetch the artifacts of release `release_id`, authenticating with
+    /// `auth`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "list-artifacts"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn release_artifacts_as(
+        &self,
+        auth: Auth<'_>,
+        release_id: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = release_artifacts_path(&self.account_id, release_id);
+        self.send(Method::GET, path, None, Some(auth), None)
+    }
+
+    /// Request the download of artifact `artifact_id`, authenticating
+    /// with `auth`.
+    ///
+    /// Keygen answers `303 See Other`; redirects are never followed, so
+    /// the signed response itself is returned.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "download-artifact"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn artifact_as(
+        &self,
+        auth: Auth<'_>,
+        artifact_id: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = artifact_path(&self.account_id, artifact_id);
+        self.send(Method::GET, path, None, Some(auth), None)
+    }
+
+    /// Fetch group `group_id`, authenticating with `auth`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "retrieve-group"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn group_as(
+        &self,
+        auth: Auth<'_>,
+        group_id: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = group_path(&self.account_id, group_id);
+        self.send(Method::GET, path, None, Some(auth), None)
+    }
+
+    /// Fetch user `user_id`, authenticating with `auth`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "retrieve-user"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn user_as(
+        &self,
+        auth: Auth<'_>,
+        user_id: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = user_path(&self.account_id, user_id);
+        self.send(Method::GET, path, None, Some(auth), None)
+    }
+
+    /// Validate a license key scoped to machine `fingerprint`, its
+    /// hardware `components`, and `user` if given.
+    ///
+    /// Keygen answers `NO_MACHINES`, `NO_MACHINE`, or `TOO_MANY_MACHINES`
+    /// for machine-bound licenses until a machine with the fingerprint is
+    /// activated, and `COMPONENTS_SCOPE_MISMATCH` when its components
+    /// differ.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "validate-machine"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn validate_fingerprint(
+        &self,
+        license_key: &RedactedKey,
+        fingerprint: &str,
+        components: &[Component],
+        user: Option<&str>,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let mut body = LicenseCredential::Key(license_key).validation_body(&[]);
+        body["meta"]["scope"] = serde_json::json!({ "fingerprint": fingerprint });
+        if let Some(user) = user {
+            body["meta"]["scope"]["user"] = user.into();
+        }
+        if !components.is_empty() {
+            let components: Vec<&str> = components.iter().map(Component::fingerprint).collect();
+            body["meta"]["scope"]["components"] = components.into();
+        }
+        self.scope_body(&mut body);
+        let response = self.post(validate_key_path(&self.account_id), &body, None, None);
+        zeroize_body(&mut body);
+        response
+    }
+
+    /// Activate a machine with `fingerprint` and hardware `components` for
+    /// license `license_id`, authenticating with `auth`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "activate-machine"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn activate_machine_as(
+        &self,
+        auth: Auth<'_>,
+        license_id: &str,
+        fingerprint: &str,
+        components: &[Component],
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let components: Vec<serde_json::Value> = components
+            .iter()
+            .map(|component| {
+                serde_json::json!({
+                    "type": "components",
+                    "attributes": {
+                        "fingerprint": component.fingerprint(),
+                        "name": component.name()
+                    }
+                })
             })
-        };
-        let body_bytes = serde_json::to_vec(&body)
-            .map_err(|e| GatewardenError::ProtocolError(format!("Failed to serialize: {}", e)))?;
+            .collect();
+        let body = serde_json::json!({
+            "data": {
+                "type": "machines",
+                "attributes": { "fingerprint": fingerprint },
+                "relationships": {
+                    "license": {
+                        "data": { "type": "licenses", "id": license_id }
+                    },
+                    "components": { "data": components }
+                }
+            }
+        });
+
+        self.post(machines_path(&self.account_id), &body, Some(auth), None)
+    }
+
+    /// Deactivate machine `machine_id`, authenticating with `auth`;
+    /// `deadline` overrides the client timeout.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "deactivate-machine"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn deactivate_machine_as(
+        &self,
+        auth: Auth<'_>,
+        machine_id: &str,
+        deadline: Option<Duration>,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = machine_path(&self.account_id, machine_id);
+        self.send(Method::DELETE, path, None, Some(auth), deadline)
+    }
+
+    /// Send a heartbeat ping for machine `machine_id`, authenticating with
+    /// `auth`.
+    ///
+    /// Keygen answers HTTP 404 once the machine has been culled, and
+    /// HTTP 422 when its heartbeat is dead and the policy does not
+    /// resurrect it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "gatewarden.keygen_request",
+            level = "debug",
+            skip_all,
+            fields(host = %self.host, action = "ping-machine"),
+            err(level = "warn")
+        )
+    )]
+    pub(crate) fn ping_machine_as(
+        &self,
+        auth: Auth<'_>,
+        machine_id: &str,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let path = machine_ping_path(&self.account_id, machine_id);
+        self.send(Method::POST, path, None, Some(auth), None)
+    }
+
+    /// POST a JSON body and capture the signed response.
+    ///
+    /// `deadline` overrides the client timeout for this request.
+    fn post(
+        &self,
+        path: String,
+        body: &serde_json::Value,
+        auth: Option<Auth<'_>>,
+        deadline: Option<Duration>,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let body_bytes = serde_json::to_vec(body).map(Zeroizing::new).map_err(|e| {
+            GatewardenError::ProtocolError {
+                message: "Failed to serialize".to_string(),
+                source: Some(e.into()),
+            }
+        })?;
+
+        self.send(Method::POST, path, Some(body_bytes), auth, deadline)
+    }
+
+    /// Send a request (with a JSON body, if any) and capture the signed response.
+    fn send(
+        &self,
+        method: Method,
+        path: String,
+        body: Option<Zeroizing<Vec<u8>>>,
+        auth: Option<Auth<'_>>,
+        deadline: Option<Duration>,
+    ) -> Result<KeygenResponse, GatewardenError> {
+        let url = format!("https://{}{}", self.host, path);
+        let authorization = auth.map(|auth| auth.header_value());
 
-        // Compute digest for request
-        let digest_header = format_digest_header(&body_bytes);
+        #[cfg(any(test, feature = "test-seams"))]
+        if let Some(faults) = &self.faults {
+            faults.before_request()?;
+        }
 
-        let response = self
+        #[cfg(any(test, feature = "test-seams"))]
+        if let Some(mock) = &self.mock {
+            let mut response = mock.respond(
+                method.as_str(),
+                &self.host,
+                &path,
+                body.as_deref().map(Vec::as_slice).unwrap_or_default(),
+                authorization.as_deref().map(String::as_str),
+            );
+            if let Some(faults) = &self.faults {
+                faults.after_response(&mut response, deadline)?;
+            }
+            return Ok(response);
+        }
+
+        #[cfg(feature = "tracing")]
+        let started = std::time::Instant::now();
+
+        let mut request = self
             .client
-            .post(&url)
+            .request(method.clone(), &url)
             .header(USER_AGENT, &self.user_agent)
             .header(HOST, &self.host)
-            .header(CONTENT_TYPE, "application/vnd.api+json")
-            .header("Digest", &digest_header)
-            .header("Accept", "application/vnd.api+json")
-            .body(body_bytes)
-            .send()
-            .map_err(|e| GatewardenError::KeygenTransport(format!("Request failed: {}", e)))?;
+            .header("Accept", "application/vnd.api+json");
+        if let Some(body) = body {
+            let length = body.len() as u64;
+            // Streamed from our buffer, which is zeroized when reqwest drops it
+            request = request
+                .header(CONTENT_TYPE, "application/vnd.api+json")
+                .header("Digest", format_digest_header(&body))
+                .body(Body::sized(Cursor::new(body), length));
+        }
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization.as_str());
+        }
+        if let Some(deadline) = deadline {
+            request = request.timeout(deadline);
+        }
+
+        let response = request.send().map_err(|e| {
+            #[cfg(feature = "rustls")]
+            if crate::client::tls::is_pin_mismatch(&e) {
+                return GatewardenError::TlsPinMismatch;
+            }
+            // The URL can carry a license key (entitlements by key)
+            let message = match self.proxy.proxy_for(&self.host) {
+                Some(proxy) => format!("Request failed (via proxy {})", proxy),
+                None => "Request failed".to_string(),
+            };
+            GatewardenError::KeygenTransport {
+                message,
+                kind: transport_failure(&e),
+                source: Some(e.without_url().into()),
+            }
+        })?;
 
-        KeygenResponse::from_response(response, path, self.host.clone())
+        #[allow(unused_mut)]
+        let mut response =
+            KeygenResponse::from_response(response, method.as_str(), path, self.host.clone())?;
+        trace_debug!(
+            status = response.status,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Keygen responded"
+        );
+
+        #[cfg(any(test, feature = "test-seams"))]
+        if let Some(faults) = &self.faults {
+            faults.after_response(&mut response, deadline)?;
+        }
+
+        Ok(response)
     }
 
     /// Get the configured host.
@@ -203,34 +909,84 @@ impl KeygenClient {
     }
 }
 
-/// Build a User-Agent string from config.
-///
-/// Format: `<product>/gatewarden <app>/<version>`
-/// Example: `shimmy-vision/gatewarden shimmy/1.0.0`
-pub fn build_user_agent(config: &GatewardenConfig) -> String {
-    let product = &config.user_agent_product;
-    let app = &config.app_name;
+/// Build the underlying HTTP client with timeouts, proxy selection, and
+/// optional certificate pins.
+/// Classify a failed request, so the fallback policy and callers can tell
+/// an unreachable Keygen from a dropped connection.
+fn transport_failure(error: &reqwest::Error) -> TransportFailure {
+    if error.is_timeout() {
+        return TransportFailure::Timeout;
+    }
+    #[cfg(feature = "rustls")]
+    if crate::client::tls::is_tls_failure(error) {
+        return TransportFailure::Tls;
+    }
+    let mut current: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(err) = current {
+        // hyper-util reports resolver failures as a bare "dns error"
+        if err.to_string() == "dns error" {
+            return TransportFailure::Dns;
+        }
+        current = err.source();
+    }
+    if error.is_connect() {
+        TransportFailure::Connect
+    } else {
+        TransportFailure::Other
+    }
+}
 
-    // Get gatewarden version from Cargo.toml
-    let gw_version = env!("CARGO_PKG_VERSION");
+pub(crate) fn build_http_client(
+    timeout: Duration,
+    connect_timeout: Duration,
+    proxy: ProxyConfig,
+    tls_pins: &[&str],
+    pool: ConnectionPool,
+    ip_strategy: IpStrategy,
+) -> Result<Client, GatewardenError> {
+    #[allow(unused_mut)]
+    // Keygen signs the response to the request path it was sent, so a
+    // followed redirect could never verify; artifact downloads read the
+    // signed redirect instead
+    let mut builder = proxy.apply(
+        ip_strategy.apply(
+            pool.apply(
+                Client::builder()
+                    .timeout(timeout)
+                    .connect_timeout(connect_timeout)
+                    .redirect(reqwest::redirect::Policy::none()),
+            ),
+            KEYGEN_HOST,
+        ),
+    )?;
+
+    #[cfg(feature = "rustls")]
+    if !tls_pins.is_empty() {
+        builder = builder.use_preconfigured_tls(crate::client::tls::pinned_tls_config(tls_pins)?);
+    }
+    #[cfg(not(feature = "rustls"))]
+    crate::client::tls::validate_pins(tls_pins)?;
 
-    format!("{}/gatewarden-{} {}", product, gw_version, app)
+    builder
+        .build()
+        .map_err(|e| GatewardenError::ConfigError(format!("Failed to build HTTP client: {}", e)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::request::UserAgentExtras;
+    use crate::policy::fallback::FallbackPolicy;
 
     fn test_config() -> GatewardenConfig {
         GatewardenConfig {
             app_name: "shimmy/1.0.0",
             feature_name: "vision",
             account_id: "test-account-id",
-            public_key_hex: "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
             required_entitlements: &["vision"],
             user_agent_product: "shimmy-vision",
             cache_namespace: "shimmy",
-            offline_grace: Duration::from_secs(86400),
+            ..crate::mock::test_config()
         }
     }
 
@@ -249,11 +1005,30 @@ mod tests {
             app_name: "myapp/2.0.0",
             feature_name: "pro",
             account_id: "acc",
-            public_key_hex: "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            public_key_hex: crate::mock::TEST_PUBLIC_KEY_HEX,
+            public_key_id: None,
+            trusted_keys: &[],
             required_entitlements: &[],
             user_agent_product: "myproduct",
             cache_namespace: "myproduct",
             offline_grace: Duration::from_secs(0),
+            feature_grace: &[],
+            max_cache_age: Duration::from_secs(90 * 24 * 60 * 60),
+            fallback_policy: FallbackPolicy::default(),
+            online_budget: None,
+            request_timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+            proxy: ProxyConfig::System,
+            tls_pins: &[],
+            require_digest: false,
+            memo_ttl: Duration::ZERO,
+            negative_cache_ttl: Duration::ZERO,
+            validation_rate_limit: None,
+            app_version: None,
+            key_format: Default::default(),
+            user_agent_extras: Default::default(),
+            connection_pool: Default::default(),
+            ip_strategy: Default::default(),
         };
 
         let ua = build_user_agent(&config);
@@ -265,6 +1040,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_user_agent_extras() {
+        let config = GatewardenConfig {
+            user_agent_extras: UserAgentExtras::new().os().arch().build("1234"),
+            ..test_config()
+        };
+        let ua = build_user_agent(&config);
+
+        assert!(ua.ends_with(&format!(
+            "shimmy/1.0.0 ({}; {}; build 1234)",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )));
+        assert!(KeygenClient::new(&config).is_ok());
+    }
+
+    #[test]
+    fn test_client_with_connection_pool() {
+        let pool = ConnectionPool::new()
+            .idle_timeout(Duration::from_secs(300))
+            .max_idle_per_host(4);
+        let client = KeygenClient::new(&test_config())
+            .unwrap()
+            .with_connection_pool(pool)
+            .unwrap();
+        let config = GatewardenConfig {
+            connection_pool: pool,
+            ..test_config()
+        };
+        assert!(client.for_config(&config).is_ok());
+        assert!(client.for_config(&test_config()).is_err());
+
+        let invalid = ConnectionPool::new().idle_timeout(Duration::ZERO);
+        assert!(KeygenClient::new(&test_config())
+            .unwrap()
+            .with_connection_pool(invalid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_client_with_ip_strategy() {
+        let strategy = IpStrategy::Static("192.0.2.10".parse().unwrap());
+        let client = KeygenClient::new(&test_config())
+            .unwrap()
+            .with_ip_strategy(strategy)
+            .unwrap();
+        let config = GatewardenConfig {
+            ip_strategy: strategy,
+            ..test_config()
+        };
+        assert!(client.for_config(&config).is_ok());
+        assert!(client.for_config(&test_config()).is_err());
+        assert!(KeygenClient::new(&test_config())
+            .unwrap()
+            .with_ip_strategy(IpStrategy::Ipv4Only)
+            .is_ok());
+    }
+
     #[test]
     fn test_keygen_response_body_str_valid_utf8() {
         let response = KeygenResponse {
@@ -273,8 +1106,11 @@ mod tests {
             signature: None,
             digest: None,
             body: b"hello world".to_vec(),
+            method: "post".to_string(),
             request_path: "/test".to_string(),
             host: "api.keygen.sh".to_string(),
+            request_id: None,
+            rate_limit: None,
         };
 
         assert_eq!(response.body_str().unwrap(), "hello world");
@@ -288,8 +1124,11 @@ mod tests {
             signature: None,
             digest: None,
             body: vec![0xFF, 0xFE],
+            method: "post".to_string(),
             request_path: "/test".to_string(),
             host: "api.keygen.sh".to_string(),
+            request_id: None,
+            rate_limit: None,
         };
 
         assert!(response.body_str().is_err());
@@ -302,10 +1141,40 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_client_creation_with_tls_pins() {
+        let config = GatewardenConfig {
+            tls_pins: &["sha256/47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="],
+            ..test_config()
+        };
+        assert!(KeygenClient::new(&config).is_ok());
+    }
+
     #[test]
     fn test_client_host() {
         let config = test_config();
         let client = KeygenClient::new(&config).unwrap();
         assert_eq!(client.host(), "api.keygen.sh");
     }
+
+    #[test]
+    fn test_transport_error_omits_key_in_url() {
+        let config = GatewardenConfig {
+            proxy: ProxyConfig::Direct,
+            ..test_config()
+        };
+        // Nothing listens on port 1, so the request fails to connect
+        let client = KeygenClient::with_host(&config, "127.0.0.1:1".to_string()).unwrap();
+        let error = client
+            .list_entitlements(&RedactedKey::new("SECRET-KEY"), 1)
+            .unwrap_err();
+
+        assert!(matches!(error, GatewardenError::KeygenTransport { .. }));
+        let mut source: Option<&dyn std::error::Error> = Some(&error);
+        while let Some(e) = source {
+            assert!(!format!("{} {:?}", e, e).contains("SECRET"));
+            source = e.source();
+        }
+    }
 }
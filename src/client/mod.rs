@@ -1,3 +1,11 @@
 //! HTTP client for Keygen API.
 
+pub mod dns;
+pub mod fetch;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod http;
+pub mod pool;
+pub mod proxy;
+pub(crate) mod request;
+pub mod response;
+pub(crate) mod tls;
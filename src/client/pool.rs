@@ -0,0 +1,126 @@
+//! Connection reuse for the Keygen HTTP client.
+
+use crate::GatewardenError;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::blocking::ClientBuilder;
+use std::time::Duration;
+
+/// Idle timeout of pooled connections unless configured (reqwest's default).
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How the Keygen client keeps connections open between requests.
+///
+/// Servers that validate often reuse pooled connections rather than paying
+/// a TLS handshake per call; keep idle connections longer than the gap
+/// between validations.
+///
+/// ```
+/// use gatewarden::ConnectionPool;
+/// use std::time::Duration;
+///
+/// let pool = ConnectionPool::new()
+///     .idle_timeout(Duration::from_secs(300))
+///     .max_idle_per_host(8);
+/// assert!(pool.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionPool {
+    idle_timeout: Option<Duration>,
+    max_idle_per_host: usize,
+    prefer_http2: bool,
+}
+
+impl ConnectionPool {
+    /// Idle connections close after 90 seconds, with no limit on how many
+    /// are kept, over HTTP/1.1.
+    pub const fn new() -> Self {
+        Self {
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            max_idle_per_host: usize::MAX,
+            prefer_http2: false,
+        }
+    }
+
+    /// Close connections idle for longer than `timeout`.
+    pub const fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Keep idle connections open until Keygen closes them.
+    pub const fn keep_idle(mut self) -> Self {
+        self.idle_timeout = None;
+        self
+    }
+
+    /// Keep at most `max` idle connections to Keygen; zero disables reuse.
+    pub const fn max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = max;
+        self
+    }
+
+    /// Negotiate HTTP/2 with Keygen (falling back to HTTP/1.1), so
+    /// concurrent validations share one connection. Requires the `http2`
+    /// feature.
+    pub const fn prefer_http2(mut self) -> Self {
+        self.prefer_http2 = true;
+        self
+    }
+
+    /// Check that the settings can be honored by this build.
+    ///
+    /// # Errors
+    /// - `ConfigError` - `prefer_http2` is set without the `http2` feature,
+    ///   or the idle timeout is zero
+    pub fn validate(&self) -> Result<(), GatewardenError> {
+        if self.idle_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(GatewardenError::ConfigError(
+                "connection_pool idle_timeout must be greater than zero".to_string(),
+            ));
+        }
+        if self.prefer_http2 && !cfg!(feature = "http2") {
+            return Err(GatewardenError::ConfigError(
+                "connection_pool prefer_http2 requires the `http2` feature".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply these settings to a client builder.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        let builder = builder
+            .pool_idle_timeout(self.idle_timeout)
+            .pool_max_idle_per_host(self.max_idle_per_host);
+        #[cfg(feature = "http2")]
+        if !self.prefer_http2 {
+            return builder.http1_only();
+        }
+        builder
+    }
+}
+
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(ConnectionPool::new().validate().is_ok());
+        assert!(ConnectionPool::new().keep_idle().validate().is_ok());
+        assert!(ConnectionPool::new()
+            .idle_timeout(Duration::ZERO)
+            .validate()
+            .is_err());
+        assert_eq!(
+            ConnectionPool::new().prefer_http2().validate().is_ok(),
+            cfg!(feature = "http2")
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Keygen request targets and credentials, shared by the blocking and
+//! fetch-based clients.
+
+// The fetch-based client only sends validate-key requests
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
+
+use crate::config::GatewardenConfig;
+use crate::redact::RedactedKey;
+use crate::GatewardenError;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Host Keygen signs responses for.
+pub(crate) const KEYGEN_HOST: &str = "api.keygen.sh";
+
+/// Request path of the validate-key action for an account.
+pub(crate) fn validate_key_path(account_id: &str) -> String {
+    format!("/v1/accounts/{}/licenses/actions/validate-key", account_id)
+}
+
+/// Request path of the validate action for a license ID.
+pub(crate) fn validate_license_path(account_id: &str, license_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/licenses/{}/actions/validate",
+        account_id,
+        encode_path_segment(license_id)
+    )
+}
+
+/// Request path for generating tokens.
+pub(crate) fn tokens_path(account_id: &str) -> String {
+    format!("/v1/accounts/{}/tokens", account_id)
+}
+
+/// Request path for creating licenses.
+pub(crate) fn licenses_path(account_id: &str) -> String {
+    format!("/v1/accounts/{}/licenses", account_id)
+}
+
+/// Request path of the increment-usage action for a license.
+pub(crate) fn increment_usage_path(account_id: &str, license_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/licenses/{}/actions/increment-usage",
+        account_id, license_id
+    )
+}
+
+/// Request path of a group.
+pub(crate) fn group_path(account_id: &str, group_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/groups/{}",
+        account_id,
+        encode_path_segment(group_id)
+    )
+}
+
+/// Request path of a user.
+pub(crate) fn user_path(account_id: &str, user_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/users/{}",
+        account_id,
+        encode_path_segment(user_id)
+    )
+}
+
+/// Request path for activating machines.
+pub(crate) fn machines_path(account_id: &str) -> String {
+    format!("/v1/accounts/{}/machines", account_id)
+}
+
+/// Request path of a machine, for deactivating it.
+pub(crate) fn machine_path(account_id: &str, machine_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/machines/{}",
+        account_id,
+        encode_path_segment(machine_id)
+    )
+}
+
+/// Request path of a machine's heartbeat ping action.
+pub(crate) fn machine_ping_path(account_id: &str, machine_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/machines/{}/actions/ping",
+        account_id,
+        encode_path_segment(machine_id)
+    )
+}
+
+/// Page size requested when listing a license's entitlements (Keygen's maximum).
+pub(crate) const ENTITLEMENTS_PAGE_SIZE: u32 = 100;
+
+/// Request path (with query) of one page of a license's entitlements.
+///
+/// Keygen looks the license up by ID or key. The query is pre-encoded so
+/// the path is byte-for-byte what Keygen signs as the request target.
+pub(crate) fn license_entitlements_path(account_id: &str, license: &str, page: u32) -> String {
+    format!(
+        "/v1/accounts/{}/licenses/{}/entitlements?page%5Bnumber%5D={}&page%5Bsize%5D={}",
+        account_id,
+        encode_path_segment(license),
+        page,
+        ENTITLEMENTS_PAGE_SIZE
+    )
+}
+
+/// Request path (with query) of the upgrade action: the newest release
+/// after `current_version` on `channel`.
+pub(crate) fn release_upgrade_path(
+    account_id: &str,
+    current_version: &str,
+    channel: &str,
+) -> String {
+    format!(
+        "/v1/accounts/{}/releases/{}/upgrade?channel={}",
+        account_id,
+        encode_path_segment(current_version),
+        encode_path_segment(channel)
+    )
+}
+
+/// Page size requested when listing a release's artifacts (Keygen's maximum).
+pub(crate) const ARTIFACTS_PAGE_SIZE: u32 = 100;
+
+/// Request path (with query) of the first page of a release's artifacts.
+pub(crate) fn release_artifacts_path(account_id: &str, release_id: &str) -> String {
+    format!(
+        "/v1/accounts/{}/releases/{}/artifacts?page%5Bnumber%5D=1&page%5Bsize%5D={}",
+        account_id,
+        encode_path_segment(release_id),
+        ARTIFACTS_PAGE_SIZE
+    )
+}
+
+/// Request path of an artifact download (by ID or filename).
+pub(crate) fn artifact_path(account_id: &str, artifact: &str) -> String {
+    format!(
+        "/v1/accounts/{}/artifacts/{}",
+        account_id,
+        encode_path_segment(artifact)
+    )
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+pub(crate) fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// How a validation request identifies and authenticates the license.
+#[derive(Clone, Copy)]
+pub(crate) enum LicenseCredential<'a> {
+    /// A license key, sent in the body of the validate-key action.
+    Key(&'a RedactedKey),
+
+    /// A license token authorizing validation of `license_id` through the
+    /// license's validate action.
+    Token {
+        /// Keygen license ID.
+        license_id: &'a str,
+        /// License (or activation) token, sent as a bearer token.
+        token: &'a str,
+    },
+}
+
+impl LicenseCredential<'_> {
+    /// JSON body of a validation request with `scope_entitlements` in scope.
+    pub(crate) fn validation_body(&self, scope_entitlements: &[&str]) -> serde_json::Value {
+        // Include scope.entitlements to get entitlements echoed back in response
+        let mut meta = serde_json::Map::new();
+        if !scope_entitlements.is_empty() {
+            meta.insert(
+                "scope".to_string(),
+                serde_json::json!({ "entitlements": scope_entitlements }),
+            );
+        }
+        if let Self::Key(license_key) = self {
+            meta.insert("key".to_string(), license_key.expose().into());
+        }
+        serde_json::json!({ "meta": meta })
+    }
+}
+
+/// Credential sent in the `Authorization` header.
+#[derive(Clone, Copy)]
+pub(crate) enum Auth<'a> {
+    /// `Authorization: License <key>`.
+    License(&'a RedactedKey),
+    /// `Authorization: Bearer <token>`.
+    Bearer(&'a str),
+}
+
+impl Auth<'_> {
+    /// The header value, cleared from memory when dropped.
+    pub(crate) fn header_value(&self) -> Zeroizing<String> {
+        Zeroizing::new(match self {
+            Self::License(key) => format!("License {}", key.expose()),
+            Self::Bearer(token) => format!("Bearer {}", token),
+        })
+    }
+}
+
+/// Clear every string in a request body once it has been sent, since it
+/// may hold a license key.
+pub(crate) fn zeroize_body(body: &mut serde_json::Value) {
+    match body {
+        serde_json::Value::String(s) => s.zeroize(),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(zeroize_body),
+        serde_json::Value::Object(fields) => fields.values_mut().for_each(zeroize_body),
+        _ => {}
+    }
+}
+
+/// Opt-in platform and build details appended to the User-Agent.
+///
+/// Keygen's request analytics group clients by User-Agent, which helps
+/// spot cracked builds (e.g. a build ID that was never shipped, or a
+/// platform the product does not support). Nothing is added by default.
+///
+/// ```
+/// use gatewarden::UserAgentExtras;
+///
+/// let extras = UserAgentExtras::new().os().arch().build("2025.01.3+a1b2c3");
+/// assert!(extras.comment().unwrap().ends_with("; build 2025.01.3+a1b2c3"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UserAgentExtras {
+    os: bool,
+    arch: bool,
+    build: Option<&'static str>,
+}
+
+impl UserAgentExtras {
+    /// Extras that add nothing.
+    pub const fn new() -> Self {
+        Self {
+            os: false,
+            arch: false,
+            build: None,
+        }
+    }
+
+    /// Add the operating system (e.g. `linux`, `windows`, `macos`).
+    pub const fn os(mut self) -> Self {
+        self.os = true;
+        self
+    }
+
+    /// Add the CPU architecture (e.g. `x86_64`, `aarch64`).
+    pub const fn arch(mut self) -> Self {
+        self.arch = true;
+        self
+    }
+
+    /// Add the application's build metadata (e.g. a build number or
+    /// commit hash).
+    pub const fn build(mut self, build: &'static str) -> Self {
+        self.build = Some(build);
+        self
+    }
+
+    /// The User-Agent comment, e.g. `linux; x86_64; build 1234`, or `None`
+    /// when no extras are enabled.
+    pub fn comment(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.os {
+            parts.push(std::env::consts::OS.to_string());
+        }
+        if self.arch {
+            parts.push(std::env::consts::ARCH.to_string());
+        }
+        if let Some(build) = self.build {
+            parts.push(format!("build {}", build));
+        }
+        (!parts.is_empty()).then(|| parts.join("; "))
+    }
+
+    /// Check that the build metadata fits in a User-Agent comment.
+    ///
+    /// # Errors
+    /// - `ConfigError` - The build is empty, or contains characters other
+    ///   than printable ASCII, or `(`, `)`, or `;`
+    pub(crate) fn validate(&self) -> Result<(), GatewardenError> {
+        if let Some(build) = self.build {
+            let printable = |c: char| c.is_ascii_graphic() || c == ' ';
+            if build.trim().is_empty()
+                || !build.chars().all(printable)
+                || build.contains(['(', ')', ';'])
+            {
+                return Err(GatewardenError::ConfigError(
+                    "user_agent_extras build must be printable ASCII without '(', ')' or ';'"
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build a User-Agent string from config.
+///
+/// Format: `<product>/gatewarden-<version> <app>`, followed by a
+/// `(<os>; <arch>; build <build>)` comment for the enabled
+/// `user_agent_extras`.
+/// Example: `shimmy-vision/gatewarden-0.1.2 shimmy/1.0.0 (linux; x86_64)`
+pub fn build_user_agent(config: &GatewardenConfig) -> String {
+    let product = &config.user_agent_product;
+    let app = &config.app_name;
+
+    // Get gatewarden version from Cargo.toml
+    let gw_version = env!("CARGO_PKG_VERSION");
+
+    match config.user_agent_extras.comment() {
+        Some(comment) => format!(
+            "{}/gatewarden-{} {} ({})",
+            product, gw_version, app, comment
+        ),
+        None => format!("{}/gatewarden-{} {}", product, gw_version, app),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zeroize_body_clears_key() {
+        let key = RedactedKey::new("SECRET-KEY");
+        let mut body = LicenseCredential::Key(&key).validation_body(&["PRO"]);
+        assert!(body.to_string().contains("SECRET-KEY"));
+
+        zeroize_body(&mut body);
+        assert!(!body.to_string().contains("SECRET"));
+        assert!(!body.to_string().contains("PRO"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Keygen HTTP responses, captured for verification.
+
+use crate::GatewardenError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// HTTP response with captured headers and body.
+#[derive(Debug)]
+pub struct KeygenResponse {
+    /// HTTP status code.
+    pub status: u16,
+
+    /// Date header value.
+    pub date: Option<String>,
+
+    /// Keygen-Signature header value.
+    pub signature: Option<String>,
+
+    /// Digest header value.
+    pub digest: Option<String>,
+
+    /// Raw response body.
+    pub body: Vec<u8>,
+
+    /// HTTP method of the request (for signing string reconstruction).
+    pub method: String,
+
+    /// Request path used (for signing string reconstruction).
+    pub request_path: String,
+
+    /// Host used (for signing string reconstruction).
+    pub host: String,
+
+    /// Keygen's `X-Request-Id` header, for support requests.
+    pub request_id: Option<String>,
+
+    /// Keygen's `X-RateLimit-*` headers, if sent.
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Keygen's rate-limit window as reported with a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Requests allowed in the window (`X-RateLimit-Limit`).
+    pub limit: u32,
+
+    /// Requests left in the window (`X-RateLimit-Remaining`).
+    pub remaining: u32,
+
+    /// When the window resets (`X-RateLimit-Reset`), if sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl RateLimit {
+    /// Parse the rate-limit headers; `None` unless both the limit and the
+    /// remaining count are present and numeric.
+    pub(crate) fn from_headers(header: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let count = |name: &str| header(name)?.trim().parse().ok();
+        Some(Self {
+            limit: count("X-RateLimit-Limit")?,
+            remaining: count("X-RateLimit-Remaining")?,
+            reset_at: header("X-RateLimit-Reset")
+                .and_then(|reset| reset.trim().parse().ok())
+                .and_then(|seconds| DateTime::from_timestamp(seconds, 0)),
+        })
+    }
+}
+
+impl KeygenResponse {
+    /// Build a response from raw parts captured elsewhere (e.g. a proxy).
+    ///
+    /// Header names are matched case-insensitively. `request_path` and
+    /// `host` are what the caller expects Keygen to have signed, not
+    /// values taken from the headers. The method is `post`, as for Keygen's
+    /// license actions; set `method` afterwards for other requests.
+    pub fn from_parts(
+        status: u16,
+        body: &[u8],
+        headers: &[(&str, &str)],
+        request_path: String,
+        host: String,
+    ) -> Self {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.to_string())
+        };
+
+        Self {
+            status,
+            date: header("Date"),
+            signature: header("Keygen-Signature"),
+            digest: header("Digest"),
+            body: body.to_vec(),
+            method: "post".to_string(),
+            request_path,
+            host,
+            request_id: header("X-Request-Id"),
+            rate_limit: RateLimit::from_headers(header),
+        }
+    }
+
+    /// Get the body as a UTF-8 string.
+    pub fn body_str(&self) -> Result<&str, GatewardenError> {
+        std::str::from_utf8(&self.body).map_err(|e| GatewardenError::ProtocolError {
+            message: "Invalid UTF-8 in body".to_string(),
+            source: Some(e.into()),
+        })
+    }
+}
+
+/// Map Keygen availability failures (HTTP 429 and 5xx) to their errors.
+pub(crate) fn check_available(response: &KeygenResponse) -> Result<(), GatewardenError> {
+    let request_id = response.request_id.clone();
+    match response.status {
+        429 => Err(GatewardenError::RateLimited { request_id }),
+        status @ 500..=599 => Err(GatewardenError::ServerError { status, request_id }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_parts_captures_request_headers() {
+        let response = KeygenResponse::from_parts(
+            200,
+            b"{}",
+            &[
+                ("x-request-id", "req-1"),
+                ("X-RateLimit-Limit", "60"),
+                ("X-RateLimit-Remaining", "59"),
+                ("X-RateLimit-Reset", "1736942400"),
+            ],
+            "/v1/test".to_string(),
+            "api.keygen.sh".to_string(),
+        );
+        assert_eq!(response.request_id.as_deref(), Some("req-1"));
+        let rate_limit = response.rate_limit.unwrap();
+        assert_eq!((rate_limit.limit, rate_limit.remaining), (60, 59));
+        assert_eq!(
+            rate_limit.reset_at.map(|t| t.to_rfc3339()).as_deref(),
+            Some("2025-01-15T12:00:00+00:00")
+        );
+
+        let partial = KeygenResponse::from_parts(
+            200,
+            b"{}",
+            &[("X-RateLimit-Limit", "60")],
+            "/v1/test".to_string(),
+            "api.keygen.sh".to_string(),
+        );
+        assert!(partial.request_id.is_none());
+        assert!(partial.rate_limit.is_none());
+    }
+}
@@ -0,0 +1,336 @@
+//! Certificate pinning for the Keygen HTTP client.
+//!
+//! Response signatures already protect license state from a network
+//! attacker, so pinning is not needed for integrity. What it adds is a
+//! clear failure: behind a TLS-intercepting proxy, every request would
+//! otherwise end in `SignatureInvalid` (or a generic transport error),
+//! which reads like tampering. With pins set, the handshake itself fails
+//! with `TlsPinMismatch`.
+//!
+//! Pins are `sha256/<base64>` hashes of a certificate's DER-encoded
+//! SubjectPublicKeyInfo, the format used by HPKP and curl's
+//! `--pinnedpubkey`. The chain must still validate against the bundled
+//! webpki roots; a pin then only has to match one certificate in the
+//! chain the server presents (leaf or intermediate). Pinning is only
+//! available with the `rustls` feature.
+
+use crate::GatewardenError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Prefix of a SHA-256 SPKI pin.
+const PIN_PREFIX: &str = "sha256/";
+
+/// Parse `sha256/<base64>` pins into raw SHA-256 digests.
+///
+/// # Errors
+/// - `ConfigError` - A pin lacks the prefix or is not a base64 SHA-256 digest
+pub(crate) fn parse_pins(pins: &[&str]) -> Result<Vec<[u8; 32]>, GatewardenError> {
+    pins.iter()
+        .map(|pin| {
+            let encoded = pin.strip_prefix(PIN_PREFIX).ok_or_else(|| {
+                GatewardenError::ConfigError(format!(
+                    "TLS pin must start with '{}': {}",
+                    PIN_PREFIX, pin
+                ))
+            })?;
+            STANDARD
+                .decode(encoded)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .ok_or_else(|| {
+                    GatewardenError::ConfigError(format!(
+                        "TLS pin is not a base64 SHA-256 digest: {}",
+                        pin
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Check that pins are well-formed and supported by the TLS backend.
+///
+/// # Errors
+/// - `ConfigError` - A pin is malformed, or pins are set without the `rustls` feature
+pub(crate) fn validate_pins(pins: &[&str]) -> Result<(), GatewardenError> {
+    parse_pins(pins)?;
+    if !pins.is_empty() && !cfg!(feature = "rustls") {
+        return Err(GatewardenError::ConfigError(
+            "tls_pins requires the `rustls` feature".to_string(),
+        ));
+    }
+    if !pins.is_empty() && cfg!(target_arch = "wasm32") {
+        return Err(GatewardenError::ConfigError(
+            "tls_pins is not supported on wasm32; the browser handles TLS".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "rustls", not(target_arch = "wasm32")))]
+pub(crate) use pinned::{is_pin_mismatch, is_tls_failure, pinned_tls_config};
+
+#[cfg(all(feature = "rustls", not(target_arch = "wasm32")))]
+mod pinned {
+    use super::parse_pins;
+    use crate::GatewardenError;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::crypto::ring;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore};
+    use rustls::{Error as TlsError, SignatureScheme};
+    use sha2::{Digest, Sha256};
+    use std::error::Error;
+    use std::sync::Arc;
+
+    /// Verifier that runs normal webpki validation, then requires a pin match.
+    #[derive(Debug)]
+    struct PinnedVerifier {
+        inner: Arc<WebPkiServerVerifier>,
+        pins: Vec<[u8; 32]>,
+    }
+
+    impl PinnedVerifier {
+        /// Whether the SPKI hash of `cert` is one of the pins.
+        fn matches(&self, cert: &CertificateDer<'_>) -> bool {
+            webpki::EndEntityCert::try_from(cert).is_ok_and(|parsed| {
+                let digest: [u8; 32] = Sha256::digest(parsed.subject_public_key_info()).into();
+                self.pins.contains(&digest)
+            })
+        }
+    }
+
+    impl ServerCertVerifier for PinnedVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, TlsError> {
+            let verified = self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            )?;
+
+            if std::iter::once(end_entity)
+                .chain(intermediates)
+                .any(|cert| self.matches(cert))
+            {
+                Ok(verified)
+            } else {
+                Err(TlsError::InvalidCertificate(
+                    CertificateError::ApplicationVerificationFailure,
+                ))
+            }
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, TlsError> {
+            self.inner.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, TlsError> {
+            self.inner.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.inner.supported_verify_schemes()
+        }
+    }
+
+    /// Build a rustls config that only trusts chains matching `pins`.
+    ///
+    /// # Errors
+    /// - `ConfigError` - A pin is malformed or the TLS config cannot be built
+    pub(crate) fn pinned_tls_config(pins: &[&str]) -> Result<ClientConfig, GatewardenError> {
+        let config_error = |e: TlsError| {
+            GatewardenError::ConfigError(format!("Failed to build TLS config: {}", e))
+        };
+
+        let provider = Arc::new(ring::default_provider());
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build()
+            .map_err(|e| {
+                GatewardenError::ConfigError(format!("Failed to build TLS verifier: {}", e))
+            })?;
+        let verifier = PinnedVerifier {
+            inner,
+            pins: parse_pins(pins)?,
+        };
+
+        Ok(ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(config_error)?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth())
+    }
+
+    /// Whether a request error was caused by a pin mismatch.
+    pub(crate) fn is_pin_mismatch(error: &(dyn Error + 'static)) -> bool {
+        matches!(
+            tls_error(error),
+            Some(TlsError::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure
+            ))
+        )
+    }
+
+    /// Whether a request error was caused by a failed TLS handshake.
+    pub(crate) fn is_tls_failure(error: &(dyn Error + 'static)) -> bool {
+        tls_error(error).is_some()
+    }
+
+    /// The rustls error somewhere in a request error's source chain.
+    fn tls_error<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a TlsError> {
+        let mut current = Some(error);
+        while let Some(err) = current {
+            // hyper wraps the handshake failure in an io::Error
+            let tls = err.downcast_ref::<TlsError>().or_else(|| {
+                err.downcast_ref::<std::io::Error>()
+                    .and_then(|io| io.get_ref())
+                    .and_then(|inner| inner.downcast_ref::<TlsError>())
+            });
+            if tls.is_some() {
+                return tls;
+            }
+            current = err.source();
+        }
+        None
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+
+        /// A CA and a leaf for `api.keygen.sh` it issued, as DER.
+        fn chain() -> (CertificateDer<'static>, CertificateDer<'static>, KeyPair) {
+            let ca_key = KeyPair::generate().unwrap();
+            let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = ca_params.self_signed(&ca_key).unwrap();
+
+            let leaf_key = KeyPair::generate().unwrap();
+            let leaf = CertificateParams::new(vec!["api.keygen.sh".to_string()])
+                .unwrap()
+                .signed_by(&leaf_key, &ca, &ca_key)
+                .unwrap();
+            (ca.der().clone(), leaf.der().clone(), leaf_key)
+        }
+
+        fn verifier(root: &CertificateDer<'static>, pins: Vec<[u8; 32]>) -> PinnedVerifier {
+            let mut roots = RootCertStore::empty();
+            roots.add(root.clone()).unwrap();
+            let inner = WebPkiServerVerifier::builder_with_provider(
+                Arc::new(roots),
+                Arc::new(ring::default_provider()),
+            )
+            .build()
+            .unwrap();
+            PinnedVerifier { inner, pins }
+        }
+
+        fn verify(verifier: &PinnedVerifier, leaf: &CertificateDer<'_>) -> Result<(), TlsError> {
+            let name = ServerName::try_from("api.keygen.sh").unwrap();
+            verifier
+                .verify_server_cert(leaf, &[], &name, &[], UnixTime::now())
+                .map(|_| ())
+        }
+
+        #[test]
+        fn test_pinned_verifier() {
+            let (root, leaf, leaf_key) = chain();
+            let leaf_pin: [u8; 32] = Sha256::digest(leaf_key.public_key_der()).into();
+            let other_pin = [0u8; 32];
+
+            let pinned = verifier(&root, vec![other_pin, leaf_pin]);
+            assert!(pinned.matches(&leaf));
+            assert!(verify(&pinned, &leaf).is_ok());
+
+            let mismatched = verifier(&root, vec![other_pin]);
+            assert!(!mismatched.matches(&leaf));
+            assert_eq!(
+                verify(&mismatched, &leaf),
+                Err(TlsError::InvalidCertificate(
+                    CertificateError::ApplicationVerificationFailure
+                ))
+            );
+        }
+
+        #[test]
+        fn test_pinned_verifier_requires_valid_chain() {
+            // A pin match does not stand in for chain validation
+            let (_, leaf, leaf_key) = chain();
+            let (other_root, _, _) = chain();
+            let leaf_pin: [u8; 32] = Sha256::digest(leaf_key.public_key_der()).into();
+
+            let untrusted = verifier(&other_root, vec![leaf_pin]);
+            assert!(untrusted.matches(&leaf));
+            let err = verify(&untrusted, &leaf).unwrap_err();
+            assert_ne!(
+                err,
+                TlsError::InvalidCertificate(CertificateError::ApplicationVerificationFailure)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIN: &str = "sha256/47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+
+    #[test]
+    fn test_parse_pins() {
+        let parsed = parse_pins(&[PIN]).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0][..4], [0xe3, 0xb0, 0xc4, 0x42]);
+
+        for bad in [
+            "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+            "sha256/not base64",
+            "sha256/AAAA",
+        ] {
+            assert!(matches!(
+                parse_pins(&[bad]),
+                Err(GatewardenError::ConfigError(_))
+            ));
+        }
+    }
+
+    #[cfg(feature = "rustls")]
+    #[test]
+    fn test_pin_mismatch_detection() {
+        use rustls::{CertificateError, Error as TlsError};
+
+        assert!(pinned_tls_config(&[PIN]).is_ok());
+
+        let mismatch = std::io::Error::other(TlsError::InvalidCertificate(
+            CertificateError::ApplicationVerificationFailure,
+        ));
+        assert!(is_pin_mismatch(&mismatch));
+
+        let expired =
+            std::io::Error::other(TlsError::InvalidCertificate(CertificateError::Expired));
+        assert!(!is_pin_mismatch(&expired));
+    }
+}
@@ -36,9 +36,11 @@ impl MockClock {
 
     /// Create a mock clock from an RFC 3339 string.
     pub fn from_rfc3339(s: &str) -> Result<Self, GatewardenError> {
-        let parsed = DateTime::parse_from_rfc3339(s).map_err(|e| {
-            GatewardenError::ProtocolError(format!("invalid RFC 3339 timestamp: {e}"))
-        })?;
+        let parsed =
+            DateTime::parse_from_rfc3339(s).map_err(|e| GatewardenError::ProtocolError {
+                message: "invalid RFC 3339 timestamp".to_string(),
+                source: Some(e.into()),
+            })?;
 
         Ok(Self {
             now: parsed.with_timezone(&Utc),
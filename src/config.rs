@@ -1,7 +1,33 @@
 //! Gatewarden configuration.
 
+use crate::client::dns::IpStrategy;
+use crate::client::pool::ConnectionPool;
+use crate::client::proxy::ProxyConfig;
+use crate::client::request::UserAgentExtras;
+use crate::crypto::keys::{KeyRing, TrustedKey};
+use crate::policy::fallback::FallbackPolicy;
+use crate::policy::key_format::KeyFormat;
+use crate::GatewardenError;
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::sync::Mutex;
 use std::time::Duration;
 
+/// Default offline grace period used by the builder (24 hours).
+pub const DEFAULT_OFFLINE_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Default hard ceiling on cache record age used by the builder (90 days).
+pub const DEFAULT_MAX_CACHE_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// Default total timeout for a Keygen request used by the builder (30 seconds).
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default timeout for establishing a connection used by the builder (10 seconds).
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default time a successful result is reused from memory.
+pub const DEFAULT_MEMO_TTL: Duration = Duration::from_secs(60);
+
 /// Configuration for Gatewarden license validation.
 ///
 /// This struct contains all product-specific settings needed to validate
@@ -22,6 +48,15 @@ pub struct GatewardenConfig {
     /// SECURITY: This should be hard-coded in your application, not from environment.
     pub public_key_hex: &'static str,
 
+    /// Signature `keyid` that `public_key_hex` signs as, if it should be
+    /// bound to one (e.g. the account's UUID when `account_id` is a slug).
+    /// `None` accepts any `keyid`.
+    pub public_key_id: Option<&'static str>,
+
+    /// Additional Keygen verify keys, tried in order after `public_key_hex`.
+    /// Ship the next key here before rotating so older binaries keep verifying.
+    pub trusted_keys: &'static [TrustedKey],
+
     /// Required entitlement codes that the license must have.
     /// All codes must be present for access to be granted.
     pub required_entitlements: &'static [&'static str],
@@ -37,9 +72,108 @@ pub struct GatewardenConfig {
     /// Grace period for offline operation.
     /// Cached licenses remain valid for this duration after last successful online validation.
     pub offline_grace: Duration,
+
+    /// Per-feature offline grace overrides, as `(feature, grace)` pairs.
+    /// Features not listed here use `offline_grace`.
+    pub feature_grace: &'static [(&'static str, Duration)],
+
+    /// Hard ceiling on cache record age, whatever the grace settings.
+    /// Older records are purged and never used; grace periods are clamped to this.
+    pub max_cache_age: Duration,
+
+    /// Which online failures may fall back to the offline cache.
+    /// Security failures never fall back, whatever the policy.
+    pub fallback_policy: FallbackPolicy,
+
+    /// Longest to wait for Keygen before answering from a valid cached record.
+    /// Only applies when such a record exists; otherwise the full request timeout is used.
+    pub online_budget: Option<Duration>,
+
+    /// Total time allowed for a Keygen request, including reading the body.
+    pub request_timeout: Duration,
+
+    /// Time allowed to establish a connection to Keygen (or the proxy).
+    pub connect_timeout: Duration,
+
+    /// How requests to Keygen are proxied.
+    /// `ProxyConfig::System` honors `HTTPS_PROXY` and the OS proxy settings.
+    pub proxy: ProxyConfig,
+
+    /// SPKI pins for Keygen's TLS certificate chain (`"sha256/<base64>"`).
+    /// Empty disables pinning. Requires the `rustls` feature.
+    pub tls_pins: &'static [&'static str],
+
+    /// Reject responses (and cached records) without a Digest header.
+    /// By default a missing digest is tolerated and the body is not bound to the signature.
+    pub require_digest: bool,
+
+    /// How long a successful result is reused from memory by `validate_key`
+    /// and `check_access`, skipping Keygen, the disk cache, and verification.
+    /// Zero disables memoization.
+    pub memo_ttl: Duration,
+
+    /// How long Keygen's refusal of a key (e.g. `EXPIRED`, `NOT_FOUND`) is
+    /// reused by `validate_key` before asking Keygen again, so aggressive
+    /// retries of a doomed key stay local. Zero disables negative caching.
+    pub negative_cache_ttl: Duration,
+
+    /// Most online validations per key per minute (a token bucket).
+    /// Calls over the limit are answered from a valid cached record, or
+    /// fail with `RateLimited` without one. `None` disables the limit.
+    pub validation_rate_limit: Option<u32>,
+
+    /// Version of the running application (e.g. `"2.3.1"`). When set,
+    /// licenses with a `maxVersion` metadata value older than it fail with
+    /// `VersionNotEntitled`. `None` skips the check.
+    pub app_version: Option<&'static str>,
+
+    /// How license keys are normalized and checked before they are hashed
+    /// or sent to Keygen. The default leaves keys untouched.
+    pub key_format: KeyFormat,
+
+    /// Platform and build details appended to the User-Agent for Keygen's
+    /// request analytics. The default appends nothing.
+    pub user_agent_extras: UserAgentExtras,
+
+    /// How long idle connections to Keygen are kept for reuse, how many,
+    /// and whether HTTP/2 is negotiated. Fixed when the client is built.
+    pub connection_pool: ConnectionPool,
+
+    /// How `api.keygen.sh` is resolved: either address family, one of
+    /// them, or a static address. Fixed when the client is built.
+    pub ip_strategy: IpStrategy,
 }
 
 impl GatewardenConfig {
+    /// Start building a configuration with sensible defaults.
+    ///
+    /// `app_name`, `account_id`, and `public_key_hex` are required. When not
+    /// set explicitly, `cache_namespace` and `user_agent_product` default to
+    /// `<app_name>-<feature_name>` (or `<app_name>` without a feature),
+    /// `offline_grace` defaults to 24 hours, and `max_cache_age` to 90 days.
+    pub fn builder() -> GatewardenConfigBuilder {
+        GatewardenConfigBuilder::default()
+    }
+
+    /// Offline grace period for a feature profile.
+    ///
+    /// Returns the matching `feature_grace` override, or `offline_grace`,
+    /// clamped to `max_cache_age`.
+    pub fn offline_grace_for(&self, feature: &str) -> Duration {
+        self.feature_grace
+            .iter()
+            .find(|(name, _)| *name == feature)
+            .map(|(_, grace)| *grace)
+            .unwrap_or(self.offline_grace)
+            .min(self.max_cache_age)
+    }
+
+    /// Keys that may sign Keygen responses: `public_key_hex` (signing as
+    /// `public_key_id`, if set), then `trusted_keys`.
+    pub fn key_ring(&self) -> KeyRing<'static> {
+        KeyRing::new(self.public_key_hex, self.public_key_id, self.trusted_keys)
+    }
+
     /// Validate configuration for obvious errors.
     pub fn validate(&self) -> Result<(), crate::GatewardenError> {
         if self.account_id.is_empty() {
@@ -53,11 +187,497 @@ impl GatewardenConfig {
                 self.public_key_hex.len()
             )));
         }
+        for key in self.trusted_keys {
+            if key.public_key_hex.len() != 64 {
+                return Err(crate::GatewardenError::ConfigError(format!(
+                    "trusted_keys public keys must be 64 hex characters, got {}",
+                    key.public_key_hex.len()
+                )));
+            }
+            if key.key_id == Some("") {
+                return Err(crate::GatewardenError::ConfigError(
+                    "trusted_keys key_id cannot be empty".to_string(),
+                ));
+            }
+        }
         if self.cache_namespace.is_empty() {
             return Err(crate::GatewardenError::ConfigError(
                 "cache_namespace cannot be empty".to_string(),
             ));
         }
+        if self.max_cache_age.is_zero() {
+            return Err(crate::GatewardenError::ConfigError(
+                "max_cache_age must be greater than zero".to_string(),
+            ));
+        }
+        if self.request_timeout.is_zero() || self.connect_timeout.is_zero() {
+            return Err(crate::GatewardenError::ConfigError(
+                "request_timeout and connect_timeout must be greater than zero".to_string(),
+            ));
+        }
+        if self.online_budget.is_some_and(|budget| budget.is_zero()) {
+            return Err(crate::GatewardenError::ConfigError(
+                "online_budget must be greater than zero".to_string(),
+            ));
+        }
+        if self.validation_rate_limit == Some(0) {
+            return Err(crate::GatewardenError::ConfigError(
+                "validation_rate_limit must be greater than zero".to_string(),
+            ));
+        }
+        if let Some(version) = self.app_version {
+            if crate::policy::version::parse(version).is_none() {
+                return Err(crate::GatewardenError::ConfigError(format!(
+                    "app_version '{}' is not a dotted version number",
+                    version
+                )));
+            }
+        }
+        self.proxy.validate()?;
+        self.user_agent_extras.validate()?;
+        self.connection_pool.validate()?;
+        self.ip_strategy.validate()?;
+        crate::client::tls::validate_pins(self.tls_pins)?;
+        for (i, (feature, _)) in self.feature_grace.iter().enumerate() {
+            if feature.is_empty() {
+                return Err(crate::GatewardenError::ConfigError(
+                    "feature_grace feature names cannot be empty".to_string(),
+                ));
+            }
+            if self.feature_grace[..i].iter().any(|(f, _)| f == feature) {
+                return Err(crate::GatewardenError::ConfigError(format!(
+                    "feature_grace lists '{}' more than once",
+                    feature
+                )));
+            }
+        }
         Ok(())
     }
 }
+
+/// Builder for [`GatewardenConfig`], created by [`GatewardenConfig::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct GatewardenConfigBuilder {
+    app_name: Option<&'static str>,
+    feature_name: Option<&'static str>,
+    account_id: Option<&'static str>,
+    public_key_hex: Option<&'static str>,
+    public_key_id: Option<&'static str>,
+    trusted_keys: &'static [TrustedKey],
+    required_entitlements: &'static [&'static str],
+    user_agent_product: Option<&'static str>,
+    cache_namespace: Option<&'static str>,
+    offline_grace: Option<Duration>,
+    feature_grace: &'static [(&'static str, Duration)],
+    max_cache_age: Option<Duration>,
+    fallback_policy: FallbackPolicy,
+    online_budget: Option<Duration>,
+    request_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: ProxyConfig,
+    tls_pins: &'static [&'static str],
+    require_digest: bool,
+    memo_ttl: Option<Duration>,
+    negative_cache_ttl: Duration,
+    validation_rate_limit: Option<u32>,
+    app_version: Option<&'static str>,
+    key_format: KeyFormat,
+    user_agent_extras: UserAgentExtras,
+    connection_pool: ConnectionPool,
+    ip_strategy: IpStrategy,
+}
+
+impl GatewardenConfigBuilder {
+    /// Set the application name (required).
+    pub fn app_name(mut self, app_name: &'static str) -> Self {
+        self.app_name = Some(app_name);
+        self
+    }
+
+    /// Set the feature name.
+    pub fn feature_name(mut self, feature_name: &'static str) -> Self {
+        self.feature_name = Some(feature_name);
+        self
+    }
+
+    /// Set the Keygen account ID (required).
+    pub fn account_id(mut self, account_id: &'static str) -> Self {
+        self.account_id = Some(account_id);
+        self
+    }
+
+    /// Set the Keygen Ed25519 public key, hex-encoded (required).
+    pub fn public_key_hex(mut self, public_key_hex: &'static str) -> Self {
+        self.public_key_hex = Some(public_key_hex);
+        self
+    }
+
+    /// Only accept `public_key_hex` signatures whose `keyid` is `key_id`.
+    pub fn public_key_id(mut self, key_id: &'static str) -> Self {
+        self.public_key_id = Some(key_id);
+        self
+    }
+
+    /// Accept additional Keygen verify keys (for signing-key rotation).
+    pub fn trusted_keys(mut self, keys: &'static [TrustedKey]) -> Self {
+        self.trusted_keys = keys;
+        self
+    }
+
+    /// Set the entitlement codes the license must have.
+    pub fn required_entitlements(mut self, codes: &'static [&'static str]) -> Self {
+        self.required_entitlements = codes;
+        self
+    }
+
+    /// Override the derived User-Agent product identifier.
+    pub fn user_agent_product(mut self, product: &'static str) -> Self {
+        self.user_agent_product = Some(product);
+        self
+    }
+
+    /// Override the derived cache namespace.
+    pub fn cache_namespace(mut self, namespace: &'static str) -> Self {
+        self.cache_namespace = Some(namespace);
+        self
+    }
+
+    /// Override the default 24-hour offline grace period.
+    pub fn offline_grace(mut self, grace: Duration) -> Self {
+        self.offline_grace = Some(grace);
+        self
+    }
+
+    /// Set per-feature offline grace overrides.
+    pub fn feature_grace(mut self, overrides: &'static [(&'static str, Duration)]) -> Self {
+        self.feature_grace = overrides;
+        self
+    }
+
+    /// Override the default 90-day ceiling on cache record age.
+    pub fn max_cache_age(mut self, max_age: Duration) -> Self {
+        self.max_cache_age = Some(max_age);
+        self
+    }
+
+    /// Set the offline fallback policy.
+    pub fn fallback_policy(mut self, policy: FallbackPolicy) -> Self {
+        self.fallback_policy = policy;
+        self
+    }
+
+    /// Cap time spent on online validation when a valid cache exists.
+    ///
+    /// If Keygen has not answered within `budget`, the request is abandoned
+    /// and the verified cached result is used instead.
+    pub fn online_budget(mut self, budget: Duration) -> Self {
+        self.online_budget = Some(budget);
+        self
+    }
+
+    /// Set the total timeout for Keygen requests (defaults to 30 seconds).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the connection timeout for Keygen requests (defaults to 10 seconds).
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how requests to Keygen are proxied (defaults to the system proxy).
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Pin Keygen's TLS certificate chain to these SPKI hashes.
+    ///
+    /// Include a backup pin so a certificate rotation does not lock
+    /// clients out.
+    pub fn tls_pins(mut self, pins: &'static [&'static str]) -> Self {
+        self.tls_pins = pins;
+        self
+    }
+
+    /// Require a Digest header on every response, binding the body to the signature.
+    pub fn require_digest(mut self, require: bool) -> Self {
+        self.require_digest = require;
+        self
+    }
+
+    /// Set how long successful results are reused from memory (defaults
+    /// to 60 seconds; zero disables).
+    pub fn memo_ttl(mut self, ttl: Duration) -> Self {
+        self.memo_ttl = Some(ttl);
+        self
+    }
+
+    /// Set how long Keygen's refusal of a key is reused (defaults to
+    /// zero, which disables negative caching).
+    pub fn negative_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_cache_ttl = ttl;
+        self
+    }
+
+    /// Cap online validations per key per minute; calls over the cap are
+    /// answered from the verified cache.
+    pub fn validation_rate_limit(mut self, per_minute: u32) -> Self {
+        self.validation_rate_limit = Some(per_minute);
+        self
+    }
+
+    /// Set the running application's version, checked against license
+    /// `maxVersion` metadata.
+    pub fn app_version(mut self, version: &'static str) -> Self {
+        self.app_version = Some(version);
+        self
+    }
+
+    /// Normalize and check license keys with `format` before use.
+    pub fn key_format(mut self, format: KeyFormat) -> Self {
+        self.key_format = format;
+        self
+    }
+
+    /// Append the platform and build details in `extras` to the
+    /// User-Agent.
+    pub fn user_agent_extras(mut self, extras: UserAgentExtras) -> Self {
+        self.user_agent_extras = extras;
+        self
+    }
+
+    /// Reuse connections to Keygen as configured by `pool`.
+    pub fn connection_pool(mut self, pool: ConnectionPool) -> Self {
+        self.connection_pool = pool;
+        self
+    }
+
+    /// Resolve `api.keygen.sh` with `strategy`.
+    pub fn ip_strategy(mut self, strategy: IpStrategy) -> Self {
+        self.ip_strategy = strategy;
+        self
+    }
+
+    /// Build and validate the configuration.
+    ///
+    /// # Errors
+    /// - `ConfigError` - A required field is missing or validation fails
+    pub fn build(self) -> Result<GatewardenConfig, GatewardenError> {
+        let app_name = required(self.app_name, "app_name")?;
+        let account_id = required(self.account_id, "account_id")?;
+        let public_key_hex = required(self.public_key_hex, "public_key_hex")?;
+        let feature_name = self.feature_name.unwrap_or("");
+
+        let derived = || match feature_name {
+            "" => app_name,
+            feature => intern(format!("{}-{}", app_name, feature)),
+        };
+
+        let config = GatewardenConfig {
+            app_name,
+            feature_name,
+            account_id,
+            public_key_hex,
+            public_key_id: self.public_key_id,
+            trusted_keys: self.trusted_keys,
+            required_entitlements: self.required_entitlements,
+            user_agent_product: self.user_agent_product.unwrap_or_else(derived),
+            cache_namespace: self.cache_namespace.unwrap_or_else(derived),
+            offline_grace: self.offline_grace.unwrap_or(DEFAULT_OFFLINE_GRACE),
+            feature_grace: self.feature_grace,
+            max_cache_age: self.max_cache_age.unwrap_or(DEFAULT_MAX_CACHE_AGE),
+            fallback_policy: self.fallback_policy,
+            online_budget: self.online_budget,
+            request_timeout: self.request_timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT),
+            connect_timeout: self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT),
+            proxy: self.proxy,
+            tls_pins: self.tls_pins,
+            require_digest: self.require_digest,
+            memo_ttl: self.memo_ttl.unwrap_or(DEFAULT_MEMO_TTL),
+            negative_cache_ttl: self.negative_cache_ttl,
+            validation_rate_limit: self.validation_rate_limit,
+            app_version: self.app_version,
+            key_format: self.key_format,
+            user_agent_extras: self.user_agent_extras,
+            connection_pool: self.connection_pool,
+            ip_strategy: self.ip_strategy,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Error for a missing or empty required builder field.
+fn required(value: Option<&'static str>, field: &str) -> Result<&'static str, GatewardenError> {
+    match value {
+        Some(v) if !v.is_empty() => Ok(v),
+        _ => Err(GatewardenError::ConfigError(format!(
+            "{} is required",
+            field
+        ))),
+    }
+}
+
+/// Derived names, leaked once each so they can be stored as `&'static str`.
+static INTERNED: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Intern a derived string, reusing an earlier allocation if one exists.
+fn intern(value: String) -> &'static str {
+    match INTERNED.lock() {
+        Ok(mut interned) => {
+            if let Some(existing) = interned.get(value.as_str()) {
+                return existing;
+            }
+            let leaked: &'static str = Box::leak(value.into_boxed_str());
+            interned.insert(leaked);
+            leaked
+        }
+        Err(_) => Box::leak(value.into_boxed_str()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::TEST_PUBLIC_KEY_HEX;
+
+    #[test]
+    fn test_builder_defaults() {
+        let config = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .feature_name("vision")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.cache_namespace, "shimmy-vision");
+        assert_eq!(config.user_agent_product, "shimmy-vision");
+        assert_eq!(config.offline_grace, DEFAULT_OFFLINE_GRACE);
+        assert_eq!(config.max_cache_age, DEFAULT_MAX_CACHE_AGE);
+        assert_eq!(config.fallback_policy, FallbackPolicy::default());
+        assert_eq!(config.online_budget, None);
+        assert_eq!(config.request_timeout, DEFAULT_REQUEST_TIMEOUT);
+        assert_eq!(config.connect_timeout, DEFAULT_CONNECT_TIMEOUT);
+        assert_eq!(config.proxy, ProxyConfig::System);
+        assert!(!config.require_digest);
+        assert_eq!(config.memo_ttl, DEFAULT_MEMO_TTL);
+        assert_eq!(config.negative_cache_ttl, Duration::ZERO);
+        assert_eq!(config.validation_rate_limit, None);
+        assert_eq!(config.app_version, None);
+        assert_eq!(config.key_format, KeyFormat::default());
+        assert_eq!(config.user_agent_extras, UserAgentExtras::default());
+        assert_eq!(config.connection_pool, ConnectionPool::new());
+        assert_eq!(config.ip_strategy, IpStrategy::System);
+        assert_eq!(config.public_key_id, None);
+        assert!(config.required_entitlements.is_empty());
+    }
+
+    #[test]
+    fn test_builder_overrides() {
+        let config = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .cache_namespace("custom-ns")
+            .offline_grace(Duration::from_secs(60))
+            .required_entitlements(&["PRO"])
+            .online_budget(Duration::from_secs(2))
+            .validation_rate_limit(10)
+            .app_version("2.3.1")
+            .public_key_id("1fddcec8-8dd3-4d8d-9b16-215cac0f9b52")
+            .build()
+            .unwrap();
+
+        assert_eq!(config.cache_namespace, "custom-ns");
+        assert_eq!(config.user_agent_product, "shimmy");
+        assert_eq!(config.offline_grace, Duration::from_secs(60));
+        assert_eq!(config.required_entitlements, ["PRO"]);
+        assert_eq!(config.online_budget, Some(Duration::from_secs(2)));
+        assert_eq!(config.validation_rate_limit, Some(10));
+        assert_eq!(config.app_version, Some("2.3.1"));
+        assert_eq!(
+            config.public_key_id,
+            Some("1fddcec8-8dd3-4d8d-9b16-215cac0f9b52")
+        );
+    }
+
+    #[test]
+    fn test_builder_missing_required() {
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .build();
+        assert!(
+            matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("account_id"))
+        );
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex("too-short")
+            .build();
+        assert!(matches!(result, Err(GatewardenError::ConfigError(_))));
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .online_budget(Duration::ZERO)
+            .build();
+        assert!(
+            matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("online_budget"))
+        );
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .app_version("nightly")
+            .build();
+        assert!(
+            matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("app_version"))
+        );
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .user_agent_extras(UserAgentExtras::new().build("1.0 (patched)"))
+            .build();
+        assert!(
+            matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("user_agent_extras"))
+        );
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .connect_timeout(Duration::ZERO)
+            .build();
+        assert!(
+            matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("connect_timeout"))
+        );
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .proxy(ProxyConfig::Url("not a url"))
+            .build();
+        assert!(matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("proxy")));
+
+        let result = GatewardenConfig::builder()
+            .app_name("shimmy")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .tls_pins(&["sha1/not-a-pin"])
+            .build();
+        assert!(
+            matches!(result, Err(GatewardenError::ConfigError(msg)) if msg.contains("TLS pin"))
+        );
+    }
+}
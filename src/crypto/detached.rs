@@ -0,0 +1,54 @@
+//! Detached Ed25519 signatures over Gatewarden's own signed documents.
+//!
+//! Entitlement bundles, management links, usage reports, and billing
+//! exports are all signed the same way: an Ed25519 signature over a
+//! domain-separation prefix followed by the payload, carried next to the
+//! payload as base64. The prefix keeps a signature made for one kind of
+//! document from verifying as another.
+
+use super::verify::{decode_public_key, verify_ed25519};
+use crate::GatewardenError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signer, SigningKey};
+
+/// Base64-encoded signature over `domain` followed by `payload`.
+pub(crate) fn sign_detached(domain: &str, payload: &str, signing_seed: &[u8; 32]) -> String {
+    let signing_key = SigningKey::from_bytes(signing_seed);
+    let message = format!("{}{}", domain, payload);
+    STANDARD.encode(signing_key.sign(message.as_bytes()).to_bytes())
+}
+
+/// Verify a [`sign_detached`] signature.
+///
+/// # Errors
+/// - `SignatureInvalid` - Payload, domain, or signature does not match
+/// - `ConfigError` - Public key is malformed
+pub(crate) fn verify_detached(
+    domain: &str,
+    payload: &str,
+    signature: &str,
+    public_key_hex: &str,
+) -> Result<(), GatewardenError> {
+    let verifying_key = decode_public_key(public_key_hex)?;
+    let message = format!("{}{}", domain, payload);
+    verify_ed25519(signature, &message, &verifying_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+
+    #[test]
+    fn test_signature_bound_to_domain() {
+        let signature = sign_detached("doc-a-v1\n", "payload", &TEST_SIGNING_SEED);
+        assert!(verify_detached("doc-a-v1\n", "payload", &signature, TEST_PUBLIC_KEY_HEX).is_ok());
+
+        for (domain, payload) in [("doc-b-v1\n", "payload"), ("doc-a-v1\n", "payload!")] {
+            assert!(matches!(
+                verify_detached(domain, payload, &signature, TEST_PUBLIC_KEY_HEX),
+                Err(GatewardenError::SignatureInvalid { .. })
+            ));
+        }
+    }
+}
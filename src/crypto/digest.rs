@@ -1,8 +1,40 @@
-//! SHA-256 digest computation.
+//! Body digest computation and verification.
+//!
+//! Keygen currently sends `Digest: sha-256=<base64>`. SHA-512 and
+//! multi-value headers (`sha-256=..., sha-512=...`) are also accepted; the
+//! strongest supported value is checked and unknown algorithms are ignored.
 
 use crate::GatewardenError;
 use base64::{engine::general_purpose::STANDARD, Engine};
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
+
+/// Digest algorithms that can be verified, ordered weakest to strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DigestAlgorithm {
+    /// `sha-256`
+    Sha256,
+    /// `sha-512`
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Look up an algorithm by its Digest header name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "sha-256" => Some(Self::Sha256),
+            "sha-512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Base64-encoded digest of `body` with this algorithm.
+    pub fn digest_b64(&self, body: &[u8]) -> String {
+        match self {
+            Self::Sha256 => sha256_b64(body),
+            Self::Sha512 => STANDARD.encode(Sha512::digest(body)),
+        }
+    }
+}
 
 /// Compute SHA-256 digest of body and return base64-encoded result.
 ///
@@ -17,15 +49,19 @@ pub fn format_digest_header(body: &[u8]) -> String {
     format!("sha-256={}", sha256_b64(body))
 }
 
-/// Parse a Digest header and extract the base64 value.
+/// Parse a Digest header and pick the strongest supported value.
 ///
-/// Expected format: `sha-256=<base64>`
-pub fn parse_digest_header(header: &str) -> Option<String> {
-    let header = header.trim();
+/// Accepts one or more comma-separated `<algorithm>=<base64>` entries,
+/// e.g. `sha-256=<base64>, sha-512=<base64>`. Returns `None` if no entry
+/// uses a supported algorithm.
+pub fn parse_digest_header(header: &str) -> Option<(DigestAlgorithm, String)> {
     header
-        .strip_prefix("sha-256=")
-        .or_else(|| header.strip_prefix("SHA-256="))
-        .map(|s| s.to_string())
+        .split(',')
+        .filter_map(|entry| {
+            let (name, value) = entry.trim().split_once('=')?;
+            Some((DigestAlgorithm::from_name(name)?, value.to_string()))
+        })
+        .max_by_key(|(algorithm, _)| *algorithm)
 }
 
 /// Compare computed digest to Digest header.
@@ -43,15 +79,15 @@ pub fn verify_digest(body: &[u8], digest_header: Option<&str>) -> Result<(), Gat
         return Ok(());
     };
 
-    let Some(expected_b64) = parse_digest_header(header) else {
-        // Malformed digest header - treat as mismatch
-        return Err(GatewardenError::DigestMismatch);
+    let Some((algorithm, expected_b64)) = parse_digest_header(header) else {
+        // Malformed or unknown-only digest header - treat as mismatch
+        return Err(GatewardenError::DigestMismatch { request_id: None });
     };
 
-    let computed_b64 = sha256_b64(body);
+    let computed_b64 = algorithm.digest_b64(body);
 
     if computed_b64 != expected_b64 {
-        return Err(GatewardenError::DigestMismatch);
+        return Err(GatewardenError::DigestMismatch { request_id: None });
     }
 
     Ok(())
@@ -83,13 +119,19 @@ mod tests {
     #[test]
     fn test_parse_digest_header_valid() {
         let result = parse_digest_header("sha-256=abc123=");
-        assert_eq!(result, Some("abc123=".to_string()));
+        assert_eq!(
+            result,
+            Some((DigestAlgorithm::Sha256, "abc123=".to_string()))
+        );
     }
 
     #[test]
     fn test_parse_digest_header_uppercase() {
         let result = parse_digest_header("SHA-256=abc123=");
-        assert_eq!(result, Some("abc123=".to_string()));
+        assert_eq!(
+            result,
+            Some((DigestAlgorithm::Sha256, "abc123=".to_string()))
+        );
     }
 
     #[test]
@@ -98,6 +140,40 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn test_parse_digest_header_picks_strongest() {
+        let result = parse_digest_header("sha-512=def456=, SHA-256=abc123=, md5=xyz");
+        assert_eq!(
+            result,
+            Some((DigestAlgorithm::Sha512, "def456=".to_string()))
+        );
+        assert_eq!(parse_digest_header("md5=abc, crc32=def"), None);
+    }
+
+    #[test]
+    fn test_verify_digest_sha512() {
+        let body = b"test body";
+        let sha512 = format!("sha-512={}", DigestAlgorithm::Sha512.digest_b64(body));
+        assert!(verify_digest(body, Some(&sha512)).is_ok());
+
+        // The strongest value decides, even if a weaker one matches
+        let mixed = format!(
+            "{}, sha-512={}",
+            format_digest_header(body),
+            sha256_b64(body)
+        );
+        assert!(matches!(
+            verify_digest(body, Some(&mixed)),
+            Err(GatewardenError::DigestMismatch { .. })
+        ));
+
+        // Unknown algorithms only: fail closed
+        assert!(matches!(
+            verify_digest(body, Some("sha-3-256=abc=")),
+            Err(GatewardenError::DigestMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_verify_digest_match() {
         let body = b"test body";
@@ -111,7 +187,10 @@ mod tests {
         let body = b"test body";
         let wrong_header = "sha-256=AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
         let result = verify_digest(body, Some(wrong_header));
-        assert!(matches!(result, Err(GatewardenError::DigestMismatch)));
+        assert!(matches!(
+            result,
+            Err(GatewardenError::DigestMismatch { .. })
+        ));
     }
 
     #[test]
@@ -125,6 +204,9 @@ mod tests {
     fn test_verify_digest_malformed_header() {
         let body = b"test body";
         let result = verify_digest(body, Some("not-a-valid-digest"));
-        assert!(matches!(result, Err(GatewardenError::DigestMismatch)));
+        assert!(matches!(
+            result,
+            Err(GatewardenError::DigestMismatch { .. })
+        ));
     }
 }
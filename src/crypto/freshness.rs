@@ -16,8 +16,9 @@ pub const MAX_FUTURE_TOLERANCE_SECONDS: i64 = 60;
 pub fn parse_rfc2822_date(date_str: &str) -> Result<DateTime<Utc>, GatewardenError> {
     DateTime::parse_from_rfc2822(date_str)
         .map(|dt| dt.with_timezone(&Utc))
-        .map_err(|e| {
-            GatewardenError::ProtocolError(format!("Invalid date header: {} ({})", date_str, e))
+        .map_err(|e| GatewardenError::ProtocolError {
+            message: format!("Invalid date header: {}", date_str),
+            source: Some(e.into()),
         })
 }
 
@@ -39,12 +40,15 @@ pub fn check_freshness<C: Clock + ?Sized>(
 
     // Reject stale responses (replay attack)
     if age_seconds > MAX_RESPONSE_AGE_SECONDS {
-        return Err(GatewardenError::ResponseTooOld { age_seconds });
+        return Err(GatewardenError::ResponseTooOld {
+            age_seconds,
+            request_id: None,
+        });
     }
 
     // Reject future responses (clock tampering)
     if age_seconds < -MAX_FUTURE_TOLERANCE_SECONDS {
-        return Err(GatewardenError::ResponseFromFuture);
+        return Err(GatewardenError::ResponseFromFuture { request_id: None });
     }
 
     Ok(())
@@ -74,7 +78,7 @@ mod tests {
     #[test]
     fn test_parse_rfc2822_invalid() {
         let result = parse_rfc2822_date("not a date");
-        assert!(matches!(result, Err(GatewardenError::ProtocolError(_))));
+        assert!(matches!(result, Err(GatewardenError::ProtocolError { .. })));
     }
 
     #[test]
@@ -140,7 +144,10 @@ mod tests {
 
         // Response is 135 seconds in the future - exceeds 60s tolerance
         let result = check_freshness(response_date, &clock);
-        assert!(matches!(result, Err(GatewardenError::ResponseFromFuture)));
+        assert!(matches!(
+            result,
+            Err(GatewardenError::ResponseFromFuture { .. })
+        ));
     }
 
     #[test]
@@ -0,0 +1,138 @@
+//! Trusted Keygen verify keys and signing-key rotation.
+//!
+//! A response is accepted if any trusted key verifies its signature. Keys
+//! are tried in order (the configured `public_key_hex` first), skipping
+//! those whose `key_id` differs from the signature header's `keyid`, so a
+//! vendor can ship a new key alongside the old one and let old binaries
+//! keep working through the transition window.
+
+use crate::crypto::verify::{decode_public_key, verify_ed25519, ParsedSignatureHeader};
+use crate::GatewardenError;
+
+/// An additional Keygen verify key accepted for response signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedKey {
+    /// `keyid` this key signs as (Keygen uses the account ID).
+    /// `None` tries the key whatever the header's `keyid`.
+    pub key_id: Option<&'static str>,
+
+    /// Ed25519 verify key (64 hex characters).
+    pub public_key_hex: &'static str,
+}
+
+/// The ordered set of keys a signature is checked against.
+///
+/// A bare `&str` converts to a ring holding just that key, which accepts
+/// any `keyid`.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyRing<'a> {
+    primary: &'a str,
+    primary_key_id: Option<&'a str>,
+    additional: &'a [TrustedKey],
+}
+
+impl<'a> KeyRing<'a> {
+    /// Ring with a primary key (signing as `primary_key_id`) followed by
+    /// `additional` keys.
+    pub fn new(
+        primary: &'a str,
+        primary_key_id: Option<&'a str>,
+        additional: &'a [TrustedKey],
+    ) -> Self {
+        Self {
+            primary,
+            primary_key_id,
+            additional,
+        }
+    }
+
+    /// Keys eligible for a header's `keyid`, in order.
+    fn candidates(&self, key_id: Option<&'a str>) -> impl Iterator<Item = &'a str> + 'a {
+        let eligible = move |candidate: Option<&str>| match (candidate, key_id) {
+            (Some(expected), Some(actual)) => expected.eq_ignore_ascii_case(actual),
+            _ => true,
+        };
+
+        std::iter::once((self.primary_key_id, self.primary))
+            .chain(self.additional.iter().map(|k| (k.key_id, k.public_key_hex)))
+            .filter(move |(candidate, _)| eligible(*candidate))
+            .map(|(_, key)| key)
+    }
+
+    /// Verify a parsed signature over `signing_string` with the first key
+    /// that accepts it.
+    ///
+    /// # Errors
+    /// - `SignatureInvalid` - No eligible key verifies the signature
+    /// - `ConfigError` - An eligible key is malformed
+    /// - `ProtocolError` - The signature is not valid base64
+    pub fn verify(
+        &self,
+        parsed: &ParsedSignatureHeader,
+        signing_string: &str,
+    ) -> Result<(), GatewardenError> {
+        for key in self.candidates(parsed.key_id.as_deref()) {
+            let verifying_key = decode_public_key(key)?;
+            match verify_ed25519(&parsed.signature, signing_string, &verifying_key) {
+                Err(GatewardenError::SignatureInvalid { .. }) => continue,
+                result => return result,
+            }
+        }
+        Err(GatewardenError::SignatureInvalid { request_id: None })
+    }
+}
+
+impl<'a> From<&'a str> for KeyRing<'a> {
+    fn from(public_key_hex: &'a str) -> Self {
+        Self::new(public_key_hex, None, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const OTHER_KEY_HEX: &str = "799efc7752286e6c3815b13358d98fc0f0b566764458adcb48f1be2c10a55906";
+
+    fn signed(message: &str, key_id: Option<&str>) -> ParsedSignatureHeader {
+        let signature = SigningKey::from_bytes(&TEST_SIGNING_SEED).sign(message.as_bytes());
+        ParsedSignatureHeader {
+            key_id: key_id.map(String::from),
+            algorithm: "ed25519".to_string(),
+            signature: STANDARD.encode(signature.to_bytes()),
+            headers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rotated_key_verifies() {
+        let rotated = [TrustedKey {
+            key_id: Some("new-account"),
+            public_key_hex: TEST_PUBLIC_KEY_HEX,
+        }];
+        let ring = KeyRing::new(OTHER_KEY_HEX, Some("old-account"), &rotated);
+
+        assert!(ring
+            .verify(&signed("msg", Some("new-account")), "msg")
+            .is_ok());
+        assert!(ring.verify(&signed("msg", None), "msg").is_ok());
+        assert!(matches!(
+            ring.verify(&signed("msg", Some("new-account")), "other"),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+        // keyid selects the old key only, which did not sign
+        assert!(matches!(
+            ring.verify(&signed("msg", Some("old-account")), "msg"),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bare_key_accepts_any_key_id() {
+        let ring = KeyRing::from(TEST_PUBLIC_KEY_HEX);
+        assert!(ring.verify(&signed("msg", Some("anything")), "msg").is_ok());
+    }
+}
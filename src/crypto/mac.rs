@@ -0,0 +1,48 @@
+//! HMAC-SHA256 for locally sealed files (usage meter, audit log).
+
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// HMAC-SHA256 keyed per RFC 2104.
+///
+/// Keys longer than the 64-byte block are hashed first, so any key
+/// length gives the standard MAC.
+pub(crate) fn new_hmac(key: &[u8]) -> Hmac<Sha256> {
+    <Hmac<Sha256> as Mac>::new_from_slice(key).unwrap_or_else(|_| {
+        // Not reached: HMAC accepts keys of any length. This is the
+        // RFC 2104 long-key path spelled out, so it gives the same MAC.
+        let mut block = [0u8; 64];
+        block[..32].copy_from_slice(&Sha256::digest(key));
+        <Hmac<Sha256> as KeyInit>::new(&block.into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mac_hex(key: &[u8], data: &[u8]) -> String {
+        let mut mac = new_hmac(key);
+        mac.update(data);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn test_hmac_matches_rfc4231() {
+        // Test case 2 (short key)
+        assert_eq!(
+            mac_hex(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        // Test case 6 (131-byte key, hashed before use)
+        assert_eq!(
+            mac_hex(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            ),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
@@ -1,7 +1,11 @@
 //! Cryptographic primitives for response verification.
 
+pub(crate) mod detached;
 pub mod digest;
 pub mod freshness;
+pub mod keys;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod mac;
 pub mod pipeline;
 pub mod signing;
 pub mod verify;
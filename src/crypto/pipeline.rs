@@ -6,13 +6,11 @@
 //! 3. Verify signature
 //! 4. Check freshness (not replayed, not future-dated)
 
-use crate::client::http::KeygenResponse;
+use crate::client::response::KeygenResponse;
 use crate::clock::Clock;
 use crate::crypto::{
-    digest::verify_digest,
-    freshness::check_date_freshness,
-    signing::build_signing_string,
-    verify::{decode_public_key, parse_signature_header, verify_ed25519},
+    digest::verify_digest, freshness::check_date_freshness, keys::KeyRing,
+    signing::build_signing_string_for, verify::parse_signature_header,
 };
 use crate::GatewardenError;
 
@@ -26,7 +24,7 @@ use crate::GatewardenError;
 ///
 /// # Arguments
 /// * `response` - The HTTP response to verify
-/// * `public_key_hex` - The Keygen account's Ed25519 public key (hex-encoded)
+/// * `keys` - Trusted Keygen verify keys (a hex-encoded key or a `KeyRing`)
 /// * `clock` - Clock for freshness checks
 ///
 /// # Returns
@@ -34,129 +32,98 @@ use crate::GatewardenError;
 /// * `Err(SignatureMissing)` - Missing required headers (fail-closed)
 /// * `Err(DigestMismatch)` - Digest header doesn't match body
 /// * `Err(SignatureInvalid)` - Signature verification failed
+/// * `Err(ProtocolError)` - Signature header malformed, or its `headers` list omits a required component
 /// * `Err(ResponseTooOld)` - Response is stale (possible replay)
 /// * `Err(ResponseFromFuture)` - Response date is in the future
-pub fn verify_response(
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "gatewarden.verify_response",
+        level = "debug",
+        skip_all,
+        fields(status = response.status, has_digest = response.digest.is_some()),
+        err(level = "warn")
+    )
+)]
+pub fn verify_response<'a>(
     response: &KeygenResponse,
-    public_key_hex: &str,
+    keys: impl Into<KeyRing<'a>>,
     clock: &dyn Clock,
 ) -> Result<(), GatewardenError> {
-    // 1. Fail-closed on missing required headers
-    let signature_header = response
-        .signature
-        .as_ref()
-        .ok_or(GatewardenError::SignatureMissing)?;
-
-    let date_header = response
-        .date
-        .as_ref()
-        .ok_or(GatewardenError::SignatureMissing)?;
-
-    // 2. Verify digest if present
-    verify_digest(&response.body, response.digest.as_deref())?;
-
-    // 3. Parse signature header
-    let parsed_sig = parse_signature_header(signature_header)?;
-
-    // 4. Decode public key
-    let verifying_key = decode_public_key(public_key_hex)?;
-
-    // 5. Build signing string
-    let signing_string = build_signing_string(
-        "post",
-        &response.request_path,
-        &response.host,
-        date_header,
-        response.digest.as_deref(),
-    );
-
-    // 6. Verify Ed25519 signature
-    verify_ed25519(&parsed_sig.signature, &signing_string, &verifying_key)?;
-
-    // 7. Check freshness
-    check_date_freshness(date_header, clock)?;
-
-    Ok(())
+    verify(response, keys.into(), Some(clock))
+        .map_err(|e| e.with_request_id(response.request_id.as_deref()))
 }
 
 /// Verify a Keygen response without freshness checks.
 ///
 /// This is used for cached responses where we don't apply the 5-minute window.
 /// The offline_grace is checked separately by the cache layer.
-pub fn verify_response_signature_only(
+pub fn verify_response_signature_only<'a>(
     response: &KeygenResponse,
-    public_key_hex: &str,
+    keys: impl Into<KeyRing<'a>>,
 ) -> Result<(), GatewardenError> {
-    // Fail-closed on missing required headers
+    verify(response, keys.into(), None)
+        .map_err(|e| e.with_request_id(response.request_id.as_deref()))
+}
+
+/// The shared pipeline; freshness is checked only given a `clock`.
+fn verify(
+    response: &KeygenResponse,
+    keys: KeyRing<'_>,
+    clock: Option<&dyn Clock>,
+) -> Result<(), GatewardenError> {
+    // 1. Fail-closed on missing required headers
     let signature_header = response
         .signature
         .as_ref()
-        .ok_or(GatewardenError::SignatureMissing)?;
+        .ok_or(GatewardenError::SignatureMissing { request_id: None })?;
 
     let date_header = response
         .date
         .as_ref()
-        .ok_or(GatewardenError::SignatureMissing)?;
+        .ok_or(GatewardenError::SignatureMissing { request_id: None })?;
 
-    // Verify digest if present
+    // 2. Verify digest if present
     verify_digest(&response.body, response.digest.as_deref())?;
 
-    // Parse signature header
+    // 3. Parse signature header
     let parsed_sig = parse_signature_header(signature_header)?;
 
-    // Decode public key
-    let verifying_key = decode_public_key(public_key_hex)?;
-
-    // Build signing string
-    let signing_string = build_signing_string(
-        "post",
+    // 4. Build signing string
+    let signing_string = build_signing_string_for(
+        &parsed_sig.headers,
+        &response.method,
         &response.request_path,
         &response.host,
         date_header,
         response.digest.as_deref(),
-    );
+    )?;
 
-    // Verify Ed25519 signature
-    verify_ed25519(&parsed_sig.signature, &signing_string, &verifying_key)?;
+    // 5. Verify Ed25519 signature with the first eligible trusted key
+    keys.verify(&parsed_sig, &signing_string)?;
+
+    // 6. Check freshness
+    if let Some(clock) = clock {
+        check_date_freshness(date_header, clock)?;
+    }
 
     Ok(())
 }
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::clock::MockClock;
     use crate::crypto::digest::format_digest_header;
+    use crate::crypto::signing::build_signing_string;
+    use crate::mock::{test_signature_header, TEST_PUBLIC_KEY_HEX};
     use base64::{engine::general_purpose::STANDARD, Engine};
     use chrono::{TimeZone, Utc};
-    use ed25519_dalek::{Signer, SigningKey};
-
-    // Test signing seed + verifying key (DO NOT USE IN PRODUCTION)
-    // This is a well-known Ed25519 test vector seed.
-    const TEST_SIGNING_SEED_BYTES: [u8; 32] = [
-        0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c,
-        0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae,
-        0x7f, 0x60,
-    ];
-    const TEST_VERIFY_KEY_HEX: &str =
-        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
-
-    fn get_test_signing_key() -> SigningKey {
-        SigningKey::from_bytes(&TEST_SIGNING_SEED_BYTES)
-    }
-
-    fn sign_test_data(signing_string: &str) -> String {
-        let signing_key = get_test_signing_key();
-        let signature = signing_key.sign(signing_string.as_bytes());
-        STANDARD.encode(signature.to_bytes())
-    }
 
     fn create_test_response(body: &str, date: &str, host: &str, path: &str) -> KeygenResponse {
         let body_bytes = body.as_bytes().to_vec();
         let digest = format_digest_header(&body_bytes);
         let signing_string = build_signing_string("post", path, host, date, Some(&digest));
-        let signature_b64 = sign_test_data(&signing_string);
-        let signature_header = format!(r#"algorithm="ed25519", signature="{}""#, signature_b64);
+        let signature_header = test_signature_header(&signing_string);
 
         KeygenResponse {
             status: 200,
@@ -164,8 +131,11 @@ mod tests {
             signature: Some(signature_header),
             digest: Some(digest),
             body: body_bytes,
+            method: "post".to_string(),
             request_path: path.to_string(),
             host: host.to_string(),
+            request_id: None,
+            rate_limit: None,
         }
     }
 
@@ -179,10 +149,32 @@ mod tests {
             "/v1/accounts/test/licenses/actions/validate-key",
         );
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_verify_get_response() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        let date = "Wed, 15 Jan 2025 12:00:00 GMT";
+        let path = "/v1/accounts/test/licenses/lic-1/entitlements";
+        let body = r#"{"data":[]}"#;
+        let digest = format_digest_header(body.as_bytes());
+        let signing_string =
+            build_signing_string("get", path, "api.keygen.sh", date, Some(&digest));
+        let mut response = create_test_response(body, date, "api.keygen.sh", path);
+        response.signature = Some(test_signature_header(&signing_string));
+
+        // Verified as a POST, the signing string does not match
+        assert!(matches!(
+            verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+
+        response.method = "GET".to_string();
+        assert!(verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock).is_ok());
+    }
+
     #[test]
     fn test_verify_response_missing_signature() {
         let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
@@ -194,8 +186,11 @@ mod tests {
         );
         response.signature = None;
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
-        assert!(matches!(result, Err(GatewardenError::SignatureMissing)));
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureMissing { .. })
+        ));
     }
 
     #[test]
@@ -209,8 +204,11 @@ mod tests {
         );
         response.date = None;
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
-        assert!(matches!(result, Err(GatewardenError::SignatureMissing)));
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureMissing { .. })
+        ));
     }
 
     #[test]
@@ -225,8 +223,11 @@ mod tests {
         // Tamper with body
         response.body = b"tampered body".to_vec();
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
-        assert!(matches!(result, Err(GatewardenError::DigestMismatch)));
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::DigestMismatch { .. })
+        ));
     }
 
     #[test]
@@ -242,8 +243,11 @@ mod tests {
         let wrong_sig = STANDARD.encode([0u8; 64]);
         response.signature = Some(format!(r#"algorithm="ed25519", signature="{}""#, wrong_sig));
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
-        assert!(matches!(result, Err(GatewardenError::SignatureInvalid)));
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
     }
 
     #[test]
@@ -256,7 +260,7 @@ mod tests {
             "/v1/accounts/test/licenses/actions/validate-key",
         );
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
         assert!(matches!(
             result,
             Err(GatewardenError::ResponseTooOld { .. })
@@ -273,8 +277,11 @@ mod tests {
             "/v1/accounts/test/licenses/actions/validate-key",
         );
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
-        assert!(matches!(result, Err(GatewardenError::ResponseFromFuture)));
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::ResponseFromFuture { .. })
+        ));
     }
 
     #[test]
@@ -286,7 +293,7 @@ mod tests {
             "/v1/accounts/test/licenses/actions/validate-key",
         );
 
-        let result = verify_response_signature_only(&response, TEST_VERIFY_KEY_HEX);
+        let result = verify_response_signature_only(&response, TEST_PUBLIC_KEY_HEX);
         assert!(result.is_ok());
     }
 
@@ -300,8 +307,7 @@ mod tests {
 
         // Sign without digest
         let signing_string = build_signing_string("post", path, host, date, None);
-        let signature_b64 = sign_test_data(&signing_string);
-        let signature_header = format!(r#"algorithm="ed25519", signature="{}""#, signature_b64);
+        let signature_header = test_signature_header(&signing_string);
 
         let response = KeygenResponse {
             status: 200,
@@ -309,11 +315,14 @@ mod tests {
             signature: Some(signature_header),
             digest: None,
             body: body.as_bytes().to_vec(),
+            method: "post".to_string(),
             request_path: path.to_string(),
             host: host.to_string(),
+            request_id: None,
+            rate_limit: None,
         };
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
         assert!(result.is_ok());
     }
 
@@ -326,11 +335,17 @@ mod tests {
             signature: None,
             digest: None,
             body: b"{}".to_vec(),
+            method: "post".to_string(),
             request_path: "/test".to_string(),
             host: "api.keygen.sh".to_string(),
+            request_id: None,
+            rate_limit: None,
         };
 
-        let result = verify_response(&response, TEST_VERIFY_KEY_HEX, &clock);
-        assert!(matches!(result, Err(GatewardenError::SignatureMissing)));
+        let result = verify_response(&response, TEST_PUBLIC_KEY_HEX, &clock);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureMissing { .. })
+        ));
     }
 }
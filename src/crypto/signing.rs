@@ -7,6 +7,14 @@
 //! date: <Date header>
 //! digest: sha-256=<base64>
 //! ```
+//!
+//! The `headers` parameter of the signature header lists the components
+//! and their order; [`build_signing_string_for`] follows it.
+
+use crate::GatewardenError;
+
+/// Components every response signature must cover.
+const REQUIRED_COMPONENTS: [&str; 3] = ["(request-target)", "host", "date"];
 
 /// Build the signing string for response signature verification.
 ///
@@ -44,6 +52,73 @@ pub fn build_signing_string(
     }
 }
 
+/// Build the signing string from the signature header's `headers` list.
+///
+/// Components are emitted in the listed order. The list must cover
+/// `(request-target)`, `host`, and `date`, plus `digest` whenever the
+/// response has a Digest header. An empty list (no `headers` parameter)
+/// uses the default `(request-target) host date digest` layout.
+///
+/// # Errors
+/// - `ProtocolError` - A required component is not signed, or an unsupported one is
+/// - `SignatureInvalid` - `digest` is signed but the response has no Digest header
+pub fn build_signing_string_for(
+    headers: &[String],
+    method: &str,
+    path: &str,
+    host: &str,
+    date: &str,
+    digest_header: Option<&str>,
+) -> Result<String, GatewardenError> {
+    if headers.is_empty() {
+        return Ok(build_signing_string(
+            method,
+            path,
+            host,
+            date,
+            digest_header,
+        ));
+    }
+
+    let required = REQUIRED_COMPONENTS
+        .iter()
+        .copied()
+        .chain(digest_header.map(|_| "digest"));
+    for name in required {
+        if !headers.iter().any(|h| h.eq_ignore_ascii_case(name)) {
+            return Err(GatewardenError::ProtocolError {
+                message: format!("Signature does not cover required component: {}", name),
+                source: None,
+            });
+        }
+    }
+
+    let lines = headers
+        .iter()
+        .map(|name| {
+            let name = name.to_ascii_lowercase();
+            let value = match name.as_str() {
+                "(request-target)" => format!("{} {}", method.to_lowercase(), path),
+                "host" => host.to_string(),
+                "date" => date.to_string(),
+                // Stripped Digest header: the signature cannot verify
+                "digest" => digest_header
+                    .ok_or(GatewardenError::SignatureInvalid { request_id: None })?
+                    .to_string(),
+                other => {
+                    return Err(GatewardenError::ProtocolError {
+                        message: format!("Unsupported signed component: {}", other),
+                        source: None,
+                    })
+                }
+            };
+            Ok(format!("{}: {}", name, value))
+        })
+        .collect::<Result<Vec<_>, GatewardenError>>()?;
+
+    Ok(lines.join("\n"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +203,82 @@ mod tests {
 
         assert_eq!(signing, expected);
     }
+
+    fn components(list: &str) -> Vec<String> {
+        list.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_signing_string_follows_headers_list() {
+        let date = "Wed, 09 Jun 2021 16:08:15 GMT";
+        let digest = Some("sha-256=abc123=");
+
+        let default = build_signing_string("post", "/v1/test", "api.keygen.sh", date, digest);
+        let listed = build_signing_string_for(
+            &components("(request-target) host date digest"),
+            "post",
+            "/v1/test",
+            "api.keygen.sh",
+            date,
+            digest,
+        )
+        .unwrap();
+        assert_eq!(listed, default);
+
+        let reordered = build_signing_string_for(
+            &components("date (request-target) Digest host"),
+            "post",
+            "/v1/test",
+            "api.keygen.sh",
+            date,
+            digest,
+        )
+        .unwrap();
+        assert_eq!(
+            reordered,
+            "date: Wed, 09 Jun 2021 16:08:15 GMT\n\
+             (request-target): post /v1/test\n\
+             digest: sha-256=abc123=\n\
+             host: api.keygen.sh"
+        );
+
+        let empty =
+            build_signing_string_for(&[], "post", "/v1/test", "api.keygen.sh", date, digest);
+        assert_eq!(empty.unwrap(), default);
+    }
+
+    #[test]
+    fn test_signing_string_rejects_incomplete_headers_list() {
+        let date = "Wed, 09 Jun 2021 16:08:15 GMT";
+        let build = |list: &str, digest: Option<&str>| {
+            build_signing_string_for(
+                &components(list),
+                "post",
+                "/v1/test",
+                "api.keygen.sh",
+                date,
+                digest,
+            )
+        };
+
+        // Digest present but not signed
+        assert!(matches!(
+            build("(request-target) host date", Some("sha-256=abc123=")),
+            Err(GatewardenError::ProtocolError { message, .. }) if message.contains("digest")
+        ));
+        assert!(matches!(
+            build("host date digest", Some("sha-256=abc123=")),
+            Err(GatewardenError::ProtocolError { message, .. }) if message.contains("request-target")
+        ));
+        assert!(matches!(
+            build("(request-target) host date content-type", None),
+            Err(GatewardenError::ProtocolError { message, .. }) if message.contains("content-type")
+        ));
+        // Signed digest stripped from the response
+        assert!(matches!(
+            build("(request-target) host date digest", None),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+        assert!(build("(request-target) host date", None).is_ok());
+    }
 }
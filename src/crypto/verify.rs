@@ -43,22 +43,27 @@ pub fn parse_signature_header(header: &str) -> Result<ParsedSignatureHeader, Gat
 
     let algorithm = parts
         .get("algorithm")
-        .ok_or_else(|| {
-            GatewardenError::ProtocolError("Missing algorithm in signature header".to_string())
+        .ok_or_else(|| GatewardenError::ProtocolError {
+            message: "Missing algorithm in signature header".to_string(),
+            source: None,
         })?
         .clone();
 
     if algorithm != "ed25519" {
-        return Err(GatewardenError::ProtocolError(format!(
-            "Unsupported signature algorithm: {} (expected ed25519)",
-            algorithm
-        )));
+        return Err(GatewardenError::ProtocolError {
+            message: format!(
+                "Unsupported signature algorithm: {} (expected ed25519)",
+                algorithm
+            ),
+            source: None,
+        });
     }
 
     let signature = parts
         .get("signature")
-        .ok_or_else(|| {
-            GatewardenError::ProtocolError("Missing signature in signature header".to_string())
+        .ok_or_else(|| GatewardenError::ProtocolError {
+            message: "Missing signature in signature header".to_string(),
+            source: None,
         })?
         .clone();
 
@@ -117,17 +122,20 @@ pub fn verify_ed25519(
 ) -> Result<(), GatewardenError> {
     let sig_bytes = STANDARD
         .decode(signature_b64)
-        .map_err(|e| GatewardenError::ProtocolError(format!("Invalid signature base64: {}", e)))?;
+        .map_err(|e| GatewardenError::ProtocolError {
+            message: "Invalid signature base64".to_string(),
+            source: Some(e.into()),
+        })?;
 
     let sig_array: [u8; 64] = sig_bytes
         .try_into()
-        .map_err(|_| GatewardenError::SignatureInvalid)?;
+        .map_err(|_| GatewardenError::SignatureInvalid { request_id: None })?;
 
     let signature = Signature::from_bytes(&sig_array);
 
     verifying_key
         .verify(signing_string.as_bytes(), &signature)
-        .map_err(|_| GatewardenError::SignatureInvalid)?;
+        .map_err(|_| GatewardenError::SignatureInvalid { request_id: None })?;
 
     Ok(())
 }
@@ -154,21 +162,21 @@ mod tests {
     fn test_parse_signature_header_missing_algorithm() {
         let header = r#"keyid="test-id", signature="dGVzdA==""#;
         let result = parse_signature_header(header);
-        assert!(matches!(result, Err(GatewardenError::ProtocolError(_))));
+        assert!(matches!(result, Err(GatewardenError::ProtocolError { .. })));
     }
 
     #[test]
     fn test_parse_signature_header_wrong_algorithm() {
         let header = r#"algorithm="rsa-sha256", signature="dGVzdA==""#;
         let result = parse_signature_header(header);
-        assert!(matches!(result, Err(GatewardenError::ProtocolError(_))));
+        assert!(matches!(result, Err(GatewardenError::ProtocolError { .. })));
     }
 
     #[test]
     fn test_parse_signature_header_missing_signature() {
         let header = r#"algorithm="ed25519", keyid="test""#;
         let result = parse_signature_header(header);
-        assert!(matches!(result, Err(GatewardenError::ProtocolError(_))));
+        assert!(matches!(result, Err(GatewardenError::ProtocolError { .. })));
     }
 
     #[test]
@@ -199,7 +207,7 @@ mod tests {
         let hex_key = "799efc7752286e6c3815b13358d98fc0f0b566764458adcb48f1be2c10a55906";
         let key = decode_public_key(hex_key).unwrap();
         let result = verify_ed25519("not-valid-base64!!!", "test", &key);
-        assert!(matches!(result, Err(GatewardenError::ProtocolError(_))));
+        assert!(matches!(result, Err(GatewardenError::ProtocolError { .. })));
     }
 
     #[test]
@@ -208,7 +216,10 @@ mod tests {
         let key = decode_public_key(hex_key).unwrap();
         // Valid base64 but wrong length
         let result = verify_ed25519("dGVzdA==", "test", &key);
-        assert!(matches!(result, Err(GatewardenError::SignatureInvalid)));
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
     }
 
     #[test]
@@ -218,6 +229,9 @@ mod tests {
         // 64 bytes of zeros (valid length but wrong signature)
         let fake_sig = STANDARD.encode([0u8; 64]);
         let result = verify_ed25519(&fake_sig, "test signing string", &key);
-        assert!(matches!(result, Err(GatewardenError::SignatureInvalid)));
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
     }
 }
@@ -0,0 +1,181 @@
+//! Compile-time embedding of Keygen credentials.
+//!
+//! Account IDs and public keys should be hard-coded, not read from the
+//! environment. [`embed_key!`](crate::embed_key) and
+//! [`embed_account_id!`](crate::embed_account_id) validate the value at
+//! compile time and store it XOR-masked, so the plain string does not
+//! appear verbatim in the binary.
+//!
+//! The masking is light obfuscation against casual `strings` inspection,
+//! not protection: the public key is not secret, and a determined attacker
+//! with the binary can always recover it.
+//!
+//! ```
+//! use gatewarden::{embed_account_id, embed_key};
+//!
+//! let account_id = embed_account_id!("12345678-90ab-cdef-1234-567890abcdef");
+//! let public_key = embed_key!("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+//! assert_eq!(public_key.len(), 64);
+//! # let _ = account_id;
+//! ```
+//!
+//! Malformed values fail the build:
+//!
+//! ```compile_fail
+//! let public_key = gatewarden::embed_key!("not-a-key");
+//! ```
+
+use once_cell::sync::OnceCell;
+
+/// Length of a hex-encoded Ed25519 public key.
+pub const PUBLIC_KEY_HEX_LEN: usize = 64;
+
+/// Length of a hyphenated UUID.
+pub const ACCOUNT_ID_LEN: usize = 36;
+
+/// A credential validated at compile time and stored masked.
+///
+/// Construct through the macros; the constructors are `const fn` so that
+/// validation failures become compile errors.
+#[derive(Clone, Copy)]
+pub struct EmbeddedSecret<const N: usize> {
+    masked: [u8; N],
+}
+
+impl EmbeddedSecret<PUBLIC_KEY_HEX_LEN> {
+    /// Validate and mask an Ed25519 public key (64 hex characters).
+    pub const fn public_key(hex: &str) -> Self {
+        let bytes = hex.as_bytes();
+        if bytes.len() != PUBLIC_KEY_HEX_LEN {
+            panic!("public key must be 64 hex characters");
+        }
+        let mut i = 0;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_hexdigit() {
+                panic!("public key must contain only hex characters");
+            }
+            i += 1;
+        }
+        Self::mask(bytes)
+    }
+}
+
+impl EmbeddedSecret<ACCOUNT_ID_LEN> {
+    /// Validate and mask a Keygen account ID (hyphenated UUID).
+    pub const fn account_id(uuid: &str) -> Self {
+        let bytes = uuid.as_bytes();
+        if bytes.len() != ACCOUNT_ID_LEN {
+            panic!("account ID must be a 36-character UUID");
+        }
+        let mut i = 0;
+        while i < bytes.len() {
+            let valid = match i {
+                8 | 13 | 18 | 23 => bytes[i] == b'-',
+                _ => bytes[i].is_ascii_hexdigit(),
+            };
+            if !valid {
+                panic!("account ID must be a UUID (xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx)");
+            }
+            i += 1;
+        }
+        Self::mask(bytes)
+    }
+}
+
+impl<const N: usize> EmbeddedSecret<N> {
+    const fn mask(bytes: &[u8]) -> Self {
+        let mut masked = [0u8; N];
+        let mut i = 0;
+        while i < N {
+            masked[i] = bytes[i] ^ mask_byte(i);
+            i += 1;
+        }
+        Self { masked }
+    }
+
+    /// Recover the original string.
+    pub fn reveal(&self) -> String {
+        self.masked
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b ^ mask_byte(i)) as char)
+            .collect()
+    }
+}
+
+/// Position-dependent mask byte (small LCG; not cryptographic).
+const fn mask_byte(i: usize) -> u8 {
+    let x = (i as u32).wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    (x >> 16) as u8 | 0x80
+}
+
+/// Lazily revealed value with `'static` lifetime, used by the macros.
+pub struct RevealCell(OnceCell<String>);
+
+impl RevealCell {
+    /// Create an empty cell.
+    pub const fn new() -> Self {
+        Self(OnceCell::new())
+    }
+
+    /// Reveal `secret` on first use and return the cached string.
+    pub fn get<const N: usize>(&'static self, secret: &EmbeddedSecret<N>) -> &'static str {
+        self.0.get_or_init(|| secret.reveal())
+    }
+}
+
+impl Default for RevealCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Embed a Keygen Ed25519 public key, validated at compile time.
+///
+/// Evaluates to a `&'static str` suitable for `GatewardenConfig::public_key_hex`.
+#[macro_export]
+macro_rules! embed_key {
+    ($hex:expr) => {{
+        const EMBEDDED: $crate::embed::EmbeddedSecret<{ $crate::embed::PUBLIC_KEY_HEX_LEN }> =
+            $crate::embed::EmbeddedSecret::public_key($hex);
+        static REVEALED: $crate::embed::RevealCell = $crate::embed::RevealCell::new();
+        REVEALED.get(&EMBEDDED)
+    }};
+}
+
+/// Embed a Keygen account ID, validated at compile time.
+///
+/// Evaluates to a `&'static str` suitable for `GatewardenConfig::account_id`.
+#[macro_export]
+macro_rules! embed_account_id {
+    ($uuid:expr) => {{
+        const EMBEDDED: $crate::embed::EmbeddedSecret<{ $crate::embed::ACCOUNT_ID_LEN }> =
+            $crate::embed::EmbeddedSecret::account_id($uuid);
+        static REVEALED: $crate::embed::RevealCell = $crate::embed::RevealCell::new();
+        REVEALED.get(&EMBEDDED)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::TEST_PUBLIC_KEY_HEX;
+
+    #[test]
+    fn test_embed_key_roundtrip() {
+        let key =
+            crate::embed_key!("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        assert_eq!(key, TEST_PUBLIC_KEY_HEX);
+
+        let account = crate::embed_account_id!("12345678-90ab-cdef-1234-567890abcdef");
+        assert_eq!(account, "12345678-90ab-cdef-1234-567890abcdef");
+    }
+
+    #[test]
+    fn test_embedded_bytes_are_masked() {
+        const SECRET: EmbeddedSecret<PUBLIC_KEY_HEX_LEN> =
+            EmbeddedSecret::public_key(TEST_PUBLIC_KEY_HEX);
+        assert_ne!(&SECRET.masked[..], TEST_PUBLIC_KEY_HEX.as_bytes());
+        assert!(!SECRET.masked.iter().any(|b| b.is_ascii_hexdigit()));
+    }
+}
@@ -4,9 +4,16 @@
 //!
 //! ## License Errors (user-actionable)
 //! - [`GatewardenError::InvalidLicense`] — license expired, revoked, or invalid
+//! - [`GatewardenError::LicenseSuspended`] — license suspended by the vendor
+//! - [`GatewardenError::LicenseBanned`] — license owner is banned
+//! - [`GatewardenError::LicenseOverdue`] — license missed a required check-in
+//! - [`GatewardenError::LicenseExpired`] — cached license is past its expiry date
 //! - [`GatewardenError::EntitlementMissing`] — license lacks required feature
 //! - [`GatewardenError::MissingLicense`] — no license key provided
+//! - [`GatewardenError::MalformedLicenseKey`] — key fails the configured format
+//! - [`GatewardenError::NoValidKey`] — none of several candidate keys was accepted
 //! - [`GatewardenError::UsageLimitExceeded`] — usage cap reached
+//! - [`GatewardenError::VersionNotEntitled`] — app version is newer than the license allows
 //!
 //! ## Security Errors (investigate)
 //! - [`GatewardenError::SignatureInvalid`] — response signature didn't verify
@@ -15,65 +22,159 @@
 //! - [`GatewardenError::ResponseTooOld`] — possible replay attack
 //! - [`GatewardenError::ResponseFromFuture`] — clock tampering suspected
 //! - [`GatewardenError::CacheTampered`] — cached record was modified
+//! - [`GatewardenError::ClockRollback`] — clock set back behind verified server time
+//! - [`GatewardenError::LinkExpired`] — management link is past its expiry
+//! - [`GatewardenError::MeterTampered`] — usage meter was edited or rolled back
+//! - [`GatewardenError::TlsPinMismatch`] — Keygen's certificate matched no configured pin
 //!
 //! ## Network/IO Errors (retry or use cache)
 //! - [`GatewardenError::KeygenTransport`] — network error to Keygen
+//! - [`GatewardenError::RateLimited`] — Keygen responded with HTTP 429
+//! - [`GatewardenError::ServerError`] — Keygen responded with HTTP 5xx
 //! - [`GatewardenError::CacheIO`] — cache read/write failed
 //! - [`GatewardenError::CacheExpired`] — offline grace period exceeded
 //! - [`GatewardenError::MeterIO`] — usage meter I/O failed
+//! - [`GatewardenError::Entropy`] — random number generation failed
 //!
 //! ## Configuration Errors (fix config)
 //! - [`GatewardenError::ConfigError`] — invalid configuration
 //! - [`GatewardenError::ProtocolError`] — unexpected Keygen response format
+//!
+//! [`GatewardenError::category`] returns these groups as an
+//! [`ErrorCategory`], and [`GatewardenError::code`] a stable identifier
+//! for FFI layers and logs that must not depend on `Display` text.
 
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// Underlying error preserved as the `source` of a [`GatewardenError`].
+///
+/// Downcast it to reach the original error, e.g.
+/// `source.downcast_ref::<std::io::Error>()`.
+pub type ErrorSource = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Errors that can occur during license validation.
+///
+/// New variants may be added in minor releases; match with a wildcard arm,
+/// or branch on [`category`](Self::category) instead.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum GatewardenError {
     /// Configuration is invalid.
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
     /// Response signature or date header is missing (fail-closed).
-    #[error("Response signature or date header missing")]
-    SignatureMissing,
+    #[error("Response signature or date header missing{}", request_suffix(.request_id))]
+    SignatureMissing {
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
+    },
 
     /// Response signature verification failed.
-    #[error("Response signature verification failed")]
-    SignatureInvalid,
+    #[error("Response signature verification failed{}", request_suffix(.request_id))]
+    SignatureInvalid {
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
+    },
 
     /// Computed digest does not match Digest header.
-    #[error("Response digest mismatch")]
-    DigestMismatch,
+    #[error("Response digest mismatch{}", request_suffix(.request_id))]
+    DigestMismatch {
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
+    },
 
     /// Response is older than allowed freshness window (replay attack).
-    #[error("Response too old ({age_seconds}s), possible replay attack")]
+    #[error(
+        "Response too old ({age_seconds}s), possible replay attack{}",
+        request_suffix(.request_id)
+    )]
     ResponseTooOld {
         /// Age of the response in seconds.
         age_seconds: i64,
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
     },
 
     /// Response date is in the future (clock tampering).
-    #[error("Response date is in the future, possible clock tampering")]
-    ResponseFromFuture,
+    #[error(
+        "Response date is in the future, possible clock tampering{}",
+        request_suffix(.request_id)
+    )]
+    ResponseFromFuture {
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
+    },
 
     /// Failed to parse Keygen protocol response.
-    #[error("Protocol error: {0}")]
-    ProtocolError(String),
+    #[error("Protocol error: {message}")]
+    ProtocolError {
+        /// What could not be parsed.
+        message: String,
+        /// Underlying parse error, if any.
+        #[source]
+        source: Option<ErrorSource>,
+    },
 
     /// HTTP transport error communicating with Keygen.
-    #[error("Keygen transport error: {0}")]
-    KeygenTransport(String),
+    #[error("Keygen transport error: {message}")]
+    KeygenTransport {
+        /// What failed.
+        message: String,
+        /// Which stage of the request failed.
+        kind: TransportFailure,
+        /// Underlying `reqwest` error, if any.
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    /// Keygen rate-limited the request (HTTP 429), or `validation_rate_limit`
+    /// held it back and no cached result could answer instead.
+    #[error("Keygen rate limit exceeded{}", request_suffix(.request_id))]
+    RateLimited {
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
+    },
+
+    /// Keygen returned a server error (HTTP 5xx).
+    #[error("Keygen server error (HTTP {status}){}", request_suffix(.request_id))]
+    ServerError {
+        /// HTTP status code returned by Keygen.
+        status: u16,
+        /// Keygen's `X-Request-Id` for the response, if known.
+        request_id: Option<String>,
+    },
 
     /// Cache I/O error.
-    #[error("Cache I/O error: {0}")]
-    CacheIO(String),
+    #[error("Cache I/O error: {message}")]
+    CacheIO {
+        /// What failed.
+        message: String,
+        /// Underlying I/O or serialization error, if any.
+        #[source]
+        source: Option<ErrorSource>,
+    },
 
     /// Cache has been tampered with.
     #[error("Cache tampering detected")]
     CacheTampered,
 
+    /// System clock is earlier than the last verified server time.
+    #[error("System clock is behind last verified server time ({high_water_mark})")]
+    ClockRollback {
+        /// Latest verified server time.
+        high_water_mark: DateTime<Utc>,
+    },
+
+    /// Signed management link is past its expiry.
+    #[error("Management link expired at {expires_at}")]
+    LinkExpired {
+        /// When the link expired.
+        expires_at: DateTime<Utc>,
+    },
+
     /// Cache has expired beyond offline grace period.
     #[error("Cache expired (offline grace exceeded)")]
     CacheExpired,
@@ -86,6 +187,13 @@ pub enum GatewardenError {
     #[error("Invalid or expired license")]
     InvalidLicense,
 
+    /// License expiry date has passed (enforced on cached results).
+    #[error("License expired at {expires_at}")]
+    LicenseExpired {
+        /// When the license expired.
+        expires_at: DateTime<Utc>,
+    },
+
     /// Required entitlement is missing.
     #[error("Required entitlement missing: {code}")]
     EntitlementMissing {
@@ -98,6 +206,457 @@ pub enum GatewardenError {
     UsageLimitExceeded,
 
     /// Meter I/O error.
-    #[error("Meter I/O error: {0}")]
-    MeterIO(String),
+    #[error("Meter I/O error: {message}")]
+    MeterIO {
+        /// What failed.
+        message: String,
+        /// Underlying I/O or serialization error, if any.
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    /// Usage meter failed authentication or its count went backwards.
+    #[error("Usage meter has been tampered with")]
+    MeterTampered,
+
+    /// Entropy source failed to produce random bytes.
+    #[error("Entropy source error: {0}")]
+    Entropy(String),
+
+    /// Keygen's TLS certificate chain matched none of the configured pins.
+    #[error("TLS certificate does not match any configured pin (intercepting proxy?)")]
+    TlsPinMismatch,
+
+    /// Keygen reported the license as suspended (code `SUSPENDED`).
+    #[error("License is suspended")]
+    LicenseSuspended,
+
+    /// Keygen reported the license's owner as banned (code `BANNED`).
+    #[error("License owner is banned")]
+    LicenseBanned,
+
+    /// Keygen reported the license as overdue for check-in (code `OVERDUE`).
+    #[error("License is overdue for check-in")]
+    LicenseOverdue,
+
+    /// The running `app_version` is newer than the license's `maxVersion`.
+    #[error("This version is not covered by the license (up to {max_version})")]
+    VersionNotEntitled {
+        /// Newest version the license covers.
+        max_version: String,
+    },
+
+    /// The license key fails the configured `KeyFormat`; never sent to Keygen.
+    #[error("License key is malformed: {reason}")]
+    MalformedLicenseKey {
+        /// Which check failed; never includes the key.
+        reason: String,
+    },
+
+    /// None of the keys passed to `LicenseManager::validate_any` was
+    /// accepted.
+    #[error("None of {} candidate license keys was accepted", .failures.len())]
+    NoValidKey {
+        /// Each key's error, in the order the keys were given.
+        failures: Vec<GatewardenError>,
+    },
+
+    /// Every seat of a floating license is checked out (codes
+    /// `TOO_MANY_MACHINES`, or HTTP 422 on machine activation).
+    #[error("All seats of the license are in use")]
+    NoSeatAvailable,
+
+    /// The machine's hardware components no longer match the ones it was
+    /// activated with (code `COMPONENTS_SCOPE_MISMATCH`).
+    #[error("Hardware components do not match the activated machine")]
+    ComponentsMismatch,
+
+    /// The license is not associated with the user validation is scoped
+    /// to (code `USER_SCOPE_MISMATCH`).
+    #[error("License is not assigned to this user")]
+    UserMismatch,
+}
+
+/// Why a request to Keygen failed before a response was received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TransportFailure {
+    /// The Keygen host name could not be resolved.
+    Dns,
+    /// No connection could be established.
+    Connect,
+    /// The TLS handshake failed.
+    Tls,
+    /// The request or its deadline timed out.
+    Timeout,
+    /// Any other failure, e.g. the connection dropped mid-response.
+    Other,
+}
+
+/// Broad error groups, as listed in the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The license itself denies access; show the user.
+    License,
+    /// An authenticity check failed; investigate.
+    Security,
+    /// Network or local I/O failed; retry or rely on the cache.
+    Network,
+    /// Configuration or protocol mismatch; fix the integration.
+    Config,
+}
+
+impl GatewardenError {
+    /// Stable identifier for this error, e.g. `"SIGNATURE_INVALID"`.
+    ///
+    /// Codes never change once released, unlike `Display` messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ConfigError(_) => "CONFIG_ERROR",
+            Self::SignatureMissing { .. } => "SIGNATURE_MISSING",
+            Self::SignatureInvalid { .. } => "SIGNATURE_INVALID",
+            Self::DigestMismatch { .. } => "DIGEST_MISMATCH",
+            Self::ResponseTooOld { .. } => "RESPONSE_TOO_OLD",
+            Self::ResponseFromFuture { .. } => "RESPONSE_FROM_FUTURE",
+            Self::ProtocolError { .. } => "PROTOCOL_ERROR",
+            Self::KeygenTransport { .. } => "KEYGEN_TRANSPORT",
+            Self::RateLimited { .. } => "RATE_LIMITED",
+            Self::ServerError { .. } => "SERVER_ERROR",
+            Self::CacheIO { .. } => "CACHE_IO",
+            Self::CacheTampered => "CACHE_TAMPERED",
+            Self::ClockRollback { .. } => "CLOCK_ROLLBACK",
+            Self::LinkExpired { .. } => "LINK_EXPIRED",
+            Self::CacheExpired => "CACHE_EXPIRED",
+            Self::MissingLicense => "MISSING_LICENSE",
+            Self::InvalidLicense => "INVALID_LICENSE",
+            Self::LicenseExpired { .. } => "LICENSE_EXPIRED",
+            Self::EntitlementMissing { .. } => "ENTITLEMENT_MISSING",
+            Self::UsageLimitExceeded => "USAGE_LIMIT_EXCEEDED",
+            Self::MeterIO { .. } => "METER_IO",
+            Self::MeterTampered => "METER_TAMPERED",
+            Self::Entropy(_) => "ENTROPY",
+            Self::TlsPinMismatch => "TLS_PIN_MISMATCH",
+            Self::LicenseSuspended => "LICENSE_SUSPENDED",
+            Self::LicenseBanned => "LICENSE_BANNED",
+            Self::LicenseOverdue => "LICENSE_OVERDUE",
+            Self::VersionNotEntitled { .. } => "VERSION_NOT_ENTITLED",
+            Self::MalformedLicenseKey { .. } => "MALFORMED_LICENSE_KEY",
+            Self::NoValidKey { .. } => "NO_VALID_KEY",
+            Self::NoSeatAvailable => "NO_SEAT_AVAILABLE",
+            Self::ComponentsMismatch => "COMPONENTS_MISMATCH",
+            Self::UserMismatch => "USER_MISMATCH",
+        }
+    }
+
+    /// Which group this error belongs to.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::InvalidLicense
+            | Self::LicenseSuspended
+            | Self::LicenseBanned
+            | Self::LicenseOverdue
+            | Self::VersionNotEntitled { .. }
+            | Self::MalformedLicenseKey { .. }
+            | Self::NoValidKey { .. }
+            | Self::NoSeatAvailable
+            | Self::ComponentsMismatch
+            | Self::UserMismatch
+            | Self::LicenseExpired { .. }
+            | Self::EntitlementMissing { .. }
+            | Self::MissingLicense
+            | Self::UsageLimitExceeded => ErrorCategory::License,
+            Self::SignatureInvalid { .. }
+            | Self::SignatureMissing { .. }
+            | Self::DigestMismatch { .. }
+            | Self::ResponseTooOld { .. }
+            | Self::ResponseFromFuture { .. }
+            | Self::CacheTampered
+            | Self::ClockRollback { .. }
+            | Self::LinkExpired { .. }
+            | Self::MeterTampered
+            | Self::TlsPinMismatch => ErrorCategory::Security,
+            Self::KeygenTransport { .. }
+            | Self::RateLimited { .. }
+            | Self::ServerError { .. }
+            | Self::CacheIO { .. }
+            | Self::CacheExpired
+            | Self::MeterIO { .. }
+            | Self::Entropy(_) => ErrorCategory::Network,
+            Self::ConfigError(_) | Self::ProtocolError { .. } => ErrorCategory::Config,
+        }
+    }
+
+    /// Whether the same call may succeed if retried later.
+    ///
+    /// True for transient transport, availability, and local I/O
+    /// failures, and for `NoValidKey` if any key failed that way; never
+    /// for license or security errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::NoValidKey { failures } => failures.iter().any(Self::is_retryable),
+            _ => matches!(
+                self,
+                Self::KeygenTransport { .. }
+                    | Self::RateLimited { .. }
+                    | Self::ServerError { .. }
+                    | Self::CacheIO { .. }
+                    | Self::MeterIO { .. }
+            ),
+        }
+    }
+
+    /// The error for a license Keygen reported as not valid with
+    /// validation `code`.
+    ///
+    /// `SUSPENDED`, `BANNED`, `OVERDUE`, `COMPONENTS_SCOPE_MISMATCH`, and
+    /// `USER_SCOPE_MISMATCH` get their own variants, since applications tell the user different
+    /// things for each; any other code is `InvalidLicense`.
+    pub(crate) fn from_validation_code(code: &str) -> Self {
+        match code {
+            "SUSPENDED" => Self::LicenseSuspended,
+            "BANNED" => Self::LicenseBanned,
+            "OVERDUE" => Self::LicenseOverdue,
+            "COMPONENTS_SCOPE_MISMATCH" => Self::ComponentsMismatch,
+            "USER_SCOPE_MISMATCH" => Self::UserMismatch,
+            _ => Self::InvalidLicense,
+        }
+    }
+
+    /// Keygen's request ID for the response behind this error, if known.
+    ///
+    /// Set on verification and availability errors when Keygen sent an
+    /// `X-Request-Id` header; quote it in support requests to Keygen.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Self::SignatureMissing { request_id }
+            | Self::SignatureInvalid { request_id }
+            | Self::DigestMismatch { request_id }
+            | Self::ResponseTooOld { request_id, .. }
+            | Self::ResponseFromFuture { request_id }
+            | Self::RateLimited { request_id }
+            | Self::ServerError { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// This error with `id` attached, unless it already has a request ID
+    /// or is not about a Keygen response.
+    pub(crate) fn with_request_id(mut self, id: Option<&str>) -> Self {
+        if let (
+            Self::SignatureMissing { request_id }
+            | Self::SignatureInvalid { request_id }
+            | Self::DigestMismatch { request_id }
+            | Self::ResponseTooOld { request_id, .. }
+            | Self::ResponseFromFuture { request_id }
+            | Self::RateLimited { request_id }
+            | Self::ServerError { request_id, .. },
+            Some(id),
+        ) = (&mut self, id)
+        {
+            if request_id.is_none() {
+                *request_id = Some(id.to_string());
+            }
+        }
+        self
+    }
+
+    /// Whether Keygen refused the license itself rather than the
+    /// request failing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn is_refusal(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidLicense
+                | Self::LicenseSuspended
+                | Self::LicenseBanned
+                | Self::LicenseOverdue
+                | Self::UserMismatch
+        )
+    }
+
+    /// A copy of this error for sharing with other callers.
+    ///
+    /// Sources are replaced by their message, so the copy keeps its
+    /// variant, category, and text but cannot be downcast to the original.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn duplicate(&self) -> Self {
+        let source = |source: &Option<ErrorSource>| -> Option<ErrorSource> {
+            source.as_ref().map(|s| s.to_string().into())
+        };
+
+        match self {
+            Self::ConfigError(message) => Self::ConfigError(message.clone()),
+            Self::SignatureMissing { request_id } => Self::SignatureMissing {
+                request_id: request_id.clone(),
+            },
+            Self::SignatureInvalid { request_id } => Self::SignatureInvalid {
+                request_id: request_id.clone(),
+            },
+            Self::DigestMismatch { request_id } => Self::DigestMismatch {
+                request_id: request_id.clone(),
+            },
+            Self::ResponseTooOld {
+                age_seconds,
+                request_id,
+            } => Self::ResponseTooOld {
+                age_seconds: *age_seconds,
+                request_id: request_id.clone(),
+            },
+            Self::ResponseFromFuture { request_id } => Self::ResponseFromFuture {
+                request_id: request_id.clone(),
+            },
+            Self::ProtocolError { message, source: s } => Self::ProtocolError {
+                message: message.clone(),
+                source: source(s),
+            },
+            Self::KeygenTransport {
+                message,
+                kind,
+                source: s,
+            } => Self::KeygenTransport {
+                message: message.clone(),
+                kind: *kind,
+                source: source(s),
+            },
+            Self::RateLimited { request_id } => Self::RateLimited {
+                request_id: request_id.clone(),
+            },
+            Self::ServerError { status, request_id } => Self::ServerError {
+                status: *status,
+                request_id: request_id.clone(),
+            },
+            Self::CacheIO { message, source: s } => Self::CacheIO {
+                message: message.clone(),
+                source: source(s),
+            },
+            Self::CacheTampered => Self::CacheTampered,
+            Self::ClockRollback { high_water_mark } => Self::ClockRollback {
+                high_water_mark: *high_water_mark,
+            },
+            Self::LinkExpired { expires_at } => Self::LinkExpired {
+                expires_at: *expires_at,
+            },
+            Self::CacheExpired => Self::CacheExpired,
+            Self::MissingLicense => Self::MissingLicense,
+            Self::InvalidLicense => Self::InvalidLicense,
+            Self::LicenseExpired { expires_at } => Self::LicenseExpired {
+                expires_at: *expires_at,
+            },
+            Self::EntitlementMissing { code } => Self::EntitlementMissing { code: code.clone() },
+            Self::UsageLimitExceeded => Self::UsageLimitExceeded,
+            Self::MeterIO { message, source: s } => Self::MeterIO {
+                message: message.clone(),
+                source: source(s),
+            },
+            Self::MeterTampered => Self::MeterTampered,
+            Self::Entropy(message) => Self::Entropy(message.clone()),
+            Self::TlsPinMismatch => Self::TlsPinMismatch,
+            Self::LicenseSuspended => Self::LicenseSuspended,
+            Self::LicenseBanned => Self::LicenseBanned,
+            Self::LicenseOverdue => Self::LicenseOverdue,
+            Self::VersionNotEntitled { max_version } => Self::VersionNotEntitled {
+                max_version: max_version.clone(),
+            },
+            Self::MalformedLicenseKey { reason } => Self::MalformedLicenseKey {
+                reason: reason.clone(),
+            },
+            Self::NoValidKey { failures } => Self::NoValidKey {
+                failures: failures.iter().map(Self::duplicate).collect(),
+            },
+            Self::NoSeatAvailable => Self::NoSeatAvailable,
+            Self::ComponentsMismatch => Self::ComponentsMismatch,
+            Self::UserMismatch => Self::UserMismatch,
+        }
+    }
+}
+
+/// ` (Keygen request <id>)`, or nothing without a request ID.
+fn request_suffix(request_id: &Option<String>) -> String {
+    match request_id {
+        Some(id) => format!(" (Keygen request {id})"),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_and_category() {
+        let error = GatewardenError::SignatureInvalid { request_id: None };
+        assert_eq!(error.code(), "SIGNATURE_INVALID");
+        assert_eq!(error.category(), ErrorCategory::Security);
+        assert!(!error.is_retryable());
+
+        let error = GatewardenError::ServerError {
+            status: 503,
+            request_id: None,
+        };
+        assert_eq!(error.code(), "SERVER_ERROR");
+        assert_eq!(error.category(), ErrorCategory::Network);
+        assert!(error.is_retryable());
+
+        let error = GatewardenError::EntitlementMissing {
+            code: "PRO".to_string(),
+        };
+        assert_eq!(error.code(), "ENTITLEMENT_MISSING");
+        assert_eq!(error.category(), ErrorCategory::License);
+
+        assert_eq!(
+            GatewardenError::ConfigError("x".to_string()).category(),
+            ErrorCategory::Config
+        );
+        assert!(!GatewardenError::CacheExpired.is_retryable());
+    }
+
+    #[test]
+    fn test_from_validation_code() {
+        let error = GatewardenError::from_validation_code("SUSPENDED");
+        assert!(matches!(error, GatewardenError::LicenseSuspended));
+        assert_eq!(error.code(), "LICENSE_SUSPENDED");
+        assert_eq!(error.category(), ErrorCategory::License);
+        assert!(matches!(
+            GatewardenError::from_validation_code("BANNED"),
+            GatewardenError::LicenseBanned
+        ));
+        assert!(matches!(
+            GatewardenError::from_validation_code("OVERDUE"),
+            GatewardenError::LicenseOverdue
+        ));
+        assert!(matches!(
+            GatewardenError::from_validation_code("COMPONENTS_SCOPE_MISMATCH"),
+            GatewardenError::ComponentsMismatch
+        ));
+        assert!(matches!(
+            GatewardenError::from_validation_code("USER_SCOPE_MISMATCH"),
+            GatewardenError::UserMismatch
+        ));
+        assert!(matches!(
+            GatewardenError::from_validation_code("EXPIRED"),
+            GatewardenError::InvalidLicense
+        ));
+    }
+
+    #[test]
+    fn test_source_is_preserved() {
+        use std::error::Error;
+
+        let io_error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let error = GatewardenError::CacheIO {
+            message: "Failed to write cache".to_string(),
+            source: Some(io_error.into()),
+        };
+        assert_eq!(error.to_string(), "Cache I/O error: Failed to write cache");
+
+        let source = error.source().unwrap();
+        let io_error = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_error.kind(), std::io::ErrorKind::PermissionDenied);
+
+        // Shared copies keep the source text
+        let copy = error.duplicate();
+        assert_eq!(copy.to_string(), error.to_string());
+        assert_eq!(copy.source().unwrap().to_string(), "denied");
+    }
 }
@@ -0,0 +1,80 @@
+//! Validation event callbacks.
+//!
+//! An [`EventSink`] registered on `LicenseManager` is notified of validation
+//! outcomes, so applications can log, report telemetry, or alert on
+//! security events without wrapping every call site.
+//!
+//! Sinks are called synchronously on the validating thread; keep them fast
+//! and non-blocking. They never receive the license key.
+
+use crate::manager::ValidationResult;
+use crate::GatewardenError;
+
+/// Receiver for license validation events.
+///
+/// All methods default to no-ops; implement only the ones you need.
+pub trait EventSink: Send + Sync {
+    /// Online validation succeeded (signature, freshness, and policy checks passed).
+    fn on_validated(&self, _result: &ValidationResult) {}
+
+    /// Online validation failed and an authenticated cached result was used.
+    ///
+    /// `online_error` is the failure that triggered the fallback.
+    fn on_cache_fallback(&self, _result: &ValidationResult, _online_error: &GatewardenError) {}
+
+    /// A response or cache record failed authentication: missing or invalid
+    /// signature, digest mismatch, replay/clock anomaly, or cache tampering.
+    fn on_security_error(&self, _error: &GatewardenError) {}
+
+    /// The cached record exceeded the offline grace period.
+    fn on_cache_expired(&self) {}
+
+    /// A validation finished, whatever its outcome.
+    ///
+    /// Called once per `validate_key`, `check_access`, or `force_refresh`
+    /// call (and their variants), after the more specific callbacks.
+    fn on_attempt(&self, _outcome: Result<&ValidationResult, &GatewardenError>) {}
+}
+
+/// Whether an error indicates a failed authenticity check.
+pub(crate) fn is_security_error(error: &GatewardenError) -> bool {
+    matches!(
+        error,
+        GatewardenError::SignatureMissing { .. }
+            | GatewardenError::SignatureInvalid { .. }
+            | GatewardenError::DigestMismatch { .. }
+            | GatewardenError::ResponseTooOld { .. }
+            | GatewardenError::ResponseFromFuture { .. }
+            | GatewardenError::CacheTampered
+            | GatewardenError::ClockRollback { .. }
+            | GatewardenError::MeterTampered
+            | GatewardenError::TlsPinMismatch
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_security_error() {
+        assert!(is_security_error(&GatewardenError::SignatureInvalid {
+            request_id: None
+        }));
+        assert!(is_security_error(&GatewardenError::CacheTampered));
+        assert!(is_security_error(&GatewardenError::MeterTampered));
+        assert!(is_security_error(&GatewardenError::TlsPinMismatch));
+        assert!(is_security_error(&GatewardenError::ResponseTooOld {
+            age_seconds: 600,
+            request_id: None,
+        }));
+
+        assert!(!is_security_error(&GatewardenError::CacheExpired));
+        assert!(!is_security_error(&GatewardenError::KeygenTransport {
+            message: "timeout".to_string(),
+            kind: crate::TransportFailure::Timeout,
+            source: None
+        }));
+        assert!(!is_security_error(&GatewardenError::InvalidLicense));
+    }
+}
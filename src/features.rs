@@ -0,0 +1,275 @@
+//! Named features gated on entitlement policies.
+//!
+//! Map each feature the application ships to the [`EntitlementPolicy`]
+//! that unlocks it, install the registry once at startup, then ask
+//! [`feature_enabled`] anywhere:
+//!
+//! ```ignore
+//! use gatewarden::{features::FeatureRegistry, EntitlementPolicy};
+//!
+//! gatewarden::init(config)?;
+//! FeatureRegistry::builder()
+//!     .feature("ocr", EntitlementPolicy::code("OCR"))
+//!     .feature("export", EntitlementPolicy::any_of(["PRO", "ENTERPRISE"]))
+//!     .build(license_key)?
+//!     .install();
+//!
+//! if gatewarden::feature_enabled("ocr") { /* ... */ }
+//! ```
+//!
+//! `build` validates with the process-wide default manager from
+//! [`init`](crate::init); `build_with` takes a manager instead.
+//!
+//! The registry validates once when built and shares the result. Once it
+//! is older than the refresh interval, the next lookup revalidates on a
+//! background thread and lookups keep the previous answer until that
+//! completes, so only `build` ever blocks. Each refresh also fetches the
+//! license's full entitlement list, since validation only reports scoped
+//! codes; offline, the last list fetched is kept.
+//!
+//! Lookups fail closed: unknown features, an invalid license, and a
+//! missing registry all answer `false`.
+
+use crate::integrations::gate::Gate;
+use crate::{global, EntitlementPolicy, GatewardenError, LicenseManager};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Default age after which the shared result is refreshed.
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Feature names mapped to the entitlement policies that unlock them.
+pub struct FeatureRegistry {
+    gate: Arc<Gate>,
+    features: HashMap<String, EntitlementPolicy>,
+}
+
+/// Builder for [`FeatureRegistry`].
+#[derive(Debug, Clone)]
+pub struct FeatureRegistryBuilder {
+    features: HashMap<String, EntitlementPolicy>,
+    refresh_interval: Duration,
+}
+
+static INSTALLED: RwLock<Option<Arc<FeatureRegistry>>> = RwLock::new(None);
+
+impl FeatureRegistry {
+    /// Start defining features.
+    pub fn builder() -> FeatureRegistryBuilder {
+        FeatureRegistryBuilder {
+            features: HashMap::new(),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        }
+    }
+
+    /// Whether feature `name` is registered and its policy is satisfied
+    /// by the license.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let Some(policy) = self.features.get(name) else {
+            return false;
+        };
+        match self.gate.current() {
+            Ok(result) => policy.is_satisfied_by(&result.state.entitlements),
+            Err(_) => false,
+        }
+    }
+
+    /// Names of the registered features that are enabled, sorted.
+    pub fn enabled_features(&self) -> Vec<&str> {
+        let Ok(result) = self.gate.current() else {
+            return Vec::new();
+        };
+        let mut names: Vec<&str> = self
+            .features
+            .iter()
+            .filter(|(_, policy)| policy.is_satisfied_by(&result.state.entitlements))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Revalidate now, blocking, instead of waiting for the interval.
+    ///
+    /// # Errors
+    /// Any error from `LicenseManager::validate_key`; lookups then answer
+    /// `false` until a refresh succeeds.
+    pub fn refresh(&self) -> Result<(), GatewardenError> {
+        self.gate.refresh()
+    }
+
+    /// Make this the registry consulted by [`feature_enabled`], replacing
+    /// any earlier one.
+    pub fn install(self) {
+        let mut slot = INSTALLED
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *slot = Some(Arc::new(self));
+    }
+}
+
+impl FeatureRegistryBuilder {
+    /// Unlock feature `name` when `policy` holds, replacing any earlier
+    /// policy for it.
+    pub fn feature(mut self, name: impl Into<String>, policy: EntitlementPolicy) -> Self {
+        self.features.insert(name.into(), policy);
+        self
+    }
+
+    /// Age after which the shared result is refreshed.
+    ///
+    /// Defaults to [`DEFAULT_REFRESH_INTERVAL`].
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Validate `license_key` once with the default manager and build
+    /// the registry.
+    ///
+    /// # Errors
+    /// - `ConfigError` - No features were registered, or `init` has not
+    ///   been called
+    /// - Any error from the initial `LicenseManager::validate_key`
+    pub fn build(self, license_key: impl Into<String>) -> Result<FeatureRegistry, GatewardenError> {
+        let manager = global()?.clone();
+        self.build_with(manager, license_key)
+    }
+
+    /// Validate `license_key` once with `manager` and build the registry.
+    ///
+    /// # Errors
+    /// - `ConfigError` - No features were registered
+    /// - Any error from the initial `LicenseManager::validate_key`
+    pub fn build_with(
+        self,
+        manager: LicenseManager,
+        license_key: impl Into<String>,
+    ) -> Result<FeatureRegistry, GatewardenError> {
+        if self.features.is_empty() {
+            return Err(GatewardenError::ConfigError(
+                "Feature registry has no features".to_string(),
+            ));
+        }
+        let gate =
+            Gate::start_with_entitlements(manager, license_key.into(), self.refresh_interval)?;
+        Ok(FeatureRegistry {
+            gate,
+            features: self.features,
+        })
+    }
+}
+
+/// Whether feature `name` is enabled in the installed registry.
+///
+/// `false` if no registry is installed.
+pub fn feature_enabled(name: &str) -> bool {
+    installed().is_some_and(|registry| registry.is_enabled(name))
+}
+
+/// The installed registry, if any.
+pub fn installed() -> Option<Arc<FeatureRegistry>> {
+    INSTALLED
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Remove the installed registry; every feature then reads as disabled.
+pub fn uninstall() {
+    let mut slot = INSTALLED
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *slot = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::file::FileCache;
+    use crate::mock::{MockKeygen, MockLicense};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::GatewardenConfig;
+    use tempfile::TempDir;
+
+    fn manager(temp_dir: &TempDir, keygen: Arc<MockKeygen>) -> LicenseManager {
+        let config = GatewardenConfig::builder()
+            .app_name("features-test")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        LicenseManager::new(config)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_meter_dir(temp_dir.path().join("meter"))
+            .with_mock_keygen(keygen)
+    }
+
+    fn registry() -> FeatureRegistryBuilder {
+        FeatureRegistry::builder()
+            .feature("ocr", EntitlementPolicy::code("OCR"))
+            .feature("export", EntitlementPolicy::any_of(["PRO", "ENTERPRISE"]))
+            .feature("sso", EntitlementPolicy::code("SSO"))
+    }
+
+    #[test]
+    fn test_features_follow_full_entitlement_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license(
+            "key",
+            MockLicense::new("lic-1").with_entitlements(&["OCR", "ENTERPRISE"]),
+        );
+
+        let registry = registry()
+            .build_with(manager(&temp_dir, keygen), "key")
+            .unwrap();
+        assert!(registry.is_enabled("ocr"));
+        assert!(registry.is_enabled("export"));
+        assert!(!registry.is_enabled("sso"));
+        assert!(!registry.is_enabled("unknown"));
+        assert_eq!(registry.enabled_features(), vec!["export", "ocr"]);
+
+        assert!(!feature_enabled("ocr"));
+        registry.install();
+        assert!(feature_enabled("ocr"));
+        assert!(!feature_enabled("sso"));
+        uninstall();
+        assert!(!feature_enabled("ocr"));
+    }
+
+    #[test]
+    fn test_suspended_license_disables_everything() {
+        let temp_dir = TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("key", MockLicense::new("lic-1").with_entitlements(&["OCR"]));
+
+        let registry = registry()
+            .build_with(manager(&temp_dir, keygen.clone()), "key")
+            .unwrap();
+        assert!(registry.is_enabled("ocr"));
+
+        keygen.add_license(
+            "key",
+            MockLicense::new("lic-1")
+                .with_entitlements(&["OCR"])
+                .suspended(),
+        );
+        assert!(registry.refresh().is_err());
+        assert!(!registry.is_enabled("ocr"));
+        assert!(registry.enabled_features().is_empty());
+    }
+
+    #[test]
+    fn test_empty_registry_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        let result = FeatureRegistry::builder().build_with(manager(&temp_dir, keygen), "key");
+        assert!(matches!(result, Err(GatewardenError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,360 @@
+//! C ABI for embedding gatewarden in C, C++, and Go applications
+//! (feature `ffi`).
+//!
+//! Build a shared library and use `include/gatewarden.h`:
+//!
+//! ```bash
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! ```
+//!
+//! Every function returns a status: `GATEWARDEN_OK` (0), a positive
+//! status for each [`GatewardenError`] (in the order of
+//! `GATEWARDEN_ERROR_CODES`), or a negative status for misuse of the API.
+//! After a failure, `gatewarden_last_error_code` returns the same string
+//! as `GatewardenError::code()` and `gatewarden_last_error_message` a
+//! description; both are per thread. Panics never cross the boundary.
+
+use crate::{GatewardenConfig, GatewardenError, LicenseManager};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// The call succeeded.
+pub const GATEWARDEN_OK: i32 = 0;
+
+/// A pointer argument was null, or a string was not UTF-8.
+pub const GATEWARDEN_ERR_INVALID_ARGUMENT: i32 = -1;
+
+/// gatewarden panicked; the handle should not be used again.
+pub const GATEWARDEN_ERR_PANIC: i32 = -2;
+
+/// An error whose code is missing from [`GATEWARDEN_ERROR_CODES`].
+pub const GATEWARDEN_ERR_OTHER: i32 = -3;
+
+/// `GatewardenError::code()` for each positive status: status `n` is
+/// entry `n - 1`. Append only; statuses never change once released.
+pub const GATEWARDEN_ERROR_CODES: &[&str] = &[
+    "CONFIG_ERROR",
+    "SIGNATURE_MISSING",
+    "SIGNATURE_INVALID",
+    "DIGEST_MISMATCH",
+    "RESPONSE_TOO_OLD",
+    "RESPONSE_FROM_FUTURE",
+    "PROTOCOL_ERROR",
+    "KEYGEN_TRANSPORT",
+    "RATE_LIMITED",
+    "SERVER_ERROR",
+    "CACHE_IO",
+    "CACHE_TAMPERED",
+    "CLOCK_ROLLBACK",
+    "LINK_EXPIRED",
+    "CACHE_EXPIRED",
+    "MISSING_LICENSE",
+    "INVALID_LICENSE",
+    "LICENSE_EXPIRED",
+    "ENTITLEMENT_MISSING",
+    "USAGE_LIMIT_EXCEEDED",
+    "METER_IO",
+    "METER_TAMPERED",
+    "ENTROPY",
+    "TLS_PIN_MISMATCH",
+    "LICENSE_SUSPENDED",
+    "LICENSE_BANNED",
+    "LICENSE_OVERDUE",
+    "VERSION_NOT_ENTITLED",
+    "MALFORMED_LICENSE_KEY",
+    "NO_VALID_KEY",
+    "NO_SEAT_AVAILABLE",
+    "COMPONENTS_MISMATCH",
+    "USER_MISMATCH",
+];
+
+/// Opaque license manager handle.
+pub struct GatewardenManager(LicenseManager);
+
+/// Code and message of the last failure on this thread.
+struct LastError {
+    code: CString,
+    message: CString,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<LastError>> = const { RefCell::new(None) };
+}
+
+/// Why a call failed.
+enum Failure {
+    Argument(&'static str),
+    Panic,
+    Error(GatewardenError),
+}
+
+/// Run `f`, record any failure for this thread, and return its status.
+fn call(f: impl FnOnce() -> Result<(), Failure>) -> i32 {
+    let outcome = catch_unwind(AssertUnwindSafe(f)).unwrap_or(Err(Failure::Panic));
+    let (status, code, message) = match outcome {
+        Ok(()) => {
+            LAST_ERROR.with(|last| last.borrow_mut().take());
+            return GATEWARDEN_OK;
+        }
+        Err(Failure::Argument(message)) => (
+            GATEWARDEN_ERR_INVALID_ARGUMENT,
+            "INVALID_ARGUMENT",
+            message.to_string(),
+        ),
+        Err(Failure::Panic) => (
+            GATEWARDEN_ERR_PANIC,
+            "PANIC",
+            "gatewarden panicked".to_string(),
+        ),
+        Err(Failure::Error(e)) => {
+            let status = GATEWARDEN_ERROR_CODES
+                .iter()
+                .position(|code| *code == e.code())
+                .map_or(GATEWARDEN_ERR_OTHER, |i| i as i32 + 1);
+            (status, e.code(), e.to_string())
+        }
+    };
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = Some(LastError {
+            code: c_string(code),
+            message: c_string(&message),
+        });
+    });
+    status
+}
+
+/// `value` as a C string, dropping any interior NULs.
+fn c_string(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap_or_default()
+}
+
+/// Borrow a string argument.
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string that outlives
+/// the call.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &'static str) -> Result<&'a str, Failure> {
+    if ptr.is_null() {
+        return Err(Failure::Argument(name));
+    }
+    // SAFETY: non-null and NUL-terminated per the caller's contract.
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| Failure::Argument(name))
+}
+
+/// Config fields are `&'static str`; each manager's strings live for the
+/// rest of the process.
+fn leak(value: &str) -> &'static str {
+    Box::leak(value.to_string().into_boxed_str())
+}
+
+/// Create a license manager and store its handle in `*out`.
+///
+/// Free the handle with `gatewarden_manager_free`.
+///
+/// # Safety
+/// The strings must be null or NUL-terminated, and `out` must be null or
+/// valid for a pointer write.
+#[no_mangle]
+pub unsafe extern "C" fn gatewarden_manager_new(
+    app_name: *const c_char,
+    account_id: *const c_char,
+    public_key_hex: *const c_char,
+    out: *mut *mut GatewardenManager,
+) -> i32 {
+    call(|| {
+        if out.is_null() {
+            return Err(Failure::Argument("out is null"));
+        }
+        // SAFETY: per this function's contract.
+        let (app_name, account_id, public_key_hex) = unsafe {
+            (
+                str_arg(app_name, "app_name is null or not UTF-8")?,
+                str_arg(account_id, "account_id is null or not UTF-8")?,
+                str_arg(public_key_hex, "public_key_hex is null or not UTF-8")?,
+            )
+        };
+        let config = GatewardenConfig::builder()
+            .app_name(leak(app_name))
+            .account_id(leak(account_id))
+            .public_key_hex(leak(public_key_hex))
+            .build()
+            .map_err(Failure::Error)?;
+        let manager = LicenseManager::new(config).map_err(Failure::Error)?;
+        // SAFETY: `out` is non-null and valid for writes per the contract.
+        unsafe { *out = Box::into_raw(Box::new(GatewardenManager(manager))) };
+        Ok(())
+    })
+}
+
+/// Free a manager handle. Null is ignored.
+///
+/// # Safety
+/// `manager` must be null or a handle from `gatewarden_manager_new` that
+/// has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn gatewarden_manager_free(manager: *mut GatewardenManager) {
+    if !manager.is_null() {
+        // SAFETY: the handle came from `Box::into_raw` and is freed once.
+        drop(unsafe { Box::from_raw(manager) });
+    }
+}
+
+/// Validate a license key, online with offline fallback, as
+/// `LicenseManager::validate_key`.
+///
+/// On success, if `out_json` is not null, `*out_json` receives the
+/// `ValidationResult` as JSON; free it with `gatewarden_string_free`.
+/// The handle may be shared between threads.
+///
+/// # Safety
+/// `manager` must be a live handle, `license_key` null or NUL-terminated,
+/// and `out_json` null or valid for a pointer write.
+#[no_mangle]
+pub unsafe extern "C" fn gatewarden_validate_key(
+    manager: *const GatewardenManager,
+    license_key: *const c_char,
+    out_json: *mut *mut c_char,
+) -> i32 {
+    call(|| {
+        // SAFETY: a live handle per this function's contract.
+        let manager = unsafe { manager.as_ref() }.ok_or(Failure::Argument("manager is null"))?;
+        // SAFETY: per this function's contract.
+        let license_key = unsafe { str_arg(license_key, "license_key is null or not UTF-8")? };
+        let result = manager
+            .0
+            .validate_key(license_key)
+            .map_err(Failure::Error)?;
+        if !out_json.is_null() {
+            let json = serde_json::to_string(&result).map_err(|e| {
+                Failure::Error(GatewardenError::ProtocolError {
+                    message: format!("Failed to serialize result: {}", e),
+                    source: None,
+                })
+            })?;
+            // SAFETY: `out_json` is non-null and valid for writes per the contract.
+            unsafe { *out_json = c_string(&json).into_raw() };
+        }
+        Ok(())
+    })
+}
+
+/// Free a string returned by gatewarden. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a string from gatewarden that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn gatewarden_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: the string came from `CString::into_raw` and is freed once.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Code of the last failure on this thread (e.g. `"INVALID_LICENSE"`),
+/// or null after a success.
+///
+/// Valid until the next gatewarden call on this thread.
+#[no_mangle]
+pub extern "C" fn gatewarden_last_error_code() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |last| last.code.as_ptr())
+    })
+}
+
+/// Message of the last failure on this thread, or null after a success.
+///
+/// Valid until the next gatewarden call on this thread.
+#[no_mangle]
+pub extern "C" fn gatewarden_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |last| last.message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::TEST_PUBLIC_KEY_HEX;
+
+    fn last_error_code() -> String {
+        let code = gatewarden_last_error_code();
+        assert!(!code.is_null());
+        unsafe { CStr::from_ptr(code) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_manager_lifecycle_and_errors() {
+        let app_name = CString::new("ffi-test").unwrap();
+        let account_id = CString::new("test-account").unwrap();
+        let public_key = CString::new(TEST_PUBLIC_KEY_HEX).unwrap();
+        let mut manager = ptr::null_mut();
+
+        let status = unsafe {
+            gatewarden_manager_new(
+                app_name.as_ptr(),
+                account_id.as_ptr(),
+                public_key.as_ptr(),
+                &mut manager,
+            )
+        };
+        assert_eq!(status, GATEWARDEN_OK);
+        assert!(!manager.is_null());
+        assert!(gatewarden_last_error_code().is_null());
+
+        // An empty key fails before any request is made
+        let empty = CString::new("").unwrap();
+        let mut json = ptr::null_mut();
+        let status = unsafe { gatewarden_validate_key(manager, empty.as_ptr(), &mut json) };
+        assert_eq!(
+            GATEWARDEN_ERROR_CODES[status as usize - 1],
+            "MISSING_LICENSE"
+        );
+        assert_eq!(last_error_code(), "MISSING_LICENSE");
+        assert!(json.is_null());
+
+        let status = unsafe { gatewarden_validate_key(manager, ptr::null(), &mut json) };
+        assert_eq!(status, GATEWARDEN_ERR_INVALID_ARGUMENT);
+        assert_eq!(last_error_code(), "INVALID_ARGUMENT");
+
+        unsafe { gatewarden_manager_free(manager) };
+    }
+
+    #[test]
+    fn test_config_errors_map_to_status() {
+        let app_name = CString::new("ffi-test").unwrap();
+        let account_id = CString::new("test-account").unwrap();
+        let bad_key = CString::new("not-hex").unwrap();
+        let mut manager = ptr::null_mut();
+
+        let status = unsafe {
+            gatewarden_manager_new(
+                app_name.as_ptr(),
+                account_id.as_ptr(),
+                bad_key.as_ptr(),
+                &mut manager,
+            )
+        };
+        assert_eq!(status, 1);
+        assert_eq!(last_error_code(), "CONFIG_ERROR");
+        assert!(manager.is_null());
+    }
+
+    #[test]
+    fn test_error_codes_are_unique() {
+        let mut codes = GATEWARDEN_ERROR_CODES.to_vec();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), GATEWARDEN_ERROR_CODES.len());
+    }
+}
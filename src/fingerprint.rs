@@ -0,0 +1,158 @@
+//! Machine fingerprint.
+//!
+//! A stable, per-machine identifier used to bind local state (such as the
+//! usage meter) to the machine it was written on. The OS machine ID is
+//! read and hashed with a domain-separation prefix, so the raw ID never
+//! leaves this module:
+//! - Linux: `/etc/machine-id` (or `/var/lib/dbus/machine-id`)
+//! - macOS: `IOPlatformUUID` from `ioreg`
+//! - Windows: `MachineGuid` under `HKLM\SOFTWARE\Microsoft\Cryptography`
+//!
+//! The fingerprint is a binding, not a secret: any process on the machine
+//! can compute it.
+//!
+//! For policies that match hardware on more than the machine ID, a
+//! [`Component`] hashes an identifier the application reads itself (a GPU
+//! ID, a disk serial) the same way; see `LicenseManager::with_components`.
+
+use sha2::{Digest, Sha256};
+
+/// Domain-separation prefix for the fingerprint hash.
+const FINGERPRINT_PREFIX: &str = "gatewarden-machine-v1\n";
+
+/// Domain-separation prefix for component fingerprint hashes.
+const COMPONENT_PREFIX: &str = "gatewarden-component-v1\n";
+
+/// A hardware component registered with machine activations (a Keygen
+/// component).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    name: String,
+    fingerprint: String,
+}
+
+impl Component {
+    /// A component named `name` (e.g. `"gpu"`, `"disk"`) identified by
+    /// `raw_id`.
+    ///
+    /// Only a hash of `raw_id` is kept, so the raw identifier never
+    /// reaches Keygen.
+    pub fn new(name: impl Into<String>, raw_id: &str) -> Self {
+        let name = name.into();
+        let mut hasher = Sha256::new();
+        hasher.update(COMPONENT_PREFIX.as_bytes());
+        hasher.update(name.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(raw_id.trim().as_bytes());
+        Self {
+            fingerprint: hex::encode(hasher.finalize()),
+            name,
+        }
+    }
+
+    /// Component name, as shown in the Keygen dashboard.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Hex-encoded SHA-256 fingerprint sent to Keygen.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+}
+
+/// Hex-encoded SHA-256 fingerprint of this machine.
+///
+/// Returns `None` if the platform machine ID cannot be read.
+pub fn machine_fingerprint() -> Option<String> {
+    machine_id().map(|id| fingerprint_of(&id))
+}
+
+/// Hash a raw machine ID into a fingerprint.
+fn fingerprint_of(machine_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(FINGERPRINT_PREFIX.as_bytes());
+    hasher.update(machine_id.trim().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(target_os = "linux")]
+fn machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+fn machine_id() -> Option<String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+
+    // Line format: "IOPlatformUUID" = "XXXXXXXX-..."
+    text.lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_string)
+}
+
+#[cfg(windows)]
+fn machine_id() -> Option<String> {
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Cryptography",
+            "/v",
+            "MachineGuid",
+        ])
+        .output()
+        .ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+
+    // Line format: MachineGuid    REG_SZ    xxxxxxxx-...
+    text.lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().nth(2))
+        .map(str::to_string)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn machine_id() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_hashes_id() {
+        let fp = fingerprint_of("4c4c4544-0042-3510-8052-b4c04f4d3732\n");
+        assert_eq!(fp.len(), 64);
+        assert!(!fp.contains("4c4c4544"));
+        assert_eq!(fp, fingerprint_of("4c4c4544-0042-3510-8052-b4c04f4d3732"));
+        assert_ne!(fp, fingerprint_of("other-machine"));
+    }
+
+    #[test]
+    fn test_component_hashes_id() {
+        let gpu = Component::new("gpu", "GPU-8f3a2c1e\n");
+        assert_eq!(gpu.name(), "gpu");
+        assert_eq!(gpu.fingerprint().len(), 64);
+        assert!(!gpu.fingerprint().contains("8f3a2c1e"));
+        assert_eq!(gpu, Component::new("gpu", "GPU-8f3a2c1e"));
+        assert_ne!(
+            gpu.fingerprint(),
+            Component::new("disk", "GPU-8f3a2c1e").fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_machine_fingerprint_is_stable() {
+        assert_eq!(machine_fingerprint(), machine_fingerprint());
+    }
+}
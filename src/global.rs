@@ -0,0 +1,175 @@
+//! The process-wide license manager.
+//!
+//! [`init`] creates a default [`LicenseManager`] once per process, and
+//! [`global`] returns it, so deeply nested code (plugins, codecs) can
+//! check licenses without a manager handle threaded through every API:
+//!
+//! ```ignore
+//! gatewarden::init(config)?;
+//!
+//! // anywhere else
+//! let result = gatewarden::global()?.check_access(&license_key)?;
+//! ```
+//!
+//! Use [`init_with`] instead to install a manager built with custom
+//! backends. The default manager is the only process-wide manager:
+//! functions annotated with `#[licensed]` (feature `macros`) validate
+//! the license key [`register`]ed at startup against it, and
+//! [`FeatureRegistry::build`](crate::FeatureRegistry) shares it too.
+//!
+//! ```ignore
+//! gatewarden::init(config)?;
+//! gatewarden::global::register(license_key)?;
+//!
+//! #[gatewarden::licensed(entitlement = "PRO")]
+//! fn export_pdf(path: &str) -> Result<(), gatewarden::GatewardenError> {
+//!     // only runs while the license is valid and has `PRO`
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Checks go through `LicenseManager::validate_key`, so they are
+//! memoized for `memo_ttl` and fall back to the offline cache. As with
+//! `ValidationResult::has_entitlement`, an entitlement code must be in
+//! `required_entitlements` or the entitlement policy to be reported.
+
+use crate::{GatewardenConfig, GatewardenError, LicenseManager, RedactedKey, ValidationResult};
+use std::sync::{Arc, OnceLock, RwLock};
+
+static DEFAULT_MANAGER: OnceLock<LicenseManager> = OnceLock::new();
+
+/// Create the process-wide default manager from `config`.
+///
+/// Call once at startup; the manager lives until the process exits.
+///
+/// # Errors
+/// - `ConfigError` - The default manager was already initialized, or
+///   `config` is invalid
+/// - Any error from `LicenseManager::new`
+pub fn init(config: GatewardenConfig) -> Result<&'static LicenseManager, GatewardenError> {
+    if DEFAULT_MANAGER.get().is_some() {
+        return Err(already_initialized());
+    }
+    init_with(LicenseManager::new(config)?)
+}
+
+/// Make `manager` the process-wide default manager.
+///
+/// For managers `init` cannot build: custom cache or key-store backends,
+/// a meter directory, or a mock Keygen in tests.
+///
+/// # Errors
+/// `ConfigError` if the default manager was already initialized.
+pub fn init_with(manager: LicenseManager) -> Result<&'static LicenseManager, GatewardenError> {
+    // Another thread may have won the race since any earlier check
+    DEFAULT_MANAGER
+        .set(manager)
+        .map_err(|_| already_initialized())?;
+    global()
+}
+
+fn already_initialized() -> GatewardenError {
+    GatewardenError::ConfigError("Default license manager already initialized".to_string())
+}
+
+/// The process-wide default manager created by `init`.
+///
+/// # Errors
+/// `ConfigError` if `init` has not been called.
+pub fn global() -> Result<&'static LicenseManager, GatewardenError> {
+    DEFAULT_MANAGER.get().ok_or_else(|| {
+        GatewardenError::ConfigError("gatewarden::init has not been called".to_string())
+    })
+}
+
+static REGISTERED_KEY: RwLock<Option<Arc<RedactedKey>>> = RwLock::new(None);
+
+/// Register the license key checked by `#[licensed]` functions against
+/// the default manager, replacing any earlier key.
+///
+/// # Errors
+/// `ConfigError` if `init` has not been called.
+pub fn register(license_key: impl Into<String>) -> Result<(), GatewardenError> {
+    global()?;
+    let license_key = Arc::new(RedactedKey::new(license_key));
+    // A poisoned lock only means a writer panicked; the slot is still usable
+    let mut slot = REGISTERED_KEY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *slot = Some(license_key);
+    Ok(())
+}
+
+/// Remove the registered key; `#[licensed]` functions then refuse to run.
+pub fn unregister() {
+    let mut slot = REGISTERED_KEY
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *slot = None;
+}
+
+/// Whether a license key is registered.
+pub fn is_registered() -> bool {
+    registered_key().is_some()
+}
+
+/// Validate the registered license with the default manager.
+///
+/// # Errors
+/// `ConfigError` if no key is registered, otherwise any error from
+/// `LicenseManager::validate_key`.
+pub fn require_license() -> Result<ValidationResult, GatewardenError> {
+    let license_key = registered_key()
+        .ok_or_else(|| GatewardenError::ConfigError("No license key registered".to_string()))?;
+    global()?.validate_key(license_key.expose())
+}
+
+/// Validate the registered license and require entitlement `code`.
+///
+/// # Errors
+/// As `require_license`, or `EntitlementMissing` if the license lacks
+/// `code`.
+pub fn require_entitlement(code: &str) -> Result<ValidationResult, GatewardenError> {
+    let result = require_license()?;
+    if result.has_entitlement(code) {
+        Ok(result)
+    } else {
+        Err(GatewardenError::EntitlementMissing {
+            code: code.to_string(),
+        })
+    }
+}
+
+fn registered_key() -> Option<Arc<RedactedKey>> {
+    REGISTERED_KEY
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::TEST_PUBLIC_KEY_HEX;
+
+    #[test]
+    fn test_default_manager_is_initialized_once() {
+        let config = || {
+            GatewardenConfig::builder()
+                .app_name("global-test")
+                .account_id("test-account")
+                .public_key_hex(TEST_PUBLIC_KEY_HEX)
+                .build()
+                .unwrap()
+        };
+        assert!(matches!(global(), Err(GatewardenError::ConfigError(_))));
+
+        let manager = init(config()).unwrap();
+        assert!(std::ptr::eq(manager, global().unwrap()));
+        assert_eq!(global().unwrap().config().app_name, "global-test");
+        assert!(matches!(
+            init(config()),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+}
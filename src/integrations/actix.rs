@@ -0,0 +1,204 @@
+//! Actix-web extractors, guard, and middleware that gate routes on a
+//! valid license.
+//!
+//! Register a [`LicenseGate`] as app data (directly or as
+//! `web::Data<LicenseGate>`), then take [`LicenseGuard`] or [`Entitled`]
+//! as a handler argument, wrap a scope with [`require_license`], or use
+//! the gate as a route guard:
+//!
+//! ```rust,ignore
+//! let gate = LicenseGate::new(manager, license_key, Duration::from_secs(3600))?;
+//! HttpServer::new(move || {
+//!     App::new()
+//!         .app_data(gate.clone())
+//!         .route("/export", web::post().to(|_: Entitled<Pro>| async { "exported" }))
+//!         .service(web::scope("/api").wrap(from_fn(require_license)).service(api))
+//!         .route("/", web::get().guard(gate.clone()).to(dashboard))
+//! })
+//! ```
+//!
+//! Requests are refused with `402 Payment Required` while the license is
+//! invalid and `403 Forbidden` when it lacks the entitlement, with the
+//! same JSON body as the axum integration. As a route guard the gate
+//! refuses nothing itself; unlicensed requests fall through to the next
+//! matching route. The verdict is cached and revalidated off the executor
+//! every `ttl`.
+
+pub use super::guard::{
+    Entitled, LicenseGate, LicenseGuard, LicenseRejection, RequiredEntitlement,
+};
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::guard::{Guard, GuardContext};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use std::future::{ready, Ready};
+
+/// Find the gate registered as app data.
+fn gate(req: &HttpRequest) -> Result<LicenseGate, actix_web::Error> {
+    req.app_data::<LicenseGate>()
+        .cloned()
+        .or_else(|| {
+            req.app_data::<web::Data<LicenseGate>>()
+                .map(|data| data.get_ref().clone())
+        })
+        .ok_or_else(|| {
+            actix_web::error::ErrorInternalServerError("LicenseGate is not registered as app data")
+        })
+}
+
+impl FromRequest for LicenseGuard {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(gate(req).and_then(|gate| Ok(gate.check()?)))
+    }
+}
+
+impl<E: RequiredEntitlement> FromRequest for Entitled<E> {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(gate(req).and_then(|gate| Ok(gate.entitled()?)))
+    }
+}
+
+impl Guard for LicenseGate {
+    fn check(&self, _ctx: &GuardContext<'_>) -> bool {
+        self.gate.current().is_ok()
+    }
+}
+
+impl ResponseError for LicenseRejection {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status()).unwrap_or(StatusCode::FORBIDDEN)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type("application/json")
+            .body(self.body())
+    }
+}
+
+/// Middleware answering every request with the [`LicenseRejection`]
+/// response while the license is invalid; use with
+/// `actix_web::middleware::from_fn`.
+///
+/// # Errors
+/// An internal server error if no [`LicenseGate`] is registered as app
+/// data, or any error from the wrapped service.
+pub async fn require_license<B: MessageBody>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, actix_web::Error> {
+    if let Err(rejection) = gate(req.request())?.check() {
+        return Ok(req.into_response(rejection.error_response().map_into_right_body()));
+    }
+    Ok(next.call(req).await?.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockKeygen, MockLicense};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::{GatewardenConfig, LicenseManager};
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, App};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Pro;
+
+    impl RequiredEntitlement for Pro {
+        const CODE: &'static str = "PRO";
+    }
+
+    fn gate(keygen: Arc<MockKeygen>, cache_dir: &std::path::Path) -> LicenseGate {
+        let config = GatewardenConfig::builder()
+            .app_name("test")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .cache_namespace("test-actix")
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        let manager = LicenseManager::new(config)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                crate::cache::file::FileCache::with_path(cache_dir.to_path_buf()).unwrap(),
+            ))
+            .with_mock_keygen(keygen);
+        LicenseGate::new(manager, "test-key", Duration::from_secs(3600)).unwrap()
+    }
+
+    #[test]
+    fn test_extractors_check_license_and_entitlement() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let gate = gate(keygen.clone(), temp_dir.path());
+
+        actix_rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new()
+                    .app_data(web::Data::new(gate.clone()))
+                    .route("/", web::get().to(|_: LicenseGuard| async { "ok" }))
+                    .route("/pro", web::get().to(|_: Entitled<Pro>| async { "pro" })),
+            )
+            .await;
+
+            let response = test::call_service(&app, test::TestRequest::get().to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let request = test::TestRequest::get().uri("/pro").to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let body: serde_json::Value = test::read_body_json(response).await;
+            assert_eq!(body["error"], "ENTITLEMENT_MISSING");
+        });
+    }
+
+    #[test]
+    fn test_middleware_and_guard_refuse_invalid_license() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let gate = gate(keygen.clone(), temp_dir.path());
+
+        keygen.add_license("test-key", MockLicense::new("lic-1").suspended());
+        gate.gate.refresh().unwrap_err();
+
+        actix_rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new()
+                    .app_data(gate.clone())
+                    .route(
+                        "/demo",
+                        web::get().guard(gate.clone()).to(|| async { "full" }),
+                    )
+                    .route("/demo", web::get().to(|| async { "demo" }))
+                    .service(
+                        web::scope("/api")
+                            .wrap(from_fn(require_license))
+                            .route("", web::get().to(|| async { "ok" })),
+                    ),
+            )
+            .await;
+
+            let request = test::TestRequest::get().uri("/api").to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+            let body: serde_json::Value = test::read_body_json(response).await;
+            assert_eq!(body["error"], "LICENSE_SUSPENDED");
+
+            let request = test::TestRequest::get().uri("/demo").to_request();
+            assert_eq!(test::call_and_read_body(&app, request).await, "demo");
+        });
+    }
+}
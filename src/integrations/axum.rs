@@ -0,0 +1,162 @@
+//! Axum extractors that gate routes on a valid license.
+//!
+//! Put a [`LicenseGate`] in the router state (directly, or in your own
+//! state with `FromRef`), then take [`LicenseGuard`] or [`Entitled`] as a
+//! handler argument:
+//!
+//! ```rust,ignore
+//! struct Pro;
+//! impl RequiredEntitlement for Pro {
+//!     const CODE: &'static str = "PRO";
+//! }
+//!
+//! async fn export(_: Entitled<Pro>) -> &'static str { "exported" }
+//!
+//! let gate = LicenseGate::new(manager, license_key, Duration::from_secs(3600))?;
+//! let app = Router::new().route("/export", post(export)).with_state(gate);
+//! ```
+//!
+//! Requests are refused with `402 Payment Required` while the license is
+//! invalid and `403 Forbidden` when it lacks the entitlement, with a JSON
+//! body such as `{"error": "ENTITLEMENT_MISSING", "message": "..."}`.
+//! Validation runs off the executor exactly as for
+//! [`LicenseLayer`](super::tower::LicenseLayer).
+
+pub use super::guard::{
+    Entitled, LicenseGate, LicenseGuard, LicenseRejection, RequiredEntitlement,
+};
+
+use async_trait::async_trait;
+use axum_core::extract::{FromRef, FromRequestParts};
+use axum_core::response::{IntoResponse, Response};
+use http::header::CONTENT_TYPE;
+use http::request::Parts;
+use http::StatusCode;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for LicenseGuard
+where
+    LicenseGate: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = LicenseRejection;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        LicenseGate::from_ref(state).check()
+    }
+}
+
+#[async_trait]
+impl<S, E> FromRequestParts<S> for Entitled<E>
+where
+    LicenseGate: FromRef<S>,
+    S: Send + Sync,
+    E: RequiredEntitlement,
+{
+    type Rejection = LicenseRejection;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        LicenseGate::from_ref(state).entitled()
+    }
+}
+
+impl IntoResponse for LicenseRejection {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::FORBIDDEN);
+        (status, [(CONTENT_TYPE, "application/json")], self.body()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockKeygen, MockLicense};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::{GatewardenConfig, LicenseManager};
+    use http::Request;
+    use http_body_util::BodyExt;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Pro;
+
+    impl RequiredEntitlement for Pro {
+        const CODE: &'static str = "PRO";
+    }
+
+    fn gate(keygen: Arc<MockKeygen>, cache_dir: &std::path::Path) -> LicenseGate {
+        let config = GatewardenConfig::builder()
+            .app_name("test")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .cache_namespace("test-axum")
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        let manager = LicenseManager::new(config)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                crate::cache::file::FileCache::with_path(cache_dir.to_path_buf()).unwrap(),
+            ))
+            .with_mock_keygen(keygen);
+        LicenseGate::new(manager, "test-key", Duration::from_secs(3600)).unwrap()
+    }
+
+    #[test]
+    fn test_extractors_check_license_and_entitlement() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let gate = gate(keygen.clone(), temp_dir.path());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (mut parts, ()) = Request::new(()).into_parts();
+
+        let guard = runtime
+            .block_on(LicenseGuard::from_request_parts(&mut parts, &gate))
+            .unwrap();
+        assert!(guard.valid);
+
+        let rejection = runtime
+            .block_on(Entitled::<Pro>::from_request_parts(&mut parts, &gate))
+            .unwrap_err();
+        assert_eq!(
+            rejection,
+            LicenseRejection::EntitlementMissing {
+                code: "PRO".to_string()
+            }
+        );
+        let response = rejection.into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = runtime
+            .block_on(response.into_body().collect())
+            .unwrap()
+            .to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "ENTITLEMENT_MISSING");
+    }
+
+    #[test]
+    fn test_invalid_license_is_payment_required() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let gate = gate(keygen.clone(), temp_dir.path());
+
+        keygen.add_license("test-key", MockLicense::new("lic-1").suspended());
+        gate.gate.refresh().unwrap_err();
+
+        let rejection = gate.check().unwrap_err();
+        assert_eq!(
+            rejection,
+            LicenseRejection::Unlicensed {
+                code: "LICENSE_SUSPENDED"
+            }
+        );
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::PAYMENT_REQUIRED
+        );
+    }
+}
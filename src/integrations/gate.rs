@@ -0,0 +1,161 @@
+//! Cached license verdict shared by the framework integrations and the
+//! feature registry.
+//!
+//! Validation uses the blocking Keygen client, so it never runs on the
+//! async executor: the first validation happens when the gate is built,
+//! and once the verdict is older than the TTL the next request starts a
+//! revalidation on a separate thread. Requests keep using the previous
+//! verdict until it completes.
+
+use crate::{GatewardenError, LicenseManager, RedactedKey, ValidationResult};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// License verdict shared by a layer or gate and everything it guards.
+pub(crate) struct Gate {
+    /// Always `Some` until the gate is dropped.
+    manager: Option<LicenseManager>,
+    license_key: RedactedKey,
+    ttl: Duration,
+    /// Fill in the license's full entitlement list on each refresh.
+    list_entitlements: bool,
+    verdict: RwLock<Verdict>,
+    refreshing: AtomicBool,
+}
+
+/// Outcome of the latest validation.
+#[derive(Clone)]
+struct Verdict {
+    /// The result if the license was valid, otherwise the error code
+    /// explaining why not.
+    outcome: Result<Arc<ValidationResult>, &'static str>,
+    checked_at: Instant,
+}
+
+impl Gate {
+    /// Validate `license_key` once and return the shared gate.
+    #[cfg(any(feature = "tower", feature = "actix"))]
+    pub(crate) fn start(
+        manager: LicenseManager,
+        license_key: String,
+        ttl: Duration,
+    ) -> Result<Arc<Self>, GatewardenError> {
+        Self::launch(manager, license_key, ttl, false)
+    }
+
+    /// As `start`, but each valid result carries every entitlement on the
+    /// license, not only the scoped ones.
+    pub(crate) fn start_with_entitlements(
+        manager: LicenseManager,
+        license_key: String,
+        ttl: Duration,
+    ) -> Result<Arc<Self>, GatewardenError> {
+        Self::launch(manager, license_key, ttl, true)
+    }
+
+    fn launch(
+        manager: LicenseManager,
+        license_key: String,
+        ttl: Duration,
+        list_entitlements: bool,
+    ) -> Result<Arc<Self>, GatewardenError> {
+        let gate = Self {
+            manager: Some(manager),
+            license_key: RedactedKey::new(license_key),
+            ttl,
+            list_entitlements,
+            verdict: RwLock::new(Verdict {
+                outcome: Err(GatewardenError::InvalidLicense.code()),
+                checked_at: Instant::now(),
+            }),
+            refreshing: AtomicBool::new(false),
+        };
+        gate.refresh()?;
+        Ok(Arc::new(gate))
+    }
+
+    /// Validate now, blocking, and record the verdict.
+    pub(crate) fn refresh(&self) -> Result<(), GatewardenError> {
+        let Some(manager) = &self.manager else {
+            return Ok(());
+        };
+        let result = manager.validate_key(self.license_key.expose());
+        let outcome = match &result {
+            Ok(result) if result.valid => Ok(Arc::new(self.with_entitlements(manager, result))),
+            Ok(_) => Err(GatewardenError::InvalidLicense.code()),
+            Err(e) => Err(e.code()),
+        };
+        let verdict = Verdict {
+            outcome,
+            checked_at: Instant::now(),
+        };
+        if let Ok(mut current) = self.verdict.write() {
+            *current = verdict;
+        }
+        result.map(|_| ())
+    }
+
+    /// `result` with the license's full entitlement list, if requested.
+    ///
+    /// The list is fetched online; when that fails, the codes from the
+    /// previous verdict are kept.
+    fn with_entitlements(
+        &self,
+        manager: &LicenseManager,
+        result: &ValidationResult,
+    ) -> ValidationResult {
+        let mut result = result.clone();
+        if !self.list_entitlements {
+            return result;
+        }
+        let listed = match manager.list_entitlements(self.license_key.expose()) {
+            Ok(listed) => listed.into_iter().map(|e| e.code).collect(),
+            Err(_) => match self.verdict.read().map(|v| v.outcome.clone()) {
+                Ok(Ok(previous)) => previous.state.entitlements.clone(),
+                _ => Vec::new(),
+            },
+        };
+        for code in listed {
+            if !result.state.entitlements.contains(&code) {
+                result.state.entitlements.push(code);
+            }
+        }
+        result
+    }
+
+    /// The latest valid result, or the error code refusing access,
+    /// starting a background revalidation if the verdict is older than
+    /// the TTL.
+    pub(crate) fn current(self: &Arc<Self>) -> Result<Arc<ValidationResult>, &'static str> {
+        let Ok(verdict) = self.verdict.read().map(|verdict| verdict.clone()) else {
+            return Err(GatewardenError::InvalidLicense.code());
+        };
+        if verdict.checked_at.elapsed() >= self.ttl && !self.refreshing.swap(true, Ordering::SeqCst)
+        {
+            let gate = Arc::clone(self);
+            let spawned = std::thread::Builder::new()
+                .name("gatewarden-gate".to_string())
+                .spawn(move || {
+                    let _ = gate.refresh();
+                    gate.refreshing.store(false, Ordering::SeqCst);
+                });
+            if spawned.is_err() {
+                self.refreshing.store(false, Ordering::SeqCst);
+            }
+        }
+        verdict.outcome
+    }
+}
+
+impl Drop for Gate {
+    fn drop(&mut self) {
+        // The blocking Keygen client panics if dropped on an async executor,
+        // which is where the last service usually goes away
+        if let Some(manager) = self.manager.take() {
+            let _ = std::thread::Builder::new()
+                .name("gatewarden-gate-drop".to_string())
+                .spawn(move || drop(manager));
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Route guards shared by the axum and actix-web integrations.
+//!
+//! The types live here so both frameworks refuse requests the same way;
+//! each integration module re-exports them and adds its extractor and
+//! response impls.
+
+use super::gate::Gate;
+use crate::{GatewardenError, LicenseManager, ValidationResult};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Shared app state holding the embedded license's latest verdict.
+#[derive(Clone)]
+pub struct LicenseGate {
+    pub(crate) gate: Arc<Gate>,
+}
+
+impl LicenseGate {
+    /// Validate `license_key` and build a gate that revalidates it every
+    /// `ttl`.
+    ///
+    /// Blocks on the first validation; call it before entering the async
+    /// runtime or from `spawn_blocking`.
+    ///
+    /// # Errors
+    /// Any error from `LicenseManager::validate_key`, so a server can
+    /// refuse to start without a license.
+    pub fn new(
+        manager: LicenseManager,
+        license_key: impl Into<String>,
+        ttl: Duration,
+    ) -> Result<Self, GatewardenError> {
+        Ok(Self {
+            gate: Gate::start(manager, license_key.into(), ttl)?,
+        })
+    }
+
+    /// The latest valid result, or the rejection a guarded route returns.
+    pub fn check(&self) -> Result<LicenseGuard, LicenseRejection> {
+        self.gate
+            .current()
+            .map(LicenseGuard)
+            .map_err(|code| LicenseRejection::Unlicensed { code })
+    }
+
+    /// Check the license and entitlement `E::CODE`.
+    pub(crate) fn entitled<E: RequiredEntitlement>(&self) -> Result<Entitled<E>, LicenseRejection> {
+        let guard = self.check()?;
+        guard.require(E::CODE)?;
+        Ok(Entitled {
+            guard,
+            entitlement: PhantomData,
+        })
+    }
+}
+
+/// Extractor that succeeds only while the license is valid.
+///
+/// Derefs to the latest [`ValidationResult`].
+#[derive(Debug, Clone)]
+pub struct LicenseGuard(pub Arc<ValidationResult>);
+
+impl LicenseGuard {
+    /// Refuse unless the license has entitlement `code`.
+    ///
+    /// As with `ValidationResult::has_entitlement`, `code` must be in
+    /// `required_entitlements` or the entitlement policy to be reported.
+    pub fn require(&self, code: &str) -> Result<(), LicenseRejection> {
+        if self.0.has_entitlement(code) {
+            Ok(())
+        } else {
+            Err(LicenseRejection::EntitlementMissing {
+                code: code.to_string(),
+            })
+        }
+    }
+}
+
+impl Deref for LicenseGuard {
+    type Target = ValidationResult;
+
+    fn deref(&self) -> &ValidationResult {
+        &self.0
+    }
+}
+
+/// Entitlement code checked by the [`Entitled`] extractor.
+pub trait RequiredEntitlement {
+    /// The Keygen entitlement code, e.g. `"PRO"`.
+    const CODE: &'static str;
+}
+
+/// Extractor that succeeds only while the license is valid and has
+/// entitlement `E::CODE`.
+pub struct Entitled<E> {
+    /// The guard for the valid license.
+    pub guard: LicenseGuard,
+    entitlement: PhantomData<fn() -> E>,
+}
+
+// Manual impls: marker types need not be `Clone` or `Debug`
+impl<E> Clone for Entitled<E> {
+    fn clone(&self) -> Self {
+        Self {
+            guard: self.guard.clone(),
+            entitlement: PhantomData,
+        }
+    }
+}
+
+impl<E: RequiredEntitlement> std::fmt::Debug for Entitled<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entitled")
+            .field("code", &E::CODE)
+            .field("guard", &self.guard)
+            .finish()
+    }
+}
+
+/// Why a guarded route refused a request.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LicenseRejection {
+    /// The license is not valid; answered with `402 Payment Required`.
+    #[error("A valid license is required")]
+    Unlicensed {
+        /// Error code of the failed validation, e.g. `LICENSE_EXPIRED`.
+        code: &'static str,
+    },
+
+    /// The license lacks an entitlement; answered with `403 Forbidden`.
+    #[error("Required entitlement missing: {code}")]
+    EntitlementMissing {
+        /// The entitlement code that was required.
+        code: String,
+    },
+}
+
+impl LicenseRejection {
+    /// HTTP status code of the refusal.
+    pub(crate) fn status(&self) -> u16 {
+        match self {
+            Self::Unlicensed { .. } => 402,
+            Self::EntitlementMissing { .. } => 403,
+        }
+    }
+
+    /// JSON body of the refusal, e.g.
+    /// `{"error": "ENTITLEMENT_MISSING", "message": "..."}`.
+    pub(crate) fn body(&self) -> String {
+        let error = match self {
+            Self::Unlicensed { code } => code,
+            Self::EntitlementMissing { .. } => "ENTITLEMENT_MISSING",
+        };
+        serde_json::json!({ "error": error, "message": self.to_string() }).to_string()
+    }
+}
@@ -1,2 +1,13 @@
 //! Optional framework integrations.
-// Future: axum integration behind feature flag
+
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+pub(crate) mod gate;
+#[cfg(any(feature = "axum", feature = "actix"))]
+mod guard;
+#[cfg(feature = "tauri")]
+pub mod tauri;
+#[cfg(feature = "tower")]
+pub mod tower;
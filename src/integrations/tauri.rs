@@ -0,0 +1,131 @@
+//! Tauri plugin exposing license commands to the frontend.
+//!
+//! Register the plugin and grant its default permission set
+//! (`gatewarden:default`) in a capability:
+//!
+//! ```rust,ignore
+//! tauri::Builder::default()
+//!     .plugin(gatewarden::integrations::tauri::init(manager))
+//!     .run(tauri::generate_context!())?;
+//! ```
+//!
+//! ```js
+//! const result = await invoke('plugin:gatewarden|validate_license', { licenseKey });
+//! const status = await invoke('plugin:gatewarden|license_status');
+//! await invoke('plugin:gatewarden|clear_license');
+//! ```
+//!
+//! - `validate_license` validates a key and, if it is valid, saves it in
+//!   the key store; it resolves to a [`ValidationResult`].
+//! - `license_status` runs `startup_state` on the stored key and resolves
+//!   to the serialized [`StartupState`].
+//! - `clear_license` deletes the stored key and license token.
+//!
+//! Failures reject with `{"code": "INVALID_LICENSE", "message": "..."}`.
+//! Keys are kept in the manager's key store, or in a [`FileKeyStore`]
+//! under the app data directory if it has none. Commands run on Tauri's
+//! blocking thread pool, never on the main thread.
+
+use crate::{FileKeyStore, GatewardenError, LicenseManager, StartupState, ValidationResult};
+use serde::Serialize;
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{Manager, Runtime, State};
+
+/// Managed state holding the plugin's manager.
+struct Licensing(LicenseManager);
+
+/// Error a command rejects with.
+#[derive(Debug, Serialize)]
+struct CommandError {
+    code: &'static str,
+    message: String,
+}
+
+impl From<GatewardenError> for CommandError {
+    fn from(e: GatewardenError) -> Self {
+        Self {
+            code: e.code(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Build the `gatewarden` plugin around `manager`.
+pub fn init<R: Runtime>(manager: LicenseManager) -> TauriPlugin<R> {
+    Builder::new("gatewarden")
+        .invoke_handler(tauri::generate_handler![
+            validate_license,
+            license_status,
+            clear_license
+        ])
+        .setup(move |app, _api| {
+            let manager = if manager.key_store().is_some() {
+                manager
+            } else {
+                let path = app.path().app_data_dir()?.join("license.key");
+                manager.with_key_store(Box::new(FileKeyStore::with_path(path)?))
+            };
+            app.manage(Licensing(manager));
+            Ok(())
+        })
+        .build()
+}
+
+/// Run `f` on the blocking pool; validation uses the blocking client.
+async fn blocking<T, F>(f: F) -> Result<T, CommandError>
+where
+    F: FnOnce() -> Result<T, GatewardenError> + Send + 'static,
+    T: Send + 'static,
+{
+    match tauri::async_runtime::spawn_blocking(f).await {
+        Ok(result) => result.map_err(CommandError::from),
+        Err(e) => Err(CommandError {
+            code: "INTERNAL",
+            message: e.to_string(),
+        }),
+    }
+}
+
+#[tauri::command]
+async fn validate_license(
+    licensing: State<'_, Licensing>,
+    license_key: String,
+) -> Result<ValidationResult, CommandError> {
+    let manager = licensing.0.clone();
+    blocking(move || {
+        let result = manager.validate_key(&license_key)?;
+        if result.valid {
+            if let Some(store) = manager.key_store() {
+                store.save_license_key(&license_key)?;
+            }
+        }
+        Ok(result)
+    })
+    .await
+}
+
+#[tauri::command]
+async fn license_status(licensing: State<'_, Licensing>) -> Result<StartupState, CommandError> {
+    let manager = licensing.0.clone();
+    blocking(move || {
+        let license_key = match manager.key_store() {
+            Some(store) => store.load_license_key()?,
+            None => None,
+        };
+        Ok(manager.startup_state(license_key.as_deref()))
+    })
+    .await
+}
+
+#[tauri::command]
+async fn clear_license(licensing: State<'_, Licensing>) -> Result<(), CommandError> {
+    let manager = licensing.0.clone();
+    blocking(move || {
+        if let Some(store) = manager.key_store() {
+            store.delete_license_key()?;
+            store.delete_license_token()?;
+        }
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,225 @@
+//! Tower middleware that gates requests on a valid license.
+//!
+//! [`LicenseLayer`] validates a license key once when it is built, then
+//! lets requests through while the license is valid and answers
+//! `403 Forbidden` otherwise. Works with any `http`-based Tower stack
+//! (axum, hyper, tonic):
+//!
+//! ```rust,ignore
+//! let layer = LicenseLayer::new(manager, license_key, Duration::from_secs(3600))?;
+//! let app = Router::new().route("/", get(handler)).layer(layer);
+//! ```
+//!
+//! Validation uses the blocking Keygen client, so it never runs on the
+//! async executor: build the layer before starting the runtime (or inside
+//! `spawn_blocking`), and once the verdict is older than the TTL the next
+//! request starts a revalidation on a separate thread. Requests keep
+//! using the previous verdict until it completes. Offline fallback, memo,
+//! and rate limiting apply as for any `validate_key` call.
+
+use super::gate::Gate;
+use crate::{GatewardenError, LicenseManager};
+use http::{Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer that wraps services in a [`LicenseService`].
+#[derive(Clone)]
+pub struct LicenseLayer {
+    gate: Arc<Gate>,
+}
+
+impl LicenseLayer {
+    /// Validate `license_key` and build a layer that revalidates it every
+    /// `ttl`.
+    ///
+    /// Blocks on the first validation; call it before entering the async
+    /// runtime or from `spawn_blocking`.
+    ///
+    /// # Errors
+    /// Any error from `LicenseManager::validate_key`, so a server can
+    /// refuse to start without a license.
+    pub fn new(
+        manager: LicenseManager,
+        license_key: impl Into<String>,
+        ttl: Duration,
+    ) -> Result<Self, GatewardenError> {
+        Ok(Self {
+            gate: Gate::start(manager, license_key.into(), ttl)?,
+        })
+    }
+}
+
+impl<S> Layer<S> for LicenseLayer {
+    type Service = LicenseService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LicenseService {
+            inner,
+            gate: Arc::clone(&self.gate),
+        }
+    }
+}
+
+/// Service that forwards requests only while the license is valid.
+#[derive(Clone)]
+pub struct LicenseService<S> {
+    inner: S,
+    gate: Arc<Gate>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for LicenseService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = LicenseFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let state = if self.gate.current().is_ok() {
+            State::Allowed {
+                inner: self.inner.call(request),
+            }
+        } else {
+            let mut response = Response::new(ResBody::default());
+            *response.status_mut() = StatusCode::FORBIDDEN;
+            State::Rejected {
+                response: Some(response),
+            }
+        };
+        LicenseFuture { state }
+    }
+}
+
+pin_project! {
+    /// Response future of [`LicenseService`].
+    pub struct LicenseFuture<F, B> {
+        #[pin]
+        state: State<F, B>,
+    }
+}
+
+pin_project! {
+    /// Whether the request was forwarded or refused with `403 Forbidden`.
+    #[project = StateProj]
+    enum State<F, B> {
+        Allowed { #[pin] inner: F },
+        Rejected { response: Option<Response<B>> },
+    }
+}
+
+impl<F, B, E> Future for LicenseFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().state.project() {
+            StateProj::Allowed { inner } => inner.poll(cx),
+            StateProj::Rejected { response } => match response.take() {
+                Some(response) => Poll::Ready(Ok(response)),
+                // Polled again after completion; stay pending like a fused future
+                None => Poll::Pending,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockKeygen, MockLicense};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::GatewardenConfig;
+    use std::convert::Infallible;
+    use std::future::{poll_fn, Ready};
+
+    /// Answers every request with `200 OK` and body "ok".
+    #[derive(Clone)]
+    struct Ok200;
+
+    impl Service<Request<()>> for Ok200 {
+        type Response = Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<String>, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: Request<()>) -> Self::Future {
+            std::future::ready(Ok(Response::new("ok".to_string())))
+        }
+    }
+
+    fn manager(keygen: Arc<MockKeygen>, cache_dir: &std::path::Path) -> LicenseManager {
+        let config = GatewardenConfig::builder()
+            .app_name("test")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .cache_namespace("test-tower")
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        LicenseManager::new(config)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                crate::cache::file::FileCache::with_path(cache_dir.to_path_buf()).unwrap(),
+            ))
+            .with_mock_keygen(keygen)
+    }
+
+    async fn status(service: &mut LicenseService<Ok200>) -> StatusCode {
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+        service.call(Request::new(())).await.unwrap().status()
+    }
+
+    #[test]
+    fn test_layer_gates_requests() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let manager = manager(keygen.clone(), temp_dir.path());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        // Validation blocks, so it runs outside the runtime
+        let layer = LicenseLayer::new(manager, "test-key", Duration::from_secs(3600)).unwrap();
+        let mut service = layer.layer(Ok200);
+        assert_eq!(runtime.block_on(status(&mut service)), StatusCode::OK);
+
+        // A revoked license is refused once the verdict is refreshed
+        keygen.add_license("test-key", MockLicense::new("lic-1").suspended());
+        service.gate.refresh().unwrap_err();
+        assert_eq!(
+            runtime.block_on(status(&mut service)),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(keygen.request_count(), 2);
+    }
+
+    #[test]
+    fn test_layer_requires_a_license() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        let manager = manager(keygen, temp_dir.path());
+        assert!(matches!(
+            LicenseLayer::new(manager, "unknown-key", Duration::from_secs(3600)),
+            Err(GatewardenError::InvalidLicense)
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+//! Cross-process leader election for background tasks.
+//!
+//! When several processes of the same product run at once, only one of
+//! them should refresh the cache or sync meters. A [`LeaderLock`] is an
+//! exclusive OS file lock under the cache namespace; whoever holds it is
+//! the leader for that task. The OS releases the lock if the process
+//! exits or crashes, so another process takes over on its next attempt.
+
+use crate::GatewardenError;
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Exclusive leadership of a background task, held until dropped.
+#[derive(Debug)]
+pub struct LeaderLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl LeaderLock {
+    /// Try to become leader for `task` within a cache namespace.
+    ///
+    /// The lock file lives at `dirs::data_dir()/<namespace>/locks/<task>.lock`.
+    /// Returns `Ok(None)` if another process (or another handle in this
+    /// process) is already leader.
+    pub fn try_acquire(namespace: &str, task: &str) -> Result<Option<Self>, GatewardenError> {
+        let base_dir = dirs::data_dir().ok_or_else(|| GatewardenError::CacheIO {
+            message: "Could not find data directory".to_string(),
+            source: None,
+        })?;
+        Self::try_acquire_at(
+            base_dir
+                .join(namespace)
+                .join("locks")
+                .join(format!("{}.lock", task)),
+        )
+    }
+
+    /// Try to become leader using a specific lock file path.
+    pub fn try_acquire_at(path: PathBuf) -> Result<Option<Self>, GatewardenError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to create lock dir".to_string(),
+                source: Some(e.into()),
+            })?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| GatewardenError::CacheIO {
+                message: "Failed to open lock file".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        if file.try_lock_exclusive().is_err() {
+            return Ok(None);
+        }
+
+        // Record the leader's PID for diagnostics only; the lock is what counts.
+        let _ = file.set_len(0);
+        let _ = write!(file, "{}", std::process::id());
+
+        Ok(Some(Self { file, path }))
+    }
+
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LeaderLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock too; unlock explicitly for clarity.
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_single_leader() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("locks").join("revalidator.lock");
+
+        let leader = LeaderLock::try_acquire_at(path.clone()).unwrap();
+        assert!(leader.is_some());
+        assert!(LeaderLock::try_acquire_at(path.clone()).unwrap().is_none());
+
+        drop(leader);
+        assert!(LeaderLock::try_acquire_at(path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_independent_tasks() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = LeaderLock::try_acquire_at(temp_dir.path().join("a.lock")).unwrap();
+        let b = LeaderLock::try_acquire_at(temp_dir.path().join("b.lock")).unwrap();
+        assert!(a.is_some());
+        assert!(b.is_some());
+    }
+}
@@ -17,7 +17,7 @@
 //! ## Quickstart
 //!
 //! ```no_run
-//! use gatewarden::{GatewardenConfig, LicenseManager};
+//! use gatewarden::{FallbackPolicy, GatewardenConfig, LicenseManager, ProxyConfig};
 //! use std::time::Duration;
 //!
 //! fn main() -> Result<(), gatewarden::GatewardenError> {
@@ -26,10 +26,29 @@
 //!         feature_name: "pro",
 //!         account_id: "your-keygen-account-id",
 //!         public_key_hex: "your-keygen-ed25519-public-key-hex",
+//!         public_key_id: None,
+//!         trusted_keys: &[],
 //!         required_entitlements: &["PRO_FEATURE"],
 //!         user_agent_product: "myapp-pro",
 //!         cache_namespace: "myapp-pro",
 //!         offline_grace: Duration::from_secs(24 * 60 * 60), // 24 hours
+//!         feature_grace: &[],
+//!         max_cache_age: Duration::from_secs(90 * 24 * 60 * 60), // 90 days
+//!         fallback_policy: FallbackPolicy::default(),
+//!         online_budget: None,
+//!         request_timeout: Duration::from_secs(30),
+//!         connect_timeout: Duration::from_secs(10),
+//!         proxy: ProxyConfig::System,
+//!         tls_pins: &[],
+//!         require_digest: false,
+//!         memo_ttl: Duration::from_secs(60),
+//!         negative_cache_ttl: Duration::ZERO,
+//!         validation_rate_limit: None,
+//!         app_version: None,
+//!         key_format: Default::default(),
+//!         user_agent_extras: Default::default(),
+//!         connection_pool: Default::default(),
+//!         ip_strategy: Default::default(),
 //!     };
 //!
 //!     let manager = LicenseManager::new(config)?;
@@ -59,6 +78,8 @@
 //! - `public_key_hex` — Keygen's Ed25519 verify key (64 hex chars)
 //! - `required_entitlements` — Entitlement codes the license must have
 //! - `offline_grace` — How long cached validations remain valid offline
+//! - `feature_grace` — Per-feature overrides of `offline_grace`
+//! - `max_cache_age` — Hard ceiling on cached record age (clamps all grace periods)
 //!
 //! See [`GatewardenConfig`] for full documentation.
 
@@ -66,10 +87,29 @@
 #![deny(missing_docs)]
 #![doc(html_root_url = "https://docs.rs/gatewarden/0.1.0")]
 
+#[cfg(all(
+    not(feature = "rustls"),
+    not(feature = "native-tls"),
+    not(target_arch = "wasm32")
+))]
+compile_error!("gatewarden needs a TLS backend: enable the `rustls` or `native-tls` feature");
+
+// Internal tracing macros (must precede their users)
+#[macro_use]
+#[cfg_attr(target_arch = "wasm32", allow(unused_macros))]
+mod trace;
+
 // Core modules
 pub mod clock;
 pub mod config;
+pub mod entropy;
 pub mod errors;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod fingerprint;
+pub mod redact;
+
+// Compile-time credential embedding
+pub mod embed;
 
 // Crypto layer
 pub mod crypto;
@@ -84,24 +124,165 @@ pub mod client;
 pub mod cache;
 
 // Metering layer
+#[cfg(not(target_arch = "wasm32"))]
 pub mod meter;
 
 // Policy layer
 pub mod policy;
 
+// Validation results
+pub mod validation;
+
 // Manager (main public API)
+#[cfg(not(target_arch = "wasm32"))]
 pub mod manager;
 
+// Validation event callbacks
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+
+// HMAC-chained audit log of validation attempts (feature `audit`)
+#[cfg(all(feature = "audit", not(target_arch = "wasm32")))]
+pub mod audit;
+
+// First-run key migration
+#[cfg(not(target_arch = "wasm32"))]
+pub mod migrate;
+
+// Clock-skew correction from verified server dates
+#[cfg(not(target_arch = "wasm32"))]
+pub mod skew;
+
+// Security posture reporting
+#[cfg(not(target_arch = "wasm32"))]
+pub mod posture;
+
+// Machine activations and concurrent seats of floating licenses
+#[cfg(not(target_arch = "wasm32"))]
+pub mod seats;
+
+// Opt-in reporting of security events to the vendor
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reporter;
+
+// Cross-process coordination of background tasks
+#[cfg(not(target_arch = "wasm32"))]
+pub mod leader;
+
+// Background revalidation
+#[cfg(not(target_arch = "wasm32"))]
+pub mod revalidator;
+
+// Coalescing of concurrent identical requests
+#[cfg(not(target_arch = "wasm32"))]
+mod singleflight;
+
+// Per-key rate limiting of online validations
+#[cfg(not(target_arch = "wasm32"))]
+mod limiter;
+
+// Fault injection (test seams only)
+#[cfg(all(any(test, feature = "test-seams"), not(target_arch = "wasm32")))]
+pub mod chaos;
+
+// In-process Keygen mock (test seams only)
+#[cfg(any(test, feature = "test-seams"))]
+pub mod mock;
+
+// Fetch-based validation for browsers
+pub mod web;
+
 // Optional integrations
+#[cfg(not(target_arch = "wasm32"))]
 pub mod integrations;
 
+// Process-wide default manager, and the one `#[licensed]` functions use
+#[cfg(not(target_arch = "wasm32"))]
+pub mod global;
+
+// Named features gated on entitlement policies
+#[cfg(not(target_arch = "wasm32"))]
+pub mod features;
+
+// Entitlement-aware update checks
+#[cfg(not(target_arch = "wasm32"))]
+pub mod releases;
+
+// Several managers addressed by feature name
+#[cfg(not(target_arch = "wasm32"))]
+pub mod registry;
+
+// C ABI (feature `ffi`)
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
+
 // Re-exports for public API
+#[cfg(all(feature = "audit", not(target_arch = "wasm32")))]
+pub use audit::{AuditEntry, AuditKey, AuditLog, AuditOutcome};
+pub use cache::backend::CacheBackend;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::keystore::{FileKeyStore, KeyStore};
+pub use client::dns::IpStrategy;
+pub use client::pool::ConnectionPool;
+pub use client::proxy::ProxyConfig;
+pub use client::request::UserAgentExtras;
 pub use clock::{Clock, SystemClock};
-pub use config::GatewardenConfig;
-pub use errors::GatewardenError;
-pub use manager::{LicenseManager, ValidationResult};
+pub use config::{GatewardenConfig, GatewardenConfigBuilder};
+pub use crypto::keys::{KeyRing, TrustedKey};
+pub use entropy::{EntropySource, OsEntropy};
+pub use errors::{ErrorCategory, ErrorSource, GatewardenError, TransportFailure};
+#[cfg(not(target_arch = "wasm32"))]
+pub use events::EventSink;
+#[cfg(not(target_arch = "wasm32"))]
+pub use features::{feature_enabled, FeatureRegistry};
+#[cfg(not(target_arch = "wasm32"))]
+pub use fingerprint::Component;
+#[cfg(not(target_arch = "wasm32"))]
+pub use global::{global, init, init_with};
+#[cfg(not(target_arch = "wasm32"))]
+pub use leader::LeaderLock;
+#[cfg(not(target_arch = "wasm32"))]
+pub use manager::{LicenseManager, StartupState, TrialLicense};
 pub use policy::access::UsageCaps;
-pub use protocol::models::LicenseState;
+pub use policy::bundle::{EntitlementBundle, SignedBundle};
+pub use policy::entitlements::EntitlementPolicy;
+pub use policy::fallback::{FallbackPolicy, FallbackReason};
+pub use policy::key_format::{CheckDigit, KeyFormat};
+pub use policy::links::{ManagementLink, SignedLink};
+pub use policy::upgrade::{UpgradeLinks, UpgradeReason};
+#[cfg(not(target_arch = "wasm32"))]
+pub use posture::SecurityPosture;
+pub use protocol::models::{Entitlement, LicenseGroup, LicenseOwner, LicenseState, LicenseToken};
+pub use redact::RedactedKey;
+#[cfg(not(target_arch = "wasm32"))]
+pub use registry::{ManagerRegistry, ManagerRegistryBuilder};
+#[cfg(not(target_arch = "wasm32"))]
+pub use releases::{Artifact, ArtifactDownload, Release, ReleaseChannel};
+#[cfg(not(target_arch = "wasm32"))]
+pub use reporter::{SecurityReport, SecurityReporter};
+#[cfg(not(target_arch = "wasm32"))]
+pub use revalidator::{LicenseStatus, RevalidationEvent, RevalidatorHandle};
+#[cfg(not(target_arch = "wasm32"))]
+pub use seats::{HeartbeatHandle, HeartbeatStatus, MachineLease, Seat};
+#[cfg(not(target_arch = "wasm32"))]
+pub use skew::{ClockSkew, SkewCorrectedClock};
+pub use validation::{ValidationOutcome, ValidationResult, ValidationWarning};
+pub use web::WebValidator;
+
+#[cfg(all(feature = "macros", not(target_arch = "wasm32")))]
+pub use gatewarden_macros::licensed;
+
+#[cfg(target_arch = "wasm32")]
+pub use web::LocalStorageCache;
 
+#[cfg(all(any(test, feature = "test-seams"), not(target_arch = "wasm32")))]
+pub use chaos::{FaultInjector, HeaderFault};
 #[cfg(any(test, feature = "test-seams"))]
 pub use clock::MockClock;
+#[cfg(any(test, feature = "test-seams"))]
+pub use entropy::MockEntropy;
+#[cfg(any(test, feature = "test-seams"))]
+pub use mock::{MockKeygen, MockLicense, MockRelease};
+
+#[cfg(all(windows, feature = "windows-registry"))]
+pub use cache::registry::RegistryCache;
@@ -0,0 +1,105 @@
+//! Per-key token buckets for outbound validations.
+//!
+//! Callers that validate on every operation would otherwise send Keygen
+//! one request per call. Each key gets a bucket holding up to
+//! `per_minute` tokens, refilled continuously at `per_minute` per minute;
+//! a validation spends one token, and calls finding the bucket empty are
+//! answered from the cache instead.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Tokens left in one key's bucket as of `updated`.
+struct Bucket {
+    tokens: f64,
+    updated: DateTime<Utc>,
+}
+
+/// Token-bucket limiter keyed by license key hash.
+pub(crate) struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Allow `per_minute` calls per key per minute, in bursts of up to
+    /// `per_minute`.
+    pub(crate) fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Spend a token for `key` at `now`; false if its bucket is empty.
+    pub(crate) fn try_acquire(&self, key: &str, now: DateTime<Utc>) -> bool {
+        // Limiting is a courtesy to Keygen; never block on a poisoned map
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return true;
+        };
+        let capacity = f64::from(self.per_minute);
+
+        // Full buckets carry no state; drop them so the map stays small
+        buckets.retain(|_, bucket| refill(bucket, capacity, now) < capacity);
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+/// Top up `bucket` for the time elapsed since its last update and return
+/// its tokens. A clock set back refills nothing.
+fn refill(bucket: &mut Bucket, capacity: f64, now: DateTime<Utc>) -> f64 {
+    let elapsed = now.signed_duration_since(bucket.updated);
+    if elapsed > chrono::Duration::zero() {
+        let minutes = elapsed.num_milliseconds() as f64 / 60_000.0;
+        bucket.tokens = (bucket.tokens + minutes * capacity).min(capacity);
+        bucket.updated = now;
+    }
+    bucket.tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_bucket_empties_and_refills() {
+        let limiter = RateLimiter::new(3);
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire("key", now));
+        }
+        assert!(!limiter.try_acquire("key", now));
+
+        // Other keys have their own bucket
+        assert!(limiter.try_acquire("other", now));
+
+        // One token comes back every 20 seconds
+        assert!(!limiter.try_acquire("key", now + chrono::Duration::seconds(19)));
+        assert!(limiter.try_acquire("key", now + chrono::Duration::seconds(20)));
+        assert!(!limiter.try_acquire("key", now + chrono::Duration::seconds(20)));
+    }
+
+    #[test]
+    fn test_full_buckets_are_dropped() {
+        let limiter = RateLimiter::new(2);
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+
+        assert!(limiter.try_acquire("key", now));
+        assert!(limiter.try_acquire("other", now + chrono::Duration::minutes(5)));
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(!buckets.contains_key("key"));
+        assert!(buckets.contains_key("other"));
+    }
+}
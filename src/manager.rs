@@ -5,48 +5,306 @@
 //! - Offline fallback with authenticated cache
 //! - Usage tracking and cap enforcement
 
+use crate::cache::backend::CacheBackend;
 use crate::cache::file::{hash_license_key, FileCache};
 use crate::cache::format::CacheRecord;
+use crate::cache::high_water::{HighWaterMark, TimeProof};
+use crate::cache::keystore::KeyStore;
+#[cfg(any(test, feature = "test-seams"))]
+use crate::chaos::{FaultInjector, FaultyCache};
 use crate::client::http::KeygenClient;
+use crate::client::request::{
+    validate_key_path, zeroize_body, Auth, LicenseCredential, KEYGEN_HOST,
+};
+use crate::client::response::{check_available, KeygenResponse};
 use crate::clock::{Clock, SystemClock};
 use crate::config::GatewardenConfig;
+use crate::crypto::freshness::parse_rfc2822_date;
 use crate::crypto::pipeline::verify_response;
-use crate::policy::access::{check_access_with_usage, UsageCaps};
-use crate::protocol::models::{KeygenValidateResponse, LicenseState};
+use crate::entropy::{EntropySource, OsEntropy};
+use crate::events::{is_security_error, EventSink};
+use crate::fingerprint::{machine_fingerprint, Component};
+use crate::limiter::RateLimiter;
+use crate::meter::export::{ExportFormat, SignedExport};
+use crate::meter::report::UsageReport;
+use crate::meter::usage::{MeterKey, MeterWriteFailure, UsageMeter, UsageRecord};
+#[cfg(any(test, feature = "test-seams"))]
+use crate::mock::MockKeygen;
+use crate::policy::access::{check_access_with_usage, check_not_expired, check_version, UsageCaps};
+use crate::policy::entitlements::EntitlementPolicy;
+use crate::policy::fallback::FallbackReason;
+use crate::policy::links::SignedLink;
+use crate::policy::upgrade::UpgradeReason;
+use crate::posture::{SecurityPosture, FRESHNESS_WINDOW, FUTURE_TOLERANCE};
+use crate::protocol::models::{
+    Entitlement, KeygenEntitlementsResponse, KeygenGroupResponse, KeygenLicenseResponse,
+    KeygenTokenResponse, KeygenUserResponse, KeygenValidateResponse, LicenseGroup, LicenseOwner,
+    LicenseState, LicenseToken,
+};
+use crate::redact::RedactedKey;
+use crate::singleflight::SingleFlight;
+use crate::skew::{ClockSkew, SkewCorrectedClock};
+use crate::validation::{compute_warnings, ValidationOutcome};
 use crate::GatewardenError;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zeroize::{Zeroize, Zeroizing};
 
-/// License validation result.
-#[derive(Debug, Clone)]
-pub struct ValidationResult {
-    /// Whether the license is valid.
-    pub valid: bool,
+pub use crate::validation::{ValidationResult, ValidationWarning};
+
+/// Keygen validation codes meaning the license needs a machine activation.
+pub(crate) const ACTIVATION_CODES: &[&str] =
+    &["NO_MACHINE", "NO_MACHINES", "FINGERPRINT_SCOPE_MISMATCH"];
+
+/// Give up listing entitlements after this many pages.
+const MAX_ENTITLEMENT_PAGES: u32 = 100;
+
+/// License state at application startup, from `LicenseManager::startup_state`.
+///
+/// Serializes with a `kind` tag for IPC, e.g.
+/// `{"kind": "needs_activation", "code": "NO_MACHINE", "management_link": null}`;
+/// `Failed` carries the error's `code` and `message`.
+#[derive(Debug)]
+pub enum StartupState {
+    /// No license key is stored (first run or signed out).
+    NoLicense,
+
+    /// Validated online.
+    Licensed(ValidationResult),
+
+    /// Keygen was unreachable; an authenticated cached result was used.
+    OfflineCached(ValidationResult),
+
+    /// Keygen was unreachable and the cached result exceeded its grace period.
+    CacheExpired,
+
+    /// The license has expired.
+    Expired {
+        /// Vendor-signed renewal link from the license, if any (unverified).
+        management_link: Option<SignedLink>,
+        /// Prefilled renewal URL from license metadata, if any (see
+        /// `UpgradeLinks::url_for`).
+        upgrade_url: Option<String>,
+    },
+
+    /// The license must be activated on this machine first.
+    NeedsActivation {
+        /// Keygen validation code (e.g. `NO_MACHINE`).
+        code: String,
+        /// Vendor-signed seat management link from the license, if any (unverified).
+        management_link: Option<SignedLink>,
+    },
+
+    /// Keygen rejected the key (suspended, banned, not found, ...).
+    Invalid {
+        /// Keygen validation code.
+        code: String,
+        /// Vendor-signed management link from the license, if any (unverified).
+        management_link: Option<SignedLink>,
+    },
+
+    /// Any other failure: a malformed key, no usable cache while offline,
+    /// a security error, or a policy failure such as a missing entitlement.
+    Failed(GatewardenError),
+}
+
+impl StartupState {
+    /// Classify a license state that Keygen reported as not valid.
+    fn from_rejected(state: LicenseState) -> Self {
+        if state.code == "EXPIRED" {
+            return Self::expired(state);
+        }
+        let management_link = state.management_link;
+        if ACTIVATION_CODES.contains(&state.code.as_str()) {
+            Self::NeedsActivation {
+                code: state.code,
+                management_link,
+            }
+        } else {
+            Self::Invalid {
+                code: state.code,
+                management_link,
+            }
+        }
+    }
+
+    /// `Expired`, with the renewal links of the expired license's state.
+    fn expired(state: LicenseState) -> Self {
+        let upgrade_url = state
+            .upgrade
+            .url_for(&UpgradeReason::Expired, state.license_id.as_deref());
+        Self::Expired {
+            management_link: state.management_link,
+            upgrade_url,
+        }
+    }
+
+    /// Signed management link attached to a denial, if any.
+    ///
+    /// Call [`SignedLink::verify`] with the vendor's link key before
+    /// showing it; never open an unverified link.
+    pub fn management_link(&self) -> Option<&SignedLink> {
+        match self {
+            Self::Expired {
+                management_link, ..
+            }
+            | Self::NeedsActivation {
+                management_link, ..
+            }
+            | Self::Invalid {
+                management_link, ..
+            } => management_link.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the application may proceed as licensed.
+    pub fn is_licensed(&self) -> bool {
+        matches!(self, Self::Licensed(_) | Self::OfflineCached(_))
+    }
+}
+
+impl Serialize for StartupState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(tag = "kind", rename_all = "snake_case")]
+        enum Repr<'a> {
+            NoLicense,
+            Licensed {
+                result: &'a ValidationResult,
+            },
+            OfflineCached {
+                result: &'a ValidationResult,
+            },
+            CacheExpired,
+            Expired {
+                management_link: &'a Option<SignedLink>,
+                upgrade_url: &'a Option<String>,
+            },
+            NeedsActivation {
+                code: &'a str,
+                management_link: &'a Option<SignedLink>,
+            },
+            Invalid {
+                code: &'a str,
+                management_link: &'a Option<SignedLink>,
+            },
+            Failed {
+                code: &'static str,
+                message: String,
+            },
+        }
 
-    /// The license state from Keygen.
-    pub state: LicenseState,
+        let repr = match self {
+            Self::NoLicense => Repr::NoLicense,
+            Self::Licensed(result) => Repr::Licensed { result },
+            Self::OfflineCached(result) => Repr::OfflineCached { result },
+            Self::CacheExpired => Repr::CacheExpired,
+            Self::Expired {
+                management_link,
+                upgrade_url,
+            } => Repr::Expired {
+                management_link,
+                upgrade_url,
+            },
+            Self::NeedsActivation {
+                code,
+                management_link,
+            } => Repr::NeedsActivation {
+                code,
+                management_link,
+            },
+            Self::Invalid {
+                code,
+                management_link,
+            } => Repr::Invalid {
+                code,
+                management_link,
+            },
+            Self::Failed(e) => Repr::Failed {
+                code: e.code(),
+                message: e.to_string(),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
 
-    /// Usage cap information.
-    pub caps: UsageCaps,
+/// A trial license created by `LicenseManager::start_trial`.
+///
+/// `Debug` output shows only a hash prefix of the license key.
+#[derive(Debug, Clone)]
+pub struct TrialLicense {
+    /// The new license key; store `license_key.expose()` like any other.
+    pub license_key: RedactedKey,
 
-    /// Whether this result came from cache.
-    pub from_cache: bool,
+    /// Verified validation of the new key.
+    pub result: ValidationResult,
 }
 
 /// Main license manager for Gatewarden.
 ///
 /// This is the primary public API. Create one instance per application
 /// and reuse it for all license checks.
+///
+/// The manager is `Send + Sync` and cheap to clone: clones share the
+/// cache, memoized results, in-flight validations and usage meters, so a
+/// server can keep one in its application state and hand a clone to each
+/// worker without wrapping it in a mutex.
+#[derive(Clone)]
 pub struct LicenseManager {
     config: GatewardenConfig,
-    clock: Arc<dyn Clock>,
-    client: KeygenClient,
-    cache: FileCache,
+    clock: Arc<SkewCorrectedClock>,
+    entropy: Arc<dyn EntropySource>,
+    client: Arc<KeygenClient>,
+    cache: Arc<dyn CacheBackend>,
+    high_water: Arc<HighWaterMark>,
+    meter_dir: Option<PathBuf>,
+    meter_write_failure: MeterWriteFailure,
+    meter_lock: Arc<Mutex<()>>,
+    machine_fingerprint: String,
+    components: Vec<Component>,
+    user: Option<String>,
+    entitlement_policy: Option<EntitlementPolicy>,
+    sinks: Vec<Arc<dyn EventSink>>,
+    key_store: Option<Arc<dyn KeyStore>>,
+    inflight: Arc<SingleFlight<Result<ValidationResult, GatewardenError>>>,
+    memo: Arc<Mutex<Memo>>,
+    checked_summaries: Arc<Mutex<HashMap<String, [u8; 32]>>>,
+    refusals: Arc<Mutex<Refusals>>,
+    rejections: Arc<Mutex<HashMap<String, LicenseState>>>,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+// Shared across server worker threads; keep it that way.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<LicenseManager>();
+};
+
+/// Memoized results by memo key, with the time each was stored.
+type Memo = HashMap<String, (DateTime<Utc>, ValidationResult)>;
+
+/// Refusals by flight key: when Keygen refused, and with which error.
+type Refusals = HashMap<String, (DateTime<Utc>, GatewardenError)>;
+
+/// A license token as persisted in the key store, bound to its key.
+#[derive(Serialize, Deserialize)]
+struct StoredToken {
+    key_hash: String,
+    token: LicenseToken,
 }
 
 impl LicenseManager {
     /// Create a new license manager with the given configuration.
     ///
-    /// Uses the system clock for time operations.
+    /// Uses the system clock for time operations, corrected by any clock
+    /// skew previously measured for this cache namespace.
     ///
     /// # Errors
     /// Returns an error if:
@@ -55,34 +313,194 @@ impl LicenseManager {
     /// - Cache directory creation fails
     pub fn new(config: GatewardenConfig) -> Result<Self, GatewardenError> {
         config.validate()?;
-        Self::with_clock(config, Arc::new(SystemClock))
+        let skew = ClockSkew::load(config.cache_namespace);
+        let high_water = HighWaterMark::for_namespace(config.cache_namespace);
+        let client = KeygenClient::new(&config)?;
+        Self::with_clock(config, Arc::new(SystemClock), skew, high_water, client)
     }
 
     /// Create a license manager with a custom clock (for testing).
+    ///
+    /// Skew and the time high-water mark are still tracked, but start
+    /// empty and are never persisted.
     #[cfg(any(test, feature = "test-seams"))]
     pub fn new_with_clock(
         config: GatewardenConfig,
         clock: Arc<dyn Clock>,
     ) -> Result<Self, GatewardenError> {
         config.validate()?;
-        Self::with_clock(config, clock)
+        let client = KeygenClient::new(&config)?;
+        Self::with_clock(
+            config,
+            clock,
+            ClockSkew::in_memory(),
+            HighWaterMark::with_path(None),
+            client,
+        )
     }
 
-    fn with_clock(
+    /// Assemble a manager around an already-built client; `config` must
+    /// already be validated.
+    pub(crate) fn with_clock(
         config: GatewardenConfig,
         clock: Arc<dyn Clock>,
+        skew: ClockSkew,
+        high_water: HighWaterMark,
+        client: KeygenClient,
     ) -> Result<Self, GatewardenError> {
-        let client = KeygenClient::new(&config)?;
         let cache = FileCache::new(config.cache_namespace)?;
+        let meter_dir = dirs::data_dir().map(|d| d.join(config.cache_namespace));
+        let limiter = config
+            .validation_rate_limit
+            .map(|per_minute| Arc::new(RateLimiter::new(per_minute)));
 
         Ok(Self {
             config,
-            clock,
-            client,
-            cache,
+            clock: Arc::new(SkewCorrectedClock::new(clock, skew)),
+            entropy: Arc::new(OsEntropy),
+            client: Arc::new(client),
+            cache: Arc::new(cache),
+            high_water: Arc::new(high_water),
+            meter_dir,
+            meter_write_failure: MeterWriteFailure::default(),
+            meter_lock: Arc::new(Mutex::new(())),
+            machine_fingerprint: machine_fingerprint().unwrap_or_default(),
+            components: Vec::new(),
+            user: None,
+            entitlement_policy: None,
+            sinks: Vec::new(),
+            key_store: None,
+            inflight: Arc::new(SingleFlight::new()),
+            memo: Arc::new(Mutex::new(HashMap::new())),
+            checked_summaries: Arc::new(Mutex::new(HashMap::new())),
+            refusals: Arc::new(Mutex::new(HashMap::new())),
+            rejections: Arc::new(Mutex::new(HashMap::new())),
+            limiter,
         })
     }
 
+    /// Replace the cache storage backend.
+    ///
+    /// By default records are stored as files under the platform data
+    /// directory. Use this to plug in an alternative backend such as
+    /// `RegistryCache` (Windows, `windows-registry` feature).
+    pub fn with_cache_backend(mut self, backend: Box<dyn CacheBackend>) -> Self {
+        self.cache = Arc::from(backend);
+        self
+    }
+
+    /// Persist license tokens in `store`.
+    ///
+    /// Without a key store, `exchange_license_token` still returns the
+    /// token but it is not used for later requests.
+    pub fn with_key_store(mut self, store: Box<dyn KeyStore>) -> Self {
+        self.key_store = Some(Arc::from(store));
+        self
+    }
+
+    /// Store usage meters in `dir` instead of the cache namespace directory.
+    pub fn with_meter_dir(mut self, dir: PathBuf) -> Self {
+        self.meter_dir = Some(dir);
+        self
+    }
+
+    /// Choose what `record_use` does when a use cannot be journaled.
+    ///
+    /// Defaults to [`MeterWriteFailure::Reject`].
+    pub fn with_meter_write_failure(mut self, policy: MeterWriteFailure) -> Self {
+        self.meter_write_failure = policy;
+        self
+    }
+
+    /// Require entitlements by policy, e.g. `PRO` or `ENTERPRISE`.
+    ///
+    /// Applied on top of `required_entitlements`. Online validation lists
+    /// the license's entitlements, evaluates the policy against them, and
+    /// validates once with the codes it holds in scope; cached and offline
+    /// results are checked against it too.
+    pub fn with_entitlement_policy(mut self, policy: EntitlementPolicy) -> Self {
+        self.entitlement_policy = Some(policy);
+        self
+    }
+
+    /// Register hardware `components` with machine activations and scope
+    /// seat validations to them.
+    ///
+    /// For policies that require component matching: Keygen then answers
+    /// `COMPONENTS_SCOPE_MISMATCH` (`ComponentsMismatch`) when the machine
+    /// holding a seat no longer has the components it was activated with.
+    pub fn with_components(mut self, components: Vec<Component>) -> Self {
+        self.components = components;
+        self
+    }
+
+    /// Scope validations to `user`, a Keygen user's email or ID.
+    ///
+    /// For per-user licenses: Keygen answers `USER_SCOPE_MISMATCH`
+    /// (`UserMismatch`) when the license is not assigned to the user, and
+    /// cached results are only accepted if they were scoped to the same
+    /// user.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Scope validations to product `product_id`.
+    ///
+    /// For accounts with several products: Keygen answers
+    /// `PRODUCT_SCOPE_MISMATCH` (`InvalidLicense`) for keys of a sibling
+    /// product, and cached results are only accepted for licenses of the
+    /// product.
+    pub fn with_product_scope(mut self, product_id: impl Into<String>) -> Self {
+        self.client = Arc::new((*self.client).clone().with_product_scope(product_id));
+        self
+    }
+
+    /// Scope validations to policy `policy_id`.
+    ///
+    /// Keygen answers `POLICY_SCOPE_MISMATCH` (`InvalidLicense`) for keys
+    /// issued under any other policy, and cached results are only accepted
+    /// for licenses of the policy.
+    pub fn with_policy_scope(mut self, policy_id: impl Into<String>) -> Self {
+        self.client = Arc::new((*self.client).clone().with_policy_scope(policy_id));
+        self
+    }
+
+    /// Replace the entropy source (defaults to the OS RNG).
+    ///
+    /// Use a deterministic source in tests, or an approved generator in
+    /// environments that constrain where randomness comes from.
+    pub fn with_entropy_source(mut self, entropy: Arc<dyn EntropySource>) -> Self {
+        self.entropy = entropy;
+        self
+    }
+
+    /// Register an event sink to be notified of validation outcomes.
+    ///
+    /// Multiple sinks may be registered; they are called in order.
+    pub fn with_event_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Inject faults into the cache and client layers (for resilience testing).
+    ///
+    /// Wraps the current cache backend, so call this after
+    /// `with_cache_backend` if both are used.
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_fault_injector(mut self, faults: Arc<FaultInjector>) -> Self {
+        Arc::make_mut(&mut self.client).set_fault_injector(faults.clone());
+        self.cache = Arc::new(FaultyCache::new(self.cache, faults));
+        self
+    }
+
+    /// Send Keygen requests to a mock instead of the network (for testing).
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_mock_keygen(mut self, mock: Arc<MockKeygen>) -> Self {
+        Arc::make_mut(&mut self.client).set_mock_keygen(mock);
+        self
+    }
+
     /// Validate a license key.
     ///
     /// This performs the full validation pipeline:
@@ -91,193 +509,1810 @@ impl LicenseManager {
     /// 3. Cache successful responses
     /// 4. Fall back to cached response if online fails and cache is valid
     ///
+    /// A license rejected by Keygen or by the cache is always an error
+    /// (`InvalidLicense` or a more specific license error); a returned
+    /// result always has `valid: true`. Prefer
+    /// [`validate_key_outcome`](Self::validate_key_outcome), which reports
+    /// rejections as a [`ValidationOutcome`] instead of an error.
+    ///
     /// # Errors
     /// - `MissingLicense` - No license key provided
+    /// - `MalformedLicenseKey` - The key fails the configured `key_format`
     /// - `SignatureMissing` - Response missing required security headers
     /// - `SignatureInvalid` - Response signature verification failed
     /// - `InvalidLicense` - License is not valid
+    /// - `LicenseSuspended` / `LicenseBanned` / `LicenseOverdue` - Keygen
+    ///   reported the license as suspended, banned, or overdue for check-in
     /// - `EntitlementMissing` - Required entitlement not found
     /// - `UsageLimitExceeded` - Usage cap exceeded
     /// - `CacheExpired` - Offline and cache has expired
+    /// - `LicenseExpired` - Offline and the cached license is past its expiry
+    ///
+    /// Offline fallback uses the grace period of the configured
+    /// `feature_name` (see `GatewardenConfig::offline_grace_for`).
+    ///
+    /// Concurrent calls for the same key share a single Keygen request:
+    /// the first caller's verified result (or error) is returned to all.
+    /// A successful online result is then reused from memory for
+    /// `memo_ttl`, and Keygen's refusal of the key for
+    /// `negative_cache_ttl`; use `force_refresh` to bypass both.
     pub fn validate_key(&self, license_key: &str) -> Result<ValidationResult, GatewardenError> {
-        if license_key.is_empty() {
+        self.validate_feature(license_key, self.config.feature_name)
+    }
+
+    /// Validate a license key like `validate_key`, reporting what was
+    /// concluded about the license as a [`ValidationOutcome`].
+    ///
+    /// The preferred entry point: a rejected license is `Invalid` whether
+    /// Keygen, the cache, or a local policy rejected it, and a missing
+    /// entitlement is `Denied`, so only failures to get a trustworthy
+    /// answer need error handling.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - No license key provided
+    /// - `MalformedLicenseKey` - The key fails the configured `key_format`
+    /// - Any verification, transport, cache, or configuration error from
+    ///   `validate_key` (e.g. `SignatureInvalid`, `CacheExpired`)
+    pub fn validate_key_outcome(
+        &self,
+        license_key: &str,
+    ) -> Result<ValidationOutcome, GatewardenError> {
+        ValidationOutcome::from_result(self.validate_key(license_key))
+    }
+
+    /// Validate candidate keys in order and return the first accepted one,
+    /// with its index in `license_keys`.
+    ///
+    /// Useful during a key migration: pass the new key, then the legacy
+    /// one. Each key goes through `validate_key` on its own, so each keeps
+    /// its own cache record and memoized result.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_keys` is empty
+    /// - `NoValidKey` - No key was accepted; `failures` holds each key's
+    ///   error in order
+    pub fn validate_any(
+        &self,
+        license_keys: &[&str],
+    ) -> Result<(usize, ValidationResult), GatewardenError> {
+        if license_keys.is_empty() {
             return Err(GatewardenError::MissingLicense);
         }
 
-        let key_hash = hash_license_key(license_key);
-
-        // Try online validation first
-        match self.validate_online(license_key, &key_hash) {
-            Ok(result) => Ok(result),
-            Err(online_error) => {
-                // Try offline fallback
-                self.validate_offline(&key_hash, online_error)
+        let mut failures = Vec::with_capacity(license_keys.len());
+        for (index, license_key) in license_keys.iter().enumerate() {
+            match self.validate_key(license_key) {
+                Ok(result) if result.valid => return Ok((index, result)),
+                Ok(_) => failures.push(GatewardenError::InvalidLicense),
+                Err(e) => failures.push(e),
             }
         }
+        Err(GatewardenError::NoValidKey { failures })
     }
 
-    /// Check access for a license without additional validation.
+    /// Validate a license key for a specific feature profile.
     ///
-    /// This uses the cached license state if available.
-    /// Use `validate_key` for full validation.
-    pub fn check_access(&self, license_key: &str) -> Result<ValidationResult, GatewardenError> {
-        if license_key.is_empty() {
-            return Err(GatewardenError::MissingLicense);
+    /// Identical to `validate_key`, except that offline fallback enforces
+    /// the grace period configured for `feature` in `feature_grace`.
+    pub fn validate_feature(
+        &self,
+        license_key: &str,
+        feature: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+
+        let memo_key = memo_key(&license_key.hash(), feature);
+        if let Some(result) = self.memoized(&memo_key, true) {
+            return Ok(result);
         }
 
-        let key_hash = hash_license_key(license_key);
+        let result = self.validate_credential(LicenseCredential::Key(&license_key), feature, None);
+        self.memoize(memo_key, &result);
+        result
+    }
 
-        // Load from cache
-        let record = self
-            .cache
-            .load(&key_hash)?
-            .ok_or(GatewardenError::InvalidLicense)?;
+    /// Validate a license key like `validate_key`, waiting at most
+    /// `max_wait` for Keygen.
+    ///
+    /// For interactive applications that need a bounded license check:
+    /// when Keygen has not answered by the deadline, the request is
+    /// abandoned and the check falls back to the offline cache as for a
+    /// network failure (`FallbackReason::Network` with
+    /// `TransportFailure::Timeout`). Unlike `online_budget`, the deadline
+    /// applies even when nothing usable is cached.
+    ///
+    /// # Errors
+    /// - `KeygenTransport` - The deadline passed and the cache cannot answer
+    /// - Any other error from `validate_key`
+    pub fn validate_key_with_deadline(
+        &self,
+        license_key: &str,
+        max_wait: Duration,
+    ) -> Result<ValidationResult, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        let feature = self.config.feature_name;
 
-        // Verify cache is authentic and within grace
-        record.verify(
-            self.config.public_key_hex,
-            self.config.offline_grace,
-            self.clock.as_ref(),
-        )?;
+        let memo_key = memo_key(&license_key.hash(), feature);
+        if let Some(result) = self.memoized(&memo_key, true) {
+            return Ok(result);
+        }
 
-        // Parse cached response
-        let response: KeygenValidateResponse = serde_json::from_str(record.body())
-            .map_err(|e| GatewardenError::ProtocolError(format!("Cache parse error: {}", e)))?;
+        let result = self.validate_credential(
+            LicenseCredential::Key(&license_key),
+            feature,
+            Some(max_wait),
+        );
+        self.memoize(memo_key, &result);
+        result
+    }
 
-        let state = LicenseState::from_keygen_response(&response)?;
-        let caps = check_access_with_usage(
-            &state,
-            self.config.required_entitlements,
-            0, // No new usage
-        )?;
+    /// Validate a license by ID, authenticating with a license token.
+    ///
+    /// For deployments that distribute license (activation) tokens rather
+    /// than raw keys: calls Keygen's validate action for `license_id` with
+    /// `Authorization: Bearer <token>`. Otherwise runs the same pipeline as
+    /// `validate_key`, including offline fallback. Results are cached under
+    /// the license ID, so `check_access(license_id)` answers from them.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - No license ID or token provided
+    /// - `InvalidLicense` - Keygen rejected the token (HTTP 401/403/404)
+    /// - Any error from `validate_key`
+    pub fn validate_license_id(
+        &self,
+        license_id: &str,
+        token: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        if license_id.is_empty() || token.is_empty() {
+            return Err(GatewardenError::MissingLicense);
+        }
 
-        Ok(ValidationResult {
-            valid: state.valid,
-            state,
-            caps,
-            from_cache: true,
-        })
+        self.validate_credential(
+            LicenseCredential::Token { license_id, token },
+            self.config.feature_name,
+            None,
+        )
     }
 
-    /// Online validation with Keygen API.
-    fn validate_online(
+    /// Online-first validation behind `validate_feature` and
+    /// `validate_license_id`, caching under the key or license ID; a
+    /// `max_wait` bounds the wait for Keygen whether or not the cache can
+    /// answer.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "gatewarden.validate", skip_all, fields(feature = %feature))
+    )]
+    fn validate_credential(
         &self,
-        license_key: &str,
-        key_hash: &str,
+        credential: LicenseCredential<'_>,
+        feature: &str,
+        max_wait: Option<Duration>,
     ) -> Result<ValidationResult, GatewardenError> {
-        // Call Keygen with required entitlements in scope
-        // This ensures Keygen echoes back the entitlements in the response
-        let response = self
-            .client
-            .validate_key(license_key, self.config.required_entitlements)?;
+        let grace = self.config.offline_grace_for(feature);
+        let (key_hash, flight_key) = match credential {
+            LicenseCredential::Key(license_key) => (license_key.hash(), license_key.hash()),
+            LicenseCredential::Token { license_id, token } => {
+                let key_hash = hash_license_key(license_id);
+                let flight_key = format!("{}:{}", key_hash, hash_license_key(token));
+                (key_hash, flight_key)
+            }
+        };
 
-        // Verify signature, digest, and freshness
-        verify_response(&response, self.config.public_key_hex, self.clock.as_ref())?;
+        // Don't ask Keygen again about a credential it just refused
+        if let Some(refusal) = self.recently_refused(&flight_key) {
+            return self.notify(Err(refusal));
+        }
 
-        // Extract fields we need for caching before parsing body
-        let date = response.date.clone().unwrap_or_default();
-        let signature = response.signature.clone().unwrap_or_default();
-        let digest = response.digest.clone();
-        let request_path = response.request_path.clone();
-        let host = response.host.clone();
+        // Over the rate limit, answer from the cache without asking Keygen
+        if let Some(limiter) = &self.limiter {
+            if !limiter.try_acquire(&flight_key, self.clock.now_utc()) {
+                trace_debug!("validation rate limit reached, using cache");
+                let result = self
+                    .serve_cached(&key_hash, grace, FallbackReason::Throttled)
+                    .and_then(|result| {
+                        result.ok_or(GatewardenError::RateLimited { request_id: None })
+                    });
+                return self.notify(result);
+            }
+        }
 
-        // Parse response
-        let body_str = response.body_str()?;
-        let keygen_response: KeygenValidateResponse = serde_json::from_str(body_str)
-            .map_err(|e| GatewardenError::ProtocolError(format!("Parse error: {}", e)))?;
+        // Within the latency budget, a slow Keygen gives way to the cache
+        let deadline = max_wait.or_else(|| {
+            self.config
+                .online_budget
+                .filter(|_| self.cache_can_answer(&key_hash, grace))
+        });
 
-        let state = LicenseState::from_keygen_response(&keygen_response)?;
+        // Try online validation first, sharing one request among
+        // concurrent callers with the same credential; deadline-bounded
+        // calls never wait on an unbounded request
+        let inflight_key = match max_wait {
+            Some(max_wait) => format!("{}:{}ms", flight_key, max_wait.as_millis()),
+            None => flight_key.clone(),
+        };
+        let (online, leader) = self.inflight.run(
+            &inflight_key,
+            || self.validate_online_within(credential, &key_hash, deadline),
+            |result| match result {
+                Ok(result) => Ok(result.clone()),
+                Err(e) => Err(e.duplicate()),
+            },
+        );
 
-        // Check access policy
-        let caps = check_access_with_usage(
-            &state,
-            self.config.required_entitlements,
-            0, // No new usage for validation
-        )?;
+        let result = match online {
+            Ok(result) => {
+                if leader {
+                    self.emit(|sink| sink.on_validated(&result));
+                }
+                Ok(result)
+            }
+            Err(refusal) if refusal.is_refusal() => {
+                self.remember_refusal(flight_key, &refusal);
+                Err(refusal)
+            }
+            Err(online_error) => {
+                // Try offline fallback
+                self.validate_offline(&key_hash, grace, online_error)
+            }
+        };
 
-        // Cache successful validation
-        let cache_record = CacheRecord::new(
-            date,
-            signature,
-            digest,
-            body_str.to_string(),
-            request_path,
-            host,
-            self.clock.as_ref(),
-        );
-        self.cache.save(key_hash, &cache_record)?;
+        self.notify(result)
+    }
 
-        Ok(ValidationResult {
-            valid: state.valid,
-            state,
-            caps,
-            from_cache: false,
-        })
+    /// Force an online re-validation, bypassing the offline cache.
+    ///
+    /// Unlike `validate_key`, this never falls back to a cached response:
+    /// any network or verification failure is returned to the caller.
+    /// A successful validation still refreshes the cache.
+    ///
+    /// Use this for "Re-check license" actions or after a purchase/upgrade,
+    /// where a stale cached result is unacceptable.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - No license key provided
+    /// - `MalformedLicenseKey` - The key fails the configured `key_format`
+    /// - `KeygenTransport` - Keygen could not be reached
+    /// - Any verification or policy error from `validate_key`
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "gatewarden.force_refresh", skip_all)
+    )]
+    pub fn force_refresh(&self, license_key: &str) -> Result<ValidationResult, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+
+        let key_hash = license_key.hash();
+        self.forget_memo(&key_hash);
+        let result = self.validate_online(&license_key, &key_hash);
+        if let Ok(ref result) = result {
+            self.emit(|sink| sink.on_validated(result));
+        }
+
+        self.notify(result)
     }
 
-    /// Offline validation from authenticated cache.
-    fn validate_offline(
+    /// Check access for a license without additional validation.
+    ///
+    /// This uses the cached license state if available, or a result
+    /// memoized within `memo_ttl`. Use `validate_key` for full validation.
+    pub fn check_access(&self, license_key: &str) -> Result<ValidationResult, GatewardenError> {
+        self.check_feature_access(license_key, self.config.feature_name)
+    }
+
+    /// Entitlement codes of a license, answered from cache when possible.
+    ///
+    /// Equivalent to `check_access(key)?.state.entitlements`; use it to
+    /// gate features at call sites that do not need the full result.
+    ///
+    /// # Errors
+    /// Any error from `check_access`.
+    pub fn entitlements(&self, license_key: &str) -> Result<Vec<String>, GatewardenError> {
+        Ok(self.check_access(license_key)?.state.entitlements)
+    }
+
+    /// Check cached access for a specific feature profile.
+    ///
+    /// The cached record must be within the grace period configured for
+    /// `feature` in `feature_grace`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "gatewarden.check_access", skip_all, fields(feature = %feature))
+    )]
+    pub fn check_feature_access(
         &self,
-        key_hash: &str,
-        online_error: GatewardenError,
+        license_key: &str,
+        feature: &str,
     ) -> Result<ValidationResult, GatewardenError> {
-        // Only fall back for transport errors
-        if !matches!(online_error, GatewardenError::KeygenTransport(_)) {
-            return Err(online_error);
-        }
+        let license_key = self.redact(license_key)?;
 
-        // Load cached record
-        let record = self.cache.load(key_hash)?.ok_or(online_error)?;
+        let grace = self.config.offline_grace_for(feature);
+        let key_hash = license_key.hash();
+        let memo_key = memo_key(&key_hash, feature);
+        if let Some(result) = self.memoized(&memo_key, false) {
+            return Ok(result);
+        }
 
-        // Verify cache authenticity and grace period
-        record.verify(
-            self.config.public_key_hex,
-            self.config.offline_grace,
-            self.clock.as_ref(),
-        )?;
+        let result = self.notify(self.check_access_cached(&key_hash, grace));
+        self.memoize(memo_key, &result);
+        result
+    }
 
-        // Parse cached response
-        let response: KeygenValidateResponse = serde_json::from_str(record.body())
-            .map_err(|e| GatewardenError::ProtocolError(format!("Cache parse error: {}", e)))?;
+    /// Record `n` uses of the licensed product, enforcing usage caps.
+    ///
+    /// Checks access like `check_access`, then journals `n` uses of
+    /// `feature_name` in the local usage meter for this key, tagged with
+    /// the license ID for billing export. Caps are enforced against Keygen's
+    /// `current_uses` plus uses recorded locally but not yet synced; if
+    /// the total would exceed `max_uses`, nothing is recorded.
+    ///
+    /// Returns the caps with `current_uses` including the local uses.
+    ///
+    /// # Errors
+    /// - `UsageLimitExceeded` - The uses would exceed the license's cap
+    /// - `MeterIO` - The meter could not be read or persisted
+    /// - `MeterTampered` - The meter was edited or rolled back
+    /// - Any error from `check_access`
+    pub fn record_use(&self, license_key: &str, n: u64) -> Result<UsageCaps, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        let result = self.check_access(license_key.expose())?;
 
-        let state = LicenseState::from_keygen_response(&response)?;
+        let _guard = self
+            .meter_lock
+            .lock()
+            .map_err(|_| GatewardenError::MeterIO {
+                message: "Usage meter lock poisoned".to_string(),
+                source: None,
+            })?;
+        let mut meter = self.usage_meter(&license_key.hash())?;
 
-        // Check access policy
-        let caps = check_access_with_usage(&state, self.config.required_entitlements, 0)?;
+        let local_uses = meter.unsynced_count().saturating_add(n);
+        self.check_state(&result.state, local_uses)?;
+        meter.record(&UsageRecord {
+            timestamp: self.clock.now_utc(),
+            feature: self.config.feature_name.to_string(),
+            quantity: n,
+            license_id: result.state.license_id.clone().unwrap_or_default(),
+        })?;
 
-        Ok(ValidationResult {
-            valid: state.valid,
-            state,
-            caps,
-            from_cache: true,
-        })
+        let mut caps = result.caps;
+        caps.current_uses = Some(caps.current_uses.unwrap_or(0).saturating_add(local_uses));
+        Ok(caps)
     }
 
-    /// Get the current configuration.
-    pub fn config(&self) -> &GatewardenConfig {
-        &self.config
-    }
-}
+    /// Report locally recorded uses to Keygen and reconcile the meter.
+    ///
+    /// Validates the key online (refreshing the cache and obtaining the
+    /// license ID), sends the meter's unsynced uses to Keygen's
+    /// increment-usage action, verifies the signed response, and marks
+    /// those uses as synced. Keygen's updated `uses` is saved with the
+    /// cached record, so `record_use` counts the synced uses against the
+    /// cap. Returns caps with Keygen's updated `uses`.
+    ///
+    /// The increment is journaled before it is sent. If Keygen is
+    /// unreachable or the response fails verification, the uses stay
+    /// unsynced; the next call treats the earlier increment as counted if
+    /// Keygen's `uses` has grown by at least its size, and sends it again
+    /// otherwise.
+    ///
+    /// # Errors
+    /// - `UsageLimitExceeded` - Keygen refused the increment (HTTP 422)
+    /// - `ProtocolError` - No license ID, or an unexpected response status
+    /// - `MeterIO` - The meter could not be read or updated
+    /// - `MeterTampered` - The meter was edited or rolled back
+    /// - `CacheIO` - The updated `uses` could not be cached
+    /// - Any error from online validation
+    pub fn sync_usage(&self, license_key: &str) -> Result<UsageCaps, GatewardenError> {
+        let license_key = self.redact(license_key)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::Duration;
+        let key_hash = license_key.hash();
+        self.forget_memo(&key_hash);
+        let result = self.notify(self.validate_online(&license_key, &key_hash))?;
 
-    fn test_config() -> GatewardenConfig {
-        GatewardenConfig {
-            app_name: "test-app",
-            feature_name: "test",
-            account_id: "test-account",
-            public_key_hex: "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
-            required_entitlements: &[],
-            user_agent_product: "test-product",
-            cache_namespace: "gatewarden-test",
-            offline_grace: Duration::from_secs(86400),
+        let _guard = self
+            .meter_lock
+            .lock()
+            .map_err(|_| GatewardenError::MeterIO {
+                message: "Usage meter lock poisoned".to_string(),
+                source: None,
+            })?;
+        let mut meter = self.usage_meter(&key_hash)?;
+
+        let base_uses = result.state.current_uses.unwrap_or(0);
+        if let Some(sent) = meter.in_flight() {
+            if base_uses >= sent.base_uses.saturating_add(sent.n) {
+                // The earlier increment reached Keygen; only its response was lost
+                meter.mark_synced(sent.n, self.clock.as_ref())?;
+                trace_info!(synced = sent.n, "usage sync reconciled");
+            }
+        }
+
+        let pending = meter.unsynced_count();
+        if pending == 0 {
+            return Ok(result.caps);
         }
+
+        let license_id =
+            result
+                .state
+                .license_id
+                .as_deref()
+                .ok_or_else(|| GatewardenError::ProtocolError {
+                    message: "Keygen response has no license ID".to_string(),
+                    source: None,
+                })?;
+        let token = self.license_token(license_key.expose())?;
+        meter.begin_sync(pending, base_uses, self.clock.as_ref())?;
+        let response = self.client.increment_usage_as(
+            license_auth(&license_key, token.as_ref()),
+            license_id,
+            pending,
+        )?;
+        let caps = self.accept_usage_response(&response)?;
+
+        meter.mark_synced(pending, self.clock.as_ref())?;
+        trace_info!(synced = pending, "usage synced to Keygen");
+        self.record_synced_uses(&key_hash, caps.current_uses)?;
+        Ok(caps)
     }
 
-    #[test]
+    /// Save Keygen's `uses` after an increment with the cached record,
+    /// and drop memoized results that predate it.
+    fn record_synced_uses(&self, key_hash: &str, uses: Option<u64>) -> Result<(), GatewardenError> {
+        self.forget_memo(key_hash);
+        let (Some(uses), Some(mut record)) = (uses, self.cache.load(key_hash)?) else {
+            return Ok(());
+        };
+        record.synced_uses = Some(record.synced_uses.map_or(uses, |synced| synced.max(uses)));
+        self.cache.save(key_hash, &record)
+    }
+
+    /// Per-day uses recorded on this machine for a license key.
+    ///
+    /// `source` identifies the installation in the report, as for
+    /// `export_usage`. Reads the local meter only; nothing is sent to
+    /// Keygen.
+    ///
+    /// # Errors
+    /// - `MeterIO` - The meter could not be read
+    /// - `MeterTampered` - The meter was edited or rolled back
+    pub fn usage_report(
+        &self,
+        license_key: &str,
+        source: &str,
+    ) -> Result<UsageReport, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        let _guard = self
+            .meter_lock
+            .lock()
+            .map_err(|_| GatewardenError::MeterIO {
+                message: "Usage meter lock poisoned".to_string(),
+                source: None,
+            })?;
+        let meter = self.usage_meter(&license_key.hash())?;
+        Ok(meter.report(source, self.clock.as_ref()))
+    }
+
+    /// Signed billing export of the uses recorded on this machine for a
+    /// license key, one event per `record_use` call.
+    ///
+    /// `source` identifies the installation and `signing_seed` is its
+    /// Ed25519 key; see `SignedExport`. Nothing is sent to Keygen.
+    ///
+    /// # Errors
+    /// - `MeterIO` - The meter could not be read, or rendering failed
+    /// - `MeterTampered` - The meter was edited or rolled back
+    pub fn export_usage(
+        &self,
+        license_key: &str,
+        format: ExportFormat,
+        source: &str,
+        signing_seed: &[u8; 32],
+    ) -> Result<SignedExport, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        let _guard = self
+            .meter_lock
+            .lock()
+            .map_err(|_| GatewardenError::MeterIO {
+                message: "Usage meter lock poisoned".to_string(),
+                source: None,
+            })?;
+        let meter = self.usage_meter(&license_key.hash())?;
+        meter.export(format, source, signing_seed)
+    }
+
+    /// Delete cached results for one license key, or for all keys if
+    /// `license_key` is `None`.
+    ///
+    /// Memoized results and remembered refusals go too, so the next
+    /// validation must reach Keygen.
+    ///
+    /// # Errors
+    /// - `CacheIO` - A cache record could not be deleted
+    pub fn clear_cache(&self, license_key: Option<&str>) -> Result<(), GatewardenError> {
+        match license_key {
+            Some(license_key) => {
+                let license_key = self.redact(license_key)?;
+                let key_hash = license_key.hash();
+                self.cache.delete(&key_hash)?;
+                self.forget_memo(&key_hash);
+                if let Ok(mut checked) = self.checked_summaries.lock() {
+                    checked.remove(&key_hash);
+                }
+            }
+            None => {
+                self.cache.clear()?;
+                if let Ok(mut memo) = self.memo.lock() {
+                    memo.clear();
+                }
+                if let Ok(mut refusals) = self.refusals.lock() {
+                    refusals.clear();
+                }
+                if let Ok(mut rejections) = self.rejections.lock() {
+                    rejections.clear();
+                }
+                if let Ok(mut checked) = self.checked_summaries.lock() {
+                    checked.clear();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify an increment-usage response and extract the updated caps.
+    fn accept_usage_response(
+        &self,
+        response: &KeygenResponse,
+    ) -> Result<UsageCaps, GatewardenError> {
+        check_available(response)?;
+        self.verify_with_skew(response)?;
+
+        match response.status {
+            200..=299 => {}
+            422 => return Err(GatewardenError::UsageLimitExceeded),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Usage increment failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        let license: KeygenLicenseResponse =
+            serde_json::from_str(response.body_str()?).map_err(|e| {
+                GatewardenError::ProtocolError {
+                    message: "Parse error".to_string(),
+                    source: Some(e.into()),
+                }
+            })?;
+
+        Ok(UsageCaps {
+            monthly_limit: license.data.attributes.max_uses,
+            current_uses: license.data.attributes.uses,
+        })
+    }
+
+    /// List every entitlement attached to a license.
+    ///
+    /// Unlike validation, which only confirms the entitlements it asks
+    /// about, this fetches the license's full entitlement list from
+    /// Keygen, authenticating with the license key. Each page's signature
+    /// is verified before it is used; pages are followed until Keygen
+    /// reports no next page. The cache is neither read nor updated.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `InvalidLicense` - Keygen does not know the key (HTTP 401/403/404)
+    /// - `ProtocolError` - Unparseable page, unexpected status, or too many pages
+    /// - Any transport, availability, or verification error
+    pub fn list_entitlements(
+        &self,
+        license_key: &str,
+    ) -> Result<Vec<Entitlement>, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(&license_key, token.as_ref());
+        self.fetch_entitlements(auth, license_key.expose(), None)
+    }
+
+    /// Fetch every page of the entitlements of `license` (ID or key),
+    /// giving up once `deadline` has passed if set.
+    fn fetch_entitlements(
+        &self,
+        auth: Auth<'_>,
+        license: &str,
+        deadline: Option<Duration>,
+    ) -> Result<Vec<Entitlement>, GatewardenError> {
+        let started = std::time::Instant::now();
+        let mut entitlements = Vec::new();
+        for page in 1..=MAX_ENTITLEMENT_PAGES {
+            let remaining = deadline.map(|deadline| deadline.saturating_sub(started.elapsed()));
+            let response = self
+                .client
+                .list_entitlements_as(auth, license, page, remaining)?;
+            let page = self.accept_entitlements_page(&response)?;
+
+            let done = page.data.is_empty() || page.links.and_then(|links| links.next).is_none();
+            entitlements.extend(page.data.into_iter().map(Entitlement::from));
+            if done {
+                return Ok(entitlements);
+            }
+        }
+
+        Err(GatewardenError::ProtocolError {
+            message: format!("Entitlement list exceeds {} pages", MAX_ENTITLEMENT_PAGES),
+            source: None,
+        })
+    }
+
+    /// Verify one page of a license's entitlements and parse it.
+    fn accept_entitlements_page(
+        &self,
+        response: &KeygenResponse,
+    ) -> Result<KeygenEntitlementsResponse, GatewardenError> {
+        check_available(response)?;
+        self.verify_with_skew(response)?;
+
+        match response.status {
+            200..=299 => {}
+            401 | 403 | 404 => return Err(GatewardenError::InvalidLicense),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Entitlement listing failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        serde_json::from_str(response.body_str()?).map_err(|e| GatewardenError::ProtocolError {
+            message: "Parse error".to_string(),
+            source: Some(e.into()),
+        })
+    }
+
+    /// Fill in the license's group (name and limits) and owner (name and
+    /// email) in `state`, e.g. from `validate_key`.
+    ///
+    /// Validation only returns their IDs. Each is fetched with a GET
+    /// authenticated like `list_entitlements`, and its signed response is
+    /// verified before it is used; the cache is neither read nor updated.
+    /// A group or owner the license may not read (HTTP 403/404) keeps just
+    /// its ID.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `InvalidLicense` - Keygen refused the license (HTTP 401)
+    /// - `ProtocolError` - Unparseable response or unexpected status
+    /// - Any transport, availability, or verification error
+    pub fn fetch_group_and_owner(
+        &self,
+        license_key: &str,
+        mut state: LicenseState,
+    ) -> Result<LicenseState, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        if state.group.is_none() && state.owner.is_none() {
+            return Ok(state);
+        }
+
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(&license_key, token.as_ref());
+        if let Some(group) = &mut state.group {
+            let response = self.client.group_as(auth, &group.id)?;
+            if let Some(document) = self.accept_related::<KeygenGroupResponse>(&response)? {
+                *group = LicenseGroup::from(document.data);
+            }
+        }
+        if let Some(owner) = &mut state.owner {
+            let response = self.client.user_as(auth, &owner.id)?;
+            if let Some(document) = self.accept_related::<KeygenUserResponse>(&response)? {
+                *owner = LicenseOwner::from(document.data);
+            }
+        }
+        Ok(state)
+    }
+
+    /// Verify a group or user response and parse it; `None` when the
+    /// license may not read it (HTTP 403/404).
+    fn accept_related<T: DeserializeOwned>(
+        &self,
+        response: &KeygenResponse,
+    ) -> Result<Option<T>, GatewardenError> {
+        check_available(response)?;
+        self.verify_with_skew(response)?;
+
+        match response.status {
+            200..=299 => {}
+            403 | 404 => return Ok(None),
+            401 => return Err(GatewardenError::InvalidLicense),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Group or owner lookup failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        serde_json::from_str(response.body_str()?)
+            .map(Some)
+            .map_err(|e| GatewardenError::ProtocolError {
+                message: "Parse error".to_string(),
+                source: Some(e.into()),
+            })
+    }
+
+    /// Exchange a license key for a license token and store it.
+    ///
+    /// Calls Keygen's tokens endpoint authenticated with the key and
+    /// verifies the signed response like any other. With a key store (see
+    /// `with_key_store`), the token replaces any stored one and is used
+    /// instead of the raw key for later license-authenticated requests
+    /// (usage sync, entitlement listing) until it expires.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `InvalidLicense` - Keygen refused the key (HTTP 401/403/404)
+    /// - `ProtocolError` - Unparseable token or unexpected status
+    /// - `CacheIO` - The token could not be stored
+    /// - Any transport, availability, or verification error
+    pub fn exchange_license_token(
+        &self,
+        license_key: &str,
+    ) -> Result<LicenseToken, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+
+        let mut response = self.client.create_token(&license_key)?;
+        check_available(&response)?;
+        self.verify_with_skew(&response)?;
+
+        match response.status {
+            200..=299 => {}
+            401 | 403 | 404 => return Err(GatewardenError::InvalidLicense),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Token exchange failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        // The body carries the token; clear it once parsed
+        let document = serde_json::from_str::<KeygenTokenResponse>(response.body_str()?);
+        response.body.zeroize();
+        let document = document.map_err(|e| GatewardenError::ProtocolError {
+            message: "Parse error".to_string(),
+            source: Some(e.into()),
+        })?;
+        let token = LicenseToken::from(document.data);
+
+        if let Some(store) = &self.key_store {
+            let stored = StoredToken {
+                key_hash: license_key.hash(),
+                token: token.clone(),
+            };
+            let json = serde_json::to_string(&stored)
+                .map(Zeroizing::new)
+                .map_err(|e| GatewardenError::CacheIO {
+                    message: "Failed to serialize license token".to_string(),
+                    source: Some(e.into()),
+                })?;
+            store.save_license_token(&json)?;
+        }
+
+        trace_info!("license token issued");
+        Ok(token)
+    }
+
+    /// Create a trial license under `policy_id` and validate it.
+    ///
+    /// Calls Keygen's license-creation endpoint as the user `user_token`
+    /// belongs to, which Keygen only allows for unprotected policies. The
+    /// license is marked `"trial": true` in its metadata, so its state
+    /// reports `is_trial`; the policy's duration sets its expiry. The
+    /// signed response is verified like any other, then the new key is
+    /// saved to the key store (if any) and validated.
+    ///
+    /// # Errors
+    /// - `ConfigError` - Empty argument, or Keygen refused to create a
+    ///   license under the policy (HTTP 401/403/404)
+    /// - `ProtocolError` - Unparseable response or unexpected status
+    /// - `CacheIO` - The key could not be stored
+    /// - Any transport, availability, verification, or validation error
+    pub fn start_trial(
+        &self,
+        policy_id: &str,
+        user_token: &str,
+    ) -> Result<TrialLicense, GatewardenError> {
+        if policy_id.is_empty() || user_token.is_empty() {
+            return Err(GatewardenError::ConfigError(
+                "Trial policy ID and user token cannot be empty".to_string(),
+            ));
+        }
+
+        let mut response = self.client.create_trial_license(policy_id, user_token)?;
+        check_available(&response)?;
+        self.verify_with_skew(&response)?;
+
+        match response.status {
+            200..=299 => {}
+            401 | 403 | 404 => {
+                return Err(GatewardenError::ConfigError(format!(
+                    "Keygen refused to create a trial license (HTTP {})",
+                    response.status
+                )))
+            }
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Trial creation failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        // The body carries the new key; clear it and the parsed copy
+        let document = serde_json::from_str::<serde_json::Value>(response.body_str()?);
+        response.body.zeroize();
+        let mut document = document.map_err(|e| GatewardenError::ProtocolError {
+            message: "Parse error".to_string(),
+            source: Some(e.into()),
+        })?;
+        let license_key = document["data"]["attributes"]["key"]
+            .as_str()
+            .filter(|key| !key.is_empty())
+            .map(RedactedKey::from);
+        zeroize_body(&mut document);
+        let license_key = license_key.ok_or_else(|| GatewardenError::ProtocolError {
+            message: "Created license has no key".to_string(),
+            source: None,
+        })?;
+
+        // The license exists now; keep the key even if validation fails
+        if let Some(store) = &self.key_store {
+            store.save_license_key(license_key.expose())?;
+        }
+        trace_info!("trial license created");
+
+        let result = self.validate_key(license_key.expose())?;
+        Ok(TrialLicense {
+            license_key,
+            result,
+        })
+    }
+
+    /// The stored license token for `license_key`, if it is still usable.
+    ///
+    /// Returns `None` without a key store, or when the stored token was
+    /// issued for another key, has expired, or cannot be read back.
+    ///
+    /// # Errors
+    /// - `CacheIO` - The key store could not be read
+    pub fn license_token(
+        &self,
+        license_key: &str,
+    ) -> Result<Option<LicenseToken>, GatewardenError> {
+        let Some(store) = &self.key_store else {
+            return Ok(None);
+        };
+        let Some(json) = store.load_license_token()?.map(Zeroizing::new) else {
+            return Ok(None);
+        };
+
+        // A malformed token only costs us the raw key as credential
+        let license_key = self.redact(license_key)?;
+        let key_hash = license_key.hash();
+        Ok(serde_json::from_str::<StoredToken>(&json)
+            .ok()
+            .filter(|stored| stored.key_hash == key_hash)
+            .map(|stored| stored.token)
+            .filter(|token| !token.is_expired(self.clock.as_ref())))
+    }
+
+    /// Usage meter for a license key hash.
+    ///
+    /// Meters are tamper-evident, keyed from the key hash and this
+    /// machine's fingerprint.
+    pub(crate) fn usage_meter(&self, key_hash: &str) -> Result<UsageMeter, GatewardenError> {
+        let dir = self
+            .meter_dir
+            .as_ref()
+            .ok_or_else(|| GatewardenError::MeterIO {
+                message: "Could not find data directory".to_string(),
+                source: None,
+            })?;
+        let key = MeterKey::derive(key_hash, &self.machine_fingerprint);
+        Ok(
+            UsageMeter::with_key(dir.join(format!("usage_{}.json", key_hash)), key)?
+                .with_write_failure(self.meter_write_failure),
+        )
+    }
+
+    /// Verify a validate-key response served by an untrusted intermediary.
+    ///
+    /// For deployments whose egress proxy re-serves recorded Keygen
+    /// responses. Pass the response body and headers as received. The
+    /// response must carry Keygen's signature over `api.keygen.sh` and
+    /// this account's validate-key path, a `Digest` covering the body,
+    /// and a `Date` inside the freshness window.
+    ///
+    /// The response must also be for `license`, the license key or
+    /// license ID the caller asked about: a signed response for another
+    /// license of the same account is rejected, so it cannot be replayed.
+    ///
+    /// The result is checked against `required_entitlements` but never
+    /// cached.
+    ///
+    /// # Errors
+    /// - `ProtocolError` - The response names another license, or none
+    /// - `SignatureMissing` - Signature, date, or digest header is missing
+    /// - Any verification or access error from `validate_key`
+    pub fn verify_untrusted_response(
+        &self,
+        license: &str,
+        body: &[u8],
+        headers: &[(&str, &str)],
+    ) -> Result<ValidationResult, GatewardenError> {
+        let result = self.verify_untrusted(license, body, headers);
+        self.notify(result)
+    }
+
+    /// Verification behind `verify_untrusted_response`.
+    fn verify_untrusted(
+        &self,
+        license: &str,
+        body: &[u8],
+        headers: &[(&str, &str)],
+    ) -> Result<ValidationResult, GatewardenError> {
+        // Host and path come from our expectations, so the signature
+        // check rejects responses Keygen signed for anything else
+        let response = KeygenResponse::from_parts(
+            200,
+            body,
+            headers,
+            validate_key_path(self.config.account_id),
+            KEYGEN_HOST.to_string(),
+        );
+
+        // Without a digest the signature does not cover the body
+        if response.digest.is_none() {
+            return Err(GatewardenError::SignatureMissing {
+                request_id: response.request_id,
+            });
+        }
+        verify_response(&response, self.config.key_ring(), self.clock.as_ref())?;
+
+        let keygen_response: KeygenValidateResponse = serde_json::from_str(response.body_str()?)
+            .map_err(|e| GatewardenError::ProtocolError {
+                message: "Parse error".to_string(),
+                source: Some(e.into()),
+            })?;
+        let for_license = keygen_response.data.as_ref().is_some_and(|data| {
+            data.id == license
+                || data.attributes.key.as_ref().is_some_and(|key| {
+                    self.redact(license)
+                        .is_ok_and(|expected| key.expose() == expected.expose())
+                })
+        });
+        if !for_license {
+            return Err(GatewardenError::ProtocolError {
+                message: "Response is not for the expected license".to_string(),
+                source: None,
+            });
+        }
+
+        let state = LicenseState::from_keygen_response(&keygen_response)?;
+        let caps = self.check_state(&state, 0)?;
+        let warnings = compute_warnings(&state, &caps, None, self.clock.now_utc());
+
+        Ok(ValidationResult {
+            valid: state.valid,
+            state,
+            caps,
+            from_cache: false,
+            fallback_reason: None,
+            cached_at: None,
+            cache_age: None,
+            warnings,
+        })
+    }
+
+    /// Determine the license state at application startup.
+    ///
+    /// Runs `validate_key` (online first, then the offline cache, with
+    /// its memo, rate limit, `online_budget`, and negative cache) but
+    /// returns a [`StartupState`] covering the usual boot-time branches
+    /// instead of an error to be matched on. Pass the stored license key,
+    /// or `None` if the user has never entered one.
+    pub fn startup_state(&self, license_key: Option<&str>) -> StartupState {
+        let license_key = match license_key.map(|k| self.redact(k)) {
+            None | Some(Err(GatewardenError::MissingLicense)) => return StartupState::NoLicense,
+            Some(Err(e)) => return StartupState::Failed(e),
+            Some(Ok(license_key)) => license_key,
+        };
+        let key_hash = license_key.hash();
+
+        match self.validate_key(license_key.expose()) {
+            Ok(result) if result.from_cache => StartupState::OfflineCached(result),
+            Ok(result) => StartupState::Licensed(result),
+            Err(GatewardenError::CacheExpired) => StartupState::CacheExpired,
+            Err(GatewardenError::LicenseExpired { .. }) => {
+                // Expired while offline: the renewal links come from the cache
+                match self.verified_cached_state(&key_hash) {
+                    Some(state) => StartupState::expired(state),
+                    None => StartupState::Expired {
+                        management_link: None,
+                        upgrade_url: None,
+                    },
+                }
+            }
+            Err(e) if e.is_refusal() => {
+                // Keygen answered authentically but rejected the key
+                let rejection = self
+                    .rejections
+                    .lock()
+                    .ok()
+                    .and_then(|rejections| rejections.get(&key_hash).cloned());
+                match rejection {
+                    Some(state) => StartupState::from_rejected(state),
+                    None => StartupState::Failed(e),
+                }
+            }
+            Err(e) => StartupState::Failed(e),
+        }
+    }
+
+    /// License state of the key's cached record, if it verifies within
+    /// the configured feature's grace period.
+    fn verified_cached_state(&self, key_hash: &str) -> Option<LicenseState> {
+        let grace = self.config.offline_grace_for(self.config.feature_name);
+        let record = self.load_cached(key_hash).ok()??;
+        self.verify_record(&record, grace).ok()?;
+        self.cached_state(key_hash, &record).ok()
+    }
+
+    /// Answer `license_key` from the verified cache without asking Keygen,
+    /// for offline-first startup.
+    ///
+    /// `None` when the cache cannot answer: no record, or one that fails
+    /// verification, grace, expiry, or the access checks.
+    pub(crate) fn validate_deferred(&self, license_key: &RedactedKey) -> Option<ValidationResult> {
+        let grace = self.config.offline_grace_for(self.config.feature_name);
+        let result = self
+            .serve_cached(&license_key.hash(), grace, FallbackReason::Deferred)
+            .ok()??;
+        self.notify(Ok(result)).ok()
+    }
+
+    /// Cached access check behind `check_access`.
+    fn check_access_cached(
+        &self,
+        key_hash: &str,
+        grace: Duration,
+    ) -> Result<ValidationResult, GatewardenError> {
+        // Load from cache
+        let record = self
+            .load_cached(key_hash)?
+            .ok_or(GatewardenError::InvalidLicense)?;
+
+        // Verify cache is authentic and within grace, and the clock wasn't set back
+        self.verify_record(&record, grace)?;
+        self.high_water
+            .check(&record, self.clock.now_utc(), self.config.key_ring())?;
+
+        let state = self.cached_state(key_hash, &record)?;
+
+        // Cached state never outlives the license itself
+        check_not_expired(&state, self.clock.now_utc())?;
+
+        let caps = self.check_state(&state, 0)?;
+        let warnings = compute_warnings(
+            &state,
+            &caps,
+            Some((record.cached_at, grace)),
+            self.clock.now_utc(),
+        );
+
+        Ok(ValidationResult {
+            valid: state.valid,
+            state,
+            caps,
+            from_cache: true,
+            fallback_reason: None,
+            cached_at: Some(record.cached_at),
+            cache_age: Some(self.cache_age(&record)),
+            warnings,
+        })
+    }
+
+    /// Online validation with Keygen API.
+    fn validate_online(
+        &self,
+        license_key: &RedactedKey,
+        key_hash: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        self.validate_online_within(LicenseCredential::Key(license_key), key_hash, None)
+    }
+
+    /// Online validation, abandoning the request after `deadline` if set.
+    fn validate_online_within(
+        &self,
+        credential: LicenseCredential<'_>,
+        key_hash: &str,
+        deadline: Option<Duration>,
+    ) -> Result<ValidationResult, GatewardenError> {
+        let (state, record) = self.fetch_online(credential, deadline)?;
+        self.accept_online(key_hash, state, record)
+    }
+
+    /// Fetch and verify a Keygen validation, without applying access policy.
+    ///
+    /// Validates once, with the scope from [`Self::entitlement_scope`];
+    /// fails with `EntitlementMissing` if Keygen reports
+    /// `ENTITLEMENTS_MISSING`.
+    ///
+    /// Returns the license state and the record to cache if it is accepted.
+    fn fetch_online(
+        &self,
+        credential: LicenseCredential<'_>,
+        deadline: Option<Duration>,
+    ) -> Result<(LicenseState, CacheRecord), GatewardenError> {
+        // The deadline covers the entitlement listing too
+        let started = std::time::Instant::now();
+        let held = match &self.entitlement_policy {
+            Some(_) => Some(self.held_entitlements(credential, deadline)?),
+            None => None,
+        };
+        let scope = self.entitlement_scope(held.as_deref());
+
+        let remaining = deadline.map(|deadline| deadline.saturating_sub(started.elapsed()));
+        let (state, record) = self.fetch_scoped(credential, &scope, remaining)?;
+        if state.code == "ENTITLEMENTS_MISSING" {
+            return Err(GatewardenError::EntitlementMissing {
+                code: self.entitlement_requirement(),
+            });
+        }
+        Ok((state, record))
+    }
+
+    /// Codes of every entitlement attached to the license in `credential`.
+    fn held_entitlements(
+        &self,
+        credential: LicenseCredential<'_>,
+        deadline: Option<Duration>,
+    ) -> Result<Vec<String>, GatewardenError> {
+        let entitlements = match credential {
+            LicenseCredential::Key(license_key) => {
+                let token = self.license_token(license_key.expose())?;
+                let auth = license_auth(license_key, token.as_ref());
+                self.fetch_entitlements(auth, license_key.expose(), deadline)?
+            }
+            LicenseCredential::Token { license_id, token } => {
+                self.fetch_entitlements(Auth::Bearer(token), license_id, deadline)?
+            }
+        };
+        Ok(entitlements.into_iter().map(|e| e.code).collect())
+    }
+
+    /// The required entitlements and entitlement policy, as reported by
+    /// `EntitlementMissing`: e.g. `"PRO"` or `"all of (PRO, any of (A, B))"`.
+    fn entitlement_requirement(&self) -> String {
+        let mut parts: Vec<String> = self
+            .config
+            .required_entitlements
+            .iter()
+            .map(|code| code.to_string())
+            .collect();
+        parts.extend(self.entitlement_policy.as_ref().map(|p| p.to_string()));
+        match parts.as_slice() {
+            [] => String::new(),
+            [single] => single.clone(),
+            _ => format!("all of ({})", parts.join(", ")),
+        }
+    }
+
+    /// Entitlement scope to validate with, given the codes the license
+    /// `held` (listed only when there is an entitlement policy).
+    ///
+    /// The required entitlements, then the policy's codes: those the
+    /// license holds if it satisfies the policy, so Keygen confirms them,
+    /// or all of them otherwise, so Keygen reports why it is rejected.
+    fn entitlement_scope(&self, held: Option<&[String]>) -> Vec<String> {
+        let mut scope: Vec<String> = self
+            .config
+            .required_entitlements
+            .iter()
+            .map(|code| code.to_string())
+            .collect();
+        let (Some(policy), Some(held)) = (&self.entitlement_policy, held) else {
+            return scope;
+        };
+
+        let satisfied =
+            scope.iter().all(|code| held.contains(code)) && policy.is_satisfied_by(held);
+        for code in policy.codes() {
+            if (!satisfied || held.contains(&code)) && !scope.contains(&code) {
+                scope.push(code);
+            }
+        }
+        scope
+    }
+
+    /// Fetch and verify a Keygen validation with `entitlements` in scope.
+    fn fetch_scoped(
+        &self,
+        credential: LicenseCredential<'_>,
+        scope: &[String],
+        deadline: Option<Duration>,
+    ) -> Result<(LicenseState, CacheRecord), GatewardenError> {
+        let entitlements: Vec<&str> = scope.iter().map(String::as_str).collect();
+        // Call Keygen with the entitlements in scope
+        // This ensures Keygen echoes back the entitlements in the response
+        let response =
+            self.client
+                .validate(credential, &entitlements, self.user.as_deref(), deadline)?;
+
+        // Surface availability failures before verification so the
+        // fallback policy can distinguish them from tampering.
+        check_available(&response)?;
+
+        // Verify signature, digest, and freshness
+        self.verify_with_skew(&response)?;
+
+        // A rejected token is an authentic answer, not a validation document
+        if matches!(response.status, 401 | 403 | 404) {
+            return Err(GatewardenError::InvalidLicense);
+        }
+
+        // Extract fields we need for caching before parsing body
+        let date = response.date.clone().unwrap_or_default();
+        let signature = response.signature.clone().unwrap_or_default();
+        let digest = response.digest.clone();
+        let request_path = response.request_path.clone();
+        let host = response.host.clone();
+        let request_id = response.request_id.clone();
+
+        // Parse response
+        let body_str = response.body_str()?;
+        let keygen_response: KeygenValidateResponse =
+            serde_json::from_str(body_str).map_err(|e| GatewardenError::ProtocolError {
+                message: "Parse error".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        let state = LicenseState::from_keygen_response(&keygen_response)?;
+
+        let cache_record = CacheRecord::new(
+            date,
+            signature,
+            digest,
+            body_str.to_string(),
+            request_path,
+            host,
+            self.clock.as_ref(),
+        )
+        .with_summary(state.clone())
+        .with_request(request_id, response.rate_limit);
+
+        Ok((state, cache_record))
+    }
+
+    /// Apply required entitlements, the entitlement policy, usage caps,
+    /// `maxVersion`, and the user, product, and policy scope.
+    pub(crate) fn check_state(
+        &self,
+        state: &LicenseState,
+        additional_uses: u64,
+    ) -> Result<UsageCaps, GatewardenError> {
+        let caps =
+            check_access_with_usage(state, self.config.required_entitlements, additional_uses)?;
+        if let Some(policy) = &self.entitlement_policy {
+            policy.check(&state.entitlements)?;
+        }
+        check_version(state, self.config.app_version)?;
+        if self.user.is_some() && state.user != self.user {
+            return Err(GatewardenError::UserMismatch);
+        }
+        let out_of_scope = |scope: Option<&str>, id: &Option<String>| {
+            scope.is_some_and(|scope| id.as_deref() != Some(scope))
+        };
+        if out_of_scope(self.client.product_scope(), &state.product_id)
+            || out_of_scope(self.client.policy_scope(), &state.policy_id)
+        {
+            return Err(GatewardenError::InvalidLicense);
+        }
+        Ok(caps)
+    }
+
+    /// Fail closed on a missing Digest header when `require_digest` is set.
+    fn check_digest_present(&self, digest: Option<&str>) -> Result<(), GatewardenError> {
+        if self.config.require_digest && digest.is_none() {
+            return Err(GatewardenError::SignatureMissing { request_id: None });
+        }
+        Ok(())
+    }
+
+    /// Verify a cached record's authenticity, grace, and digest policy.
+    fn verify_record(&self, record: &CacheRecord, grace: Duration) -> Result<(), GatewardenError> {
+        self.check_digest_present(record.digest.as_deref())?;
+        record.verify(self.config.key_ring(), grace, self.clock.as_ref())
+    }
+
+    /// Verify a response, measuring clock skew from its `Date` header.
+    ///
+    /// Freshness is checked last, so a `ResponseFromFuture` response has
+    /// an authentic date. That date bounds the true time from below: if
+    /// the local clock is behind it, correct the clock and check again.
+    pub(crate) fn verify_with_skew(
+        &self,
+        response: &KeygenResponse,
+    ) -> Result<(), GatewardenError> {
+        self.check_digest_present(response.digest.as_deref())
+            .map_err(|e| e.with_request_id(response.request_id.as_deref()))?;
+        let keys = self.config.key_ring();
+        match verify_response(response, keys, self.clock.as_ref()) {
+            Err(GatewardenError::ResponseFromFuture { .. })
+                if self.observe_server_date(response) =>
+            {
+                trace_info!(
+                    offset_seconds = self.clock_skew().num_seconds(),
+                    "corrected local clock skew"
+                );
+                verify_response(response, keys, self.clock.as_ref())
+            }
+            Ok(()) => {
+                self.observe_server_date(response);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Update the skew from a verified response. Returns `true` if it changed.
+    fn observe_server_date(&self, response: &KeygenResponse) -> bool {
+        match response.date.as_deref().map(parse_rfc2822_date) {
+            Some(Ok(server_date)) => self
+                .clock
+                .skew()
+                .observe(server_date, self.clock.local_now()),
+            _ => false,
+        }
+    }
+
+    /// Apply access policy to a verified online state and cache it.
+    fn accept_online(
+        &self,
+        key_hash: &str,
+        state: LicenseState,
+        cache_record: CacheRecord,
+    ) -> Result<ValidationResult, GatewardenError> {
+        // Check access policy, keeping Keygen's rejection for `startup_state`
+        let caps = match self.check_state(&state, 0) {
+            Ok(caps) => caps,
+            Err(e) => {
+                if !state.valid {
+                    if let Ok(mut rejections) = self.rejections.lock() {
+                        rejections.insert(key_hash.to_string(), state);
+                    }
+                }
+                return Err(e);
+            }
+        };
+        if let Ok(mut rejections) = self.rejections.lock() {
+            rejections.remove(key_hash);
+        }
+
+        // Cache successful validation and record the verified server time
+        self.cache.save(key_hash, &cache_record)?;
+        self.high_water.advance(
+            TimeProof::from_record(&cache_record),
+            self.config.key_ring(),
+        );
+
+        let warnings = compute_warnings(&state, &caps, None, self.clock.now_utc());
+        Ok(ValidationResult {
+            valid: state.valid,
+            state,
+            caps,
+            from_cache: false,
+            fallback_reason: None,
+            cached_at: None,
+            cache_age: None,
+            warnings,
+        })
+    }
+
+    /// Offline validation from authenticated cache.
+    fn validate_offline(
+        &self,
+        key_hash: &str,
+        grace: Duration,
+        online_error: GatewardenError,
+    ) -> Result<ValidationResult, GatewardenError> {
+        // Only fall back for failures the policy allows
+        let Some(reason) = self.config.fallback_policy.reason(&online_error) else {
+            return Err(online_error);
+        };
+        trace_warn!(error = %online_error, "online validation failed, trying offline cache");
+
+        let Some(result) = self.serve_cached(key_hash, grace, reason)? else {
+            return Err(online_error);
+        };
+        self.emit(|sink| sink.on_cache_fallback(&result, &online_error));
+
+        Ok(result)
+    }
+
+    /// Answer from the authenticated cache in place of Keygen.
+    ///
+    /// Returns `None` if there is no cached record for the key.
+    fn serve_cached(
+        &self,
+        key_hash: &str,
+        grace: Duration,
+        reason: FallbackReason,
+    ) -> Result<Option<ValidationResult>, GatewardenError> {
+        let Some(record) = self.load_cached(key_hash)? else {
+            return Ok(None);
+        };
+
+        // Verify cache authenticity and grace period, and the clock wasn't set back
+        self.verify_record(&record, grace)?;
+        self.high_water
+            .check(&record, self.clock.now_utc(), self.config.key_ring())?;
+
+        let state = self.cached_state(key_hash, &record)?;
+
+        // Cached state never outlives the license itself
+        check_not_expired(&state, self.clock.now_utc())?;
+
+        // Check access policy
+        let caps = self.check_state(&state, 0)?;
+        let warnings = compute_warnings(
+            &state,
+            &caps,
+            Some((record.cached_at, grace)),
+            self.clock.now_utc(),
+        );
+
+        Ok(Some(ValidationResult {
+            valid: state.valid,
+            state,
+            caps,
+            from_cache: true,
+            fallback_reason: Some(reason),
+            cached_at: Some(record.cached_at),
+            cache_age: Some(self.cache_age(&record)),
+            warnings,
+        }))
+    }
+
+    /// Whether offline fallback could answer for this key right now.
+    ///
+    /// Decides if the latency budget applies; the fallback itself verifies
+    /// the record again.
+    fn cache_can_answer(&self, key_hash: &str, grace: Duration) -> bool {
+        if !self.config.fallback_policy.on_network_error {
+            return false;
+        }
+        match self.load_cached(key_hash) {
+            Ok(Some(record)) => self.verify_record(&record, grace).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// How long ago `record` was verified online (zero if in the future).
+    fn cache_age(&self, record: &CacheRecord) -> Duration {
+        self.clock
+            .now_utc()
+            .signed_duration_since(record.cached_at)
+            .to_std()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// License state of a verified cache record, counting uses synced
+    /// since it was cached.
+    fn cached_state(
+        &self,
+        key_hash: &str,
+        record: &CacheRecord,
+    ) -> Result<LicenseState, GatewardenError> {
+        let mut state = self.signed_state(key_hash, record)?;
+        if let Some(synced) = record.synced_uses {
+            state.current_uses = Some(state.current_uses.map_or(synced, |uses| uses.max(synced)));
+        }
+        Ok(state)
+    }
+
+    /// License state of a verified cache record's signed body.
+    ///
+    /// Uses the record's summary once it has been checked against the
+    /// body in this process; otherwise parses the body, and fails with
+    /// `CacheTampered` if the summary disagrees with it.
+    fn signed_state(
+        &self,
+        key_hash: &str,
+        record: &CacheRecord,
+    ) -> Result<LicenseState, GatewardenError> {
+        let fingerprint = record.summary_fingerprint();
+        if let (Some(summary), Some(fingerprint)) = (&record.summary, fingerprint) {
+            let checked = self
+                .checked_summaries
+                .lock()
+                .is_ok_and(|checked| checked.get(key_hash) == Some(&fingerprint));
+            if checked {
+                return Ok(summary.clone());
+            }
+        }
+
+        let response: KeygenValidateResponse =
+            serde_json::from_str(record.body()).map_err(|e| GatewardenError::ProtocolError {
+                message: "Cache parse error".to_string(),
+                source: Some(e.into()),
+            })?;
+        let state = LicenseState::from_keygen_response(&response)?;
+
+        if let (Some(summary), Some(fingerprint)) = (&record.summary, fingerprint) {
+            if *summary != state {
+                return Err(GatewardenError::CacheTampered);
+            }
+            if let Ok(mut checked) = self.checked_summaries.lock() {
+                checked.insert(key_hash.to_string(), fingerprint);
+            }
+        }
+        Ok(state)
+    }
+
+    /// Load a cached record, purging it if older than `max_cache_age`.
+    fn load_cached(&self, key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        let Some(record) = self.cache.load(key_hash)? else {
+            return Ok(None);
+        };
+
+        if record.is_older_than(self.config.max_cache_age, self.clock.as_ref()) {
+            // Best-effort purge; the record is rejected either way
+            let _ = self.cache.delete(key_hash);
+            return Err(GatewardenError::CacheExpired);
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Call `f` on every registered event sink.
+    fn emit(&self, f: impl Fn(&dyn EventSink)) {
+        for sink in &self.sinks {
+            f(sink.as_ref());
+        }
+    }
+
+    /// Report security and cache-expiry failures to event sinks.
+    fn notify(
+        &self,
+        result: Result<ValidationResult, GatewardenError>,
+    ) -> Result<ValidationResult, GatewardenError> {
+        match result {
+            Ok(ref _result) => {
+                trace_info!(
+                    valid = _result.valid,
+                    from_cache = _result.from_cache,
+                    "license validated"
+                );
+            }
+            Err(GatewardenError::CacheExpired) => {
+                trace_warn!("offline cache expired");
+                self.emit(|sink| sink.on_cache_expired());
+            }
+            Err(ref e) if is_security_error(e) => {
+                trace_error!(error = %e, "license security check failed");
+                self.emit(|sink| sink.on_security_error(e));
+            }
+            Err(ref _error) => {
+                trace_debug!(error = %_error, "license validation failed");
+            }
+        }
+        self.emit(|sink| sink.on_attempt(result.as_ref()));
+        result
+    }
+
+    /// A memoized result younger than `memo_ttl`, if any.
+    ///
+    /// With `online_only`, results answered from the disk cache are ignored.
+    fn memoized(&self, memo_key: &str, online_only: bool) -> Option<ValidationResult> {
+        if self.config.memo_ttl.is_zero() {
+            return None;
+        }
+        let memo = self.memo.lock().ok()?;
+        let (stored_at, result) = memo.get(memo_key)?;
+
+        // A clock set back also invalidates the entry
+        let age = self.clock.now_utc().signed_duration_since(*stored_at);
+        let fresh = age >= chrono::Duration::zero()
+            && age.to_std().is_ok_and(|age| age < self.config.memo_ttl);
+        (fresh && !(online_only && result.from_cache)).then(|| result.clone())
+    }
+
+    /// Remember a successful result, or forget the entry after a failure.
+    fn memoize(&self, memo_key: String, result: &Result<ValidationResult, GatewardenError>) {
+        if self.config.memo_ttl.is_zero() {
+            return;
+        }
+        if let Ok(mut memo) = self.memo.lock() {
+            match result {
+                Ok(result) => {
+                    memo.insert(memo_key, (self.clock.now_utc(), result.clone()));
+                }
+                Err(_) => {
+                    memo.remove(&memo_key);
+                }
+            }
+        }
+    }
+
+    /// The error Keygen refused `flight_key` with, if within
+    /// `negative_cache_ttl`.
+    fn recently_refused(&self, flight_key: &str) -> Option<GatewardenError> {
+        if self.config.negative_cache_ttl.is_zero() {
+            return None;
+        }
+        let refusals = self.refusals.lock().ok()?;
+        refusals
+            .get(flight_key)
+            .filter(|(refused_at, _)| self.refusal_is_fresh(refused_at))
+            .map(|(_, refusal)| refusal.duplicate())
+    }
+
+    /// Remember that Keygen refused `flight_key` with `refusal`, dropping
+    /// lapsed entries.
+    fn remember_refusal(&self, flight_key: String, refusal: &GatewardenError) {
+        if self.config.negative_cache_ttl.is_zero() {
+            return;
+        }
+        if let Ok(mut refusals) = self.refusals.lock() {
+            refusals.retain(|_, (refused_at, _)| self.refusal_is_fresh(refused_at));
+            refusals.insert(flight_key, (self.clock.now_utc(), refusal.duplicate()));
+        }
+    }
+
+    /// Whether a refusal recorded at `refused_at` is within
+    /// `negative_cache_ttl`. A clock set back also lapses it.
+    fn refusal_is_fresh(&self, refused_at: &DateTime<Utc>) -> bool {
+        let age = self.clock.now_utc().signed_duration_since(*refused_at);
+        age >= chrono::Duration::zero()
+            && age
+                .to_std()
+                .is_ok_and(|age| age < self.config.negative_cache_ttl)
+    }
+
+    /// Forget memoized results and refusals for a key hash, for every
+    /// feature and token.
+    fn forget_memo(&self, key_hash: &str) {
+        let matches = |key: &str| key.split(':').next() == Some(key_hash);
+        if let Ok(mut memo) = self.memo.lock() {
+            memo.retain(|memo_key, _| !matches(memo_key));
+        }
+        if let Ok(mut refusals) = self.refusals.lock() {
+            refusals.retain(|flight_key, _| !matches(flight_key));
+        }
+        if let Ok(mut rejections) = self.rejections.lock() {
+            rejections.remove(key_hash);
+        }
+    }
+
+    /// Entropy source for nonces, salts, and jitter.
+    pub(crate) fn entropy(&self) -> &dyn EntropySource {
+        self.entropy.as_ref()
+    }
+
+    /// Hardware components registered with machine activations.
+    pub(crate) fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// User validations are scoped to, if any.
+    pub(crate) fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Client for requests outside validation (releases, artifacts).
+    pub(crate) fn client(&self) -> &KeygenClient {
+        &self.client
+    }
+
+    /// `license_key` normalized per `key_format`, wrapped so that it
+    /// cannot end up in logs.
+    pub(crate) fn redact(&self, license_key: &str) -> Result<RedactedKey, GatewardenError> {
+        Ok(RedactedKey::new(
+            self.config.key_format.normalize(license_key)?,
+        ))
+    }
+
+    /// Offset currently added to the local clock.
+    ///
+    /// Non-zero when verified Keygen responses showed the local clock
+    /// running behind. Applied to freshness and offline-grace checks.
+    pub fn clock_skew(&self) -> chrono::Duration {
+        self.clock.skew().offset()
+    }
+
+    /// Protections in effect for this manager.
+    ///
+    /// Log it at startup, or assert on it in tests, to confirm the
+    /// effective threat-model coverage for this configuration and platform.
+    pub fn security_posture(&self) -> SecurityPosture {
+        SecurityPosture {
+            signature_verification: true,
+            digest_verification: true,
+            digest_required: self.config.require_digest,
+            trusted_keys: 1 + self.config.trusted_keys.len(),
+            key_id_binding: self.config.public_key_id.is_some()
+                && self
+                    .config
+                    .trusted_keys
+                    .iter()
+                    .all(|key| key.key_id.is_some()),
+            tls_pinning: !self.config.tls_pins.is_empty(),
+            freshness_window: FRESHNESS_WINDOW,
+            future_tolerance: FUTURE_TOLERANCE,
+            offline_grace: self.config.offline_grace_for(self.config.feature_name),
+            max_cache_age: self.config.max_cache_age,
+            cache_encryption: self.cache.is_encrypted(),
+            anti_rollback: self.high_water.is_persistent(),
+            fingerprint_binding: !self.machine_fingerprint.is_empty(),
+            fallback_policy: self.config.fallback_policy,
+        }
+    }
+
+    /// Get the current configuration.
+    pub fn config(&self) -> &GatewardenConfig {
+        &self.config
+    }
+
+    /// The key store set with `with_key_store`, if any.
+    pub fn key_store(&self) -> Option<&dyn KeyStore> {
+        self.key_store.as_deref()
+    }
+}
+
+/// Memo entry key for a license key hash and feature profile.
+fn memo_key(key_hash: &str, feature: &str) -> String {
+    format!("{}:{}", key_hash, feature)
+}
+
+/// Credential for a license-authenticated request: the stored token if
+/// there is one, else the key itself.
+pub(crate) fn license_auth<'a>(
+    license_key: &'a RedactedKey,
+    token: Option<&'a LicenseToken>,
+) -> Auth<'a> {
+    match token {
+        Some(token) => Auth::Bearer(&token.token),
+        None => Auth::License(license_key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chaos::FaultInjector;
+    use crate::clock::MockClock;
+    use crate::crypto::keys::TrustedKey;
+    use crate::crypto::signing::build_signing_string;
+    use crate::mock::{
+        signed_test_record, test_config, test_signature_header, TEST_PUBLIC_KEY_HEX,
+    };
+    use crate::policy::fallback::FallbackPolicy;
+    use crate::TransportFailure;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    const CORE_GRACE: &[(&str, Duration)] = &[("core", Duration::from_secs(30 * 86400))];
+
+    const VALID_BODY: &str = r#"{"meta":{"valid":true,"code":"VALID"}}"#;
+    const LICENSE_BODY: &str = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic-1","type":"licenses","attributes":{"key":"test-key"}}}"#;
+
+    /// Authentically signed cache record for `body`, cached at `cached_at`.
+    fn signed_record(body: &str, cached_at: DateTime<Utc>) -> CacheRecord {
+        signed_test_record(body, "Wed, 15 Jan 2025 12:00:00 GMT", cached_at)
+    }
+
+    #[test]
     fn test_license_manager_creation() {
         let config = test_config();
         let manager = LicenseManager::new(config);
@@ -292,12 +2327,1142 @@ mod tests {
         assert!(matches!(result, Err(GatewardenError::MissingLicense)));
     }
 
+    #[test]
+    fn test_force_refresh_empty() {
+        let config = test_config();
+        let manager = LicenseManager::new(config).unwrap();
+        let result = manager.force_refresh("");
+        assert!(matches!(result, Err(GatewardenError::MissingLicense)));
+    }
+
+    #[test]
+    fn test_check_feature_access_empty() {
+        let config = test_config();
+        let manager = LicenseManager::new(config).unwrap();
+        let result = manager.check_feature_access("", "sync");
+        assert!(matches!(result, Err(GatewardenError::MissingLicense)));
+    }
+
     #[test]
     fn test_check_access_empty() {
         let config = test_config();
         let manager = LicenseManager::new(config).unwrap();
         let result = manager.check_access("");
         assert!(matches!(result, Err(GatewardenError::MissingLicense)));
+        assert!(matches!(
+            manager.entitlements(""),
+            Err(GatewardenError::MissingLicense)
+        ));
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn on_security_error(&self, error: &GatewardenError) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("security: {}", error));
+        }
+
+        fn on_cache_expired(&self) {
+            self.events.lock().unwrap().push("expired".to_string());
+        }
+    }
+
+    fn tampered_record() -> CacheRecord {
+        CacheRecord::new(
+            "Wed, 15 Jan 2025 12:00:00 GMT".to_string(),
+            format!(r#"algorithm="ed25519", signature="{}==""#, "A".repeat(86)),
+            None,
+            "{}".to_string(),
+            "/v1/accounts/test-account/licenses/actions/validate-key".to_string(),
+            "api.keygen.sh".to_string(),
+            &crate::clock::MockClock::new(Utc::now()),
+        )
+    }
+
+    #[test]
+    fn test_event_sink_security_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache
+            .save(&hash_license_key("test-key"), &tampered_record())
+            .unwrap();
+
+        let sink = Arc::new(RecordingSink::default());
+        let manager = LicenseManager::new(test_config())
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_event_sink(sink.clone());
+
+        let result = manager.check_access("test-key");
+        assert!(matches!(result, Err(GatewardenError::CacheTampered)));
+        assert_eq!(
+            *sink.events.lock().unwrap(),
+            ["security: Cache tampering detected"]
+        );
+    }
+
+    #[test]
+    fn test_event_sink_ignores_transport_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let faults = FaultInjector::new();
+        faults.fail_next_requests(1);
+
+        let sink = Arc::new(RecordingSink::default());
+        let manager = LicenseManager::new(test_config())
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_fault_injector(faults)
+            .with_event_sink(sink.clone());
+
+        let result = manager.validate_key("test-key");
+        assert!(matches!(
+            result,
+            Err(GatewardenError::KeygenTransport { .. })
+        ));
+        assert!(sink.events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_feature_grace_overrides_default() {
+        let mut config = test_config();
+        config.feature_grace = CORE_GRACE;
+        assert_eq!(
+            config.offline_grace_for("core"),
+            Duration::from_secs(30 * 86400)
+        );
+        assert_eq!(config.offline_grace_for("sync"), config.offline_grace);
+
+        config.feature_grace = &[("core", Duration::ZERO), ("core", Duration::ZERO)];
+        assert!(matches!(
+            config.validate(),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_cached_summary_checked_against_body() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let key_hash = hash_license_key("test-key");
+        let response: KeygenValidateResponse = serde_json::from_str(VALID_BODY).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+        let record =
+            signed_record(VALID_BODY, now - chrono::Duration::hours(1)).with_summary(state.clone());
+        cache.save(&key_hash, &record).unwrap();
+
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+            ));
+        assert_eq!(manager.cached_state(&key_hash, &record).unwrap(), state);
+        assert_eq!(
+            manager.checked_summaries.lock().unwrap().get(&key_hash),
+            record.summary_fingerprint().as_ref()
+        );
+        assert!(manager.check_access("test-key").unwrap().valid);
+
+        // An edited summary no longer matches the checked fingerprint
+        let mut forged = state;
+        forged.entitlements.push("ENTERPRISE".to_string());
+        cache
+            .save(&key_hash, &record.clone().with_summary(forged))
+            .unwrap();
+        assert!(matches!(
+            manager.check_access("test-key"),
+            Err(GatewardenError::CacheTampered)
+        ));
+    }
+
+    #[test]
+    fn test_feature_grace_enforced_on_cache() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 17, 12, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let two_days_ago = now - chrono::Duration::days(2);
+        cache
+            .save(
+                &hash_license_key("test-key"),
+                &signed_record(VALID_BODY, two_days_ago),
+            )
+            .unwrap();
+
+        let mut config = test_config();
+        config.feature_grace = CORE_GRACE;
+        let manager = LicenseManager::new_with_clock(config, Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache));
+
+        // Default 24h grace has lapsed, the 30-day "core" profile has not
+        assert!(matches!(
+            manager.check_access("test-key"),
+            Err(GatewardenError::CacheExpired)
+        ));
+        let result = manager.check_feature_access("test-key", "core").unwrap();
+        assert!(result.valid);
+        assert!(result.from_cache);
+        assert_eq!(result.fallback_reason, None);
+        assert_eq!(result.cache_age, Some(Duration::from_secs(2 * 86400)));
+    }
+
+    #[test]
+    fn test_offline_fallback_records_reason() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let key_hash = hash_license_key("test-key");
+        cache
+            .save(
+                &key_hash,
+                &signed_record(VALID_BODY, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+        let grace = Duration::from_secs(86400);
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache));
+
+        let result = manager
+            .validate_offline(
+                &key_hash,
+                grace,
+                GatewardenError::ServerError {
+                    status: 503,
+                    request_id: None,
+                },
+            )
+            .unwrap();
+        assert!(result.from_cache);
+        assert_eq!(
+            result.fallback_reason,
+            Some(FallbackReason::ServerError { status: 503 })
+        );
+        assert_eq!(result.cached_at, Some(now - chrono::Duration::hours(1)));
+        assert_eq!(result.cache_age, Some(Duration::from_secs(3600)));
+
+        let dns_failure = GatewardenError::KeygenTransport {
+            message: "Request failed".to_string(),
+            kind: TransportFailure::Dns,
+            source: None,
+        };
+        let result = manager
+            .validate_offline(&key_hash, grace, dns_failure)
+            .unwrap();
+        assert_eq!(
+            result.fallback_reason,
+            Some(FallbackReason::Network {
+                failure: TransportFailure::Dns
+            })
+        );
+
+        // Rate limiting is not fallback-eligible by default
+        assert!(matches!(
+            manager.validate_offline(
+                &key_hash,
+                grace,
+                GatewardenError::RateLimited { request_id: None }
+            ),
+            Err(GatewardenError::RateLimited { .. })
+        ));
+    }
+
+    #[test]
+    fn test_online_budget_only_with_usable_cache() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let key_hash = hash_license_key("test-key");
+        cache
+            .save(
+                &key_hash,
+                &signed_record(VALID_BODY, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+        let grace = Duration::from_secs(86400);
+
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+            ));
+        assert!(manager.cache_can_answer(&key_hash, grace));
+        assert!(!manager.cache_can_answer(&hash_license_key("other-key"), grace));
+        assert!(!manager.cache_can_answer(&key_hash, Duration::from_secs(60)));
+
+        // No fast-fail when a timeout could not fall back anyway
+        let mut config = test_config();
+        config.fallback_policy = FallbackPolicy::STRICT;
+        let manager = LicenseManager::new_with_clock(config, Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache));
+        assert!(!manager.cache_can_answer(&key_hash, grace));
+    }
+
+    #[test]
+    fn test_max_cache_age_purges_record() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        let key_hash = hash_license_key("test-key");
+        cache
+            .save(
+                &key_hash,
+                &signed_record(VALID_BODY, now - chrono::Duration::days(100)),
+            )
+            .unwrap();
+
+        // An absurd one-year grace is clamped to the 90-day ceiling
+        let mut config = test_config();
+        config.offline_grace = Duration::from_secs(365 * 86400);
+        assert_eq!(config.offline_grace_for("test"), config.max_cache_age);
+
+        let manager = LicenseManager::new_with_clock(config, Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+            ));
+
+        assert!(matches!(
+            manager.check_access("test-key"),
+            Err(GatewardenError::CacheExpired)
+        ));
+        assert!(cache.load(&key_hash).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cached_license_clamped_to_expiry() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 17, 12, 0, 0).unwrap();
+        let body = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic","type":"licenses","attributes":{"expiry":"2025-01-17T00:00:00Z"}}}"#;
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache
+            .save(
+                &hash_license_key("test-key"),
+                &signed_record(body, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache));
+
+        // Within the 24h grace, but the license itself expired at midnight
+        assert!(matches!(
+            manager.check_access("test-key"),
+            Err(GatewardenError::LicenseExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cached_result_must_match_user_scope() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 17, 12, 0, 0).unwrap();
+        let body = r#"{"meta":{"valid":true,"code":"VALID","scope":{"user":"ana@acme.example"}},"data":{"id":"lic","type":"licenses","attributes":{}}}"#;
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache
+            .save(
+                &hash_license_key("test-key"),
+                &signed_record(body, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+
+        let manager_for = |user: &str| {
+            LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+                .unwrap()
+                .with_cache_backend(Box::new(
+                    FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+                ))
+                .with_user(user)
+        };
+
+        let result = manager_for("ana@acme.example")
+            .check_access("test-key")
+            .unwrap();
+        assert_eq!(result.state.user.as_deref(), Some("ana@acme.example"));
+        assert!(matches!(
+            manager_for("eve@acme.example").check_access("test-key"),
+            Err(GatewardenError::UserMismatch)
+        ));
+    }
+
+    fn rejected_state(code: &str) -> LicenseState {
+        LicenseState {
+            valid: false,
+            entitlements: vec![],
+            expires_at: None,
+            max_uses: None,
+            current_uses: None,
+            code: code.to_string(),
+            detail: None,
+            management_link: None,
+            license_id: None,
+            policy_id: None,
+            product_id: None,
+            trial: false,
+            upgrade: Default::default(),
+            max_version: None,
+            group: None,
+            owner: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn test_startup_state_no_license() {
+        let manager = LicenseManager::new(test_config()).unwrap();
+        assert!(matches!(
+            manager.startup_state(None),
+            StartupState::NoLicense
+        ));
+        assert!(matches!(
+            manager.startup_state(Some("")),
+            StartupState::NoLicense
+        ));
+    }
+
+    #[test]
+    fn test_startup_state_from_rejected() {
+        assert!(matches!(
+            StartupState::from_rejected(rejected_state("EXPIRED")),
+            StartupState::Expired {
+                upgrade_url: None,
+                ..
+            }
+        ));
+        let mut expired = rejected_state("EXPIRED");
+        expired.license_id = Some("lic-1".to_string());
+        expired.upgrade.renewal_url = Some("https://store.example.com/renew".to_string());
+        assert!(matches!(
+            StartupState::from_rejected(expired),
+            StartupState::Expired { upgrade_url: Some(url), .. }
+                if url == "https://store.example.com/renew?reason=expired&license=lic-1"
+        ));
+        assert!(matches!(
+            StartupState::from_rejected(rejected_state("NO_MACHINE")),
+            StartupState::NeedsActivation { code, .. } if code == "NO_MACHINE"
+        ));
+        assert!(matches!(
+            StartupState::from_rejected(rejected_state("SUSPENDED")),
+            StartupState::Invalid { code, .. } if code == "SUSPENDED"
+        ));
+    }
+
+    #[test]
+    fn test_startup_state_serializes_with_kind() {
+        let json = serde_json::to_value(StartupState::from_rejected(rejected_state("NO_MACHINE")))
+            .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "needs_activation", "code": "NO_MACHINE", "management_link": null
+            })
+        );
+
+        let json =
+            serde_json::to_value(StartupState::Failed(GatewardenError::CacheTampered)).unwrap();
+        assert_eq!(json["kind"], "failed");
+        assert_eq!(json["code"], "CACHE_TAMPERED");
+    }
+
+    #[test]
+    fn test_startup_state_offline() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 17, 12, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache
+            .save(
+                &hash_license_key("fresh-key"),
+                &signed_record(VALID_BODY, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+        cache
+            .save(
+                &hash_license_key("stale-key"),
+                &signed_record(VALID_BODY, now - chrono::Duration::days(3)),
+            )
+            .unwrap();
+        let expired_body = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic-1","type":"licenses","attributes":{"expiry":"2025-01-17T00:00:00Z","metadata":{"managementLink":{"payload":"{}","signature":"c2ln"}}}}}"#;
+        cache
+            .save(
+                &hash_license_key("expired-key"),
+                &signed_record(expired_body, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+
+        let faults = FaultInjector::new();
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_fault_injector(faults.clone());
+
+        faults.fail_next_requests(4);
+        let fresh = manager.startup_state(Some("fresh-key"));
+        assert!(matches!(fresh, StartupState::OfflineCached(_)));
+        assert!(fresh.is_licensed());
+        assert!(matches!(
+            manager.startup_state(Some("stale-key")),
+            StartupState::CacheExpired
+        ));
+        assert!(matches!(
+            manager.startup_state(Some("unknown-key")),
+            StartupState::Failed(GatewardenError::KeygenTransport { .. })
+        ));
+
+        // Expired while offline keeps the cached license's renewal link
+        let expired = manager.startup_state(Some("expired-key"));
+        assert!(matches!(expired, StartupState::Expired { .. }));
+        assert_eq!(expired.management_link().unwrap().signature, "c2ln");
+    }
+
+    #[test]
+    fn test_clock_rollback_rejected() {
+        // Record signed at 12:00 but the clock now reads 09:00
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache
+            .save(
+                &hash_license_key("test-key"),
+                &signed_record(VALID_BODY, now - chrono::Duration::hours(1)),
+            )
+            .unwrap();
+
+        let sink = Arc::new(RecordingSink::default());
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_event_sink(sink.clone());
+
+        assert!(matches!(
+            manager.check_access("test-key"),
+            Err(GatewardenError::ClockRollback { .. })
+        ));
+        assert_eq!(sink.events.lock().unwrap().len(), 1);
+    }
+
+    /// Authentically signed Keygen response dated 2025-01-15 12:00:00 UTC.
+    fn signed_response(body: &str) -> KeygenResponse {
+        let record = signed_record(body, Utc::now());
+        KeygenResponse {
+            status: 200,
+            date: Some(record.date),
+            signature: Some(record.signature),
+            digest: record.digest,
+            body: record.body.into_bytes(),
+            method: "post".to_string(),
+            request_path: record.request_path,
+            host: record.host,
+            request_id: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_require_digest_rejects_unbound_body() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 1, 0).unwrap();
+        let record = signed_record(VALID_BODY, now);
+        let signing_string = build_signing_string(
+            "post",
+            &record.request_path,
+            &record.host,
+            &record.date,
+            None,
+        );
+        let undigested = CacheRecord {
+            signature: test_signature_header(&signing_string),
+            digest: None,
+            ..record
+        };
+        let response = KeygenResponse {
+            status: 200,
+            date: Some(undigested.date.clone()),
+            signature: Some(undigested.signature.clone()),
+            digest: None,
+            body: undigested.body.clone().into_bytes(),
+            method: "post".to_string(),
+            request_path: undigested.request_path.clone(),
+            host: undigested.host.clone(),
+            request_id: None,
+            rate_limit: None,
+        };
+        let grace = Duration::from_secs(86400);
+
+        let lenient =
+            LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now))).unwrap();
+        assert!(lenient.verify_with_skew(&response).is_ok());
+        assert!(lenient.verify_record(&undigested, grace).is_ok());
+
+        let strict = LicenseManager::new_with_clock(
+            GatewardenConfig {
+                require_digest: true,
+                ..test_config()
+            },
+            Arc::new(MockClock::new(now)),
+        )
+        .unwrap();
+        assert!(matches!(
+            strict.verify_with_skew(&response),
+            Err(GatewardenError::SignatureMissing { .. })
+        ));
+        assert!(matches!(
+            strict.verify_record(&undigested, grace),
+            Err(GatewardenError::SignatureMissing { .. })
+        ));
+        assert!(strict
+            .verify_with_skew(&signed_response(VALID_BODY))
+            .is_ok());
+        assert!(strict.security_posture().digest_required);
+    }
+
+    #[test]
+    fn test_behind_clock_corrected_from_verified_date() {
+        let local = Utc.with_ymd_and_hms(2025, 1, 15, 11, 50, 0).unwrap();
+        let manager =
+            LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(local))).unwrap();
+
+        manager
+            .verify_with_skew(&signed_response(VALID_BODY))
+            .unwrap();
+        assert_eq!(manager.clock_skew(), chrono::Duration::minutes(10));
+    }
+
+    #[test]
+    fn test_unverified_date_does_not_move_clock() {
+        let local = Utc.with_ymd_and_hms(2025, 1, 15, 11, 50, 0).unwrap();
+        let manager =
+            LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(local))).unwrap();
+
+        let mut response = signed_response(VALID_BODY);
+        response.date = Some("Wed, 15 Jan 2025 12:30:00 GMT".to_string());
+        assert!(matches!(
+            manager.verify_with_skew(&response),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+        assert_eq!(manager.clock_skew(), chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_verify_untrusted_response() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 1, 0).unwrap();
+        let manager =
+            LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now))).unwrap();
+        let response = signed_response(LICENSE_BODY);
+        let date = response.date.clone().unwrap();
+        let signature = response.signature.clone().unwrap();
+        let digest = response.digest.clone().unwrap();
+        let headers = [
+            ("date", date.as_str()),
+            ("keygen-signature", signature.as_str()),
+            ("digest", digest.as_str()),
+        ];
+
+        // Bound by license key or license ID
+        for license in ["test-key", "lic-1"] {
+            let result = manager
+                .verify_untrusted_response(license, &response.body, &headers)
+                .unwrap();
+            assert!(result.valid);
+            assert!(!result.from_cache);
+        }
+
+        // Another customer's response is not accepted for this key
+        assert!(matches!(
+            manager.verify_untrusted_response("other-key", &response.body, &headers),
+            Err(GatewardenError::ProtocolError { .. })
+        ));
+        let unbound = signed_response(VALID_BODY);
+        let unbound_digest = unbound.digest.clone().unwrap();
+        let unbound_signature = unbound.signature.clone().unwrap();
+        assert!(matches!(
+            manager.verify_untrusted_response(
+                "test-key",
+                &unbound.body,
+                &[
+                    ("date", date.as_str()),
+                    ("keygen-signature", unbound_signature.as_str()),
+                    ("digest", unbound_digest.as_str()),
+                ]
+            ),
+            Err(GatewardenError::ProtocolError { .. })
+        ));
+
+        // Digest is mandatory
+        assert!(matches!(
+            manager.verify_untrusted_response("test-key", &response.body, &headers[..2]),
+            Err(GatewardenError::SignatureMissing { .. })
+        ));
+
+        // keyid must name the account the key is bound to
+        let foreign = format!(r#"keyid="other-account", {}"#, signature);
+        let mut cross_account = headers.to_vec();
+        cross_account[1] = ("keygen-signature", foreign.as_str());
+        assert!(manager
+            .verify_untrusted_response("test-key", &response.body, &cross_account)
+            .is_ok());
+        let bound = LicenseManager::new_with_clock(
+            GatewardenConfig {
+                public_key_id: Some("test-account"),
+                ..test_config()
+            },
+            Arc::new(MockClock::new(now)),
+        )
+        .unwrap();
+        assert!(bound
+            .verify_untrusted_response("test-key", &response.body, &headers)
+            .is_ok());
+        assert!(matches!(
+            bound.verify_untrusted_response("test-key", &response.body, &cross_account),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+
+        // Freshness is mandatory
+        let later = LicenseManager::new_with_clock(
+            test_config(),
+            Arc::new(MockClock::new(now + chrono::Duration::minutes(10))),
+        )
+        .unwrap();
+        assert!(matches!(
+            later.verify_untrusted_response("test-key", &response.body, &headers),
+            Err(GatewardenError::ResponseTooOld { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rotated_signing_key_verifies() {
+        const ROTATED: &[TrustedKey] = &[TrustedKey {
+            key_id: Some("new-account"),
+            public_key_hex: TEST_PUBLIC_KEY_HEX,
+        }];
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 1, 0).unwrap();
+        let config = GatewardenConfig {
+            public_key_hex: "799efc7752286e6c3815b13358d98fc0f0b566764458adcb48f1be2c10a55906",
+            ..test_config()
+        };
+        let response = signed_response(LICENSE_BODY);
+        let date = response.date.clone().unwrap();
+        let digest = response.digest.clone().unwrap();
+        let verify = |manager: &LicenseManager, key_id: &str| {
+            let signature = format!(
+                r#"keyid="{}", {}"#,
+                key_id,
+                response.signature.as_deref().unwrap()
+            );
+            manager.verify_untrusted_response(
+                "test-key",
+                &response.body,
+                &[
+                    ("date", date.as_str()),
+                    ("keygen-signature", signature.as_str()),
+                    ("digest", digest.as_str()),
+                ],
+            )
+        };
+
+        let old_only =
+            LicenseManager::new_with_clock(config.clone(), Arc::new(MockClock::new(now))).unwrap();
+        assert!(matches!(
+            verify(&old_only, "new-account"),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+
+        let rotated = LicenseManager::new_with_clock(
+            GatewardenConfig {
+                trusted_keys: ROTATED,
+                ..config
+            },
+            Arc::new(MockClock::new(now)),
+        )
+        .unwrap();
+        assert!(verify(&rotated, "new-account").unwrap().valid);
+        assert!(matches!(
+            verify(&rotated, "test-account"),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_record_use_after_clear_cache() {
+        let body = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic-1","type":"licenses","attributes":{"maxUses":5,"uses":3}}}"#;
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let record = signed_record(body, now - chrono::Duration::minutes(30));
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache.save(&hash_license_key("test-key"), &record).unwrap();
+
+        // Meters share the cache directory, as they do by default
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_meter_dir(temp_dir.path().to_path_buf());
+        manager.record_use("test-key", 1).unwrap();
+
+        let key_hash = hash_license_key("test-key");
+        manager.clear_cache(None).unwrap();
+        assert!(manager.check_access("test-key").is_err());
+        assert!(temp_dir
+            .path()
+            .join(format!("usage_{}.json", key_hash))
+            .exists());
+
+        // Revalidated: the meter picks up where it left off
+        FileCache::with_path(temp_dir.path().to_path_buf())
+            .unwrap()
+            .save(&hash_license_key("test-key"), &record)
+            .unwrap();
+        manager.record_use("test-key", 1).unwrap();
+        let meter = manager.usage_meter(&key_hash).unwrap();
+        assert_eq!(meter.lifetime_count(), 2);
+    }
+
+    #[test]
+    fn test_record_use_enforces_caps() {
+        let body = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic-1","type":"licenses","attributes":{"maxUses":5,"uses":3}}}"#;
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().join("cache")).unwrap();
+        cache
+            .save(
+                &hash_license_key("test-key"),
+                &signed_record(body, now - chrono::Duration::minutes(30)),
+            )
+            .unwrap();
+
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_meter_dir(temp_dir.path().join("meter"));
+
+        assert_eq!(
+            manager.record_use("test-key", 1).unwrap().current_uses,
+            Some(4)
+        );
+        assert!(matches!(
+            manager.record_use("test-key", 2),
+            Err(GatewardenError::UsageLimitExceeded)
+        ));
+        assert_eq!(
+            manager.record_use("test-key", 1).unwrap().current_uses,
+            Some(5)
+        );
+        assert!(matches!(
+            manager.record_use("test-key", 1),
+            Err(GatewardenError::UsageLimitExceeded)
+        ));
+
+        let meter = manager.usage_meter(&hash_license_key("test-key")).unwrap();
+        assert_eq!(meter.lifetime_count(), 2);
+
+        let report = manager.usage_report("test-key", "host-a").unwrap();
+        assert_eq!(report.license_id, "lic-1");
+        assert_eq!(report.source, "host-a");
+        assert_eq!(report.lifetime_count, 2);
+        assert_eq!(report.days.len(), 1);
+        assert_eq!(report.days[0].count, 2);
+
+        let export = manager
+            .export_usage("test-key", ExportFormat::Csv, "", &[7; 32])
+            .unwrap();
+        assert_eq!(
+            export.body,
+            "timestamp,feature,quantity,license_id\n\
+             2025-01-15T13:00:00Z,test,1,lic-1\n\
+             2025-01-15T13:00:00Z,test,1,lic-1\n"
+        );
+    }
+
+    #[test]
+    fn test_clear_cache() {
+        let body = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic-1","type":"licenses","attributes":{}}}"#;
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        for key in ["key-a", "key-b"] {
+            cache
+                .save(
+                    &hash_license_key(key),
+                    &signed_record(body, now - chrono::Duration::minutes(30)),
+                )
+                .unwrap();
+        }
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache));
+        assert!(manager.check_access("key-a").is_ok());
+
+        manager.clear_cache(Some("key-a")).unwrap();
+        assert!(matches!(
+            manager.check_access("key-a"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert!(manager.check_access("key-b").is_ok());
+
+        manager.clear_cache(None).unwrap();
+        assert!(matches!(
+            manager.check_access("key-b"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+    }
+
+    #[test]
+    fn test_sync_usage_keeps_uses_when_offline() {
+        let body = r#"{"meta":{"valid":true,"code":"VALID"},"data":{"id":"lic-1","type":"licenses","attributes":{"maxUses":5,"uses":3}}}"#;
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().join("cache")).unwrap();
+        cache
+            .save(
+                &hash_license_key("test-key"),
+                &signed_record(body, now - chrono::Duration::minutes(30)),
+            )
+            .unwrap();
+
+        let faults = FaultInjector::new();
+        let manager = LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_meter_dir(temp_dir.path().join("meter"))
+            .with_fault_injector(faults.clone());
+
+        manager.record_use("test-key", 2).unwrap();
+
+        faults.fail_next_requests(1);
+        assert!(manager.sync_usage("test-key").is_err());
+        let meter = manager.usage_meter(&hash_license_key("test-key")).unwrap();
+        assert_eq!(meter.unsynced_count(), 2);
+    }
+
+    #[test]
+    fn test_accept_usage_response() {
+        let body =
+            r#"{"data":{"id":"lic-1","type":"licenses","attributes":{"maxUses":5,"uses":4}}}"#;
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 1, 0).unwrap();
+        let manager =
+            LicenseManager::new_with_clock(test_config(), Arc::new(MockClock::new(now))).unwrap();
+
+        let mut response = signed_response(body);
+        let caps = manager.accept_usage_response(&response).unwrap();
+        assert_eq!(caps.current_uses, Some(4));
+        assert_eq!(caps.monthly_limit, Some(5));
+
+        response.status = 422;
+        assert!(matches!(
+            manager.accept_usage_response(&response),
+            Err(GatewardenError::UsageLimitExceeded)
+        ));
+        response.status = 503;
+        response.request_id = Some("req-503".to_string());
+        let error = manager.accept_usage_response(&response).unwrap_err();
+        assert!(matches!(
+            error,
+            GatewardenError::ServerError { status: 503, .. }
+        ));
+        assert_eq!(error.request_id(), Some("req-503"));
+    }
+
+    #[test]
+    fn test_entitlement_policy_on_cached_record() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 13, 0, 0).unwrap();
+        let body = r#"{"meta":{"valid":true,"code":"VALID","scope":{"entitlements":["VISION","ENTERPRISE"]}}}"#;
+        let temp_dir = TempDir::new().unwrap();
+        let cache = FileCache::with_path(temp_dir.path().to_path_buf()).unwrap();
+        cache
+            .save(&hash_license_key("test-key"), &signed_record(body, now))
+            .unwrap();
+
+        let mut config = test_config();
+        config.required_entitlements = &["VISION"];
+        let manager = LicenseManager::new_with_clock(config, Arc::new(MockClock::new(now)))
+            .unwrap()
+            .with_cache_backend(Box::new(cache))
+            .with_entitlement_policy(EntitlementPolicy::any_of(["PRO", "ENTERPRISE"]));
+
+        // Required entitlements lead the scope; the policy's codes follow,
+        // narrowed to those held once the policy is satisfied
+        let held = |codes: &[&str]| codes.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            manager.entitlement_scope(Some(&held(&["VISION", "ENTERPRISE", "BASIC"]))),
+            vec!["VISION", "ENTERPRISE"]
+        );
+        assert_eq!(
+            manager.entitlement_scope(Some(&held(&["ENTERPRISE"]))),
+            vec!["VISION", "PRO", "ENTERPRISE"]
+        );
+        let result = manager.check_access("test-key").unwrap();
+        assert!(result.valid);
+        assert!(result.has_entitlement("ENTERPRISE"));
+        assert!(!result.has_entitlement("PRO"));
+        assert_eq!(
+            manager.entitlements("test-key").unwrap(),
+            vec!["VISION", "ENTERPRISE"]
+        );
+
+        let manager = manager.with_entitlement_policy(EntitlementPolicy::code("PRO"));
+        assert!(matches!(
+            manager.check_access("test-key"),
+            Err(GatewardenError::EntitlementMissing { code }) if code == "PRO"
+        ));
+    }
+
+    #[test]
+    fn test_compute_warnings() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+        let mut state = LicenseState {
+            valid: true,
+            entitlements: vec![],
+            expires_at: Some(now + chrono::Duration::days(30)),
+            max_uses: Some(100),
+            current_uses: Some(50),
+            code: "VALID".to_string(),
+            detail: None,
+            management_link: None,
+            license_id: None,
+            policy_id: None,
+            product_id: None,
+            trial: false,
+            upgrade: Default::default(),
+            max_version: None,
+            group: None,
+            owner: None,
+            user: None,
+        };
+        let caps = UsageCaps::from_license_state(&state);
+        let grace = Duration::from_secs(86400);
+        let fresh = Some((now - chrono::Duration::hours(1), grace));
+        assert!(compute_warnings(&state, &caps, fresh, now).is_empty());
+
+        state.expires_at = Some(now + chrono::Duration::days(3) + chrono::Duration::hours(2));
+        state.current_uses = Some(95);
+        let caps = UsageCaps::from_license_state(&state);
+        let stale = Some((now - chrono::Duration::hours(20), grace));
+        assert_eq!(
+            compute_warnings(&state, &caps, stale, now),
+            vec![
+                ValidationWarning::ExpiresSoon { days: 3 },
+                ValidationWarning::NearingUsageCap { remaining: 5 },
+                ValidationWarning::CacheNearExpiry,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validation_result_json_schema() {
+        let state = LicenseState {
+            valid: true,
+            entitlements: vec!["PRO".to_string()],
+            expires_at: Some(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()),
+            max_uses: Some(1000),
+            current_uses: Some(42),
+            code: "VALID".to_string(),
+            detail: None,
+            management_link: None,
+            license_id: Some("lic-1".to_string()),
+            policy_id: None,
+            product_id: None,
+            trial: false,
+            upgrade: Default::default(),
+            max_version: None,
+            group: None,
+            owner: None,
+            user: None,
+        };
+        let result = ValidationResult {
+            valid: true,
+            caps: UsageCaps::from_license_state(&state),
+            state,
+            from_cache: true,
+            fallback_reason: Some(FallbackReason::Network {
+                failure: TransportFailure::Dns,
+            }),
+            cached_at: Some(Utc.with_ymd_and_hms(2025, 6, 1, 9, 0, 0).unwrap()),
+            cache_age: Some(Duration::from_secs(3 * 3600)),
+            warnings: vec![ValidationWarning::ExpiresSoon { days: 3 }],
+        };
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "valid": true,
+                "state": {
+                    "valid": true,
+                    "entitlements": ["PRO"],
+                    "expires_at": "2026-01-01T00:00:00Z",
+                    "max_uses": 1000,
+                    "current_uses": 42,
+                    "code": "VALID",
+                    "detail": null,
+                    "management_link": null,
+                    "license_id": "lic-1",
+                    "policy_id": null,
+                    "product_id": null,
+                    "trial": false,
+                    "upgrade": { "renewal_url": null, "purchase_url": null },
+                    "max_version": null,
+                    "group": null,
+                    "owner": null,
+                    "user": null
+                },
+                "caps": { "monthly_limit": 1000, "current_uses": 42 },
+                "from_cache": true,
+                "fallback_reason": { "kind": "network", "failure": "dns" },
+                "cached_at": "2025-06-01T09:00:00Z",
+                "cache_age_secs": 10800,
+                "warnings": [{ "kind": "expires_soon", "days": 3 }]
+            })
+        );
+
+        let parsed: ValidationResult = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.caps, result.caps);
+        assert_eq!(parsed.cache_age, result.cache_age);
+        assert_eq!(parsed.warnings, result.warnings);
+    }
+
+    #[test]
+    fn test_security_posture() {
+        let mut config = test_config();
+        config.fallback_policy = FallbackPolicy::STRICT;
+        let manager =
+            LicenseManager::new_with_clock(config, Arc::new(MockClock::new(Utc::now()))).unwrap();
+
+        let posture = manager.security_posture();
+        assert!(posture.signature_verification);
+        assert!(posture.digest_verification);
+        assert!(!posture.digest_required);
+        assert_eq!(posture.trusted_keys, 1);
+        assert!(!posture.key_id_binding);
+        assert!(!posture.tls_pinning);
+        assert_eq!(posture.freshness_window, Duration::from_secs(300));
+        assert!(!posture.cache_encryption);
+        // Test constructor keeps the high-water mark in memory only
+        assert!(!posture.anti_rollback);
+        assert_eq!(posture.fallback_policy, FallbackPolicy::STRICT);
+
+        const NEXT: &[TrustedKey] = &[TrustedKey {
+            key_id: Some("next-account"),
+            public_key_hex: TEST_PUBLIC_KEY_HEX,
+        }];
+        let mut config = test_config();
+        config.require_digest = true;
+        config.public_key_id = Some("test-account");
+        config.trusted_keys = NEXT;
+        if cfg!(feature = "rustls") {
+            config.tls_pins = &["sha256/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="];
+        }
+        let posture = LicenseManager::new_with_clock(config, Arc::new(MockClock::new(Utc::now())))
+            .unwrap()
+            .security_posture();
+        assert!(posture.digest_required);
+        assert_eq!(posture.trusted_keys, 2);
+        assert!(posture.key_id_binding);
+        assert_eq!(posture.tls_pinning, cfg!(feature = "rustls"));
     }
 
     #[test]
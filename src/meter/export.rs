@@ -0,0 +1,234 @@
+//! Billing export of journaled usage.
+//!
+//! Converts [`UsageRecord`]s into formats billing systems ingest directly:
+//! - CSV with a `timestamp,feature,quantity,license_id` header
+//! - OpenMeter: a JSON array of CloudEvents (`type` = `feature_usage`,
+//!   `subject` = license ID, `data` = feature and quantity)
+//!
+//! Exports are generated locally and signed with an Ed25519 key held by
+//! the installation (e.g. a per-machine key registered with the vendor),
+//! so the billing side can detect edits made after export. Signatures
+//! cover the exact export bytes with a domain-separation prefix.
+//!
+//! OpenMeter event IDs are derived from the record contents and position,
+//! so re-exporting the same journal produces the same IDs and ingestion
+//! stays idempotent.
+
+use super::usage::UsageRecord;
+use crate::crypto::detached::{sign_detached, verify_detached};
+use crate::GatewardenError;
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain-separation prefix for export signatures.
+const EXPORT_SIGNING_PREFIX: &str = "gatewarden-usage-export-v1\n";
+
+/// CloudEvents type used for OpenMeter usage events.
+pub const OPENMETER_EVENT_TYPE: &str = "feature_usage";
+
+/// Billing export format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row.
+    Csv,
+    /// OpenMeter CloudEvents JSON array.
+    OpenMeter,
+}
+
+/// Render usage records in the given format.
+///
+/// `source` identifies the exporting installation (CloudEvents `source`);
+/// it is ignored for CSV.
+pub fn render_usage(
+    records: &[UsageRecord],
+    format: ExportFormat,
+    source: &str,
+) -> Result<String, GatewardenError> {
+    match format {
+        ExportFormat::Csv => Ok(render_csv(records)),
+        ExportFormat::OpenMeter => render_openmeter(records, source),
+    }
+}
+
+fn render_csv(records: &[UsageRecord]) -> String {
+    let mut out = String::from("timestamp,feature,quantity,license_id\n");
+    for r in records {
+        out.push_str(&format!(
+            "{},{},{},{}\n",
+            r.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            csv_field(&r.feature),
+            r.quantity,
+            csv_field(&r.license_id)
+        ));
+    }
+    out
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or newline.
+pub(super) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_openmeter(records: &[UsageRecord], source: &str) -> Result<String, GatewardenError> {
+    let events: Vec<serde_json::Value> = records
+        .iter()
+        .enumerate()
+        .map(|(index, r)| {
+            let time = r.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+            serde_json::json!({
+                "specversion": "1.0",
+                "id": event_id(source, index, &time, r),
+                "source": source,
+                "type": OPENMETER_EVENT_TYPE,
+                "subject": r.license_id,
+                "time": time,
+                "data": {
+                    "feature": r.feature,
+                    "quantity": r.quantity,
+                },
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&events).map_err(|e| GatewardenError::MeterIO {
+        message: "Failed to serialize export".to_string(),
+        source: Some(e.into()),
+    })
+}
+
+/// Deterministic event ID from the record and its journal position.
+fn event_id(source: &str, index: usize, time: &str, record: &UsageRecord) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        source,
+        &index.to_string(),
+        time,
+        &record.feature,
+        &record.quantity.to_string(),
+        &record.license_id,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hex::encode(&hasher.finalize()[..16])
+}
+
+/// A rendered export together with its detached signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedExport {
+    /// Export format of `body`.
+    pub format: ExportFormat,
+
+    /// The rendered export.
+    pub body: String,
+
+    /// Base64-encoded Ed25519 signature over the prefixed body.
+    pub signature: String,
+}
+
+impl SignedExport {
+    /// Render and sign usage records with an Ed25519 signing key seed.
+    pub fn sign(
+        records: &[UsageRecord],
+        format: ExportFormat,
+        source: &str,
+        signing_seed: &[u8; 32],
+    ) -> Result<Self, GatewardenError> {
+        let body = render_usage(records, format, source)?;
+
+        let signature = sign_detached(EXPORT_SIGNING_PREFIX, &body, signing_seed);
+        Ok(Self {
+            format,
+            body,
+            signature,
+        })
+    }
+
+    /// Verify the signature over the export body.
+    ///
+    /// # Errors
+    /// - `SignatureInvalid` - Body or signature was modified
+    /// - `ConfigError` - Public key is malformed
+    pub fn verify(&self, public_key_hex: &str) -> Result<(), GatewardenError> {
+        verify_detached(
+            EXPORT_SIGNING_PREFIX,
+            &self.body,
+            &self.signature,
+            public_key_hex,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use chrono::{TimeZone, Utc};
+
+    fn make_records() -> Vec<UsageRecord> {
+        vec![
+            UsageRecord {
+                timestamp: Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+                feature: "vision".to_string(),
+                quantity: 3,
+                license_id: "lic-1".to_string(),
+            },
+            UsageRecord {
+                timestamp: Utc.with_ymd_and_hms(2025, 1, 16, 8, 30, 0).unwrap(),
+                feature: "export, pdf".to_string(),
+                quantity: 1,
+                license_id: "lic-1".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let csv = render_usage(&make_records(), ExportFormat::Csv, "").unwrap();
+        assert_eq!(
+            csv,
+            "timestamp,feature,quantity,license_id\n\
+             2025-01-15T12:00:00Z,vision,3,lic-1\n\
+             2025-01-16T08:30:00Z,\"export, pdf\",1,lic-1\n"
+        );
+    }
+
+    #[test]
+    fn test_render_openmeter() {
+        let json = render_usage(&make_records(), ExportFormat::OpenMeter, "myapp/host-1").unwrap();
+        let events: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["type"], OPENMETER_EVENT_TYPE);
+        assert_eq!(events[0]["subject"], "lic-1");
+        assert_eq!(events[0]["time"], "2025-01-15T12:00:00Z");
+        assert_eq!(events[0]["data"]["quantity"], 3);
+        assert_ne!(events[0]["id"], events[1]["id"]);
+
+        // Re-exporting yields the same IDs
+        let again = render_usage(&make_records(), ExportFormat::OpenMeter, "myapp/host-1").unwrap();
+        assert_eq!(json, again);
+    }
+
+    #[test]
+    fn test_signed_export_detects_edits() {
+        let signed =
+            SignedExport::sign(&make_records(), ExportFormat::Csv, "", &TEST_SIGNING_SEED).unwrap();
+        assert!(signed.verify(TEST_PUBLIC_KEY_HEX).is_ok());
+
+        let edited = SignedExport {
+            body: signed.body.replace(",3,", ",1,"),
+            ..signed
+        };
+        assert!(matches!(
+            edited.verify(TEST_PUBLIC_KEY_HEX),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+}
@@ -1,3 +1,5 @@
 //! Usage metering.
 
+pub mod export;
+pub mod report;
 pub mod usage;
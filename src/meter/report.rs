@@ -0,0 +1,212 @@
+//! Signed per-day usage reports for true-up billing.
+//!
+//! Air-gapped installations cannot sync usage to Keygen. Instead they
+//! periodically send the vendor a report of per-day counts taken from the
+//! usage meter (see `UsageMeter::export_report`). Reports are signed with
+//! an installation-held Ed25519 key, as with billing exports, so the vendor
+//! can detect edits made after the report was generated. Each report
+//! names the license and the installation it covers, so a report cannot
+//! be passed off as another installation's.
+//!
+//! Meters keep per-day counts for about a year; older days are rolled up
+//! into per-month totals, so reports stay bounded on long-lived installs.
+
+use super::export::csv_field;
+use crate::crypto::detached::{sign_detached, verify_detached};
+use crate::GatewardenError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Domain-separation prefix for report signatures.
+const REPORT_SIGNING_PREFIX: &str = "gatewarden-usage-report-v1\n";
+
+/// Usage report format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// JSON [`UsageReport`].
+    Json,
+    /// `license_id,source,period,count` CSV with a header row; `period`
+    /// is a month (YYYY-MM) for rolled-up rows and a day otherwise.
+    Csv,
+}
+
+/// Uses recorded on one UTC day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    /// Day (YYYY-MM-DD, UTC).
+    pub date: String,
+
+    /// Uses recorded that day.
+    pub count: u64,
+}
+
+/// Uses recorded in one UTC month, for days rolled out of per-day history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonthlyUsage {
+    /// Month (YYYY-MM, UTC).
+    pub month: String,
+
+    /// Uses recorded on rolled-up days of that month.
+    pub count: u64,
+}
+
+/// Usage counts from one installation's meter for one license.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// When the report was generated.
+    pub generated_at: DateTime<Utc>,
+
+    /// Keygen license ID the uses were recorded for (empty if no use
+    /// recorded it).
+    pub license_id: String,
+
+    /// The reporting installation, as chosen by the application (e.g. a
+    /// machine fingerprint or an install ID registered with the vendor).
+    pub source: String,
+
+    /// Total uses recorded by the meter.
+    pub lifetime_count: u64,
+
+    /// Per-month counts for days older than per-day history, oldest first.
+    #[serde(default)]
+    pub months: Vec<MonthlyUsage>,
+
+    /// Per-day counts, oldest first.
+    pub days: Vec<DailyUsage>,
+}
+
+impl UsageReport {
+    /// Render the report in the given format.
+    pub fn render(&self, format: ReportFormat) -> Result<String, GatewardenError> {
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| GatewardenError::MeterIO {
+                    message: "Failed to serialize report".to_string(),
+                    source: Some(e.into()),
+                })
+            }
+            ReportFormat::Csv => {
+                let mut out = String::from("license_id,source,period,count\n");
+                let months = self.months.iter().map(|m| (&m.month, m.count));
+                let days = self.days.iter().map(|d| (&d.date, d.count));
+                for (period, count) in months.chain(days) {
+                    out.push_str(&format!(
+                        "{},{},{},{}\n",
+                        csv_field(&self.license_id),
+                        csv_field(&self.source),
+                        period,
+                        count
+                    ));
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// A rendered report together with its detached signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedReport {
+    /// Format of `body`.
+    pub format: ReportFormat,
+
+    /// The rendered report.
+    pub body: String,
+
+    /// Base64-encoded Ed25519 signature over the prefixed body.
+    pub signature: String,
+}
+
+impl SignedReport {
+    /// Render and sign a report with an Ed25519 signing key seed.
+    pub fn sign(
+        report: &UsageReport,
+        format: ReportFormat,
+        signing_seed: &[u8; 32],
+    ) -> Result<Self, GatewardenError> {
+        let body = report.render(format)?;
+        let signature = sign_detached(REPORT_SIGNING_PREFIX, &body, signing_seed);
+        Ok(Self {
+            format,
+            body,
+            signature,
+        })
+    }
+
+    /// Verify the signature over the report body.
+    ///
+    /// # Errors
+    /// - `SignatureInvalid` - Body or signature was modified
+    /// - `ConfigError` - Public key is malformed
+    pub fn verify(&self, public_key_hex: &str) -> Result<(), GatewardenError> {
+        verify_detached(
+            REPORT_SIGNING_PREFIX,
+            &self.body,
+            &self.signature,
+            public_key_hex,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use chrono::TimeZone;
+
+    fn make_report() -> UsageReport {
+        UsageReport {
+            generated_at: Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap(),
+            license_id: "lic-1".to_string(),
+            source: "host-a".to_string(),
+            lifetime_count: 12,
+            months: vec![MonthlyUsage {
+                month: "2023-11".to_string(),
+                count: 7,
+            }],
+            days: vec![
+                DailyUsage {
+                    date: "2025-01-15".to_string(),
+                    count: 3,
+                },
+                DailyUsage {
+                    date: "2025-01-16".to_string(),
+                    count: 2,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_report() {
+        let report = make_report();
+        assert_eq!(
+            report.render(ReportFormat::Csv).unwrap(),
+            "license_id,source,period,count\n\
+             lic-1,host-a,2023-11,7\n\
+             lic-1,host-a,2025-01-15,3\n\
+             lic-1,host-a,2025-01-16,2\n"
+        );
+
+        let json = report.render(ReportFormat::Json).unwrap();
+        let parsed: UsageReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    fn test_signed_report_detects_edits() {
+        let signed =
+            SignedReport::sign(&make_report(), ReportFormat::Json, &TEST_SIGNING_SEED).unwrap();
+        assert!(signed.verify(TEST_PUBLIC_KEY_HEX).is_ok());
+
+        let edited = SignedReport {
+            body: signed.body.replace("\"count\": 3", "\"count\": 1"),
+            ..signed
+        };
+        assert!(matches!(
+            edited.verify(TEST_PUBLIC_KEY_HEX),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+}
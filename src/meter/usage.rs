@@ -2,12 +2,43 @@
 //!
 //! Tracks daily and monthly usage counts with automatic rollover
 //! based on UTC dates via the Clock trait.
+//!
+//! Increments are write-ahead journaled: each one is appended (and
+//! synced) to `<meter>.wal` with a sequence number before it counts, and
+//! the stats file records the last sequence applied. Entries newer than
+//! the stats file (left by a failed stats write) are replayed on the next
+//! start, and entries at or below the applied sequence are skipped, so
+//! each increment is counted exactly once across IO failures and retries.
+//!
+//! The journal is never truncated: it is also the usage log billing
+//! exports are built from. Each increment is a [`UsageRecord`] carrying
+//! the feature and Keygen license ID it was recorded against.
+//!
+//! Processes may share a meter. Writers hold an advisory lock on
+//! `<meter>.lock` and reload the stats and journal before appending, so
+//! concurrent increments are merged rather than overwritten. Counts read
+//! from a `UsageMeter` are a snapshot as of its last load or write.
+//!
+//! A meter opened with a [`MeterKey`] is tamper-evident: the stats file
+//! and every journal entry carry an HMAC-SHA256 keyed from the license-key
+//! hash and the machine fingerprint, and a sealed `<meter>.hwm` sidecar
+//! records the highest `lifetime_count` written. Edited files, or a stats
+//! file and journal restored from older copies, fail with `MeterTampered`.
+//! Deleting every meter file resets the local count; that cannot be detected
+//! locally, which is what syncing usage to Keygen is for.
 
+use super::export::{ExportFormat, SignedExport};
+use super::report::{DailyUsage, MonthlyUsage, ReportFormat, SignedReport, UsageReport};
 use crate::clock::Clock;
+use crate::crypto::mac::new_hmac;
 use crate::GatewardenError;
 use chrono::{DateTime, Datelike, Utc};
+use fs2::FileExt;
+use hmac::Mac;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
 
 /// Usage statistics with daily and monthly counters.
@@ -27,6 +58,44 @@ pub struct UsageStats {
 
     /// Total lifetime usage count.
     pub lifetime_count: u64,
+
+    /// Uses recorded locally but not yet reflected in Keygen's `uses`.
+    #[serde(default)]
+    pub unsynced_count: u64,
+
+    /// Sequence number of the last journaled increment applied.
+    #[serde(default)]
+    pub journal_seq: u64,
+
+    /// Uses per UTC day (YYYY-MM-DD) over the last
+    /// [`DAILY_HISTORY_DAYS`] days, for usage reports.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub daily_history: BTreeMap<String, u64>,
+
+    /// Uses per UTC month (YYYY-MM) on days rolled out of `daily_history`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub monthly_history: BTreeMap<String, u64>,
+
+    /// Keygen license ID of the most recent use that named one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub license_id: String,
+
+    /// Increment sent to Keygen without a verified response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_flight: Option<InFlightSync>,
+}
+
+/// An increment-usage request that may or may not have reached Keygen.
+///
+/// Journaled before the request is sent. If no verified response comes
+/// back, the next sync compares Keygen's `uses` against `base_uses + n`
+/// to decide whether the increment landed, instead of sending it twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InFlightSync {
+    /// Uses sent in the increment.
+    pub n: u64,
+    /// Keygen's `uses` just before the increment was sent.
+    pub base_uses: u64,
 }
 
 impl UsageStats {
@@ -37,10 +106,23 @@ impl UsageStats {
 
     /// Increment usage, handling rollovers based on clock.
     pub fn increment(&mut self, clock: &dyn Clock) {
-        let now = clock.now_utc();
+        self.add(1, clock);
+    }
+
+    /// Add `n` uses, handling rollovers based on clock.
+    pub fn add(&mut self, n: u64, clock: &dyn Clock) {
+        self.add_at(n, clock.now_utc());
+    }
+
+    /// Add `n` uses that occurred at `now`.
+    fn add_at(&mut self, n: u64, now: DateTime<Utc>) {
         let today = format_date(&now);
         let this_month = format_month(&now);
 
+        let day_total = self.daily_history.entry(today.clone()).or_insert(0);
+        *day_total = day_total.saturating_add(n);
+        self.roll_up_history(now);
+
         // Check for daily rollover
         if self.daily_date.as_ref() != Some(&today) {
             self.daily_count = 0;
@@ -53,9 +135,22 @@ impl UsageStats {
             self.monthly_period = Some(this_month);
         }
 
-        self.daily_count += 1;
-        self.monthly_count += 1;
-        self.lifetime_count += 1;
+        self.daily_count = self.daily_count.saturating_add(n);
+        self.monthly_count = self.monthly_count.saturating_add(n);
+        self.lifetime_count = self.lifetime_count.saturating_add(n);
+        self.unsynced_count = self.unsynced_count.saturating_add(n);
+    }
+
+    /// Fold days older than [`DAILY_HISTORY_DAYS`] into monthly totals.
+    fn roll_up_history(&mut self, now: DateTime<Utc>) {
+        // Keep today and the DAILY_HISTORY_DAYS - 1 days before it
+        let cutoff = format_date(&(now - chrono::Duration::days(DAILY_HISTORY_DAYS - 1)));
+        let kept = self.daily_history.split_off(&cutoff);
+        for (date, count) in std::mem::replace(&mut self.daily_history, kept) {
+            let month = date.get(..7).unwrap_or(&date).to_string();
+            let month = self.monthly_history.entry(month).or_insert(0);
+            *month = month.saturating_add(count);
+        }
     }
 
     /// Get the current daily count, applying rollover if needed.
@@ -93,37 +188,242 @@ fn format_month(dt: &DateTime<Utc>) -> String {
     format!("{:04}-{:02}", dt.year(), dt.month())
 }
 
+/// A single metered usage event, as exported for billing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// When the usage occurred.
+    pub timestamp: DateTime<Utc>,
+
+    /// Feature that was used (empty if recorded without one).
+    pub feature: String,
+
+    /// Units consumed.
+    pub quantity: u64,
+
+    /// Keygen license ID (never the license key; empty if unknown).
+    pub license_id: String,
+}
+
+/// Domain-separation key for deriving meter keys.
+const METER_KEY_CONTEXT: &[u8] = b"gatewarden-usage-meter-v1";
+
+/// Days of per-day history kept before rolling up into months.
+pub const DAILY_HISTORY_DAYS: i64 = 366;
+
+/// Key authenticating a usage meter's files.
+#[derive(Clone)]
+pub struct MeterKey([u8; 32]);
+
+impl MeterKey {
+    /// Derive the key for a license-key hash on a machine.
+    ///
+    /// `machine_fingerprint` is typically
+    /// [`machine_fingerprint`](crate::fingerprint::machine_fingerprint);
+    /// pass an empty string if it is unavailable (the key is then bound
+    /// to the license only).
+    pub fn derive(license_key_hash: &str, machine_fingerprint: &str) -> Self {
+        let mut mac = new_hmac(METER_KEY_CONTEXT);
+        mac.update(license_key_hash.as_bytes());
+        mac.update(&[0]);
+        mac.update(machine_fingerprint.as_bytes());
+        Self(mac.finalize().into_bytes().into())
+    }
+
+    /// Hex-encoded HMAC-SHA256 of `data`.
+    fn sign(&self, data: &[u8]) -> String {
+        let mut mac = new_hmac(&self.0);
+        mac.update(data);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// Constant-time check of a hex-encoded HMAC.
+    fn verify(&self, data: &[u8], mac_hex: &str) -> Result<(), GatewardenError> {
+        let tag = hex::decode(mac_hex).map_err(|_| GatewardenError::MeterTampered)?;
+        let mut mac = new_hmac(&self.0);
+        mac.update(data);
+        mac.verify_slice(&tag)
+            .map_err(|_| GatewardenError::MeterTampered)
+    }
+}
+
+impl std::fmt::Debug for MeterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MeterKey(..)")
+    }
+}
+
+/// Stats file contents for a keyed meter.
+#[derive(Serialize, Deserialize)]
+struct SealedStats {
+    stats: UsageStats,
+    mac: String,
+}
+
+/// Highest `lifetime_count` written by a keyed meter.
+#[derive(Serialize, Deserialize)]
+struct LifetimeMark {
+    lifetime_count: u64,
+    mac: String,
+}
+
+/// What to do when an increment cannot be journaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeterWriteFailure {
+    /// Return `MeterIO` and do not count the increment (safe to retry).
+    #[default]
+    Reject,
+    /// Count the increment in memory and persist it with the next
+    /// successful write; it is lost if the process exits first.
+    Defer,
+}
+
+/// A journaled increment, sync attempt, or sync acknowledgement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    seq: u64,
+    n: u64,
+    at: DateTime<Utc>,
+    /// Uses acknowledged by Keygen, removed from `unsynced_count`.
+    #[serde(default)]
+    synced: u64,
+    /// Increment about to be sent to Keygen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sending: Option<InFlightSync>,
+    /// Feature the uses in `n` were recorded against.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    feature: String,
+    /// Keygen license ID the uses in `n` were recorded for.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    license_id: String,
+    /// HMAC over the other fields (keyed meters only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mac: Option<String>,
+}
+
+impl JournalEntry {
+    /// An unnumbered, unsigned change made at `at`.
+    fn change(at: DateTime<Utc>) -> Self {
+        Self {
+            seq: 0,
+            n: 0,
+            at,
+            synced: 0,
+            sending: None,
+            feature: String::new(),
+            license_id: String::new(),
+            mac: None,
+        }
+    }
+
+    /// Bytes covered by the entry's HMAC.
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!(
+            "{}:{}:{}:{}",
+            self.seq,
+            self.n,
+            self.at.to_rfc3339(),
+            self.synced
+        );
+        // Appended only when present, so earlier entries still verify
+        if let Some(sending) = &self.sending {
+            bytes.push_str(&format!(":{}:{}", sending.n, sending.base_uses));
+        }
+        if !self.feature.is_empty() || !self.license_id.is_empty() {
+            // Length-prefixed so the boundary cannot be shifted
+            bytes.push_str(&format!(
+                ":{}:{}{}",
+                self.feature.len(),
+                self.feature,
+                self.license_id
+            ));
+        }
+        bytes.into_bytes()
+    }
+
+    /// The usage this entry records, if any.
+    fn usage_record(&self) -> Option<UsageRecord> {
+        (self.n > 0).then(|| UsageRecord {
+            timestamp: self.at,
+            feature: self.feature.clone(),
+            quantity: self.n,
+            license_id: self.license_id.clone(),
+        })
+    }
+}
+
 /// File-based usage meter store.
 pub struct UsageMeter {
     /// Path to the usage stats file.
     path: PathBuf,
     /// Current usage stats.
     stats: UsageStats,
+    /// Behavior when journaling fails.
+    write_failure: MeterWriteFailure,
+    /// Key authenticating the meter's files, if tamper-evident.
+    key: Option<MeterKey>,
+    /// Changes counted in memory but not yet journaled (`Defer` policy).
+    deferred: Vec<JournalEntry>,
 }
 
 impl UsageMeter {
     /// Create a new usage meter at the given path.
+    ///
+    /// Replays any increments left in the write-ahead journal.
     pub fn new(path: PathBuf) -> Result<Self, GatewardenError> {
-        let stats = if path.exists() {
-            let json = fs::read_to_string(&path)
-                .map_err(|e| GatewardenError::MeterIO(format!("Failed to read meter: {}", e)))?;
-            serde_json::from_str(&json)
-                .map_err(|e| GatewardenError::MeterIO(format!("Failed to parse meter: {}", e)))?
-        } else {
-            UsageStats::new()
+        Self::open(path, None)
+    }
+
+    /// Open a tamper-evident usage meter authenticated with `key`.
+    ///
+    /// An unsealed stats file from an earlier version is accepted once
+    /// (and sealed on the next write) if no lifetime mark exists yet.
+    ///
+    /// # Errors
+    /// - `MeterTampered` - A file failed authentication, or `lifetime_count`
+    ///   is lower than the highest count previously written
+    /// - `MeterIO` - A file could not be read or parsed
+    pub fn with_key(path: PathBuf, key: MeterKey) -> Result<Self, GatewardenError> {
+        Self::open(path, Some(key))
+    }
+
+    fn open(path: PathBuf, key: Option<MeterKey>) -> Result<Self, GatewardenError> {
+        let mut meter = Self {
+            path,
+            stats: UsageStats::new(),
+            write_failure: MeterWriteFailure::default(),
+            key,
+            deferred: Vec::new(),
         };
 
-        Ok(Self { path, stats })
+        // Best-effort: a meter in a read-only directory can still be read
+        let _lock = meter.lock().ok();
+        let (stats, replayed) = meter.load_state()?;
+        meter.stats = stats;
+        if replayed {
+            // A failed save is retried by the next write
+            let _ = meter.save();
+        }
+        Ok(meter)
+    }
+
+    /// Set the behavior when an increment cannot be journaled.
+    pub fn with_write_failure(mut self, policy: MeterWriteFailure) -> Self {
+        self.write_failure = policy;
+        self
     }
 
     /// Create a usage meter with a namespace under data_dir.
     pub fn with_namespace(namespace: &str) -> Result<Self, GatewardenError> {
-        let base_dir = dirs::data_dir()
-            .ok_or_else(|| GatewardenError::MeterIO("Could not find data directory".to_string()))?;
+        let base_dir = dirs::data_dir().ok_or_else(|| GatewardenError::MeterIO {
+            message: "Could not find data directory".to_string(),
+            source: None,
+        })?;
 
         let dir = base_dir.join(namespace);
-        fs::create_dir_all(&dir)
-            .map_err(|e| GatewardenError::MeterIO(format!("Failed to create dir: {}", e)))?;
+        fs::create_dir_all(&dir).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to create dir".to_string(),
+            source: Some(e.into()),
+        })?;
 
         let path = dir.join("usage.json");
         Self::new(path)
@@ -131,8 +431,124 @@ impl UsageMeter {
 
     /// Increment usage and persist.
     pub fn increment(&mut self, clock: &dyn Clock) -> Result<(), GatewardenError> {
-        self.stats.increment(clock);
-        self.save()
+        self.add(1, clock)
+    }
+
+    /// Add `n` uses, attributed to no feature or license, and persist.
+    pub fn add(&mut self, n: u64, clock: &dyn Clock) -> Result<(), GatewardenError> {
+        self.record(&UsageRecord {
+            timestamp: clock.now_utc(),
+            feature: String::new(),
+            quantity: n,
+            license_id: String::new(),
+        })
+    }
+
+    /// Journal a usage event and count its quantity.
+    ///
+    /// Succeeds once the event is journaled, even if the stats file
+    /// cannot be rewritten; the journal is replayed on the next start.
+    pub fn record(&mut self, record: &UsageRecord) -> Result<(), GatewardenError> {
+        self.journal(JournalEntry {
+            n: record.quantity,
+            feature: record.feature.clone(),
+            license_id: record.license_id.clone(),
+            ..JournalEntry::change(record.timestamp)
+        })
+    }
+
+    /// Record that Keygen has counted `n` previously unsynced uses.
+    ///
+    /// Clears any in-flight increment.
+    pub fn mark_synced(&mut self, n: u64, clock: &dyn Clock) -> Result<(), GatewardenError> {
+        self.journal(JournalEntry {
+            synced: n,
+            ..JournalEntry::change(clock.now_utc())
+        })
+    }
+
+    /// Record that an increment of `n` uses is about to be sent to Keygen,
+    /// whose `uses` currently stands at `base_uses`.
+    ///
+    /// # Errors
+    /// - `MeterIO` - The attempt could not be journaled (even under the
+    ///   `Defer` policy: an attempt only in memory cannot be reconciled
+    ///   after a restart, so the increment must not be sent)
+    /// - `MeterTampered` - The meter was edited or rolled back
+    pub fn begin_sync(
+        &mut self,
+        n: u64,
+        base_uses: u64,
+        clock: &dyn Clock,
+    ) -> Result<(), GatewardenError> {
+        self.journal(JournalEntry {
+            sending: Some(InFlightSync { n, base_uses }),
+            ..JournalEntry::change(clock.now_utc())
+        })?;
+        if !self.deferred.is_empty() {
+            return Err(GatewardenError::MeterIO {
+                message: "Failed to journal usage sync".to_string(),
+                source: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Journal, apply, and save a change.
+    ///
+    /// Runs under the cross-process lock, on top of a fresh reload, so
+    /// changes made by other processes are merged rather than overwritten.
+    /// Changes deferred by earlier failures are journaled first.
+    fn journal(&mut self, change: JournalEntry) -> Result<(), GatewardenError> {
+        let locked = self.lock().and_then(|lock| Ok((lock, self.load_state()?)));
+        let _lock = match locked {
+            Ok((lock, (stats, _))) => {
+                self.stats = stats;
+                lock
+            }
+            Err(GatewardenError::MeterTampered) => return Err(GatewardenError::MeterTampered),
+            Err(e) if self.write_failure == MeterWriteFailure::Reject => return Err(e),
+            Err(_) => {
+                self.defer(change);
+                return Ok(());
+            }
+        };
+
+        let mut pending = std::mem::take(&mut self.deferred);
+        pending.push(change);
+        let mut pending = pending.into_iter();
+        while let Some(change) = pending.next() {
+            let entry = self.seal_entry(change);
+            if let Err(e) = self.append_journal(&entry) {
+                // Only `Defer` ever has earlier changes pending
+                if self.write_failure == MeterWriteFailure::Reject {
+                    return Err(e);
+                }
+                self.defer(entry);
+                for change in pending {
+                    self.defer(change);
+                }
+                return Ok(());
+            }
+            apply_entry(&mut self.stats, &entry);
+        }
+
+        // Best-effort: the changes are already journaled
+        let _ = self.save();
+        Ok(())
+    }
+
+    /// Count a change in memory only, for the next successful write to journal.
+    fn defer(&mut self, change: JournalEntry) {
+        apply_change(&mut self.stats, &change);
+        self.deferred.push(change);
+    }
+
+    /// Number a change after the last applied entry and sign it if keyed.
+    fn seal_entry(&self, mut entry: JournalEntry) -> JournalEntry {
+        entry.seq = self.stats.journal_seq + 1;
+        entry.mac = self.key.as_ref().map(|key| key.sign(&entry.signed_bytes()));
+        entry
     }
 
     /// Get current daily count.
@@ -150,33 +566,443 @@ impl UsageMeter {
         self.stats.lifetime_count
     }
 
+    /// Uses not yet reflected in Keygen's `uses` counter.
+    pub fn unsynced_count(&self) -> u64 {
+        self.stats.unsynced_count
+    }
+
+    /// Increment sent to Keygen whose response was never verified.
+    pub fn in_flight(&self) -> Option<InFlightSync> {
+        self.stats.in_flight
+    }
+
+    /// Per-day usage counts, oldest first, with days older than
+    /// [`DAILY_HISTORY_DAYS`] rolled up into months.
+    ///
+    /// `source` identifies the installation in the report. Covers the
+    /// days recorded since per-day history was added to the meter format;
+    /// `lifetime_count` includes earlier uses too.
+    pub fn report(&self, source: &str, clock: &dyn Clock) -> UsageReport {
+        UsageReport {
+            generated_at: clock.now_utc(),
+            license_id: self.stats.license_id.clone(),
+            source: source.to_string(),
+            lifetime_count: self.stats.lifetime_count,
+            months: self
+                .stats
+                .monthly_history
+                .iter()
+                .map(|(month, count)| MonthlyUsage {
+                    month: month.clone(),
+                    count: *count,
+                })
+                .collect(),
+            days: self
+                .stats
+                .daily_history
+                .iter()
+                .map(|(date, count)| DailyUsage {
+                    date: date.clone(),
+                    count: *count,
+                })
+                .collect(),
+        }
+    }
+
+    /// Export a signed per-day usage report for true-up billing.
+    ///
+    /// `source` identifies the installation, and `signing_seed` is its
+    /// Ed25519 key whose public half the vendor uses with
+    /// `SignedReport::verify`.
+    pub fn export_report(
+        &self,
+        format: ReportFormat,
+        source: &str,
+        signing_seed: &[u8; 32],
+        clock: &dyn Clock,
+    ) -> Result<SignedReport, GatewardenError> {
+        SignedReport::sign(&self.report(source, clock), format, signing_seed)
+    }
+
+    /// Journaled usage events, oldest first.
+    ///
+    /// Uses counted in memory under the `Defer` policy but not yet
+    /// journaled are not included.
+    ///
+    /// # Errors
+    /// - `MeterIO` - The journal could not be read
+    /// - `MeterTampered` - A journal entry failed authentication
+    pub fn records(&self) -> Result<Vec<UsageRecord>, GatewardenError> {
+        let mut last_seq = 0;
+        let mut records = Vec::new();
+        for entry in self.read_journal()?.unwrap_or_default() {
+            // Skip a duplicate left by a retried append
+            if entry.seq <= last_seq {
+                continue;
+            }
+            last_seq = entry.seq;
+            records.extend(entry.usage_record());
+        }
+        Ok(records)
+    }
+
+    /// Journaled usage events with `from <= timestamp < to`, oldest first.
+    ///
+    /// # Errors
+    /// Any error from `records`.
+    pub fn records_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<UsageRecord>, GatewardenError> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter(|r| r.timestamp >= from && r.timestamp < to)
+            .collect())
+    }
+
+    /// Export the journaled usage events for billing, signed with an
+    /// installation-held Ed25519 key.
+    ///
+    /// `source` identifies the installation (CloudEvents `source`).
+    ///
+    /// # Errors
+    /// Any error from `records`, or `MeterIO` if rendering fails.
+    pub fn export(
+        &self,
+        format: ExportFormat,
+        source: &str,
+        signing_seed: &[u8; 32],
+    ) -> Result<SignedExport, GatewardenError> {
+        SignedExport::sign(&self.records()?, format, source, signing_seed)
+    }
+
     /// Get a copy of the raw stats.
     pub fn stats(&self) -> &UsageStats {
         &self.stats
     }
 
+    /// Take the cross-process write lock, held until the file is dropped.
+    fn lock(&self) -> Result<File, GatewardenError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to create dir".to_string(),
+                source: Some(e.into()),
+            })?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.path.with_extension("lock"))
+            .map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to open lock".to_string(),
+                source: Some(e.into()),
+            })?;
+        file.lock_exclusive()
+            .map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to lock meter".to_string(),
+                source: Some(e.into()),
+            })?;
+        Ok(file)
+    }
+
+    /// Read the stats file and apply journal entries newer than it.
+    ///
+    /// Returns the stats and whether a journal was replayed.
+    fn load_state(&self) -> Result<(UsageStats, bool), GatewardenError> {
+        let mut stats = if self.path.exists() {
+            let json = fs::read_to_string(&self.path).map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to read meter".to_string(),
+                source: Some(e.into()),
+            })?;
+            self.unseal(&json)?
+        } else {
+            UsageStats::new()
+        };
+
+        let replayed = self.replay_journal(&mut stats)?;
+        self.check_lifetime_mark(&stats)?;
+        Ok((stats, replayed))
+    }
+
+    /// Path of the write-ahead journal.
+    fn journal_path(&self) -> PathBuf {
+        self.path.with_extension("wal")
+    }
+
+    /// Append and sync a journal entry.
+    fn append_journal(&self, entry: &JournalEntry) -> Result<(), GatewardenError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to create dir".to_string(),
+                source: Some(e.into()),
+            })?;
+        }
+
+        let line = serde_json::to_string(entry).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to serialize".to_string(),
+            source: Some(e.into()),
+        })?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())
+            .map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to open journal".to_string(),
+                source: Some(e.into()),
+            })?;
+        writeln!(file, "{}", line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to write journal".to_string(),
+                source: Some(e.into()),
+            })
+    }
+
+    /// Parsed journal entries, authenticated if the meter is keyed, or
+    /// `None` without a journal.
+    ///
+    /// A torn final line (crash mid-append) is skipped. Unsealed entries
+    /// are accepted only until the meter is first sealed.
+    fn read_journal(&self) -> Result<Option<Vec<JournalEntry>>, GatewardenError> {
+        let journal = match fs::read_to_string(self.journal_path()) {
+            Ok(journal) => journal,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(GatewardenError::MeterIO {
+                    message: "Failed to read journal".to_string(),
+                    source: Some(e.into()),
+                })
+            }
+        };
+
+        let entries: Vec<JournalEntry> = journal
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        if let Some(key) = &self.key {
+            let sealed = self.read_lifetime_mark()?.is_some();
+            for entry in &entries {
+                match entry.mac.as_deref() {
+                    Some(mac) => key.verify(&entry.signed_bytes(), mac)?,
+                    None if sealed => return Err(GatewardenError::MeterTampered),
+                    None => {}
+                }
+            }
+        }
+        Ok(Some(entries))
+    }
+
+    /// Apply journal entries newer than `stats`.
+    ///
+    /// Returns whether any entry was applied. Entries already applied, or
+    /// written twice by a retry, are skipped.
+    fn replay_journal(&self, stats: &mut UsageStats) -> Result<bool, GatewardenError> {
+        let mut replayed = false;
+        for entry in self.read_journal()?.unwrap_or_default() {
+            if entry.seq > stats.journal_seq {
+                apply_entry(stats, &entry);
+                replayed = true;
+            }
+        }
+        Ok(replayed)
+    }
+
     /// Save stats to disk.
     fn save(&self) -> Result<(), GatewardenError> {
         // Ensure parent directory exists
         if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| GatewardenError::MeterIO(format!("Failed to create dir: {}", e)))?;
+            fs::create_dir_all(parent).map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to create dir".to_string(),
+                source: Some(e.into()),
+            })?;
         }
 
-        let json = serde_json::to_string_pretty(&self.stats)
-            .map_err(|e| GatewardenError::MeterIO(format!("Failed to serialize: {}", e)))?;
+        let json = match &self.key {
+            Some(key) => serde_json::to_string_pretty(&SealedStats {
+                stats: self.stats.clone(),
+                mac: key.sign(&stats_bytes(&self.stats)?),
+            }),
+            None => serde_json::to_string_pretty(&self.stats),
+        }
+        .map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to serialize".to_string(),
+            source: Some(e.into()),
+        })?;
 
         // Atomic write via temp + rename
         let temp_path = self.path.with_extension("tmp");
-        fs::write(&temp_path, &json)
-            .map_err(|e| GatewardenError::MeterIO(format!("Failed to write temp: {}", e)))?;
-        fs::rename(&temp_path, &self.path)
-            .map_err(|e| GatewardenError::MeterIO(format!("Failed to rename: {}", e)))?;
+        fs::write(&temp_path, &json).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to write temp".to_string(),
+            source: Some(e.into()),
+        })?;
+        fs::rename(&temp_path, &self.path).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to rename".to_string(),
+            source: Some(e.into()),
+        })?;
 
-        Ok(())
+        // A meter first sealed now adopts its unsealed journal, as it
+        // adopted the unsealed stats file
+        if self.key.is_some() && self.read_lifetime_mark()?.is_none() {
+            self.seal_journal()?;
+        }
+        self.save_lifetime_mark()
+    }
+
+    /// Rewrite the journal with every entry sealed.
+    fn seal_journal(&self) -> Result<(), GatewardenError> {
+        let (Some(key), Some(entries)) = (&self.key, self.read_journal()?) else {
+            return Ok(());
+        };
+        if entries.iter().all(|entry| entry.mac.is_some()) {
+            return Ok(());
+        }
+
+        let mut journal = String::new();
+        for mut entry in entries {
+            entry.mac = Some(key.sign(&entry.signed_bytes()));
+            let line = serde_json::to_string(&entry).map_err(|e| GatewardenError::MeterIO {
+                message: "Failed to serialize".to_string(),
+                source: Some(e.into()),
+            })?;
+            journal.push_str(&line);
+            journal.push('\n');
+        }
+
+        let temp_path = self.journal_path().with_extension("wal.tmp");
+        fs::write(&temp_path, journal).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to write temp".to_string(),
+            source: Some(e.into()),
+        })?;
+        fs::rename(&temp_path, self.journal_path()).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to rename".to_string(),
+            source: Some(e.into()),
+        })
+    }
+
+    /// Parse a stats file, verifying its HMAC if the meter is keyed.
+    fn unseal(&self, json: &str) -> Result<UsageStats, GatewardenError> {
+        let parse_error = |e: serde_json::Error| GatewardenError::MeterIO {
+            message: "Failed to parse meter".to_string(),
+            source: Some(e.into()),
+        };
+
+        let Some(key) = &self.key else {
+            return serde_json::from_str(json).map_err(parse_error);
+        };
+
+        let value: serde_json::Value = serde_json::from_str(json).map_err(parse_error)?;
+        if value.get("mac").is_none() {
+            // Unsealed file from before the meter was keyed
+            if self.read_lifetime_mark()?.is_some() {
+                return Err(GatewardenError::MeterTampered);
+            }
+            return serde_json::from_value(value).map_err(parse_error);
+        }
+
+        let sealed: SealedStats = serde_json::from_value(value).map_err(parse_error)?;
+        key.verify(&stats_bytes(&sealed.stats)?, &sealed.mac)?;
+        Ok(sealed.stats)
+    }
+
+    /// Path of the lifetime high-water mark.
+    fn lifetime_mark_path(&self) -> PathBuf {
+        self.path.with_extension("hwm")
+    }
+
+    /// Highest `lifetime_count` previously written, if keyed and recorded.
+    fn read_lifetime_mark(&self) -> Result<Option<u64>, GatewardenError> {
+        let Some(key) = &self.key else {
+            return Ok(None);
+        };
+
+        let json = match fs::read_to_string(self.lifetime_mark_path()) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(GatewardenError::MeterIO {
+                    message: "Failed to read lifetime mark".to_string(),
+                    source: Some(e.into()),
+                })
+            }
+        };
+        let mark: LifetimeMark =
+            serde_json::from_str(&json).map_err(|_| GatewardenError::MeterTampered)?;
+        key.verify(mark.lifetime_count.to_string().as_bytes(), &mark.mac)?;
+        Ok(Some(mark.lifetime_count))
+    }
+
+    /// Reject a `lifetime_count` below the highest one written.
+    fn check_lifetime_mark(&self, stats: &UsageStats) -> Result<(), GatewardenError> {
+        match self.read_lifetime_mark()? {
+            Some(mark) if stats.lifetime_count < mark => {
+                trace_warn!(
+                    lifetime_count = stats.lifetime_count,
+                    high_water = mark,
+                    "usage meter rolled back"
+                );
+                Err(GatewardenError::MeterTampered)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record the current `lifetime_count` as the high-water mark.
+    fn save_lifetime_mark(&self) -> Result<(), GatewardenError> {
+        let Some(key) = &self.key else {
+            return Ok(());
+        };
+
+        let lifetime_count = self.stats.lifetime_count;
+        let mark = LifetimeMark {
+            lifetime_count,
+            mac: key.sign(lifetime_count.to_string().as_bytes()),
+        };
+        let json = serde_json::to_string(&mark).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to serialize".to_string(),
+            source: Some(e.into()),
+        })?;
+        fs::write(self.lifetime_mark_path(), json).map_err(|e| GatewardenError::MeterIO {
+            message: "Failed to write lifetime mark".to_string(),
+            source: Some(e.into()),
+        })
+    }
+}
+
+/// Apply a journaled entry, advancing the applied sequence.
+fn apply_entry(stats: &mut UsageStats, entry: &JournalEntry) {
+    apply_change(stats, entry);
+    stats.journal_seq = entry.seq;
+}
+
+/// Apply an entry's counts without touching the sequence.
+fn apply_change(stats: &mut UsageStats, entry: &JournalEntry) {
+    if entry.n > 0 {
+        stats.add_at(entry.n, entry.at);
+    }
+    if !entry.license_id.is_empty() {
+        stats.license_id.clone_from(&entry.license_id);
+    }
+    if entry.synced > 0 {
+        stats.unsynced_count = stats.unsynced_count.saturating_sub(entry.synced);
+        stats.in_flight = None;
+    }
+    if entry.sending.is_some() {
+        stats.in_flight = entry.sending;
     }
 }
 
+/// Bytes covered by a stats file's HMAC.
+fn stats_bytes(stats: &UsageStats) -> Result<Vec<u8>, GatewardenError> {
+    serde_json::to_vec(stats).map_err(|e| GatewardenError::MeterIO {
+        message: "Failed to serialize".to_string(),
+        source: Some(e.into()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -341,4 +1167,398 @@ mod tests {
             assert_eq!(meter.lifetime_count(), 3);
         }
     }
+
+    #[test]
+    fn test_export_report_per_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let day1 = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        let day2 = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 16, 9, 0, 0).unwrap());
+
+        let mut meter = UsageMeter::new(path.clone()).unwrap();
+        meter.add(3, &day1).unwrap();
+        meter.add(2, &day2).unwrap();
+        meter.increment(&day2).unwrap();
+
+        let meter = UsageMeter::new(path).unwrap();
+        let signed = meter
+            .export_report(ReportFormat::Csv, "host-a", &[7u8; 32], &day2)
+            .unwrap();
+        assert_eq!(
+            signed.body,
+            "license_id,source,period,count\n,host-a,2025-01-15,3\n,host-a,2025-01-16,3\n"
+        );
+
+        let report = meter.report("host-a", &day2);
+        assert_eq!(report.lifetime_count, 6);
+        assert_eq!(report.days.len(), 2);
+    }
+
+    #[test]
+    fn test_report_rolls_up_old_days() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        let mut meter = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        for day in 0..400 {
+            meter
+                .record(&UsageRecord {
+                    timestamp: start + chrono::Duration::days(day),
+                    feature: String::new(),
+                    quantity: 1,
+                    license_id: "lic-1".to_string(),
+                })
+                .unwrap();
+        }
+
+        let report = UsageMeter::with_key(path, test_key())
+            .unwrap()
+            .report("host-a", &MockClock::new(start));
+        assert_eq!(report.license_id, "lic-1");
+        assert_eq!(report.lifetime_count, 400);
+        assert_eq!(report.days.len(), DAILY_HISTORY_DAYS as usize);
+        assert_eq!(report.months[0].month, "2024-01");
+        assert_eq!(report.months[0].count, 31);
+        let total: u64 = report.months.iter().map(|m| m.count).sum::<u64>()
+            + report.days.iter().map(|d| d.count).sum::<u64>();
+        assert_eq!(total, 400);
+    }
+
+    #[test]
+    fn test_failed_stats_write_replayed_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        // Block the stats write (temp file path is a directory)
+        let blocker = path.with_extension("tmp");
+        fs::create_dir(&blocker).unwrap();
+        {
+            let mut meter = UsageMeter::new(path.clone()).unwrap();
+            meter.add(2, &clock).unwrap();
+            meter.add(1, &clock).unwrap();
+        }
+        assert!(!path.exists());
+
+        // Next start replays the journal exactly once
+        fs::remove_dir(&blocker).unwrap();
+        let meter = UsageMeter::new(path.clone()).unwrap();
+        assert_eq!(meter.lifetime_count(), 3);
+        assert_eq!(meter.records().unwrap().len(), 2);
+
+        let meter = UsageMeter::new(path).unwrap();
+        assert_eq!(meter.lifetime_count(), 3);
+    }
+
+    #[test]
+    fn test_journal_failure_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        // Block the journal (journal path is a directory)
+        let mut meter = UsageMeter::new(path.clone()).unwrap();
+        fs::create_dir(path.with_extension("wal")).unwrap();
+
+        assert!(matches!(
+            meter.add(1, &clock),
+            Err(GatewardenError::MeterIO { .. })
+        ));
+        assert_eq!(meter.lifetime_count(), 0);
+
+        let mut meter = meter.with_write_failure(MeterWriteFailure::Defer);
+        meter.add(1, &clock).unwrap();
+        assert_eq!(meter.lifetime_count(), 1);
+    }
+
+    #[test]
+    fn test_mark_synced_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        {
+            let mut meter = UsageMeter::new(path.clone()).unwrap();
+            meter.add(3, &clock).unwrap();
+            meter.mark_synced(2, &clock).unwrap();
+            assert_eq!(meter.unsynced_count(), 1);
+        }
+
+        let meter = UsageMeter::new(path).unwrap();
+        assert_eq!(meter.unsynced_count(), 1);
+        assert_eq!(meter.lifetime_count(), 3);
+    }
+
+    #[test]
+    fn test_records_from_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let jan = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+        let feb = Utc.with_ymd_and_hms(2025, 2, 1, 9, 0, 0).unwrap();
+        let record = |timestamp, feature: &str, quantity| UsageRecord {
+            timestamp,
+            feature: feature.to_string(),
+            quantity,
+            license_id: "lic-1".to_string(),
+        };
+
+        let mut meter = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        assert!(meter.records().unwrap().is_empty());
+        meter.record(&record(jan, "vision", 3)).unwrap();
+        meter.mark_synced(3, &MockClock::new(jan)).unwrap();
+        meter.record(&record(feb, "export", 1)).unwrap();
+
+        // Sync acknowledgements are not usage
+        let meter = UsageMeter::with_key(path, test_key()).unwrap();
+        let records = meter.records().unwrap();
+        assert_eq!(
+            records,
+            vec![record(jan, "vision", 3), record(feb, "export", 1)]
+        );
+        assert_eq!(meter.lifetime_count(), 4);
+
+        let january = meter
+            .records_between(jan - chrono::Duration::days(14), feb)
+            .unwrap();
+        assert_eq!(january, vec![records[0].clone()]);
+    }
+
+    #[test]
+    fn test_in_flight_sync_persists_until_synced() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        {
+            let mut meter = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+            meter.add(3, &clock).unwrap();
+            meter.begin_sync(3, 10, &clock).unwrap();
+        }
+
+        let mut meter = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        let sent = InFlightSync {
+            n: 3,
+            base_uses: 10,
+        };
+        assert_eq!(meter.in_flight(), Some(sent));
+        assert_eq!(meter.unsynced_count(), 3);
+
+        meter.mark_synced(3, &clock).unwrap();
+        assert_eq!(meter.in_flight(), None);
+        assert_eq!(
+            UsageMeter::with_key(path, test_key()).unwrap().in_flight(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_concurrent_meters_merge_increments() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        // Two handles loaded before either writes (as two processes would)
+        let mut first = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        let mut second = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        first.add(1, &clock).unwrap();
+        second.add(2, &clock).unwrap();
+        first.add(3, &clock).unwrap();
+        assert_eq!(first.lifetime_count(), 6);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let clock =
+                        MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+                    let mut meter = UsageMeter::with_key(path, test_key()).unwrap();
+                    for _ in 0..25 {
+                        meter.increment(&clock).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let meter = UsageMeter::with_key(path, test_key()).unwrap();
+        assert_eq!(meter.lifetime_count(), 106);
+        assert_eq!(meter.unsynced_count(), 106);
+    }
+
+    #[test]
+    fn test_deferred_changes_journaled_later() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        let mut meter = UsageMeter::new(path.clone())
+            .unwrap()
+            .with_write_failure(MeterWriteFailure::Defer);
+        let wal = path.with_extension("wal");
+        fs::create_dir(&wal).unwrap();
+        meter.add(2, &clock).unwrap();
+        assert_eq!(meter.lifetime_count(), 2);
+
+        fs::remove_dir(&wal).unwrap();
+        meter.add(1, &clock).unwrap();
+        assert_eq!(UsageMeter::new(path).unwrap().lifetime_count(), 3);
+    }
+
+    fn test_key() -> MeterKey {
+        MeterKey::derive(&"ab".repeat(32), "machine-1")
+    }
+
+    #[test]
+    fn test_keyed_meter_detects_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        UsageMeter::with_key(path.clone(), test_key())
+            .unwrap()
+            .add(5, &clock)
+            .unwrap();
+        assert_eq!(
+            UsageMeter::with_key(path.clone(), test_key())
+                .unwrap()
+                .lifetime_count(),
+            5
+        );
+
+        // Edited counters fail authentication
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"lifetime_count\": 5", "\"lifetime_count\": 9");
+        fs::write(&path, edited).unwrap();
+        assert!(matches!(
+            UsageMeter::with_key(path.clone(), test_key()),
+            Err(GatewardenError::MeterTampered)
+        ));
+
+        // So does another license or machine's key
+        assert!(matches!(
+            UsageMeter::with_key(path, MeterKey::derive(&"ab".repeat(32), "machine-2")),
+            Err(GatewardenError::MeterTampered)
+        ));
+    }
+
+    #[test]
+    fn test_keyed_meter_detects_rollback() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        let wal = path.with_extension("wal");
+        let mut meter = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        meter.add(2, &clock).unwrap();
+        let old_copy = fs::read_to_string(&path).unwrap();
+        let old_journal = fs::read_to_string(&wal).unwrap();
+        meter.add(3, &clock).unwrap();
+
+        // An older stats file alone is brought up to date from the journal
+        fs::write(&path, &old_copy).unwrap();
+        assert_eq!(
+            UsageMeter::with_key(path.clone(), test_key())
+                .unwrap()
+                .lifetime_count(),
+            5
+        );
+
+        // Restoring an authentic older stats file and journal is detected
+        fs::write(&path, &old_copy).unwrap();
+        fs::write(&wal, &old_journal).unwrap();
+        assert!(matches!(
+            UsageMeter::with_key(path.clone(), test_key()),
+            Err(GatewardenError::MeterTampered)
+        ));
+
+        // As is deleting the stats file or replacing it with an unsealed one
+        fs::write(
+            &path,
+            r#"{"daily_count":0,"monthly_count":0,"lifetime_count":0}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            UsageMeter::with_key(path.clone(), test_key()),
+            Err(GatewardenError::MeterTampered)
+        ));
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            UsageMeter::with_key(path, test_key()),
+            Err(GatewardenError::MeterTampered)
+        ));
+    }
+
+    #[test]
+    fn test_keyed_meter_rejects_forged_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let at = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+
+        // A forged sync acknowledgement would hide unsynced uses
+        let forged = serde_json::to_string(&JournalEntry {
+            seq: 1,
+            synced: 100,
+            mac: Some("00".repeat(32)),
+            ..JournalEntry::change(at)
+        })
+        .unwrap();
+        fs::write(path.with_extension("wal"), format!("{}\n", forged)).unwrap();
+
+        assert!(matches!(
+            UsageMeter::with_key(path, test_key()),
+            Err(GatewardenError::MeterTampered)
+        ));
+    }
+
+    #[test]
+    fn test_keyed_meter_upgrades_unsealed_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        UsageMeter::new(path.clone())
+            .unwrap()
+            .add(4, &clock)
+            .unwrap();
+
+        let mut meter = UsageMeter::with_key(path.clone(), test_key()).unwrap();
+        assert_eq!(meter.lifetime_count(), 4);
+        meter.add(1, &clock).unwrap();
+
+        assert!(fs::read_to_string(&path).unwrap().contains("\"mac\""));
+        assert_eq!(
+            UsageMeter::with_key(path, test_key())
+                .unwrap()
+                .lifetime_count(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_duplicate_journal_entries_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("usage.json");
+        let at = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+
+        // A retried append can leave the same sequence twice, plus a torn line
+        let entry = serde_json::to_string(&JournalEntry {
+            seq: 1,
+            n: 5,
+            ..JournalEntry::change(at)
+        })
+        .unwrap();
+        fs::write(
+            path.with_extension("wal"),
+            format!("{}\n{}\n{{\"seq\":2,", entry, entry),
+        )
+        .unwrap();
+
+        let meter = UsageMeter::new(path).unwrap();
+        assert_eq!(meter.lifetime_count(), 5);
+        assert_eq!(meter.stats().journal_seq, 1);
+    }
 }
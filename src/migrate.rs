@@ -0,0 +1,336 @@
+//! First-run migration of license keys from prior setups.
+//!
+//! Applications adopting gatewarden often already have users with keys in
+//! an environment variable, a plain `license.key` file, or a homegrown JSON
+//! cache. A [`Migrator`] checks a list of [`LicenseSource`]s in order and
+//! moves the first key found into a [`KeyStore`]. It is a no-op once the
+//! store holds a key, so it is safe to run on every startup.
+//!
+//! Migrated keys are not validated here; call `LicenseManager::validate_key`
+//! afterwards as usual.
+
+use crate::cache::keystore::KeyStore;
+use crate::GatewardenError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A prior location a license key may be imported from.
+pub trait LicenseSource {
+    /// Short human-readable name used in [`MigrationOutcome`].
+    fn name(&self) -> String;
+
+    /// Read the license key, returning `None` if this source has none.
+    fn read_key(&self) -> Result<Option<String>, GatewardenError>;
+
+    /// Remove the key from this source after a successful migration.
+    ///
+    /// Sources that cannot be cleaned up (e.g. environment variables)
+    /// keep the default no-op.
+    fn remove(&self) -> Result<(), GatewardenError> {
+        Ok(())
+    }
+}
+
+/// License key from an environment variable.
+pub struct EnvVarSource {
+    var: String,
+}
+
+impl EnvVarSource {
+    /// Read the key from the named environment variable.
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl LicenseSource for EnvVarSource {
+    fn name(&self) -> String {
+        format!("env:{}", self.var)
+    }
+
+    fn read_key(&self) -> Result<Option<String>, GatewardenError> {
+        Ok(std::env::var(&self.var).ok().and_then(non_empty))
+    }
+}
+
+/// License key stored as the sole contents of a plain text file.
+pub struct KeyFileSource {
+    path: PathBuf,
+}
+
+impl KeyFileSource {
+    /// Read the key from a plain text file such as `license.key`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl LicenseSource for KeyFileSource {
+    fn name(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+
+    fn read_key(&self) -> Result<Option<String>, GatewardenError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to read key file".to_string(),
+            source: Some(e.into()),
+        })?;
+        Ok(non_empty(contents))
+    }
+
+    fn remove(&self) -> Result<(), GatewardenError> {
+        remove_file_if_exists(&self.path)
+    }
+}
+
+/// License key stored in a field of a legacy JSON cache file.
+///
+/// Handles the common homegrown shape `{"license_key": "...", ...}`; the
+/// field name is configurable.
+pub struct LegacyJsonSource {
+    path: PathBuf,
+    field: String,
+}
+
+impl LegacyJsonSource {
+    /// Read the key from the `license_key` field of a JSON file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self::with_field(path, "license_key")
+    }
+
+    /// Read the key from a custom top-level field of a JSON file.
+    pub fn with_field(path: impl Into<PathBuf>, field: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            field: field.into(),
+        }
+    }
+}
+
+impl LicenseSource for LegacyJsonSource {
+    fn name(&self) -> String {
+        format!("legacy-json:{}", self.path.display())
+    }
+
+    fn read_key(&self) -> Result<Option<String>, GatewardenError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to read legacy cache".to_string(),
+            source: Some(e.into()),
+        })?;
+        let value: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|e| GatewardenError::ProtocolError {
+                message: "Invalid legacy cache JSON".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        Ok(value
+            .get(&self.field)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .and_then(non_empty))
+    }
+
+    fn remove(&self) -> Result<(), GatewardenError> {
+        remove_file_if_exists(&self.path)
+    }
+}
+
+/// Result of a migration run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationOutcome {
+    /// The key store already held a key; nothing was imported.
+    AlreadyPresent,
+    /// A key was imported from the named source.
+    Migrated {
+        /// Name of the source the key came from.
+        source: String,
+    },
+    /// No source held a key.
+    NotFound,
+}
+
+/// Imports a license key from prior setups into a [`KeyStore`].
+pub struct Migrator<'a> {
+    store: &'a dyn KeyStore,
+    sources: Vec<Box<dyn LicenseSource>>,
+    remove_migrated: bool,
+}
+
+impl<'a> Migrator<'a> {
+    /// Create a migrator targeting `store`, with no sources.
+    pub fn new(store: &'a dyn KeyStore) -> Self {
+        Self {
+            store,
+            sources: Vec::new(),
+            remove_migrated: false,
+        }
+    }
+
+    /// Add a source. Sources are checked in the order they are added.
+    pub fn with_source(mut self, source: Box<dyn LicenseSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Remove the key from its source after it has been stored.
+    pub fn remove_migrated(mut self, remove: bool) -> Self {
+        self.remove_migrated = remove;
+        self
+    }
+
+    /// Run the migration.
+    ///
+    /// Unreadable sources are skipped so one broken legacy file does not
+    /// block the others; store errors are returned.
+    pub fn run(&self) -> Result<MigrationOutcome, GatewardenError> {
+        if self.store.load_license_key()?.is_some() {
+            return Ok(MigrationOutcome::AlreadyPresent);
+        }
+
+        for source in &self.sources {
+            let Ok(Some(key)) = source.read_key() else {
+                continue;
+            };
+
+            self.store.save_license_key(&key)?;
+            if self.remove_migrated {
+                source.remove()?;
+            }
+
+            return Ok(MigrationOutcome::Migrated {
+                source: source.name(),
+            });
+        }
+
+        Ok(MigrationOutcome::NotFound)
+    }
+}
+
+/// Trim a key and discard it if empty.
+fn non_empty(key: String) -> Option<String> {
+    let key = key.trim();
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<(), GatewardenError> {
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| GatewardenError::CacheIO {
+            message: "Failed to remove source".to_string(),
+            source: Some(e.into()),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::keystore::FileKeyStore;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrate_from_key_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileKeyStore::with_path(temp_dir.path().join("store.key")).unwrap();
+        let legacy = temp_dir.path().join("license.key");
+        fs::write(&legacy, "  LEGACY-KEY\n").unwrap();
+
+        let outcome = Migrator::new(&store)
+            .with_source(Box::new(KeyFileSource::new(&legacy)))
+            .run()
+            .unwrap();
+
+        assert!(matches!(outcome, MigrationOutcome::Migrated { .. }));
+        assert_eq!(
+            store.load_license_key().unwrap().as_deref(),
+            Some("LEGACY-KEY")
+        );
+        assert!(legacy.exists());
+    }
+
+    #[test]
+    fn test_migrate_legacy_json_and_remove() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileKeyStore::with_path(temp_dir.path().join("store.key")).unwrap();
+        let legacy = temp_dir.path().join("old-cache.json");
+        fs::write(&legacy, r#"{"license_key": "JSON-KEY", "valid": true}"#).unwrap();
+
+        let outcome = Migrator::new(&store)
+            .with_source(Box::new(KeyFileSource::new(
+                temp_dir.path().join("missing"),
+            )))
+            .with_source(Box::new(LegacyJsonSource::new(&legacy)))
+            .remove_migrated(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            MigrationOutcome::Migrated {
+                source: format!("legacy-json:{}", legacy.display())
+            }
+        );
+        assert_eq!(
+            store.load_license_key().unwrap().as_deref(),
+            Some("JSON-KEY")
+        );
+        assert!(!legacy.exists());
+    }
+
+    #[test]
+    fn test_migrate_from_env_var() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileKeyStore::with_path(temp_dir.path().join("store.key")).unwrap();
+        std::env::set_var("GATEWARDEN_TEST_MIGRATE_KEY", "ENV-KEY");
+
+        let outcome = Migrator::new(&store)
+            .with_source(Box::new(EnvVarSource::new("GATEWARDEN_TEST_MIGRATE_KEY")))
+            .run()
+            .unwrap();
+
+        assert!(matches!(outcome, MigrationOutcome::Migrated { .. }));
+        assert_eq!(
+            store.load_license_key().unwrap().as_deref(),
+            Some("ENV-KEY")
+        );
+    }
+
+    #[test]
+    fn test_migrate_skips_when_already_present() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileKeyStore::with_path(temp_dir.path().join("store.key")).unwrap();
+        store.save_license_key("CURRENT").unwrap();
+        let legacy = temp_dir.path().join("license.key");
+        fs::write(&legacy, "OLD").unwrap();
+
+        let outcome = Migrator::new(&store)
+            .with_source(Box::new(KeyFileSource::new(&legacy)))
+            .run()
+            .unwrap();
+
+        assert_eq!(outcome, MigrationOutcome::AlreadyPresent);
+        assert_eq!(
+            store.load_license_key().unwrap().as_deref(),
+            Some("CURRENT")
+        );
+    }
+
+    #[test]
+    fn test_migrate_not_found() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileKeyStore::with_path(temp_dir.path().join("store.key")).unwrap();
+
+        let outcome = Migrator::new(&store)
+            .with_source(Box::new(KeyFileSource::new(temp_dir.path().join("none"))))
+            .run()
+            .unwrap();
+
+        assert_eq!(outcome, MigrationOutcome::NotFound);
+    }
+}
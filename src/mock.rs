@@ -0,0 +1,2112 @@
+//! In-process mock of the Keygen API (`test-seams` only).
+//!
+//! A [`MockKeygen`] holds a set of licenses and answers `KeygenClient`
+//! requests in place of the network (see `LicenseManager::with_mock_keygen`).
+//! Responses are signed with a caller-supplied Ed25519 test key, so the
+//! full verification pipeline runs exactly as it does against Keygen:
+//! configure the manager with [`MockKeygen::public_key_hex`].
+//!
+//! Tests share one fixture: [`TEST_SIGNING_SEED`] and its
+//! [`TEST_PUBLIC_KEY_HEX`], a [`test_config`] trusting it, and
+//! [`signed_test_record`] / [`test_signature_header`] for signing cache
+//! records and responses by hand.
+//!
+//! Supported actions:
+//!
+//! - `validate-key`: `VALID`, `NOT_FOUND`, `SUSPENDED`, `EXPIRED`,
+//!   `ENTITLEMENTS_MISSING`, `PRODUCT_SCOPE_MISMATCH` or
+//!   `POLICY_SCOPE_MISMATCH` (against [`MockLicense::for_product`] and
+//!   [`MockLicense::with_policy`]), or `USER_SCOPE_MISMATCH` (for a user not
+//!   assigned with [`MockLicense::with_users`]), echoing the requested
+//!   entitlement and user scope
+//! - `validate` by license ID: the same, for a bearer token set with
+//!   [`MockLicense::with_token`] (HTTP 401 otherwise)
+//! - `increment-usage`: HTTP 422 when `maxUses` would be exceeded
+//! - `POST /tokens`: a license token for the `License` key, accepted as
+//!   a bearer token by the license actions
+//! - `GET` a license's entitlements (by ID or key), paginated by
+//!   `page[number]` and `page[size]`
+//! - `POST /licenses`: a trial license under a policy registered with
+//!   [`MockKeygen::add_trial_policy`], for any bearer token
+//! - `POST /machines` and `DELETE` a machine: machine activation for the
+//!   `License` key, HTTP 422 beyond [`MockLicense::with_max_machines`];
+//!   validation scoped to a fingerprint then answers `NO_MACHINES`,
+//!   `TOO_MANY_MACHINES`, or `NO_MACHINE` like Keygen, and
+//!   `COMPONENTS_SCOPE_MISMATCH` when a scoped component was not
+//!   registered with the machine
+//! - `ping` a machine's heartbeat: HTTP 404 once culled with
+//!   [`MockKeygen::cull_machines`]; after [`MockKeygen::kill_heartbeats`],
+//!   `RESURRECTED` for [`MockLicense::resurrects_machines`] licenses and
+//!   HTTP 422 otherwise
+//! - `GET` the group and owner of the caller's license, registered with
+//!   [`MockKeygen::add_group`] and [`MockKeygen::add_user`]
+//! - `GET` a release's `upgrade` and `artifacts`, and an artifact download
+//!   (HTTP 303 with a `links.redirect` URL), for releases published with
+//!   [`MockKeygen::add_release`] whose entitlements the license has
+//!   (HTTP 204 when there is no newer release)
+//!
+//! Anything else gets a signed HTTP 404.
+
+use crate::cache::format::CacheRecord;
+use crate::client::proxy::ProxyConfig;
+use crate::client::request::KEYGEN_HOST;
+use crate::client::response::KeygenResponse;
+use crate::clock::{Clock, MockClock, SystemClock};
+use crate::config::GatewardenConfig;
+use crate::crypto::digest::format_digest_header;
+use crate::crypto::signing::build_signing_string;
+use crate::policy::fallback::FallbackPolicy;
+use crate::policy::version;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A license served by [`MockKeygen`].
+#[derive(Debug, Clone)]
+pub struct MockLicense {
+    id: String,
+    entitlements: Vec<String>,
+    expiry: Option<DateTime<Utc>>,
+    max_uses: Option<u64>,
+    uses: u64,
+    suspended: bool,
+    token: Option<String>,
+    policy: Option<String>,
+    product: Option<String>,
+    trial: bool,
+    max_version: Option<String>,
+    max_machines: Option<usize>,
+    machines: Vec<MockMachine>,
+    resurrects_machines: bool,
+    group: Option<String>,
+    owner: Option<String>,
+    users: Vec<String>,
+}
+
+/// A machine activated for a [`MockLicense`].
+#[derive(Debug, Clone)]
+struct MockMachine {
+    id: String,
+    fingerprint: String,
+    components: Vec<String>,
+    dead: bool,
+}
+
+impl MockLicense {
+    /// A valid license with no entitlements, expiry, or usage cap.
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            entitlements: Vec::new(),
+            expiry: None,
+            max_uses: None,
+            uses: 0,
+            suspended: false,
+            token: None,
+            policy: None,
+            product: None,
+            trial: false,
+            max_version: None,
+            max_machines: None,
+            machines: Vec::new(),
+            resurrects_machines: false,
+            group: None,
+            owner: None,
+            users: Vec::new(),
+        }
+    }
+
+    /// Entitlement codes attached to the license.
+    pub fn with_entitlements(mut self, codes: &[&str]) -> Self {
+        self.entitlements = codes.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Expire the license at `expiry`.
+    pub fn expires_at(mut self, expiry: DateTime<Utc>) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Cap uses at `max_uses`, starting from `uses`.
+    pub fn with_usage(mut self, uses: u64, max_uses: u64) -> Self {
+        self.uses = uses;
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    /// Accept `token` as a bearer token for validating by license ID.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Issue the license under policy `policy_id`.
+    pub fn with_policy(mut self, policy_id: &str) -> Self {
+        self.policy = Some(policy_id.to_string());
+        self
+    }
+
+    /// Issue the license for product `product_id`.
+    pub fn for_product(mut self, product_id: &str) -> Self {
+        self.product = Some(product_id.to_string());
+        self
+    }
+
+    /// Mark the license a trial in its metadata.
+    pub fn trial(mut self) -> Self {
+        self.trial = true;
+        self
+    }
+
+    /// Cover application versions up to `max_version` (metadata `maxVersion`).
+    pub fn with_max_version(mut self, max_version: &str) -> Self {
+        self.max_version = Some(max_version.to_string());
+        self
+    }
+
+    /// Bind the license to machines, at most `max_machines` at once
+    /// (a floating policy).
+    pub fn with_max_machines(mut self, max_machines: usize) -> Self {
+        self.max_machines = Some(max_machines);
+        self
+    }
+
+    /// Resurrect machines whose heartbeat died when they ping again.
+    pub fn resurrects_machines(mut self) -> Self {
+        self.resurrects_machines = true;
+        self
+    }
+
+    /// Put the license in group `group_id`.
+    pub fn in_group(mut self, group_id: &str) -> Self {
+        self.group = Some(group_id.to_string());
+        self
+    }
+
+    /// Make user `user_id` the license's owner.
+    pub fn owned_by(mut self, user_id: &str) -> Self {
+        self.owner = Some(user_id.to_string());
+        self
+    }
+
+    /// Assign the license to users, by email or ID; the owner is always
+    /// assigned.
+    pub fn with_users(mut self, users: &[&str]) -> Self {
+        self.users = users.iter().map(|u| u.to_string()).collect();
+        self
+    }
+
+    /// Whether `user` (an email or ID) is assigned to the license.
+    fn has_user(&self, user: &str) -> bool {
+        self.owner.as_deref() == Some(user) || self.users.iter().any(|u| u == user)
+    }
+
+    /// Mark the license suspended.
+    pub fn suspended(mut self) -> Self {
+        self.suspended = true;
+        self
+    }
+
+    /// Machine validation code and detail for a machine-bound license,
+    /// scoped to `fingerprint` and `components` if given.
+    fn machine_code(
+        &self,
+        fingerprint: Option<&str>,
+        components: &[&str],
+    ) -> Option<(&'static str, &'static str)> {
+        let max = self.max_machines?;
+        if self.machines.is_empty() {
+            return Some(("NO_MACHINES", "has no associated machines"));
+        }
+        if self.machines.len() > max {
+            return Some(("TOO_MANY_MACHINES", "has too many associated machines"));
+        }
+        let fingerprint = fingerprint?;
+        let Some(machine) = self.machines.iter().find(|m| m.fingerprint == fingerprint) else {
+            return Some(("NO_MACHINE", "fingerprint is not activated"));
+        };
+        if !components
+            .iter()
+            .all(|c| machine.components.iter().any(|m| m == c))
+        {
+            return Some((
+                "COMPONENTS_SCOPE_MISMATCH",
+                "one or more components is not activated",
+            ));
+        }
+        None
+    }
+
+    fn to_json(&self) -> Value {
+        let mut license = json!({
+            "id": self.id,
+            "type": "licenses",
+            "attributes": {
+                "expiry": self.expiry.map(|e| e.to_rfc3339()),
+                "maxUses": self.max_uses,
+                "uses": self.uses,
+            }
+        });
+        let mut metadata = serde_json::Map::new();
+        if self.trial {
+            metadata.insert("trial".to_string(), json!(true));
+        }
+        if let Some(max_version) = &self.max_version {
+            metadata.insert("maxVersion".to_string(), json!(max_version));
+        }
+        if !metadata.is_empty() {
+            license["attributes"]["metadata"] = Value::Object(metadata);
+        }
+        let mut relationships = serde_json::Map::new();
+        if let Some(policy) = &self.policy {
+            relationships.insert(
+                "policy".to_string(),
+                json!({ "data": { "type": "policies", "id": policy } }),
+            );
+        }
+        if let Some(product) = &self.product {
+            relationships.insert(
+                "product".to_string(),
+                json!({ "data": { "type": "products", "id": product } }),
+            );
+        }
+        if let Some(group) = &self.group {
+            relationships.insert(
+                "group".to_string(),
+                json!({ "data": { "type": "groups", "id": group } }),
+            );
+        }
+        if let Some(owner) = &self.owner {
+            relationships.insert(
+                "owner".to_string(),
+                json!({ "data": { "type": "users", "id": owner } }),
+            );
+        }
+        if !relationships.is_empty() {
+            license["relationships"] = Value::Object(relationships);
+        }
+        license
+    }
+}
+
+/// A release served by [`MockKeygen`]'s distribution API.
+#[derive(Debug, Clone)]
+pub struct MockRelease {
+    id: String,
+    version: String,
+    channel: String,
+    entitlements: Vec<String>,
+    artifacts: Vec<(String, String, String)>,
+}
+
+impl MockRelease {
+    /// A stable release open to every license.
+    pub fn new(id: &str, version: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            version: version.to_string(),
+            channel: "stable".to_string(),
+            entitlements: Vec::new(),
+            artifacts: Vec::new(),
+        }
+    }
+
+    /// Publish the release on `channel` (`stable`, `rc`, `beta`, `alpha`, `dev`).
+    pub fn on_channel(mut self, channel: &str) -> Self {
+        self.channel = channel.to_string();
+        self
+    }
+
+    /// Entitlement codes a license needs to see the release.
+    pub fn with_entitlements(mut self, codes: &[&str]) -> Self {
+        self.entitlements = codes.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    /// Attach a file for `platform` and `arch`.
+    pub fn with_artifact(mut self, filename: &str, platform: &str, arch: &str) -> Self {
+        self.artifacts
+            .push((filename.to_string(), platform.to_string(), arch.to_string()));
+        self
+    }
+
+    /// Whether `license` satisfies the release's entitlement constraints.
+    fn visible_to(&self, license: &MockLicense) -> bool {
+        self.entitlements
+            .iter()
+            .all(|code| license.entitlements.contains(code))
+    }
+
+    /// Artifact `index` of the release.
+    fn artifact_json(&self, index: usize) -> Option<Value> {
+        let (filename, platform, arch) = self.artifacts.get(index)?;
+        Some(json!({
+            "id": format!("art-{}-{}", self.id, index + 1),
+            "type": "artifacts",
+            "attributes": {
+                "filename": filename,
+                "filetype": filename.rsplit_once('.').map(|(_, ext)| ext),
+                "filesize": 1024,
+                "platform": platform,
+                "arch": arch,
+                "checksum": null,
+                "signature": null,
+            }
+        }))
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "type": "releases",
+            "attributes": {
+                "name": null,
+                "description": null,
+                "version": self.version,
+                "channel": self.channel,
+                "metadata": {},
+                "created": "2025-01-01T00:00:00Z",
+            }
+        })
+    }
+}
+
+/// Signed, in-process stand-in for the Keygen API.
+pub struct MockKeygen {
+    signing_key: SigningKey,
+    clock: Arc<dyn Clock>,
+    licenses: Mutex<HashMap<String, MockLicense>>,
+    trial_policies: Mutex<HashMap<String, chrono::Duration>>,
+    releases: Mutex<Vec<MockRelease>>,
+    groups: Mutex<HashMap<String, Value>>,
+    users: Mutex<HashMap<String, Value>>,
+    requests: AtomicU32,
+    machine_ids: AtomicU32,
+}
+
+impl MockKeygen {
+    /// Create a mock that signs with `signing_seed` and dates responses
+    /// with the system clock.
+    pub fn new(signing_seed: &[u8; 32]) -> Arc<Self> {
+        Self::with_clock(signing_seed, Arc::new(SystemClock))
+    }
+
+    /// Create a mock that dates responses with `clock`.
+    ///
+    /// Share the manager's `MockClock` so responses are always fresh.
+    pub fn with_clock(signing_seed: &[u8; 32], clock: Arc<dyn Clock>) -> Arc<Self> {
+        Arc::new(Self {
+            signing_key: SigningKey::from_bytes(signing_seed),
+            clock,
+            licenses: Mutex::new(HashMap::new()),
+            trial_policies: Mutex::new(HashMap::new()),
+            releases: Mutex::new(Vec::new()),
+            groups: Mutex::new(HashMap::new()),
+            users: Mutex::new(HashMap::new()),
+            requests: AtomicU32::new(0),
+            machine_ids: AtomicU32::new(0),
+        })
+    }
+
+    /// Hex-encoded verifying key to use as `public_key_hex`.
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().to_bytes())
+    }
+
+    /// Serve `license` for `license_key`, replacing any previous one.
+    pub fn add_license(&self, license_key: &str, license: MockLicense) {
+        if let Ok(mut licenses) = self.licenses.lock() {
+            licenses.insert(license_key.to_string(), license);
+        }
+    }
+
+    /// Let any user create trial licenses under `policy_id`, each
+    /// expiring `duration` after creation.
+    pub fn add_trial_policy(&self, policy_id: &str, duration: chrono::Duration) {
+        if let Ok(mut policies) = self.trial_policies.lock() {
+            policies.insert(policy_id.to_string(), duration);
+        }
+    }
+
+    /// Publish `release` on the distribution API.
+    pub fn add_release(&self, release: MockRelease) {
+        if let Ok(mut releases) = self.releases.lock() {
+            releases.push(release);
+        }
+    }
+
+    /// Serve group `group_id` to licenses in it.
+    pub fn add_group(&self, group_id: &str, name: &str, max_machines: Option<u64>) {
+        if let Ok(mut groups) = self.groups.lock() {
+            groups.insert(
+                group_id.to_string(),
+                json!({ "name": name, "maxMachines": max_machines, "maxUsers": null, "maxLicenses": null }),
+            );
+        }
+    }
+
+    /// Serve user `user_id` to licenses they own.
+    pub fn add_user(&self, user_id: &str, full_name: &str, email: &str) {
+        if let Ok(mut users) = self.users.lock() {
+            users.insert(
+                user_id.to_string(),
+                json!({ "fullName": full_name, "email": email, "role": "user" }),
+            );
+        }
+    }
+
+    /// Activate a machine with `fingerprint` for `license_key` directly,
+    /// even past the license's limit (as Keygen's overage allowances do).
+    pub fn add_machine(&self, license_key: &str, fingerprint: &str) {
+        let id = self.next_machine_id();
+        if let Some(license) = self
+            .licenses
+            .lock()
+            .ok()
+            .as_mut()
+            .and_then(|licenses| licenses.get_mut(license_key))
+        {
+            license.machines.push(MockMachine {
+                id,
+                fingerprint: fingerprint.to_string(),
+                components: Vec::new(),
+                dead: false,
+            });
+        }
+    }
+
+    /// Remove every machine of `license_key`, as Keygen culls machines
+    /// whose heartbeat died.
+    pub fn cull_machines(&self, license_key: &str) {
+        if let Some(license) = self
+            .licenses
+            .lock()
+            .ok()
+            .as_mut()
+            .and_then(|licenses| licenses.get_mut(license_key))
+        {
+            license.machines.clear();
+        }
+    }
+
+    /// Mark every machine of `license_key` as having a dead heartbeat.
+    pub fn kill_heartbeats(&self, license_key: &str) {
+        if let Some(license) = self
+            .licenses
+            .lock()
+            .ok()
+            .as_mut()
+            .and_then(|licenses| licenses.get_mut(license_key))
+        {
+            for machine in &mut license.machines {
+                machine.dead = true;
+            }
+        }
+    }
+
+    /// Number of machines activated for `license_key`.
+    pub fn machines(&self, license_key: &str) -> Option<usize> {
+        let licenses = self.licenses.lock().ok()?;
+        licenses.get(license_key).map(|l| l.machines.len())
+    }
+
+    /// Component fingerprints registered with machine `machine_id`.
+    pub fn machine_components(&self, license_key: &str, machine_id: &str) -> Option<Vec<String>> {
+        let licenses = self.licenses.lock().ok()?;
+        let machine = licenses
+            .get(license_key)?
+            .machines
+            .iter()
+            .find(|m| m.id == machine_id)?;
+        Some(machine.components.clone())
+    }
+
+    /// Current use count recorded for `license_key`.
+    pub fn uses(&self, license_key: &str) -> Option<u64> {
+        let licenses = self.licenses.lock().ok()?;
+        licenses.get(license_key).map(|l| l.uses)
+    }
+
+    /// Number of requests answered so far.
+    pub fn request_count(&self) -> u32 {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Client hook: answer a request in place of Keygen.
+    pub(crate) fn respond(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        body: &[u8],
+        authorization: Option<&str>,
+    ) -> KeygenResponse {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        let request: Value = serde_json::from_slice(body).unwrap_or(Value::Null);
+        let license_auth = authorization.and_then(|a| a.strip_prefix("License "));
+        let bearer = authorization.and_then(|a| a.strip_prefix("Bearer "));
+
+        let (route, query) = path.split_once('?').unwrap_or((path, ""));
+        let (status, document) = match self.licenses.lock() {
+            Ok(mut licenses) => {
+                // Key of the license the caller authenticated as
+                let caller = licenses
+                    .iter()
+                    .find(|(key, l)| {
+                        license_auth == Some(key.as_str())
+                            || bearer.is_some() && bearer == l.token.as_deref()
+                    })
+                    .map(|(key, _)| key.clone());
+                let caller = caller.as_deref();
+                let get = method.eq_ignore_ascii_case("GET");
+                let delete = method.eq_ignore_ascii_case("DELETE");
+                if let Some(id) = machine_id(route).filter(|_| delete) {
+                    deactivate_machine(&mut licenses, id, caller)
+                } else if let Some(id) = ping_machine_id(route).filter(|_| !get) {
+                    ping_machine(&mut licenses, id, caller)
+                } else if !get && route.ends_with("/licenses/actions/validate-key") {
+                    let key = request["meta"]["key"].as_str().unwrap_or_default();
+                    self.validate(licenses.get(key), &request)
+                } else if let Some(id) = validate_license_id(route).filter(|_| !get) {
+                    match licenses.values().find(|l| l.id == id) {
+                        Some(license) if bearer.is_some() && bearer == license.token.as_deref() => {
+                            self.validate(Some(license), &request)
+                        }
+                        _ => (401, error_document("TOKEN_INVALID", "Unauthorized")),
+                    }
+                } else if !get && route.ends_with("/licenses") {
+                    self.create_trial(&mut licenses, bearer, &request)
+                } else if !get && route.ends_with("/machines") {
+                    self.activate_machine(&mut licenses, caller, &request)
+                } else if !get && route.ends_with("/tokens") {
+                    issue_token(&mut licenses, license_auth)
+                } else if let Some(id) = increment_usage_id(route).filter(|_| !get) {
+                    increment_usage(&mut licenses, id, caller, &request)
+                } else if let Some(license) = entitlements_license(route).filter(|_| get) {
+                    list_entitlements(&licenses, license, query, caller)
+                } else if let Some(id) = resource_id(route, "/groups/").filter(|_| get) {
+                    let license = caller.and_then(|key| licenses.get(key));
+                    let group = license.and_then(|l| l.group.as_deref());
+                    related(&self.groups, "groups", license, group, id)
+                } else if let Some(id) = resource_id(route, "/users/").filter(|_| get) {
+                    let license = caller.and_then(|key| licenses.get(key));
+                    let owner = license.and_then(|l| l.owner.as_deref());
+                    related(&self.users, "users", license, owner, id)
+                } else if let Some(current) = upgrade_release(route).filter(|_| get) {
+                    let license = caller.and_then(|key| licenses.get(key));
+                    self.upgrade(license, current, query)
+                } else if let Some(release) = artifacts_release(route).filter(|_| get) {
+                    let license = caller.and_then(|key| licenses.get(key));
+                    self.list_artifacts(license, release)
+                } else if let Some(artifact) = download_artifact(route).filter(|_| get) {
+                    let license = caller.and_then(|key| licenses.get(key));
+                    self.download(license, artifact)
+                } else {
+                    (404, error_document("NOT_FOUND", "Not found"))
+                }
+            }
+            Err(_) => (500, error_document("INTERNAL", "Mock state poisoned")),
+        };
+
+        self.signed(status, method, host, path, document)
+    }
+
+    fn validate(&self, license: Option<&MockLicense>, request: &Value) -> (u16, Value) {
+        let fingerprint = request["meta"]["scope"]["fingerprint"].as_str();
+        let components: Vec<&str> = request["meta"]["scope"]["components"]
+            .as_array()
+            .map(|fingerprints| fingerprints.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let scope: Vec<&str> = request["meta"]["scope"]["entitlements"]
+            .as_array()
+            .map(|codes| codes.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let user = request["meta"]["scope"]["user"].as_str();
+        let product = request["meta"]["scope"]["product"].as_str();
+        let policy = request["meta"]["scope"]["policy"].as_str();
+
+        let Some(license) = license else {
+            return (
+                200,
+                json!({
+                    "meta": { "valid": false, "code": "NOT_FOUND", "detail": "does not exist" },
+                    "data": null
+                }),
+            );
+        };
+
+        let (valid, code, detail) = if license.suspended {
+            (false, "SUSPENDED", "is suspended")
+        } else if license.expiry.is_some_and(|e| e <= self.clock.now_utc()) {
+            (false, "EXPIRED", "is expired")
+        } else if let Some((code, detail)) = license.machine_code(fingerprint, &components) {
+            (false, code, detail)
+        } else if product.is_some() && product != license.product.as_deref() {
+            (
+                false,
+                "PRODUCT_SCOPE_MISMATCH",
+                "product scope does not match",
+            )
+        } else if policy.is_some() && policy != license.policy.as_deref() {
+            (
+                false,
+                "POLICY_SCOPE_MISMATCH",
+                "policy scope does not match",
+            )
+        } else if user.is_some_and(|u| !license.has_user(u)) {
+            (false, "USER_SCOPE_MISMATCH", "user scope does not match")
+        } else if !scope
+            .iter()
+            .all(|c| license.entitlements.iter().any(|e| e == c))
+        {
+            (
+                false,
+                "ENTITLEMENTS_MISSING",
+                "is missing one or more required entitlements",
+            )
+        } else {
+            (true, "VALID", "is valid")
+        };
+
+        let mut meta = json!({ "valid": valid, "code": code, "detail": detail });
+        if !scope.is_empty() {
+            meta["scope"]["entitlements"] = json!(scope);
+        }
+        if let Some(user) = user {
+            meta["scope"]["user"] = user.into();
+        }
+        (200, json!({ "meta": meta, "data": license.to_json() }))
+    }
+
+    /// Create a trial license for a bearer under a trial policy.
+    fn create_trial(
+        &self,
+        licenses: &mut HashMap<String, MockLicense>,
+        bearer: Option<&str>,
+        request: &Value,
+    ) -> (u16, Value) {
+        if bearer.is_none() {
+            return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+        }
+        let policy = request["data"]["relationships"]["policy"]["data"]["id"]
+            .as_str()
+            .unwrap_or_default();
+        let duration = self
+            .trial_policies
+            .lock()
+            .ok()
+            .and_then(|policies| policies.get(policy).copied());
+        let Some(duration) = duration else {
+            return (403, error_document("FORBIDDEN", "Policy is protected"));
+        };
+
+        let n = licenses.len() + 1;
+        let key = format!("TRIAL-{}", n);
+        let mut license = MockLicense::new(&format!("lic-trial-{}", n))
+            .with_policy(policy)
+            .expires_at(self.clock.now_utc() + duration);
+        license.trial = request["data"]["attributes"]["metadata"]["trial"] == json!(true);
+
+        let mut document = license.to_json();
+        document["attributes"]["key"] = json!(key);
+        licenses.insert(key, license);
+        (201, json!({ "data": document }))
+    }
+
+    /// Activate a machine for the caller's license, up to its limit.
+    fn activate_machine(
+        &self,
+        licenses: &mut HashMap<String, MockLicense>,
+        license_auth: Option<&str>,
+        request: &Value,
+    ) -> (u16, Value) {
+        let license_id = &request["data"]["relationships"]["license"]["data"]["id"];
+        let license = license_auth
+            .and_then(|key| licenses.get_mut(key))
+            .filter(|license| *license_id == license.id);
+        let Some(license) = license else {
+            return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+        };
+
+        let fingerprint = request["data"]["attributes"]["fingerprint"]
+            .as_str()
+            .unwrap_or_default();
+        if license
+            .machines
+            .iter()
+            .any(|m| m.fingerprint == fingerprint)
+        {
+            return (
+                422,
+                error_document("FINGERPRINT_TAKEN", "has already been taken"),
+            );
+        }
+        if license
+            .max_machines
+            .is_some_and(|max| license.machines.len() >= max)
+        {
+            return (
+                422,
+                error_document(
+                    "MACHINE_LIMIT_EXCEEDED",
+                    "machine count has exceeded maximum allowed",
+                ),
+            );
+        }
+
+        let components = request["data"]["relationships"]["components"]["data"]
+            .as_array()
+            .map(|components| {
+                components
+                    .iter()
+                    .filter_map(|c| c["attributes"]["fingerprint"].as_str())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let id = self.next_machine_id();
+        license.machines.push(MockMachine {
+            id: id.clone(),
+            fingerprint: fingerprint.to_string(),
+            components,
+            dead: false,
+        });
+        (
+            201,
+            json!({
+                "data": {
+                    "id": id,
+                    "type": "machines",
+                    "attributes": { "fingerprint": fingerprint },
+                    "relationships": {
+                        "license": { "data": { "type": "licenses", "id": license.id } }
+                    }
+                }
+            }),
+        )
+    }
+
+    fn next_machine_id(&self) -> String {
+        format!(
+            "mach-{}",
+            self.machine_ids.fetch_add(1, Ordering::SeqCst) + 1
+        )
+    }
+
+    /// Newest visible release after `current` on the requested channel.
+    fn upgrade(&self, license: Option<&MockLicense>, current: &str, query: &str) -> (u16, Value) {
+        let Some(license) = license else {
+            return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+        };
+        let channel = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == "channel")
+            .map_or("stable", |(_, v)| v);
+        let Ok(releases) = self.releases.lock() else {
+            return (500, error_document("INTERNAL", "Mock state poisoned"));
+        };
+
+        let next = releases
+            .iter()
+            .filter(|r| release_channel_rank(&r.channel) <= release_channel_rank(channel))
+            .filter(|r| r.visible_to(license))
+            .filter(|r| version_order(&r.version, current).is_gt())
+            .max_by(|a, b| version_order(&a.version, &b.version));
+        match next {
+            Some(release) => (200, json!({ "data": release.to_json() })),
+            None => (204, Value::Null),
+        }
+    }
+
+    /// Artifacts of a release (by ID or version) visible to the caller.
+    fn list_artifacts(&self, license: Option<&MockLicense>, release: &str) -> (u16, Value) {
+        let Some(license) = license else {
+            return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+        };
+        let Ok(releases) = self.releases.lock() else {
+            return (500, error_document("INTERNAL", "Mock state poisoned"));
+        };
+        let Some(release) = releases
+            .iter()
+            .find(|r| (r.id == release || r.version == release) && r.visible_to(license))
+        else {
+            return (404, error_document("NOT_FOUND", "Not found"));
+        };
+
+        let data: Vec<Value> = (0..release.artifacts.len())
+            .filter_map(|i| release.artifact_json(i))
+            .collect();
+        (200, json!({ "data": data }))
+    }
+
+    /// Redirect to a download URL for an artifact visible to the caller.
+    fn download(&self, license: Option<&MockLicense>, artifact_id: &str) -> (u16, Value) {
+        let Some(license) = license else {
+            return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+        };
+        let Ok(releases) = self.releases.lock() else {
+            return (500, error_document("INTERNAL", "Mock state poisoned"));
+        };
+        let found = releases
+            .iter()
+            .filter(|r| r.visible_to(license))
+            .flat_map(|r| (0..r.artifacts.len()).filter_map(move |i| r.artifact_json(i)))
+            .find(|artifact| artifact["id"] == artifact_id);
+        let Some(artifact) = found else {
+            return (404, error_document("NOT_FOUND", "Not found"));
+        };
+
+        let redirect = format!(
+            "https://downloads.example.com/{}?expires={}",
+            artifact["attributes"]["filename"]
+                .as_str()
+                .unwrap_or_default(),
+            (self.clock.now_utc() + chrono::Duration::minutes(1)).timestamp()
+        );
+        (
+            303,
+            json!({ "data": artifact, "links": { "redirect": redirect } }),
+        )
+    }
+
+    /// Sign a response the way Keygen does.
+    fn signed(
+        &self,
+        status: u16,
+        method: &str,
+        host: &str,
+        path: &str,
+        document: Value,
+    ) -> KeygenResponse {
+        let method = method.to_lowercase();
+        let body = if status == 204 {
+            Vec::new()
+        } else {
+            document.to_string().into_bytes()
+        };
+        let date = self
+            .clock
+            .now_utc()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string();
+        let digest = format_digest_header(&body);
+        let signing_string = build_signing_string(&method, path, host, &date, Some(&digest));
+        let signature = self.signing_key.sign(signing_string.as_bytes());
+        // Keygen's keyid is the account ID, the third path segment
+        let account_id = path.split('/').nth(3).unwrap_or_default();
+
+        KeygenResponse {
+            status,
+            date: Some(date),
+            signature: Some(format!(
+                r#"keyid="{}", algorithm="ed25519", signature="{}", headers="(request-target) host date digest""#,
+                account_id,
+                STANDARD.encode(signature.to_bytes())
+            )),
+            digest: Some(digest),
+            body,
+            method,
+            request_path: path.to_string(),
+            host: host.to_string(),
+            request_id: Some(format!("mock-{}", self.requests.load(Ordering::SeqCst))),
+            rate_limit: None,
+        }
+    }
+}
+
+/// License ID from a validate path.
+fn validate_license_id(path: &str) -> Option<&str> {
+    let rest = path.strip_suffix("/actions/validate")?;
+    rest.rsplit_once("/licenses/").map(|(_, id)| id)
+}
+
+/// License ID from an increment-usage path.
+fn increment_usage_id(path: &str) -> Option<&str> {
+    let rest = path.strip_suffix("/actions/increment-usage")?;
+    rest.rsplit_once("/licenses/").map(|(_, id)| id)
+}
+
+fn increment_usage(
+    licenses: &mut HashMap<String, MockLicense>,
+    id: &str,
+    license_auth: Option<&str>,
+    request: &Value,
+) -> (u16, Value) {
+    let license = license_auth
+        .and_then(|key| licenses.get_mut(key))
+        .filter(|license| license.id == id);
+    let Some(license) = license else {
+        return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+    };
+
+    let increment = request["meta"]["increment"].as_u64().unwrap_or(1);
+    let uses = license.uses.saturating_add(increment);
+    if license.max_uses.is_some_and(|max| uses > max) {
+        return (
+            422,
+            error_document("USAGE_EXCEEDED", "usage exceeds maximum allowed"),
+        );
+    }
+
+    license.uses = uses;
+    (200, json!({ "data": license.to_json() }))
+}
+
+/// ID of a `kind` resource (e.g. `"/groups/"`) from its path.
+fn resource_id<'a>(path: &'a str, kind: &str) -> Option<&'a str> {
+    path.rsplit_once(kind).map(|(_, id)| id)
+}
+
+/// A group or user the caller's license is related to as `related_id`.
+fn related(
+    resources: &Mutex<HashMap<String, Value>>,
+    data_type: &str,
+    license: Option<&MockLicense>,
+    related_id: Option<&str>,
+    id: &str,
+) -> (u16, Value) {
+    if license.is_none() {
+        return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+    }
+    let attributes = resources
+        .lock()
+        .ok()
+        .filter(|_| related_id == Some(id))
+        .and_then(|resources| resources.get(id).cloned());
+    match attributes {
+        Some(attributes) => (
+            200,
+            json!({ "data": { "id": id, "type": data_type, "attributes": attributes } }),
+        ),
+        None => (404, error_document("NOT_FOUND", "Not found")),
+    }
+}
+
+/// Machine ID from a machine path.
+fn machine_id(path: &str) -> Option<&str> {
+    path.rsplit_once("/machines/").map(|(_, id)| id)
+}
+
+/// Machine ID from a heartbeat ping path.
+fn ping_machine_id(path: &str) -> Option<&str> {
+    machine_id(path.strip_suffix("/actions/ping")?)
+}
+
+/// Ping the heartbeat of one of the caller's machines.
+fn ping_machine(
+    licenses: &mut HashMap<String, MockLicense>,
+    id: &str,
+    license_auth: Option<&str>,
+) -> (u16, Value) {
+    let Some(license) = license_auth.and_then(|key| licenses.get_mut(key)) else {
+        return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+    };
+    let resurrects = license.resurrects_machines;
+    let Some(machine) = license.machines.iter_mut().find(|m| m.id == id) else {
+        return (404, error_document("NOT_FOUND", "Not found"));
+    };
+
+    let status = match (machine.dead, resurrects) {
+        (false, _) => "ALIVE",
+        (true, true) => "RESURRECTED",
+        (true, false) => {
+            return (
+                422,
+                error_document("MACHINE_HEARTBEAT_DEAD", "machine heartbeat is dead"),
+            )
+        }
+    };
+    machine.dead = false;
+    (
+        200,
+        json!({
+            "data": {
+                "id": machine.id,
+                "type": "machines",
+                "attributes": { "fingerprint": machine.fingerprint, "heartbeatStatus": status }
+            }
+        }),
+    )
+}
+
+/// Deactivate one of the caller's machines.
+fn deactivate_machine(
+    licenses: &mut HashMap<String, MockLicense>,
+    id: &str,
+    license_auth: Option<&str>,
+) -> (u16, Value) {
+    let Some(license) = license_auth.and_then(|key| licenses.get_mut(key)) else {
+        return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+    };
+    let Some(index) = license.machines.iter().position(|m| m.id == id) else {
+        return (404, error_document("NOT_FOUND", "Not found"));
+    };
+
+    license.machines.remove(index);
+    (204, Value::Null)
+}
+
+/// Issue (or re-issue) a license token for the key in `license_auth`.
+fn issue_token(
+    licenses: &mut HashMap<String, MockLicense>,
+    license_auth: Option<&str>,
+) -> (u16, Value) {
+    let Some(license) = license_auth.and_then(|key| licenses.get_mut(key)) else {
+        return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+    };
+
+    let token = license
+        .token
+        .get_or_insert_with(|| format!("activ-{}", license.id))
+        .clone();
+    (
+        201,
+        json!({
+            "data": {
+                "id": format!("tok-{}", license.id),
+                "type": "tokens",
+                "attributes": { "kind": "activation-token", "token": token, "expiry": null },
+                "relationships": {
+                    "bearer": { "data": { "type": "licenses", "id": license.id } }
+                }
+            }
+        }),
+    )
+}
+
+/// License ID or key from an entitlements path.
+fn entitlements_license(path: &str) -> Option<&str> {
+    let rest = path.strip_suffix("/entitlements")?;
+    rest.rsplit_once("/licenses/").map(|(_, license)| license)
+}
+
+fn list_entitlements(
+    licenses: &HashMap<String, MockLicense>,
+    license: &str,
+    query: &str,
+    license_auth: Option<&str>,
+) -> (u16, Value) {
+    let found = licenses
+        .iter()
+        .find(|(key, l)| key.as_str() == license || l.id == license)
+        .filter(|(key, _)| license_auth == Some(key.as_str()));
+    let Some((_, license)) = found else {
+        return (401, error_document("TOKEN_INVALID", "Unauthorized"));
+    };
+
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == name)
+            .and_then(|(_, v)| v.parse::<usize>().ok())
+    };
+    let number = param("page%5Bnumber%5D").unwrap_or(1).max(1);
+    let size = param("page%5Bsize%5D").unwrap_or(10).max(1);
+
+    let start = (number - 1).saturating_mul(size);
+    let data: Vec<Value> = license
+        .entitlements
+        .iter()
+        .skip(start)
+        .take(size)
+        .map(|code| {
+            json!({
+                "id": format!("ent-{}", code.to_lowercase()),
+                "type": "entitlements",
+                "attributes": { "name": null, "code": code, "metadata": {} }
+            })
+        })
+        .collect();
+    let next = (start.saturating_add(size) < license.entitlements.len()).then(|| {
+        format!(
+            "/v1/licenses/{}/entitlements?page%5Bnumber%5D={}&page%5Bsize%5D={}",
+            license.id,
+            number + 1,
+            size
+        )
+    });
+
+    (200, json!({ "data": data, "links": { "next": next } }))
+}
+
+/// Current version from an upgrade path.
+fn upgrade_release(path: &str) -> Option<&str> {
+    let rest = path.strip_suffix("/upgrade")?;
+    rest.rsplit_once("/releases/").map(|(_, version)| version)
+}
+
+/// Release ID from an artifacts path.
+fn artifacts_release(path: &str) -> Option<&str> {
+    let rest = path.strip_suffix("/artifacts")?;
+    rest.rsplit_once("/releases/").map(|(_, release)| release)
+}
+
+/// Artifact ID from a download path.
+fn download_artifact(path: &str) -> Option<&str> {
+    path.rsplit_once("/artifacts/")
+        .map(|(_, artifact)| artifact)
+}
+
+/// Channels are cumulative: `beta` also sees `rc` and `stable`.
+fn release_channel_rank(channel: &str) -> usize {
+    ["stable", "rc", "beta", "alpha", "dev"]
+        .iter()
+        .position(|c| *c == channel)
+        .unwrap_or(usize::MAX)
+}
+
+/// Compare dotted versions, ignoring pre-release suffixes.
+fn version_order(a: &str, b: &str) -> std::cmp::Ordering {
+    let mut a = version::parse(a).unwrap_or_default();
+    let mut b = version::parse(b).unwrap_or_default();
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a.cmp(&b)
+}
+
+fn error_document(code: &str, detail: &str) -> Value {
+    json!({ "errors": [{ "code": code, "detail": detail }] })
+}
+
+/// Well-known Ed25519 seed (RFC 8032, test 1) for signing test responses.
+///
+/// Pass it to [`MockKeygen::new`] and trust [`TEST_PUBLIC_KEY_HEX`].
+/// Never use it outside tests.
+pub const TEST_SIGNING_SEED: [u8; 32] = [
+    0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c, 0xc4,
+    0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae, 0x7f, 0x60,
+];
+
+/// Hex-encoded verifying key for [`TEST_SIGNING_SEED`].
+pub const TEST_PUBLIC_KEY_HEX: &str =
+    "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+/// Keygen path of `validate-key` for the [`test_config`] account.
+const TEST_VALIDATE_PATH: &str = "/v1/accounts/test-account/licenses/actions/validate-key";
+
+/// `Keygen-Signature` header over `signing_string`, signed with
+/// [`TEST_SIGNING_SEED`].
+pub fn test_signature_header(signing_string: &str) -> String {
+    let signature = SigningKey::from_bytes(&TEST_SIGNING_SEED).sign(signing_string.as_bytes());
+    format!(
+        r#"algorithm="ed25519", signature="{}""#,
+        STANDARD.encode(signature.to_bytes())
+    )
+}
+
+/// Cache record of a `validate-key` response with `body`, dated `date`
+/// (HTTP date format) and signed with [`TEST_SIGNING_SEED`], as if cached
+/// at `cached_at`.
+pub fn signed_test_record(body: &str, date: &str, cached_at: DateTime<Utc>) -> CacheRecord {
+    let digest = format_digest_header(body.as_bytes());
+    let signing_string =
+        build_signing_string("post", TEST_VALIDATE_PATH, KEYGEN_HOST, date, Some(&digest));
+
+    CacheRecord::new(
+        date.to_string(),
+        test_signature_header(&signing_string),
+        Some(digest),
+        body.to_string(),
+        TEST_VALIDATE_PATH.to_string(),
+        KEYGEN_HOST.to_string(),
+        &MockClock::new(cached_at),
+    )
+}
+
+/// Config for the `test-account` account trusting [`TEST_PUBLIC_KEY_HEX`],
+/// with memoization and negative caching off so every call reaches the
+/// mock.
+pub fn test_config() -> GatewardenConfig {
+    GatewardenConfig {
+        app_name: "test-app",
+        feature_name: "test",
+        account_id: "test-account",
+        public_key_hex: TEST_PUBLIC_KEY_HEX,
+        public_key_id: None,
+        trusted_keys: &[],
+        required_entitlements: &[],
+        user_agent_product: "test-product",
+        cache_namespace: "gatewarden-test",
+        offline_grace: Duration::from_secs(86400),
+        feature_grace: &[],
+        max_cache_age: Duration::from_secs(90 * 86400),
+        fallback_policy: FallbackPolicy::default(),
+        online_budget: None,
+        request_timeout: Duration::from_secs(30),
+        connect_timeout: Duration::from_secs(10),
+        proxy: ProxyConfig::System,
+        tls_pins: &[],
+        require_digest: false,
+        memo_ttl: Duration::ZERO,
+        negative_cache_ttl: Duration::ZERO,
+        validation_rate_limit: None,
+        app_version: None,
+        key_format: Default::default(),
+        user_agent_extras: Default::default(),
+        connection_pool: Default::default(),
+        ip_strategy: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::file::{hash_license_key, FileCache};
+    use crate::cache::keystore::{FileKeyStore, KeyStore};
+    use crate::chaos::{FaultInjector, HeaderFault};
+    use crate::policy::entitlements::EntitlementPolicy;
+    use crate::policy::fallback::FallbackReason;
+    use crate::policy::key_format::{CheckDigit, KeyFormat};
+    use crate::{GatewardenError, LicenseManager, TransportFailure};
+    use chrono::TimeZone;
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn setup(temp_dir: &TempDir) -> (Arc<MockKeygen>, LicenseManager) {
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let manager = LicenseManager::new_with_clock(test_config(), clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_meter_dir(temp_dir.path().join("meter"))
+            .with_mock_keygen(keygen.clone());
+        (keygen, manager)
+    }
+
+    #[test]
+    fn test_public_key_matches_seed() {
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        assert_eq!(keygen.public_key_hex(), TEST_PUBLIC_KEY_HEX);
+    }
+
+    #[test]
+    fn test_validate_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("good-key", MockLicense::new("lic-1"));
+        keygen.add_license(
+            "old-key",
+            MockLicense::new("lic-2")
+                .expires_at(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()),
+        );
+
+        let result = manager.validate_key("good-key").unwrap();
+        assert!(result.valid);
+        assert!(!result.from_cache);
+        assert_eq!(result.state.license_id.as_deref(), Some("lic-1"));
+
+        assert!(matches!(
+            manager.validate_key("old-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert!(matches!(
+            manager.validate_key("unknown-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert_eq!(keygen.request_count(), 3);
+    }
+
+    /// Clock that tests can move forward while it is shared.
+    struct StepClock(Mutex<DateTime<Utc>>);
+
+    impl Clock for StepClock {
+        fn now_utc(&self) -> DateTime<Utc> {
+            self.0.lock().map(|now| *now).unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn test_memoized_results_skip_keygen() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock = Arc::new(StepClock(Mutex::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        )));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let config = GatewardenConfig {
+            memo_ttl: Duration::from_secs(60),
+            ..test_config()
+        };
+        let manager = LicenseManager::new_with_clock(config, clock.clone())
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+
+        for _ in 0..3 {
+            assert!(manager.validate_key("test-key").unwrap().valid);
+        }
+        assert!(!manager.check_access("test-key").unwrap().from_cache);
+        assert_eq!(keygen.request_count(), 1);
+
+        *clock.0.lock().unwrap() += chrono::Duration::seconds(61);
+        manager.validate_key("test-key").unwrap();
+        assert_eq!(keygen.request_count(), 2);
+
+        // A failed refresh drops the memoized result
+        keygen.add_license("test-key", MockLicense::new("lic-1").suspended());
+        assert!(manager.force_refresh("test-key").is_err());
+        assert!(matches!(
+            manager.validate_key("test-key"),
+            Err(GatewardenError::LicenseSuspended)
+        ));
+        assert_eq!(keygen.request_count(), 4);
+    }
+
+    #[test]
+    fn test_refusals_are_cached_briefly() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock = Arc::new(StepClock(Mutex::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        )));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let config = GatewardenConfig {
+            negative_cache_ttl: Duration::from_secs(30),
+            ..test_config()
+        };
+        let manager = LicenseManager::new_with_clock(config, clock.clone())
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+
+        for _ in 0..3 {
+            assert!(matches!(
+                manager.validate_key("doomed-key"),
+                Err(GatewardenError::InvalidLicense)
+            ));
+        }
+        assert_eq!(keygen.request_count(), 1);
+
+        *clock.0.lock().unwrap() += chrono::Duration::seconds(31);
+        assert!(manager.validate_key("doomed-key").is_err());
+        assert_eq!(keygen.request_count(), 2);
+
+        // A purchase takes effect on an explicit re-check
+        keygen.add_license("doomed-key", MockLicense::new("lic-1"));
+        assert!(manager.validate_key("doomed-key").is_err());
+        assert!(manager.force_refresh("doomed-key").unwrap().valid);
+        assert!(manager.validate_key("doomed-key").unwrap().valid);
+        assert_eq!(keygen.request_count(), 4);
+    }
+
+    #[test]
+    fn test_rate_limited_validations_use_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock = Arc::new(StepClock(Mutex::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        )));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let config = GatewardenConfig {
+            validation_rate_limit: Some(2),
+            ..test_config()
+        };
+        let manager = LicenseManager::new_with_clock(config, clock.clone())
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        keygen.add_license("other-key", MockLicense::new("lic-2"));
+
+        for _ in 0..2 {
+            assert!(!manager.validate_key("test-key").unwrap().from_cache);
+        }
+        let result = manager.validate_key("test-key").unwrap();
+        assert!(result.from_cache);
+        assert_eq!(result.fallback_reason, Some(FallbackReason::Throttled));
+        assert_eq!(keygen.request_count(), 2);
+
+        // Without a cached record, an over-limit call fails locally
+        manager.validate_key("other-key").unwrap();
+        manager.validate_key("other-key").unwrap();
+        FileCache::with_path(temp_dir.path().join("cache"))
+            .unwrap()
+            .delete(&hash_license_key("other-key"))
+            .unwrap();
+        assert!(matches!(
+            manager.validate_key("other-key"),
+            Err(GatewardenError::RateLimited { .. })
+        ));
+        assert_eq!(keygen.request_count(), 4);
+
+        // The bucket refills over the minute
+        *clock.0.lock().unwrap() += chrono::Duration::seconds(30);
+        assert!(!manager.validate_key("test-key").unwrap().from_cache);
+        assert_eq!(keygen.request_count(), 5);
+    }
+
+    #[test]
+    fn test_cloned_managers_share_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock = Arc::new(StepClock(Mutex::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        )));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let config = GatewardenConfig {
+            memo_ttl: Duration::from_secs(60),
+            ..test_config()
+        };
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+
+        assert!(manager.validate_key("test-key").unwrap().valid);
+        let shared = manager.clone();
+        let result = thread::spawn(move || shared.validate_key("test-key").unwrap())
+            .join()
+            .unwrap();
+        assert!(result.valid);
+        assert_eq!(keygen.request_count(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_validations_share_one_request() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let faults = FaultInjector::new();
+        faults.delay_responses(Some(Duration::from_millis(200)));
+        let manager = Arc::new(manager.with_fault_injector(faults));
+        keygen.add_license("good-key", MockLicense::new("lic-1"));
+
+        let barrier = Arc::new(Barrier::new(4));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (manager, barrier) = (manager.clone(), barrier.clone());
+                thread::spawn(move || {
+                    barrier.wait();
+                    manager.validate_key("good-key")
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap().unwrap().valid);
+        }
+        assert_eq!(keygen.request_count(), 1);
+    }
+
+    #[test]
+    fn test_validate_key_with_deadline_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let faults = FaultInjector::new();
+        let manager = manager.with_fault_injector(faults.clone());
+        keygen.add_license("cached-key", MockLicense::new("lic-1"));
+        keygen.add_license("fresh-key", MockLicense::new("lic-2"));
+        assert!(manager.validate_key("cached-key").unwrap().valid);
+
+        faults.delay_responses(Some(Duration::from_secs(30)));
+        let started = Instant::now();
+        let result = manager
+            .validate_key_with_deadline("cached-key", Duration::from_millis(50))
+            .unwrap();
+        assert!(result.valid && result.from_cache);
+        assert_eq!(
+            result.fallback_reason,
+            Some(FallbackReason::Network {
+                failure: TransportFailure::Timeout
+            })
+        );
+        assert!(matches!(
+            manager.validate_key_with_deadline("fresh-key", Duration::from_millis(50)),
+            Err(GatewardenError::KeygenTransport {
+                kind: TransportFailure::Timeout,
+                ..
+            })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        faults.reset();
+        let result = manager
+            .validate_key_with_deadline("fresh-key", Duration::from_secs(5))
+            .unwrap();
+        assert!(result.valid && !result.from_cache);
+    }
+
+    #[test]
+    fn test_entitlement_policy_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let manager =
+            manager.with_entitlement_policy(EntitlementPolicy::any_of(["PRO", "ENTERPRISE"]));
+        keygen.add_license(
+            "test-key",
+            MockLicense::new("lic-1").with_entitlements(&["ENTERPRISE"]),
+        );
+
+        // One page of entitlements, then one validation scoped to ENTERPRISE
+        let result = manager.validate_key("test-key").unwrap();
+        assert!(result.has_entitlement("ENTERPRISE"));
+        assert!(!result.has_entitlement("PRO"));
+        assert_eq!(keygen.request_count(), 2);
+
+        // An unsatisfied policy still costs a single validation
+        keygen.add_license(
+            "test-key",
+            MockLicense::new("lic-1").with_entitlements(&["BASIC"]),
+        );
+        assert!(matches!(
+            manager.validate_key("test-key"),
+            Err(GatewardenError::EntitlementMissing { code }) if code == "any of (PRO, ENTERPRISE)"
+        ));
+        assert_eq!(keygen.request_count(), 4);
+    }
+
+    #[test]
+    fn test_sync_usage_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("test-key", MockLicense::new("lic-1").with_usage(3, 5));
+
+        manager.validate_key("test-key").unwrap();
+        manager.record_use("test-key", 2).unwrap();
+        let caps = manager.sync_usage("test-key").unwrap();
+        assert_eq!(caps.current_uses, Some(5));
+        assert_eq!(keygen.uses("test-key"), Some(5));
+
+        // The server rejects increments past the cap
+        keygen.add_license("test-key", MockLicense::new("lic-1").with_usage(3, 3));
+        let response = keygen.respond(
+            "POST",
+            "api.keygen.sh",
+            "/v1/accounts/test-account/licenses/lic-1/actions/increment-usage",
+            br#"{"meta":{"increment":1}}"#,
+            Some("License test-key"),
+        );
+        assert_eq!(response.status, 422);
+        assert_eq!(keygen.uses("test-key"), Some(3));
+    }
+
+    #[test]
+    fn test_record_use_counts_synced_uses() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("test-key", MockLicense::new("lic-1").with_usage(3, 5));
+
+        manager.validate_key("test-key").unwrap();
+        manager.record_use("test-key", 1).unwrap();
+        manager.sync_usage("test-key").unwrap();
+        assert_eq!(keygen.uses("test-key"), Some(4));
+
+        // The cached response still says 3, but the synced use counts
+        assert_eq!(
+            manager.record_use("test-key", 1).unwrap().current_uses,
+            Some(5)
+        );
+        assert!(matches!(
+            manager.record_use("test-key", 1),
+            Err(GatewardenError::UsageLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn test_sync_usage_reconciles_in_flight_increment() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("test-key", MockLicense::new("lic-1").with_usage(3, 10));
+        let key_hash = hash_license_key("test-key");
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+
+        manager.validate_key("test-key").unwrap();
+        manager.record_use("test-key", 2).unwrap();
+
+        // An increment reached Keygen but its response was lost
+        let mut meter = manager.usage_meter(&key_hash).unwrap();
+        meter.begin_sync(2, 3, &clock).unwrap();
+        keygen.respond(
+            "POST",
+            "api.keygen.sh",
+            "/v1/accounts/test-account/licenses/lic-1/actions/increment-usage",
+            br#"{"meta":{"increment":2}}"#,
+            Some("License test-key"),
+        );
+
+        manager.sync_usage("test-key").unwrap();
+        assert_eq!(keygen.uses("test-key"), Some(5));
+        let meter = manager.usage_meter(&key_hash).unwrap();
+        assert_eq!((meter.unsynced_count(), meter.in_flight()), (0, None));
+
+        // An increment that never arrived is sent again
+        manager.record_use("test-key", 1).unwrap();
+        let mut meter = manager.usage_meter(&key_hash).unwrap();
+        meter.begin_sync(1, 5, &clock).unwrap();
+        manager.sync_usage("test-key").unwrap();
+        assert_eq!(keygen.uses("test-key"), Some(6));
+    }
+
+    #[test]
+    fn test_validate_license_id_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license(
+            "test-key",
+            MockLicense::new("lic-1")
+                .with_entitlements(&["PRO"])
+                .with_token("activ-token"),
+        );
+
+        let result = manager.validate_license_id("lic-1", "activ-token").unwrap();
+        assert!(result.valid);
+        assert_eq!(result.state.license_id.as_deref(), Some("lic-1"));
+
+        // Cached under the license ID
+        assert!(manager.check_access("lic-1").unwrap().from_cache);
+
+        assert!(matches!(
+            manager.validate_license_id("lic-1", "wrong-token"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert!(matches!(
+            manager.validate_license_id("lic-1", ""),
+            Err(GatewardenError::MissingLicense)
+        ));
+    }
+
+    #[test]
+    fn test_license_token_exchange_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let store_path = temp_dir.path().join("license.key");
+        let store = FileKeyStore::with_path(store_path.clone()).unwrap();
+        let manager =
+            manager.with_key_store(Box::new(FileKeyStore::with_path(store_path).unwrap()));
+        keygen.add_license(
+            "test-key",
+            MockLicense::new("lic-1").with_entitlements(&["PRO"]),
+        );
+
+        let token = manager.exchange_license_token("test-key").unwrap();
+        assert_eq!(token.token, "activ-lic-1");
+        assert_eq!(token.license_id.as_deref(), Some("lic-1"));
+        assert!(!format!("{:?}", token).contains("activ-lic-1"));
+        assert_eq!(manager.license_token("test-key").unwrap(), Some(token));
+        assert!(manager.license_token("other-key").unwrap().is_none());
+
+        // Later license-authenticated calls present the stored token
+        assert_eq!(manager.list_entitlements("test-key").unwrap().len(), 1);
+        let stored = store.load_license_token().unwrap().unwrap();
+        store
+            .save_license_token(&stored.replace("activ-lic-1", "activ-bogus"))
+            .unwrap();
+        assert!(matches!(
+            manager.list_entitlements("test-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+
+        assert!(matches!(
+            manager.exchange_license_token("unknown-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+    }
+
+    #[test]
+    fn test_list_entitlements_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let codes: Vec<String> = (0..150).map(|i| format!("FEATURE_{}", i)).collect();
+        let codes: Vec<&str> = codes.iter().map(String::as_str).collect();
+        keygen.add_license(
+            "test-key",
+            MockLicense::new("lic-1").with_entitlements(&codes),
+        );
+
+        // Two signed pages of 100 and 50
+        let entitlements = manager.list_entitlements("test-key").unwrap();
+        assert_eq!(entitlements.len(), 150);
+        assert_eq!(entitlements[0].code, "FEATURE_0");
+        assert_eq!(entitlements[149].id, "ent-feature_149");
+        assert_eq!(keygen.request_count(), 2);
+
+        assert!(matches!(
+            manager.list_entitlements("unknown-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+    }
+
+    #[test]
+    fn test_group_and_owner_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_group("grp-acme", "ACME Corp", Some(12));
+        keygen.add_user("usr-1", "Jo Admin", "jo@acme.example");
+        keygen.add_user("usr-2", "Someone Else", "else@example.com");
+        keygen.add_license(
+            "team-key",
+            MockLicense::new("lic-1")
+                .in_group("grp-acme")
+                .owned_by("usr-1"),
+        );
+        keygen.add_license("solo-key", MockLicense::new("lic-2").owned_by("usr-3"));
+
+        let result = manager.validate_key("team-key").unwrap();
+        assert_eq!(result.state.group.as_ref().unwrap().id, "grp-acme");
+        assert_eq!(result.state.licensed_to(), None);
+
+        // Two more signed responses
+        let state = manager
+            .fetch_group_and_owner("team-key", result.state)
+            .unwrap();
+        assert_eq!(keygen.request_count(), 3);
+        assert_eq!(state.licensed_to().as_deref(), Some("ACME Corp (12 seats)"));
+        let owner = state.owner.unwrap();
+        assert_eq!(owner.name.as_deref(), Some("Jo Admin"));
+        assert_eq!(owner.email.as_deref(), Some("jo@acme.example"));
+
+        // An owner Keygen does not serve keeps just its ID
+        let result = manager.validate_key("solo-key").unwrap();
+        let state = manager
+            .fetch_group_and_owner("solo-key", result.state)
+            .unwrap();
+        assert_eq!(state.owner.unwrap().id, "usr-3");
+        assert_eq!(state.group, None);
+    }
+
+    #[test]
+    fn test_startup_state_against_mock() {
+        use crate::StartupState;
+
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("good-key", MockLicense::new("lic-1"));
+        keygen.add_license("held-key", MockLicense::new("lic-2").suspended());
+        keygen.add_license("seat-key", MockLicense::new("lic-3").with_max_machines(1));
+
+        assert!(matches!(
+            manager.startup_state(Some("good-key")),
+            StartupState::Licensed(_)
+        ));
+        assert!(matches!(
+            manager.startup_state(Some("seat-key")),
+            StartupState::NeedsActivation { code, .. } if code == "NO_MACHINES"
+        ));
+
+        // A refusal remembered by the negative cache keeps its code
+        let mut config = test_config();
+        config.negative_cache_ttl = Duration::from_secs(60);
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+        for _ in 0..2 {
+            assert!(matches!(
+                manager.startup_state(Some("held-key")),
+                StartupState::Invalid { code, .. } if code == "SUSPENDED"
+            ));
+        }
+        assert_eq!(keygen.request_count(), 3);
+    }
+
+    #[test]
+    fn test_validation_outcomes_against_mock() {
+        use crate::ValidationOutcome;
+
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let faults = FaultInjector::new();
+        let manager = manager.with_fault_injector(faults.clone());
+        keygen.add_license("good-key", MockLicense::new("lic-1"));
+        keygen.add_license("held-key", MockLicense::new("lic-2").suspended());
+
+        assert!(matches!(
+            manager.validate_key_outcome("good-key").unwrap(),
+            ValidationOutcome::Valid(_)
+        ));
+        faults.fail_next_requests(1);
+        let outcome = manager.validate_key_outcome("good-key").unwrap();
+        assert!(matches!(outcome, ValidationOutcome::ValidFromCache { .. }));
+        assert!(outcome.is_valid());
+
+        assert!(matches!(
+            manager.validate_key_outcome("unknown-key").unwrap(),
+            ValidationOutcome::Invalid { code } if code == "INVALID_LICENSE"
+        ));
+        assert!(matches!(
+            manager.validate_key_outcome("held-key").unwrap(),
+            ValidationOutcome::Invalid { code } if code == "LICENSE_SUSPENDED"
+        ));
+
+        // `validate_key` never answers a rejected license with `valid: false`
+        assert!(matches!(
+            manager.validate_key("unknown-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert!(matches!(
+            manager.validate_key("held-key"),
+            Err(GatewardenError::LicenseSuspended)
+        ));
+        assert!(manager.validate_key("good-key").unwrap().valid);
+        assert!(matches!(
+            manager
+                .with_entitlement_policy(EntitlementPolicy::code("PRO"))
+                .validate_key_outcome("good-key")
+                .unwrap(),
+            ValidationOutcome::Denied { missing_entitlement } if missing_entitlement == "PRO"
+        ));
+    }
+
+    #[test]
+    fn test_user_scope_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, _) = setup(&temp_dir);
+        keygen.add_license(
+            "team-key",
+            MockLicense::new("lic-1")
+                .owned_by("usr-1")
+                .with_users(&["ana@acme.example"]),
+        );
+        let scoped_to = |user: &str| {
+            let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+                Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+            ));
+            LicenseManager::new_with_clock(test_config(), clock)
+                .unwrap()
+                .with_cache_backend(Box::new(
+                    FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+                ))
+                .with_mock_keygen(keygen.clone())
+                .with_user(user)
+        };
+
+        let result = scoped_to("ana@acme.example")
+            .validate_key("team-key")
+            .unwrap();
+        assert_eq!(result.state.user.as_deref(), Some("ana@acme.example"));
+        assert!(scoped_to("usr-1").validate_key("team-key").unwrap().valid);
+        assert!(matches!(
+            scoped_to("eve@acme.example").validate_key("team-key"),
+            Err(GatewardenError::UserMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_product_and_policy_scope_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license(
+            "cli-key",
+            MockLicense::new("lic-1")
+                .for_product("prod-cli")
+                .with_policy("pol-cli"),
+        );
+        keygen.add_license(
+            "gui-key",
+            MockLicense::new("lic-2")
+                .for_product("prod-gui")
+                .with_policy("pol-gui"),
+        );
+        assert!(manager.validate_key("gui-key").unwrap().valid);
+
+        let faults = FaultInjector::new();
+        let scoped = LicenseManager::new_with_clock(
+            test_config(),
+            Arc::new(MockClock::new(
+                Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+            )),
+        )
+        .unwrap()
+        .with_cache_backend(Box::new(
+            FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+        ))
+        .with_mock_keygen(keygen.clone())
+        .with_fault_injector(faults.clone())
+        .with_product_scope("prod-cli")
+        .with_policy_scope("pol-cli");
+
+        let result = scoped.validate_key("cli-key").unwrap();
+        assert_eq!(result.state.product_id.as_deref(), Some("prod-cli"));
+        assert!(matches!(
+            scoped.validate_key("gui-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        // The sibling product's cached result is not accepted offline
+        faults.fail_next_requests(1);
+        assert!(matches!(
+            scoped.validate_key("gui-key"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+    }
+
+    #[test]
+    fn test_start_trial_against_mock() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        let store_path = temp_dir.path().join("license.key");
+        let store = FileKeyStore::with_path(store_path.clone()).unwrap();
+        let manager =
+            manager.with_key_store(Box::new(FileKeyStore::with_path(store_path).unwrap()));
+        keygen.add_trial_policy("pol-trial", chrono::Duration::days(14));
+
+        let trial = manager.start_trial("pol-trial", "user-token").unwrap();
+        assert!(trial.result.valid);
+        assert!(trial.result.state.is_trial());
+        assert_eq!(trial.result.state.policy_id.as_deref(), Some("pol-trial"));
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 20, 12, 0, 0).unwrap());
+        assert_eq!(trial.result.state.trial_days_remaining(&clock), Some(9));
+        assert_eq!(
+            store.load_license_key().unwrap().as_deref(),
+            Some(trial.license_key.expose())
+        );
+        assert!(!format!("{:?}", trial).contains(trial.license_key.expose()));
+
+        assert!(matches!(
+            manager.start_trial("pol-paid", "user-token"),
+            Err(GatewardenError::ConfigError(_))
+        ));
+        assert!(matches!(
+            manager.start_trial("pol-trial", ""),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_max_version_gates_newer_builds() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("v2-key", MockLicense::new("lic-1").with_max_version("2.9"));
+        keygen.add_license("v3-key", MockLicense::new("lic-2").with_max_version("3"));
+        let config = GatewardenConfig {
+            app_version: Some("3.0.1"),
+            ..test_config()
+        };
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen);
+
+        assert!(matches!(
+            manager.validate_key("v2-key"),
+            Err(GatewardenError::VersionNotEntitled { max_version }) if max_version == "2.9"
+        ));
+        // 3.0.1 is within major version 3
+        let result = manager.validate_key("v3-key").unwrap();
+        assert_eq!(result.state.max_version.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn test_validate_any_falls_back_to_legacy_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("legacy-key", MockLicense::new("lic-legacy"));
+        keygen.add_license("suspended-key", MockLicense::new("lic-2").suspended());
+
+        let (index, result) = manager.validate_any(&["new-key", "legacy-key"]).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(result.state.license_id.as_deref(), Some("lic-legacy"));
+        // Each key keeps its own cache record
+        assert!(manager.check_access("legacy-key").unwrap().valid);
+        assert!(manager.check_access("new-key").is_err());
+
+        keygen.add_license("new-key", MockLicense::new("lic-new"));
+        let (index, _) = manager.validate_any(&["new-key", "legacy-key"]).unwrap();
+        assert_eq!(index, 0);
+
+        let error = manager
+            .validate_any(&["unknown-key", "suspended-key"])
+            .unwrap_err();
+        assert_eq!(error.code(), "NO_VALID_KEY");
+        assert!(!error.is_retryable());
+        assert!(matches!(
+            error,
+            GatewardenError::NoValidKey { failures } if matches!(
+                failures.as_slice(),
+                [GatewardenError::InvalidLicense, GatewardenError::LicenseSuspended]
+            )
+        ));
+        assert!(matches!(
+            manager.validate_any(&[]),
+            Err(GatewardenError::MissingLicense)
+        ));
+    }
+
+    #[test]
+    fn test_key_format_normalizes_and_rejects_locally() {
+        let temp_dir = TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("GWAB12CD3U", MockLicense::new("lic-1"));
+        let config = GatewardenConfig {
+            key_format: KeyFormat::new()
+                .trim()
+                .uppercase()
+                .strip_separators()
+                .prefix("GW")
+                .check_digit(CheckDigit::LuhnMod36),
+            ..test_config()
+        };
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+
+        assert!(manager.validate_key(" gw-ab12-cd3u\n").unwrap().valid);
+        // Any spelling of the key finds the same cache record
+        assert!(manager.check_access("GW-AB12-CD3U").unwrap().valid);
+
+        assert!(matches!(
+            manager.validate_key("GW-AB12-CD3V"),
+            Err(GatewardenError::MalformedLicenseKey { .. })
+        ));
+        assert!(matches!(
+            manager.validate_key(" - "),
+            Err(GatewardenError::MissingLicense)
+        ));
+        assert_eq!(keygen.request_count(), 1);
+    }
+
+    #[test]
+    fn test_request_ids_reach_cache_and_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("good-key", MockLicense::new("lic-1"));
+
+        manager.validate_key("good-key").unwrap();
+        let cache = FileCache::with_path(temp_dir.path().join("cache")).unwrap();
+        let record = cache.load(&hash_license_key("good-key")).unwrap().unwrap();
+        assert_eq!(record.request_id.as_deref(), Some("mock-1"));
+
+        let faults = FaultInjector::new();
+        faults.corrupt_header(Some(HeaderFault::CorruptSignature));
+        let manager = manager.with_fault_injector(faults);
+        let error = manager.force_refresh("good-key").unwrap_err();
+        assert!(matches!(error, GatewardenError::SignatureInvalid { .. }));
+        assert_eq!(error.request_id(), Some("mock-2"));
+        assert!(error.to_string().ends_with("(Keygen request mock-2)"));
+    }
+}
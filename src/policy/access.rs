@@ -5,8 +5,11 @@
 //! - License validity (state must be valid)
 //! - Usage caps (limits from Keygen usage counters; period semantics are consumer-defined)
 
+use crate::policy::version;
 use crate::protocol::models::LicenseState;
 use crate::GatewardenError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 /// Check that a license state meets all access requirements.
 ///
@@ -16,7 +19,9 @@ use crate::GatewardenError;
 ///
 /// # Returns
 /// * `Ok(())` - Access granted
-/// * `Err(InvalidLicense)` - License is not valid
+/// * `Err(LicenseSuspended | LicenseBanned | LicenseOverdue)` - Keygen
+///   reported the license as suspended, banned, or overdue for check-in
+/// * `Err(InvalidLicense)` - License is not valid for any other reason
 /// * `Err(EntitlementMissing)` - Required entitlement not found
 pub fn check_access(
     state: &LicenseState,
@@ -24,7 +29,7 @@ pub fn check_access(
 ) -> Result<(), GatewardenError> {
     // 1. Check license is valid
     if !state.valid {
-        return Err(GatewardenError::InvalidLicense);
+        return Err(GatewardenError::from_validation_code(&state.code));
     }
 
     // 2. Check all required entitlements are present
@@ -39,6 +44,44 @@ pub fn check_access(
     Ok(())
 }
 
+/// Check that a license has not passed its expiry date.
+///
+/// Applied to cached results so offline fallback can never extend access
+/// beyond `expires_at`, however long the configured grace period.
+///
+/// # Returns
+/// * `Ok(())` - No expiry set, or expiry is in the future
+/// * `Err(LicenseExpired)` - Expiry is at or before `now`
+pub fn check_not_expired(state: &LicenseState, now: DateTime<Utc>) -> Result<(), GatewardenError> {
+    match state.expires_at {
+        Some(expires_at) if expires_at <= now => {
+            Err(GatewardenError::LicenseExpired { expires_at })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check that the running application version is covered by the license.
+///
+/// # Returns
+/// * `Ok(())` - No `app_version` given, no `maxVersion` on the license, or
+///   the version is at most `maxVersion`
+/// * `Err(VersionNotEntitled)` - The version is newer than `maxVersion`,
+///   or `maxVersion` is not a dotted version number (fails closed)
+pub fn check_version(
+    state: &LicenseState,
+    app_version: Option<&str>,
+) -> Result<(), GatewardenError> {
+    match (app_version, &state.max_version) {
+        (Some(version), Some(max_version)) if !version::at_most(version, max_version) => {
+            Err(GatewardenError::VersionNotEntitled {
+                max_version: max_version.clone(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Extract usage caps from license state.
 ///
 /// Returns usage cap information derived from Keygen's `uses`/`maxUses` counters.
@@ -46,7 +89,7 @@ pub fn check_access(
 /// Note: Keygen's `uses` counter does not inherently encode a billing period (e.g., monthly)
 /// unless your system resets it on that cadence (via a backend job calling Keygen's
 /// reset-usage action) or enforces period-based metering outside of Keygen.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UsageCaps {
     /// Usage limit (None = unlimited). Period semantics are consumer-defined.
     pub monthly_limit: Option<u64>,
@@ -118,6 +161,16 @@ mod tests {
             current_uses: None,
             code: "VALID".to_string(),
             detail: None,
+            management_link: None,
+            license_id: None,
+            policy_id: None,
+            product_id: None,
+            trial: false,
+            upgrade: Default::default(),
+            max_version: None,
+            group: None,
+            owner: None,
+            user: None,
         }
     }
 
@@ -130,6 +183,16 @@ mod tests {
             current_uses: None,
             code: "EXPIRED".to_string(),
             detail: None,
+            management_link: None,
+            license_id: None,
+            policy_id: None,
+            product_id: None,
+            trial: false,
+            upgrade: Default::default(),
+            max_version: None,
+            group: None,
+            owner: None,
+            user: None,
         }
     }
 
@@ -154,6 +217,14 @@ mod tests {
         assert!(matches!(result, Err(GatewardenError::InvalidLicense)));
     }
 
+    #[test]
+    fn test_check_access_suspended_license() {
+        let mut state = make_invalid_state();
+        state.code = "SUSPENDED".to_string();
+        let result = check_access(&state, &[]);
+        assert!(matches!(result, Err(GatewardenError::LicenseSuspended)));
+    }
+
     #[test]
     fn test_check_access_missing_entitlement() {
         let state = make_valid_state(vec!["basic".to_string()]);
@@ -179,6 +250,41 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_check_not_expired() {
+        let now = DateTime::parse_from_rfc3339("2025-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut state = make_valid_state(vec![]);
+        assert!(check_not_expired(&state, now).is_ok());
+
+        state.expires_at = Some(now + chrono::Duration::days(1));
+        assert!(check_not_expired(&state, now).is_ok());
+
+        state.expires_at = Some(now);
+        assert!(matches!(
+            check_not_expired(&state, now),
+            Err(GatewardenError::LicenseExpired { expires_at }) if expires_at == now
+        ));
+    }
+
+    #[test]
+    fn test_check_version() {
+        let mut state = make_valid_state(vec![]);
+        assert!(check_version(&state, Some("3.0.0")).is_ok());
+
+        state.max_version = Some("2.4".to_string());
+        assert!(check_version(&state, None).is_ok());
+        assert!(check_version(&state, Some("2.4.0")).is_ok());
+        assert!(matches!(
+            check_version(&state, Some("2.5.0")),
+            Err(GatewardenError::VersionNotEntitled { max_version }) if max_version == "2.4"
+        ));
+
+        state.max_version = Some("latest".to_string());
+        assert!(check_version(&state, Some("1.0")).is_err());
+    }
+
     #[test]
     fn test_usage_caps_unlimited() {
         let state = make_valid_state(vec![]);
@@ -0,0 +1,241 @@
+//! Signed entitlement bundles.
+//!
+//! An entitlement bundle maps product features and tiers to Keygen
+//! entitlement codes. Vendors sign bundles with their own Ed25519 key and
+//! ship them alongside the application, so packaging changes (new tiers,
+//! features moving between tiers) don't require recompiling.
+//!
+//! Bundles are signed over the exact JSON payload bytes with a fixed
+//! domain-separation prefix, and verified with the same Ed25519 code used
+//! for Keygen responses.
+
+use crate::crypto::detached::{sign_detached, verify_detached};
+use crate::protocol::models::LicenseState;
+use crate::GatewardenError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Domain-separation prefix for bundle signatures.
+const BUNDLE_SIGNING_PREFIX: &str = "gatewarden-entitlement-bundle-v1\n";
+
+/// Definition of a single product feature.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureDefinition {
+    /// Entitlement codes that must all be present to enable the feature.
+    pub entitlements: Vec<String>,
+
+    /// Optional human-readable description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Feature definitions and tier mappings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntitlementBundle {
+    /// Bundle version, chosen by the vendor (e.g. monotonically increasing).
+    pub version: u64,
+
+    /// Feature name to definition.
+    #[serde(default)]
+    pub features: BTreeMap<String, FeatureDefinition>,
+
+    /// Tier name to the feature names it includes.
+    #[serde(default)]
+    pub tiers: BTreeMap<String, Vec<String>>,
+}
+
+impl EntitlementBundle {
+    /// Entitlement codes required by a feature, if the feature is defined.
+    pub fn entitlements_for(&self, feature: &str) -> Option<&[String]> {
+        self.features
+            .get(feature)
+            .map(|f| f.entitlements.as_slice())
+    }
+
+    /// Feature names included in a tier, if the tier is defined.
+    pub fn features_in_tier(&self, tier: &str) -> Option<&[String]> {
+        self.tiers.get(tier).map(|t| t.as_slice())
+    }
+
+    /// Whether a license state enables the given feature.
+    ///
+    /// Returns `false` for invalid licenses and for unknown features.
+    pub fn feature_enabled(&self, feature: &str, state: &LicenseState) -> bool {
+        if !state.valid {
+            return false;
+        }
+        match self.entitlements_for(feature) {
+            Some(required) => required
+                .iter()
+                .all(|code| state.entitlements.iter().any(|e| e == code)),
+            None => false,
+        }
+    }
+}
+
+/// A bundle payload together with its detached signature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBundle {
+    /// The bundle as signed JSON.
+    pub payload: String,
+
+    /// Base64-encoded Ed25519 signature over the prefixed payload.
+    pub signature: String,
+}
+
+impl SignedBundle {
+    /// Sign a bundle with the vendor's Ed25519 signing key seed.
+    ///
+    /// Intended for vendor-side tooling; applications only need [`verify`](Self::verify).
+    pub fn sign(
+        bundle: &EntitlementBundle,
+        signing_seed: &[u8; 32],
+    ) -> Result<Self, GatewardenError> {
+        let payload =
+            serde_json::to_string(bundle).map_err(|e| GatewardenError::ProtocolError {
+                message: "Failed to serialize bundle".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        let signature = sign_detached(BUNDLE_SIGNING_PREFIX, &payload, signing_seed);
+        Ok(Self { payload, signature })
+    }
+
+    /// Verify the signature and return the bundle.
+    ///
+    /// # Errors
+    /// - `SignatureInvalid` - Signature does not match the payload
+    /// - `ConfigError` - Public key is malformed
+    /// - `ProtocolError` - Payload is not a valid bundle
+    pub fn verify(&self, public_key_hex: &str) -> Result<EntitlementBundle, GatewardenError> {
+        verify_detached(
+            BUNDLE_SIGNING_PREFIX,
+            &self.payload,
+            &self.signature,
+            public_key_hex,
+        )?;
+
+        serde_json::from_str(&self.payload).map_err(|e| GatewardenError::ProtocolError {
+            message: "Invalid bundle payload".to_string(),
+            source: Some(e.into()),
+        })
+    }
+
+    /// Serialize the signed bundle for distribution.
+    pub fn to_json(&self) -> Result<String, GatewardenError> {
+        serde_json::to_string_pretty(self).map_err(|e| GatewardenError::ProtocolError {
+            message: "Failed to serialize bundle".to_string(),
+            source: Some(e.into()),
+        })
+    }
+
+    /// Parse a distributed signed bundle (signature is not checked).
+    pub fn from_json(json: &str) -> Result<Self, GatewardenError> {
+        serde_json::from_str(json).map_err(|e| GatewardenError::ProtocolError {
+            message: "Invalid signed bundle".to_string(),
+            source: Some(e.into()),
+        })
+    }
+}
+
+/// Parse and verify a distributed signed bundle in one step.
+pub fn load_bundle(json: &str, public_key_hex: &str) -> Result<EntitlementBundle, GatewardenError> {
+    SignedBundle::from_json(json)?.verify(public_key_hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+
+    fn make_bundle() -> EntitlementBundle {
+        let mut bundle = EntitlementBundle {
+            version: 3,
+            ..Default::default()
+        };
+        bundle.features.insert(
+            "vision".to_string(),
+            FeatureDefinition {
+                entitlements: vec!["VISION_ANALYSIS".to_string()],
+                description: None,
+            },
+        );
+        bundle
+            .tiers
+            .insert("pro".to_string(), vec!["vision".to_string()]);
+        bundle
+    }
+
+    fn make_state(entitlements: &[&str]) -> LicenseState {
+        LicenseState {
+            valid: true,
+            entitlements: entitlements.iter().map(|s| s.to_string()).collect(),
+            expires_at: None,
+            max_uses: None,
+            current_uses: None,
+            code: "VALID".to_string(),
+            detail: None,
+            management_link: None,
+            license_id: None,
+            policy_id: None,
+            product_id: None,
+            trial: false,
+            upgrade: Default::default(),
+            max_version: None,
+            group: None,
+            owner: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn test_bundle_sign_verify_roundtrip() {
+        let bundle = make_bundle();
+        let signed = SignedBundle::sign(&bundle, &TEST_SIGNING_SEED).unwrap();
+        let json = signed.to_json().unwrap();
+
+        let loaded = load_bundle(&json, TEST_PUBLIC_KEY_HEX).unwrap();
+        assert_eq!(loaded, bundle);
+        assert_eq!(loaded.features_in_tier("pro").unwrap(), ["vision"]);
+    }
+
+    #[test]
+    fn test_bundle_tampered_payload() {
+        let signed = SignedBundle::sign(&make_bundle(), &TEST_SIGNING_SEED).unwrap();
+        let tampered = SignedBundle {
+            payload: signed.payload.replace("VISION_ANALYSIS", "EVERYTHING"),
+            signature: signed.signature,
+        };
+
+        let result = tampered.verify(TEST_PUBLIC_KEY_HEX);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bundle_wrong_key() {
+        let signed = SignedBundle::sign(&make_bundle(), &TEST_SIGNING_SEED).unwrap();
+        let other_key = "799efc7752286e6c3815b13358d98fc0f0b566764458adcb48f1be2c10a55906";
+
+        let result = signed.verify(other_key);
+        assert!(matches!(
+            result,
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_feature_enabled() {
+        let bundle = make_bundle();
+
+        assert!(bundle.feature_enabled("vision", &make_state(&["VISION_ANALYSIS"])));
+        assert!(!bundle.feature_enabled("vision", &make_state(&["BASIC"])));
+        assert!(!bundle.feature_enabled("unknown", &make_state(&["VISION_ANALYSIS"])));
+
+        let mut invalid = make_state(&["VISION_ANALYSIS"]);
+        invalid.valid = false;
+        assert!(!bundle.feature_enabled("vision", &invalid));
+    }
+}
@@ -0,0 +1,201 @@
+//! Entitlement policies with OR-groups.
+//!
+//! `required_entitlements` means "all of these codes". Tiered products
+//! often need "PRO or ENTERPRISE" instead, so an [`EntitlementPolicy`]
+//! combines codes with `AllOf` and `AnyOf`.
+//!
+//! Keygen checks scoped entitlements itself, only echoes back the codes
+//! that were requested, and rejects a scope unless the license has every
+//! code in it. With a policy, the manager therefore fetches the license's
+//! signed entitlement list, evaluates the policy locally, and validates
+//! once with the policy's codes the license holds in scope (or all of
+//! them, if it is not satisfied, so Keygen reports why).
+
+use crate::GatewardenError;
+use std::fmt;
+
+/// A boolean combination of entitlement codes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntitlementPolicy {
+    /// The license must have this code.
+    Code(String),
+    /// Every sub-policy must hold (an empty group always holds).
+    AllOf(Vec<EntitlementPolicy>),
+    /// At least one sub-policy must hold (an empty group never holds).
+    AnyOf(Vec<EntitlementPolicy>),
+}
+
+impl EntitlementPolicy {
+    /// A single required code.
+    pub fn code(code: &str) -> Self {
+        Self::Code(code.to_string())
+    }
+
+    /// Require all of `codes`.
+    pub fn all_of<'a>(codes: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::AllOf(codes.into_iter().map(Self::code).collect())
+    }
+
+    /// Require any one of `codes`.
+    pub fn any_of<'a>(codes: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::AnyOf(codes.into_iter().map(Self::code).collect())
+    }
+
+    /// Whether a license with `entitlements` satisfies the policy.
+    pub fn is_satisfied_by(&self, entitlements: &[String]) -> bool {
+        match self {
+            Self::Code(code) => entitlements.iter().any(|e| e == code),
+            Self::AllOf(policies) => policies.iter().all(|p| p.is_satisfied_by(entitlements)),
+            Self::AnyOf(policies) => policies.iter().any(|p| p.is_satisfied_by(entitlements)),
+        }
+    }
+
+    /// Check the policy against a license's entitlements.
+    ///
+    /// # Errors
+    /// - `EntitlementMissing` - Names the first unsatisfied code or group
+    pub fn check(&self, entitlements: &[String]) -> Result<(), GatewardenError> {
+        match self {
+            Self::AllOf(policies) => policies.iter().try_for_each(|p| p.check(entitlements)),
+            _ if self.is_satisfied_by(entitlements) => Ok(()),
+            _ => Err(GatewardenError::EntitlementMissing {
+                code: self.to_string(),
+            }),
+        }
+    }
+
+    /// Every code the policy names, in order of first mention.
+    pub fn codes(&self) -> Vec<String> {
+        match self {
+            Self::Code(code) => vec![code.clone()],
+            Self::AllOf(policies) | Self::AnyOf(policies) => policies
+                .iter()
+                .fold(Vec::new(), |codes, p| union(&codes, &p.codes())),
+        }
+    }
+
+    /// Sets of codes that each satisfy the policy, in preference order.
+    pub fn alternatives(&self) -> Vec<Vec<String>> {
+        let alternatives = match self {
+            Self::Code(code) => vec![vec![code.clone()]],
+            Self::AnyOf(policies) => policies.iter().flat_map(|p| p.alternatives()).collect(),
+            Self::AllOf(policies) => policies.iter().fold(vec![Vec::new()], |combined, policy| {
+                let options = policy.alternatives();
+                combined
+                    .iter()
+                    .flat_map(|base| options.iter().map(move |option| union(base, option)))
+                    .collect()
+            }),
+        };
+
+        let mut unique: Vec<Vec<String>> = Vec::new();
+        for alternative in alternatives {
+            if !unique.iter().any(|u| same_codes(u, &alternative)) {
+                unique.push(alternative);
+            }
+        }
+        unique
+    }
+}
+
+impl fmt::Display for EntitlementPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (label, policies) = match self {
+            Self::Code(code) => return f.write_str(code),
+            Self::AllOf(policies) => ("all of", policies),
+            Self::AnyOf(policies) => ("any of", policies),
+        };
+        let parts: Vec<String> = policies.iter().map(|p| p.to_string()).collect();
+        write!(f, "{} ({})", label, parts.join(", "))
+    }
+}
+
+/// Codes in `a` followed by those in `b` not already present.
+fn union(a: &[String], b: &[String]) -> Vec<String> {
+    let mut out = a.to_vec();
+    for code in b {
+        if !out.contains(code) {
+            out.push(code.clone());
+        }
+    }
+    out
+}
+
+/// Whether two code lists contain the same codes.
+fn same_codes(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && a.iter().all(|code| b.contains(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(list: &[&str]) -> Vec<String> {
+        list.iter().map(|c| c.to_string()).collect()
+    }
+
+    fn tiered() -> EntitlementPolicy {
+        EntitlementPolicy::AllOf(vec![
+            EntitlementPolicy::code("VISION"),
+            EntitlementPolicy::any_of(["PRO", "ENTERPRISE"]),
+        ])
+    }
+
+    #[test]
+    fn test_policy_satisfaction() {
+        let policy = tiered();
+        assert!(policy.is_satisfied_by(&codes(&["VISION", "PRO"])));
+        assert!(policy.is_satisfied_by(&codes(&["ENTERPRISE", "VISION"])));
+        assert!(!policy.is_satisfied_by(&codes(&["PRO"])));
+        assert!(!policy.is_satisfied_by(&codes(&["VISION", "BASIC"])));
+
+        assert!(EntitlementPolicy::AllOf(vec![]).is_satisfied_by(&[]));
+        assert!(!EntitlementPolicy::AnyOf(vec![]).is_satisfied_by(&codes(&["PRO"])));
+    }
+
+    #[test]
+    fn test_policy_check_names_missing_group() {
+        let policy = tiered();
+        assert!(policy.check(&codes(&["VISION", "PRO"])).is_ok());
+        assert!(matches!(
+            policy.check(&codes(&["VISION"])),
+            Err(GatewardenError::EntitlementMissing { code }) if code == "any of (PRO, ENTERPRISE)"
+        ));
+        assert!(matches!(
+            policy.check(&codes(&["PRO"])),
+            Err(GatewardenError::EntitlementMissing { code }) if code == "VISION"
+        ));
+    }
+
+    #[test]
+    fn test_policy_codes() {
+        assert_eq!(tiered().codes(), codes(&["VISION", "PRO", "ENTERPRISE"]));
+
+        let policy = EntitlementPolicy::AnyOf(vec![
+            EntitlementPolicy::all_of(["A", "B"]),
+            EntitlementPolicy::all_of(["B", "C"]),
+        ]);
+        assert_eq!(policy.codes(), codes(&["A", "B", "C"]));
+    }
+
+    #[test]
+    fn test_policy_alternatives() {
+        assert_eq!(
+            tiered().alternatives(),
+            vec![codes(&["VISION", "PRO"]), codes(&["VISION", "ENTERPRISE"])]
+        );
+
+        // Duplicate alternatives collapse
+        let policy = EntitlementPolicy::AnyOf(vec![
+            EntitlementPolicy::all_of(["A", "B"]),
+            EntitlementPolicy::all_of(["B", "A"]),
+        ]);
+        assert_eq!(policy.alternatives(), vec![codes(&["A", "B"])]);
+
+        assert_eq!(
+            EntitlementPolicy::AllOf(vec![]).alternatives(),
+            vec![Vec::<String>::new()]
+        );
+        assert!(EntitlementPolicy::AnyOf(vec![]).alternatives().is_empty());
+    }
+}
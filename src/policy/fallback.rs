@@ -0,0 +1,195 @@
+//! Offline fallback policy.
+//!
+//! Decides which online failures may be answered from the authenticated
+//! cache. Security failures (bad signature, digest mismatch, replay, etc.)
+//! are never fallback-eligible regardless of policy: a tampered response
+//! must not be papered over with a cached one.
+
+use crate::{GatewardenError, TransportFailure};
+use serde::{Deserialize, Serialize};
+
+/// Which online failures trigger fallback to the offline cache.
+///
+/// The default falls back whenever Keygen is unavailable (unreachable or
+/// answering HTTP 5xx), but not when it rate-limits the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FallbackPolicy {
+    /// Fall back when Keygen cannot be reached (DNS, connect, TLS, timeout).
+    pub on_network_error: bool,
+
+    /// Fall back when Keygen responds with HTTP 5xx.
+    pub on_server_error: bool,
+
+    /// Fall back when Keygen responds with HTTP 429.
+    pub on_rate_limited: bool,
+}
+
+/// Why an online validation was answered from the offline cache.
+///
+/// Serialized with a `kind` tag, e.g. `{"kind": "network", "failure": "dns"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FallbackReason {
+    /// Keygen could not be reached.
+    Network {
+        /// Which stage of the request failed.
+        failure: TransportFailure,
+    },
+    /// Keygen responded with an HTTP 5xx status.
+    ServerError {
+        /// HTTP status code returned by Keygen.
+        status: u16,
+    },
+    /// Keygen responded with HTTP 429.
+    RateLimited,
+    /// The key reached `validation_rate_limit`, so Keygen was not asked.
+    Throttled,
+    /// Offline-first startup answered from the cache before asking Keygen;
+    /// the online validation runs in the background.
+    Deferred,
+}
+
+impl FallbackReason {
+    /// The availability failure behind `error`, if it is one.
+    ///
+    /// Returns `None` for license, security, and local errors, which are
+    /// never fallback-eligible.
+    pub fn of(error: &GatewardenError) -> Option<Self> {
+        match error {
+            GatewardenError::KeygenTransport { kind, .. } => Some(Self::Network { failure: *kind }),
+            GatewardenError::ServerError { status, .. } => {
+                Some(Self::ServerError { status: *status })
+            }
+            GatewardenError::RateLimited { .. } => Some(Self::RateLimited),
+            _ => None,
+        }
+    }
+}
+
+impl FallbackPolicy {
+    /// Never fall back; every online failure is returned to the caller.
+    pub const STRICT: Self = Self {
+        on_network_error: false,
+        on_server_error: false,
+        on_rate_limited: false,
+    };
+
+    /// Fall back on any availability failure (network, 5xx, 429).
+    pub const AVAILABLE: Self = Self {
+        on_network_error: true,
+        on_server_error: true,
+        on_rate_limited: true,
+    };
+
+    /// Whether the given online error may be answered from cache.
+    pub fn allows(&self, error: &GatewardenError) -> bool {
+        self.reason(error).is_some()
+    }
+
+    /// Why `error` may be answered from cache, or `None` if it may not.
+    pub fn reason(&self, error: &GatewardenError) -> Option<FallbackReason> {
+        let reason = FallbackReason::of(error)?;
+        let allowed = match reason {
+            FallbackReason::Network { .. } => self.on_network_error,
+            FallbackReason::ServerError { .. } => self.on_server_error,
+            FallbackReason::RateLimited => self.on_rate_limited,
+            // Not online failures; never produced by `of`
+            FallbackReason::Throttled | FallbackReason::Deferred => true,
+        };
+        allowed.then_some(reason)
+    }
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self {
+            on_network_error: true,
+            on_server_error: true,
+            on_rate_limited: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transport(kind: TransportFailure) -> GatewardenError {
+        GatewardenError::KeygenTransport {
+            message: "down".to_string(),
+            kind,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_default_falls_back_when_unavailable() {
+        let policy = FallbackPolicy::default();
+        assert!(policy.allows(&transport(TransportFailure::Dns)));
+        assert!(policy.allows(&GatewardenError::ServerError {
+            status: 503,
+            request_id: None
+        }));
+        assert!(!policy.allows(&GatewardenError::RateLimited { request_id: None }));
+    }
+
+    #[test]
+    fn test_strict_never_falls_back() {
+        let policy = FallbackPolicy::STRICT;
+        assert!(!policy.allows(&transport(TransportFailure::Connect)));
+        assert!(!policy.allows(&GatewardenError::ServerError {
+            status: 500,
+            request_id: None
+        }));
+    }
+
+    #[test]
+    fn test_available_allows_service_failures() {
+        let policy = FallbackPolicy::AVAILABLE;
+        assert!(policy.allows(&GatewardenError::ServerError {
+            status: 502,
+            request_id: None
+        }));
+        assert!(policy.allows(&GatewardenError::RateLimited { request_id: None }));
+    }
+
+    #[test]
+    fn test_reasons_are_typed() {
+        let policy = FallbackPolicy::AVAILABLE;
+        assert_eq!(
+            policy.reason(&transport(TransportFailure::Tls)),
+            Some(FallbackReason::Network {
+                failure: TransportFailure::Tls
+            })
+        );
+        assert_eq!(
+            policy.reason(&GatewardenError::ServerError {
+                status: 502,
+                request_id: None
+            }),
+            Some(FallbackReason::ServerError { status: 502 })
+        );
+        assert_eq!(
+            FallbackPolicy::STRICT.reason(&GatewardenError::RateLimited { request_id: None }),
+            None
+        );
+        assert_eq!(
+            FallbackReason::of(&GatewardenError::RateLimited { request_id: None }),
+            Some(FallbackReason::RateLimited)
+        );
+    }
+
+    #[test]
+    fn test_security_errors_never_fall_back() {
+        let policy = FallbackPolicy::AVAILABLE;
+        assert!(!policy.allows(&GatewardenError::SignatureInvalid { request_id: None }));
+        assert!(!policy.allows(&GatewardenError::SignatureMissing { request_id: None }));
+        assert!(!policy.allows(&GatewardenError::DigestMismatch { request_id: None }));
+        assert!(!policy.allows(&GatewardenError::ResponseTooOld {
+            age_seconds: 600,
+            request_id: None
+        }));
+        assert!(!policy.allows(&GatewardenError::InvalidLicense));
+        assert!(!policy.allows(&GatewardenError::TlsPinMismatch));
+    }
+}
@@ -0,0 +1,262 @@
+//! License key normalization and format checks.
+//!
+//! Users paste keys with stray whitespace, in lower case, or with the
+//! dashes dropped. A [`KeyFormat`] normalizes keys before they are hashed
+//! for the cache or sent to Keygen, so every spelling of a key maps to the
+//! same cache record, and rejects keys that cannot be valid without a
+//! round trip. Rejections are [`GatewardenError::MalformedLicenseKey`],
+//! whose reason never includes the key.
+//!
+//! The default format leaves keys untouched and accepts anything
+//! non-empty.
+
+use crate::GatewardenError;
+use std::borrow::Cow;
+
+/// Check digit scheme carried in the last character of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckDigit {
+    /// Luhn over decimal digits (as used by card numbers).
+    Luhn,
+
+    /// Luhn mod 36 over digits and letters, case-insensitive.
+    LuhnMod36,
+}
+
+/// How license keys are normalized and checked before use.
+///
+/// Normalization runs first; the prefix, length and check digit are then
+/// checked against the normalized key, so give the prefix in normalized
+/// form.
+///
+/// ```
+/// use gatewarden::policy::key_format::{CheckDigit, KeyFormat};
+///
+/// let format = KeyFormat::new()
+///     .trim()
+///     .uppercase()
+///     .strip_separators()
+///     .prefix("GW")
+///     .length(12)
+///     .check_digit(CheckDigit::LuhnMod36);
+/// assert_eq!(format.normalize(" gw12-3456-7892 ").unwrap(), "GW1234567892");
+/// assert!(format.normalize("GW1234567890").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyFormat {
+    trim: bool,
+    uppercase: bool,
+    strip_separators: bool,
+    prefix: Option<&'static str>,
+    length: Option<usize>,
+    check_digit: Option<CheckDigit>,
+}
+
+impl KeyFormat {
+    /// A format that leaves keys untouched and accepts anything non-empty.
+    pub const fn new() -> Self {
+        Self {
+            trim: false,
+            uppercase: false,
+            strip_separators: false,
+            prefix: None,
+            length: None,
+            check_digit: None,
+        }
+    }
+
+    /// Remove leading and trailing whitespace.
+    pub const fn trim(mut self) -> Self {
+        self.trim = true;
+        self
+    }
+
+    /// Convert ASCII letters to upper case.
+    pub const fn uppercase(mut self) -> Self {
+        self.uppercase = true;
+        self
+    }
+
+    /// Remove dashes and whitespace anywhere in the key.
+    pub const fn strip_separators(mut self) -> Self {
+        self.strip_separators = true;
+        self
+    }
+
+    /// Require keys to start with `prefix`.
+    pub const fn prefix(mut self, prefix: &'static str) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Require keys to be exactly `length` characters long.
+    pub const fn length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Require the last character to be a `scheme` check digit over the
+    /// rest of the key after the prefix; dashes and whitespace are skipped.
+    pub const fn check_digit(mut self, scheme: CheckDigit) -> Self {
+        self.check_digit = Some(scheme);
+        self
+    }
+
+    /// Normalize `license_key` and check its format.
+    ///
+    /// Borrows the key unchanged when normalization has nothing to do.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - The key is empty after normalization
+    /// - `MalformedLicenseKey` - The key has the wrong prefix, length or
+    ///   check digit
+    pub fn normalize<'a>(&self, license_key: &'a str) -> Result<Cow<'a, str>, GatewardenError> {
+        let mut key = Cow::Borrowed(license_key);
+        if self.trim {
+            key = match key {
+                Cow::Borrowed(k) => Cow::Borrowed(k.trim()),
+                Cow::Owned(k) => Cow::Owned(k.trim().to_string()),
+            };
+        }
+        if self.strip_separators && key.contains(is_separator) {
+            key = Cow::Owned(key.chars().filter(|&c| !is_separator(c)).collect());
+        }
+        if self.uppercase && key.chars().any(|c| c.is_ascii_lowercase()) {
+            key = Cow::Owned(key.to_ascii_uppercase());
+        }
+
+        if key.is_empty() {
+            return Err(GatewardenError::MissingLicense);
+        }
+        let body = match self.prefix {
+            Some(prefix) => key
+                .strip_prefix(prefix)
+                .ok_or_else(|| malformed("missing the expected prefix"))?,
+            None => &key,
+        };
+        if self
+            .length
+            .is_some_and(|length| key.chars().count() != length)
+        {
+            return Err(malformed("wrong length"));
+        }
+        if let Some(scheme) = self.check_digit {
+            if !scheme.verify(body) {
+                return Err(malformed("check digit mismatch"));
+            }
+        }
+        Ok(key)
+    }
+}
+
+impl CheckDigit {
+    fn radix(self) -> u32 {
+        match self {
+            Self::Luhn => 10,
+            Self::LuhnMod36 => 36,
+        }
+    }
+
+    /// Whether the last character of `body` is the check digit for the rest.
+    fn verify(self, body: &str) -> bool {
+        let radix = self.radix();
+        let mut chars = body.chars().filter(|&c| !is_separator(c)).rev().peekable();
+        if chars.peek().is_none() {
+            return false;
+        }
+
+        // Luhn mod N: double every second value from the right, counting
+        // the check digit as the first
+        let mut sum = 0;
+        for (i, c) in chars.enumerate() {
+            let Some(value) = c.to_digit(radix) else {
+                return false;
+            };
+            let addend = if i % 2 == 1 { value * 2 } else { value };
+            sum += addend / radix + addend % radix;
+        }
+        sum % radix == 0
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    c == '-' || c.is_whitespace()
+}
+
+fn malformed(reason: &str) -> GatewardenError {
+    GatewardenError::MalformedLicenseKey {
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_format_is_passthrough() {
+        let format = KeyFormat::default();
+        assert!(matches!(
+            format.normalize(" abc-1 "),
+            Ok(Cow::Borrowed(" abc-1 "))
+        ));
+        assert!(matches!(
+            format.normalize(""),
+            Err(GatewardenError::MissingLicense)
+        ));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let format = KeyFormat::new().trim().uppercase().strip_separators();
+        assert_eq!(format.normalize("  ab12-cd34 \n").unwrap(), "AB12CD34");
+        assert_eq!(format.normalize("AB12CD34").unwrap(), "AB12CD34");
+        assert!(matches!(
+            format.normalize(" - "),
+            Err(GatewardenError::MissingLicense)
+        ));
+
+        let trim_only = KeyFormat::new().trim();
+        assert_eq!(trim_only.normalize(" ab-12 ").unwrap(), "ab-12");
+    }
+
+    #[test]
+    fn test_prefix_and_length() {
+        let format = KeyFormat::new().prefix("GW").length(6);
+        assert!(format.normalize("GW1234").is_ok());
+        assert!(matches!(
+            format.normalize("XX1234"),
+            Err(GatewardenError::MalformedLicenseKey { .. })
+        ));
+        assert!(matches!(
+            format.normalize("GW12345"),
+            Err(GatewardenError::MalformedLicenseKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_digits() {
+        // Classic Luhn example number
+        let luhn = KeyFormat::new().check_digit(CheckDigit::Luhn);
+        assert!(luhn.normalize("7992-7398-713").is_ok());
+        assert!(luhn.normalize("79927398710").is_err());
+        assert!(luhn.normalize("7992739871A").is_err());
+
+        // Mod 36: "A1" doubles 1, so the check character is
+        // 36 - (10 + 2) = 24 = 'O'
+        let mod36 = KeyFormat::new()
+            .prefix("GW-")
+            .check_digit(CheckDigit::LuhnMod36);
+        assert!(mod36.normalize("GW-A1O").is_ok());
+        assert!(mod36.normalize("GW-a1o").is_ok());
+        assert!(mod36.normalize("GW-A1P").is_err());
+        assert!(mod36.normalize("GW-").is_err());
+    }
+
+    #[test]
+    fn test_errors_omit_key() {
+        let format = KeyFormat::new().length(4);
+        let error = format.normalize("SECRET-KEY").unwrap_err();
+        assert!(!error.to_string().contains("SECRET"));
+    }
+}
@@ -0,0 +1,186 @@
+//! Signed, expiring management links.
+//!
+//! Denial screens often offer a "Manage seats" or "Renew" button. If the
+//! URL behind it comes from an unauthenticated source, anyone who can
+//! influence that source can point users at a phishing page. Vendors sign
+//! management links with their own Ed25519 key (as with entitlement
+//! bundles) and attach them to the license, typically in Keygen license
+//! metadata under `managementLink`; the application only opens a link
+//! that verifies, has not expired, and uses HTTPS.
+
+use crate::clock::Clock;
+use crate::crypto::detached::{sign_detached, verify_detached};
+use crate::GatewardenError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Domain-separation prefix for management link signatures.
+const LINK_SIGNING_PREFIX: &str = "gatewarden-management-link-v1\n";
+
+/// A management URL and the time after which it must not be opened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManagementLink {
+    /// Destination URL (must be `https://`).
+    pub url: String,
+
+    /// Link expiry.
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A link payload together with its detached signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedLink {
+    /// The link as signed JSON.
+    pub payload: String,
+
+    /// Base64-encoded Ed25519 signature over the prefixed payload.
+    pub signature: String,
+}
+
+impl SignedLink {
+    /// Sign a link with the vendor's Ed25519 signing key seed.
+    ///
+    /// Intended for vendor-side tooling; applications only need [`verify`](Self::verify).
+    pub fn sign(link: &ManagementLink, signing_seed: &[u8; 32]) -> Result<Self, GatewardenError> {
+        check_https(&link.url)?;
+
+        let payload = serde_json::to_string(link).map_err(|e| GatewardenError::ProtocolError {
+            message: "Failed to serialize link".to_string(),
+            source: Some(e.into()),
+        })?;
+
+        let signature = sign_detached(LINK_SIGNING_PREFIX, &payload, signing_seed);
+        Ok(Self { payload, signature })
+    }
+
+    /// Verify the signature, expiry, and scheme, and return the link.
+    ///
+    /// # Errors
+    /// - `SignatureInvalid` - Signature does not match the payload
+    /// - `LinkExpired` - The link is past its expiry
+    /// - `ConfigError` - Public key is malformed
+    /// - `ProtocolError` - Payload is malformed or the URL is not HTTPS
+    pub fn verify(
+        &self,
+        public_key_hex: &str,
+        clock: &dyn Clock,
+    ) -> Result<ManagementLink, GatewardenError> {
+        verify_detached(
+            LINK_SIGNING_PREFIX,
+            &self.payload,
+            &self.signature,
+            public_key_hex,
+        )?;
+
+        let link: ManagementLink =
+            serde_json::from_str(&self.payload).map_err(|e| GatewardenError::ProtocolError {
+                message: "Invalid link payload".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        if clock.now_utc() >= link.expires_at {
+            return Err(GatewardenError::LinkExpired {
+                expires_at: link.expires_at,
+            });
+        }
+        check_https(&link.url)?;
+
+        Ok(link)
+    }
+}
+
+/// Reject anything other than an absolute `https://` URL with a host.
+pub(crate) fn check_https(url: &str) -> Result<(), GatewardenError> {
+    let host = url
+        .strip_prefix("https://")
+        .and_then(|rest| rest.split(['/', '?', '#']).next())
+        .unwrap_or_default();
+
+    if host.is_empty() || host.contains('@') {
+        return Err(GatewardenError::ProtocolError {
+            message: "Management link must be an https:// URL".to_string(),
+            source: None,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use chrono::{Duration, TimeZone};
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap()
+    }
+
+    fn make_link(url: &str) -> ManagementLink {
+        ManagementLink {
+            url: url.to_string(),
+            expires_at: now() + Duration::days(7),
+        }
+    }
+
+    #[test]
+    fn test_link_sign_verify_roundtrip() {
+        let link = make_link("https://portal.example.com/seats?license=abc");
+        let signed = SignedLink::sign(&link, &TEST_SIGNING_SEED).unwrap();
+
+        let verified = signed
+            .verify(TEST_PUBLIC_KEY_HEX, &MockClock::new(now()))
+            .unwrap();
+        assert_eq!(verified, link);
+    }
+
+    #[test]
+    fn test_link_spoofed_url_rejected() {
+        let signed = SignedLink::sign(
+            &make_link("https://portal.example.com/seats"),
+            &TEST_SIGNING_SEED,
+        )
+        .unwrap();
+        let spoofed = SignedLink {
+            payload: signed
+                .payload
+                .replace("portal.example.com", "phish.example"),
+            signature: signed.signature,
+        };
+
+        assert!(matches!(
+            spoofed.verify(TEST_PUBLIC_KEY_HEX, &MockClock::new(now())),
+            Err(GatewardenError::SignatureInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_link_expired() {
+        let signed = SignedLink::sign(
+            &make_link("https://portal.example.com/seats"),
+            &TEST_SIGNING_SEED,
+        )
+        .unwrap();
+
+        let later = MockClock::new(now() + Duration::days(8));
+        assert!(matches!(
+            signed.verify(TEST_PUBLIC_KEY_HEX, &later),
+            Err(GatewardenError::LinkExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_link_requires_https() {
+        for url in [
+            "http://portal.example.com",
+            "javascript:alert(1)",
+            "https://",
+            "https://user@phish.example/",
+        ] {
+            assert!(
+                SignedLink::sign(&make_link(url), &TEST_SIGNING_SEED).is_err(),
+                "{url}"
+            );
+        }
+    }
+}
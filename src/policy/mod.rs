@@ -1,3 +1,10 @@
 //! Access policy enforcement.
 
 pub mod access;
+pub mod bundle;
+pub mod entitlements;
+pub mod fallback;
+pub mod key_format;
+pub mod links;
+pub mod upgrade;
+pub(crate) mod version;
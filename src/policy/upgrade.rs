@@ -0,0 +1,212 @@
+//! Renewal and purchase links from license metadata.
+//!
+//! When a license expires or runs out of uses, the application should send
+//! the user somewhere to fix it. Vendors put those destinations in Keygen
+//! license metadata under `renewalUrl` and `purchaseUrl`. They arrive in
+//! the signed validation response, so they are as trustworthy as the
+//! license itself; only `https://` URLs are kept. For a destination the
+//! vendor must sign themselves, use a [`SignedLink`](super::links::SignedLink).
+//!
+//! Keygen does not include policy metadata in validation responses, so
+//! the URLs must be set on each license (or copied there by the vendor's
+//! backend when it issues one).
+
+use crate::client::request::encode_path_segment;
+use crate::policy::links::check_https;
+use crate::GatewardenError;
+use serde::{Deserialize, Serialize};
+
+/// Vendor-provided renewal and purchase URLs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpgradeLinks {
+    /// Where to renew the license (metadata `renewalUrl`).
+    pub renewal_url: Option<String>,
+
+    /// Where to buy a license or a higher tier (metadata `purchaseUrl`).
+    pub purchase_url: Option<String>,
+}
+
+/// Why the user is being sent to upgrade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpgradeReason {
+    /// The license has expired.
+    Expired,
+
+    /// A trial is ending or has ended.
+    TrialEnding,
+
+    /// The license has used up its `maxUses`.
+    UsageLimitExceeded,
+
+    /// The license lacks an entitlement.
+    EntitlementMissing(String),
+}
+
+impl UpgradeLinks {
+    /// Read `renewalUrl` and `purchaseUrl` from license metadata,
+    /// ignoring values that are not `https://` URLs.
+    pub(crate) fn from_metadata(metadata: Option<&serde_json::Value>) -> Self {
+        let url = |name: &str| {
+            metadata
+                .and_then(|m| m.get(name))
+                .and_then(serde_json::Value::as_str)
+                .filter(|url| check_https(url).is_ok())
+                .map(String::from)
+        };
+        Self {
+            renewal_url: url("renewalUrl"),
+            purchase_url: url("purchaseUrl"),
+        }
+    }
+
+    /// Whether neither URL is set.
+    pub fn is_empty(&self) -> bool {
+        self.renewal_url.is_none() && self.purchase_url.is_none()
+    }
+
+    /// The URL to send the user to for `reason`, prefilled with query
+    /// parameters the vendor's store can act on.
+    ///
+    /// Expiry and trials prefer the renewal URL, other reasons the
+    /// purchase URL; each falls back to the other. Adds `reason`, then
+    /// `license` (the license ID, never the key) and `entitlement` when
+    /// known. `None` if no URL is set.
+    pub fn url_for(&self, reason: &UpgradeReason, license_id: Option<&str>) -> Option<String> {
+        let (preferred, fallback) = match reason {
+            UpgradeReason::Expired | UpgradeReason::TrialEnding => {
+                (&self.renewal_url, &self.purchase_url)
+            }
+            _ => (&self.purchase_url, &self.renewal_url),
+        };
+        let base = preferred.as_ref().or(fallback.as_ref())?;
+
+        let mut params = vec![("reason", reason.as_str())];
+        if let Some(license_id) = license_id {
+            params.push(("license", license_id));
+        }
+        if let UpgradeReason::EntitlementMissing(code) = reason {
+            params.push(("entitlement", code));
+        }
+        let query = params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, encode_path_segment(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        // Keep any fragment at the end
+        let (url, fragment) = match base.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (base.as_str(), None),
+        };
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let mut prefilled = format!("{}{}{}", url, separator, query);
+        if let Some(fragment) = fragment {
+            prefilled.push('#');
+            prefilled.push_str(fragment);
+        }
+        Some(prefilled)
+    }
+}
+
+impl UpgradeReason {
+    /// The upgrade reason behind an error, if it has one.
+    ///
+    /// `LicenseExpired`, `UsageLimitExceeded`, and `EntitlementMissing`
+    /// map to their reasons; anything else is not fixed by upgrading.
+    pub fn from_error(error: &GatewardenError) -> Option<Self> {
+        match error {
+            GatewardenError::LicenseExpired { .. } => Some(Self::Expired),
+            GatewardenError::UsageLimitExceeded => Some(Self::UsageLimitExceeded),
+            GatewardenError::EntitlementMissing { code } => {
+                Some(Self::EntitlementMissing(code.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Value of the `reason` query parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Expired => "expired",
+            Self::TrialEnding => "trial_ending",
+            Self::UsageLimitExceeded => "usage_limit_exceeded",
+            Self::EntitlementMissing(_) => "entitlement_missing",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn links() -> UpgradeLinks {
+        UpgradeLinks::from_metadata(Some(&json!({
+            "renewalUrl": "https://store.example.com/renew",
+            "purchaseUrl": "https://store.example.com/buy?plan=pro#checkout",
+        })))
+    }
+
+    #[test]
+    fn test_from_metadata_keeps_only_https() {
+        assert_eq!(
+            links().renewal_url.as_deref(),
+            Some("https://store.example.com/renew")
+        );
+
+        let links = UpgradeLinks::from_metadata(Some(&json!({
+            "renewalUrl": "http://store.example.com/renew",
+            "purchaseUrl": "javascript:alert(1)",
+        })));
+        assert!(links.is_empty());
+        assert!(UpgradeLinks::from_metadata(None).is_empty());
+    }
+
+    #[test]
+    fn test_url_for_prefills_reason() {
+        let links = links();
+        assert_eq!(
+            links.url_for(&UpgradeReason::Expired, Some("lic 1")),
+            Some("https://store.example.com/renew?reason=expired&license=lic%201".to_string())
+        );
+        assert_eq!(
+            links.url_for(&UpgradeReason::EntitlementMissing("PRO".to_string()), None),
+            Some(
+                "https://store.example.com/buy?plan=pro&reason=entitlement_missing&entitlement=PRO#checkout"
+                    .to_string()
+            )
+        );
+
+        // Falls back to the other URL
+        let renewal_only = UpgradeLinks {
+            purchase_url: None,
+            ..links
+        };
+        assert_eq!(
+            renewal_only.url_for(&UpgradeReason::UsageLimitExceeded, None),
+            Some("https://store.example.com/renew?reason=usage_limit_exceeded".to_string())
+        );
+        assert_eq!(
+            UpgradeLinks::default().url_for(&UpgradeReason::Expired, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_reason_from_error() {
+        assert_eq!(
+            UpgradeReason::from_error(&GatewardenError::UsageLimitExceeded),
+            Some(UpgradeReason::UsageLimitExceeded)
+        );
+        assert_eq!(
+            UpgradeReason::from_error(&GatewardenError::EntitlementMissing {
+                code: "PRO".to_string()
+            }),
+            Some(UpgradeReason::EntitlementMissing("PRO".to_string()))
+        );
+        assert_eq!(
+            UpgradeReason::from_error(&GatewardenError::SignatureInvalid { request_id: None }),
+            None
+        );
+    }
+}
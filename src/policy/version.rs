@@ -0,0 +1,57 @@
+//! Dotted version numbers for `maxVersion` gating.
+//!
+//! Versions compare numerically, component by component (`1.10` is newer
+//! than `1.9`). A `maxVersion` covers every release it is a prefix of, so
+//! `2.4` covers `2.4.7` and `3` covers all of `3.x`. A leading `v` and any
+//! pre-release or build suffix (`-beta.1`, `+abc`) are ignored, so
+//! `2.0.0-rc.1` counts as `2.0.0`.
+
+/// Numeric components of `version`, or `None` if it is malformed.
+pub(crate) fn parse(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let release = version.split(['-', '+']).next().unwrap_or_default();
+    if release.is_empty() {
+        return None;
+    }
+    release
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+/// Whether `max_version` covers `version`; false if either is malformed.
+pub(crate) fn at_most(version: &str, max_version: &str) -> bool {
+    let (Some(mut version), Some(max_version)) = (parse(version), parse(max_version)) else {
+        return false;
+    };
+    // Missing components read as zero; extra ones are within the prefix
+    version.resize(max_version.len(), 0);
+    version <= max_version
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("1.2.3"), Some(vec![1, 2, 3]));
+        assert_eq!(parse("v2.0-beta.1"), Some(vec![2, 0]));
+        assert_eq!(parse("3+build.7"), Some(vec![3]));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("1.x"), None);
+        assert_eq!(parse("1..2"), None);
+    }
+
+    #[test]
+    fn test_at_most() {
+        assert!(at_most("1.9.0", "1.10"));
+        assert!(at_most("2.0", "2.0.0"));
+        assert!(at_most("2.4.7", "2.4"));
+        assert!(at_most("3.9.1-rc.1", "3"));
+        assert!(!at_most("2.5", "2.4"));
+        assert!(!at_most("2.0.1", "2.0.0"));
+        assert!(!at_most("1.0", "latest"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Security posture reporting.
+//!
+//! Which protections are in effect depends on configuration and platform:
+//! the cache backend may or may not encrypt at rest, the machine ID may be
+//! unreadable, and test constructors keep anti-rollback state in memory.
+//! [`SecurityPosture`] reports the effective set so vendors can assert it
+//! in tests and log it at startup.
+
+use crate::crypto::freshness::{MAX_FUTURE_TOLERANCE_SECONDS, MAX_RESPONSE_AGE_SECONDS};
+use crate::policy::fallback::FallbackPolicy;
+use std::fmt;
+use std::time::Duration;
+
+/// Maximum accepted response age, as a `Duration`.
+pub(crate) const FRESHNESS_WINDOW: Duration = Duration::from_secs(MAX_RESPONSE_AGE_SECONDS as u64);
+
+/// Maximum accepted response date in the future, as a `Duration`.
+pub(crate) const FUTURE_TOLERANCE: Duration =
+    Duration::from_secs(MAX_FUTURE_TOLERANCE_SECONDS as u64);
+
+/// Protections active for a `LicenseManager`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityPosture {
+    /// Ed25519 response signatures are verified (always on).
+    pub signature_verification: bool,
+
+    /// Body digests are checked against the signed `Digest` header when
+    /// Keygen sends one (always on).
+    pub digest_verification: bool,
+
+    /// Responses and cached records without a Digest header are rejected.
+    pub digest_required: bool,
+
+    /// Verify keys responses and cached records are checked against
+    /// (`public_key_hex` plus `trusted_keys`).
+    pub trusted_keys: usize,
+
+    /// Every verify key is bound to a signature `keyid`, so a key only
+    /// verifies signatures made as its own account.
+    pub key_id_binding: bool,
+
+    /// Keygen's TLS certificate chain must match a configured SPKI pin.
+    pub tls_pinning: bool,
+
+    /// Responses older than this are rejected as replays.
+    pub freshness_window: Duration,
+
+    /// Responses dated further ahead than this are rejected.
+    pub future_tolerance: Duration,
+
+    /// Offline grace for the configured feature.
+    pub offline_grace: Duration,
+
+    /// Hard ceiling on cached record age.
+    pub max_cache_age: Duration,
+
+    /// Cached records are encrypted at rest by the cache backend.
+    pub cache_encryption: bool,
+
+    /// The clock-rollback high-water mark persists across restarts.
+    pub anti_rollback: bool,
+
+    /// Usage meters are bound to this machine's fingerprint.
+    pub fingerprint_binding: bool,
+
+    /// Which online failures may fall back to the cache.
+    pub fallback_policy: FallbackPolicy,
+}
+
+impl fmt::Display for SecurityPosture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        write!(
+            f,
+            "signature={} digest={} keys={} keyid_binding={} tls_pinning={} \
+             freshness={}s future_tolerance={}s offline_grace={}s \
+             max_cache_age={}s cache_encryption={} anti_rollback={} \
+             fingerprint_binding={} fallback={}",
+            on_off(self.signature_verification),
+            match (self.digest_verification, self.digest_required) {
+                (true, true) => "required",
+                (enabled, _) => on_off(enabled),
+            },
+            self.trusted_keys,
+            on_off(self.key_id_binding),
+            on_off(self.tls_pinning),
+            self.freshness_window.as_secs(),
+            self.future_tolerance.as_secs(),
+            self.offline_grace.as_secs(),
+            self.max_cache_age.as_secs(),
+            on_off(self.cache_encryption),
+            on_off(self.anti_rollback),
+            on_off(self.fingerprint_binding),
+            fallback_label(&self.fallback_policy),
+        )
+    }
+}
+
+/// Short label for a fallback policy, e.g. `network+5xx`.
+fn fallback_label(policy: &FallbackPolicy) -> String {
+    let mut parts = Vec::new();
+    if policy.on_network_error {
+        parts.push("network");
+    }
+    if policy.on_server_error {
+        parts.push("5xx");
+    }
+    if policy.on_rate_limited {
+        parts.push("429");
+    }
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join("+")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posture_display() {
+        let posture = SecurityPosture {
+            signature_verification: true,
+            digest_verification: true,
+            digest_required: false,
+            trusted_keys: 2,
+            key_id_binding: false,
+            tls_pinning: true,
+            freshness_window: FRESHNESS_WINDOW,
+            future_tolerance: FUTURE_TOLERANCE,
+            offline_grace: Duration::from_secs(86400),
+            max_cache_age: Duration::from_secs(90 * 86400),
+            cache_encryption: false,
+            anti_rollback: true,
+            fingerprint_binding: true,
+            fallback_policy: FallbackPolicy::default(),
+        };
+
+        let line = posture.to_string();
+        assert!(line.contains("digest=on keys=2 keyid_binding=off tls_pinning=on"));
+        assert!(line.contains("freshness=300s"));
+        assert!(line.contains("cache_encryption=off"));
+        assert!(line.contains("anti_rollback=on"));
+        assert!(line.ends_with("fallback=network+5xx"));
+
+        assert_eq!(fallback_label(&FallbackPolicy::STRICT), "none");
+        assert_eq!(
+            fallback_label(&FallbackPolicy::AVAILABLE),
+            "network+5xx+429"
+        );
+    }
+}
@@ -1,8 +1,14 @@
 //! Keygen response structs and license state extraction.
 
+use crate::clock::Clock;
+use crate::policy::links::SignedLink;
+use crate::policy::upgrade::UpgradeLinks;
+use crate::redact::RedactedKey;
 use crate::GatewardenError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroize;
 
 /// Raw Keygen validate-key response.
 #[derive(Debug, Clone, Deserialize)]
@@ -34,6 +40,9 @@ pub struct KeygenScopeMeta {
     /// List of entitlement codes present on this license.
     #[serde(default)]
     pub entitlements: Vec<String>,
+    /// User (email or ID) the validation was scoped to.
+    #[serde(default)]
+    pub user: Option<String>,
 }
 
 /// License data from response.
@@ -47,6 +56,321 @@ pub struct KeygenLicenseData {
     pub data_type: String,
     /// License attributes.
     pub attributes: KeygenLicenseAttributes,
+    /// License relationships.
+    #[serde(default)]
+    pub relationships: Option<KeygenLicenseRelationships>,
+}
+
+/// License relationships.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenLicenseRelationships {
+    /// The policy the license was issued under.
+    #[serde(default)]
+    pub policy: Option<KeygenRelationship>,
+    /// The product the license's policy belongs to.
+    #[serde(default)]
+    pub product: Option<KeygenRelationship>,
+    /// The group the license belongs to.
+    #[serde(default)]
+    pub group: Option<KeygenRelationship>,
+    /// The user who owns the license.
+    #[serde(default)]
+    pub owner: Option<KeygenRelationship>,
+}
+
+/// Single-license document (e.g. from the increment-usage action).
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenLicenseResponse {
+    /// License data.
+    pub data: KeygenLicenseData,
+}
+
+/// One page of a license's entitlements.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenEntitlementsResponse {
+    /// Entitlements on this page.
+    #[serde(default)]
+    pub data: Vec<KeygenEntitlementData>,
+    /// Pagination links.
+    #[serde(default)]
+    pub links: Option<KeygenPageLinks>,
+}
+
+/// Entitlement data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenEntitlementData {
+    /// Entitlement ID.
+    pub id: String,
+    /// Entitlement attributes.
+    pub attributes: KeygenEntitlementAttributes,
+}
+
+/// Entitlement attributes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenEntitlementAttributes {
+    /// Optional entitlement name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Entitlement code.
+    pub code: String,
+    /// Vendor-defined metadata.
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// Creation time as ISO 8601 string.
+    #[serde(default)]
+    pub created: Option<String>,
+}
+
+/// Pagination links of a list response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenPageLinks {
+    /// Link to the next page (`None` on the last page).
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// Release document (e.g. from the upgrade action).
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenReleaseResponse {
+    /// Release data.
+    pub data: KeygenReleaseData,
+}
+
+/// Release data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenReleaseData {
+    /// Release ID.
+    pub id: String,
+    /// Release attributes.
+    pub attributes: KeygenReleaseAttributes,
+}
+
+/// Release attributes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenReleaseAttributes {
+    /// Optional release name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Optional release notes.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Release version.
+    pub version: String,
+    /// Release channel (e.g. `stable`).
+    pub channel: String,
+    /// Vendor-defined metadata.
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// Creation time as ISO 8601 string.
+    #[serde(default)]
+    pub created: Option<String>,
+}
+
+/// One page of a release's artifacts.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenArtifactsResponse {
+    /// Artifacts on this page.
+    #[serde(default)]
+    pub data: Vec<KeygenArtifactData>,
+}
+
+/// Artifact document from a download request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenArtifactResponse {
+    /// Artifact data.
+    pub data: KeygenArtifactData,
+    /// Download links.
+    #[serde(default)]
+    pub links: Option<KeygenArtifactLinks>,
+}
+
+/// Links of an artifact download.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenArtifactLinks {
+    /// Time-limited download URL (also sent as the `Location` header).
+    #[serde(default)]
+    pub redirect: Option<String>,
+}
+
+/// Artifact data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenArtifactData {
+    /// Artifact ID.
+    pub id: String,
+    /// Artifact attributes.
+    pub attributes: KeygenArtifactAttributes,
+}
+
+/// Artifact attributes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenArtifactAttributes {
+    /// File name.
+    pub filename: String,
+    /// File type (e.g. `dmg`, `exe`).
+    #[serde(default)]
+    pub filetype: Option<String>,
+    /// File size in bytes.
+    #[serde(default)]
+    pub filesize: Option<u64>,
+    /// Target platform (e.g. `darwin`, `win32`).
+    #[serde(default)]
+    pub platform: Option<String>,
+    /// Target architecture (e.g. `arm64`, `x86_64`).
+    #[serde(default)]
+    pub arch: Option<String>,
+    /// Vendor-provided checksum of the file.
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Vendor-provided signature of the file.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// Token document from the tokens endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenTokenResponse {
+    /// Token data.
+    pub data: KeygenTokenData,
+}
+
+/// Group document.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenGroupResponse {
+    /// Group data.
+    pub data: KeygenGroupData,
+}
+
+/// Group data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenGroupData {
+    /// Group ID.
+    pub id: String,
+    /// Group attributes.
+    pub attributes: KeygenGroupAttributes,
+}
+
+/// Group attributes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeygenGroupAttributes {
+    /// Group name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Machines allowed across the group's licenses.
+    #[serde(default)]
+    pub max_machines: Option<u64>,
+    /// Users allowed in the group.
+    #[serde(default)]
+    pub max_users: Option<u64>,
+    /// Licenses allowed in the group.
+    #[serde(default)]
+    pub max_licenses: Option<u64>,
+}
+
+/// User document.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenUserResponse {
+    /// User data.
+    pub data: KeygenUserData,
+}
+
+/// User data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenUserData {
+    /// User ID.
+    pub id: String,
+    /// User attributes.
+    pub attributes: KeygenUserAttributes,
+}
+
+/// User attributes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeygenUserAttributes {
+    /// Full name.
+    #[serde(default)]
+    pub full_name: Option<String>,
+    /// Email address.
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// Machine document from the machines endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenMachineResponse {
+    /// Machine data.
+    pub data: KeygenMachineData,
+}
+
+/// Machine data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenMachineData {
+    /// Machine ID.
+    pub id: String,
+    /// Machine attributes.
+    pub attributes: KeygenMachineAttributes,
+}
+
+/// Machine attributes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeygenMachineAttributes {
+    /// Fingerprint the machine was activated with.
+    pub fingerprint: String,
+    /// Heartbeat status, e.g. `"ALIVE"` or `"RESURRECTED"`.
+    #[serde(default)]
+    pub heartbeat_status: Option<String>,
+}
+
+/// Token data from response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenTokenData {
+    /// Token ID.
+    pub id: String,
+    /// Token attributes.
+    pub attributes: KeygenTokenAttributes,
+    /// Token relationships.
+    #[serde(default)]
+    pub relationships: Option<KeygenTokenRelationships>,
+}
+
+/// Token attributes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenTokenAttributes {
+    /// Token kind (e.g. `activation-token`, `license-token`).
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// The bearer token itself.
+    pub token: String,
+    /// Expiry date as ISO 8601 string.
+    #[serde(default)]
+    pub expiry: Option<String>,
+}
+
+/// Token relationships.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenTokenRelationships {
+    /// The resource the token authenticates as.
+    #[serde(default)]
+    pub bearer: Option<KeygenRelationship>,
+}
+
+/// A to-one relationship.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenRelationship {
+    /// Linked resource (`None` if unset).
+    #[serde(default)]
+    pub data: Option<KeygenResourceId>,
+}
+
+/// Resource identifier object.
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeygenResourceId {
+    /// Resource type.
+    #[serde(rename = "type")]
+    pub data_type: String,
+    /// Resource ID.
+    pub id: String,
 }
 
 /// License attributes.
@@ -65,10 +389,16 @@ pub struct KeygenLicenseAttributes {
     /// Current use count.
     #[serde(default)]
     pub uses: Option<u64>,
+    /// Vendor-defined metadata.
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// The license key, redacted when formatted.
+    #[serde(default)]
+    pub key: Option<RedactedKey>,
 }
 
 /// Normalized license state extracted from Keygen response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LicenseState {
     /// Whether the license is valid.
     pub valid: bool,
@@ -90,6 +420,123 @@ pub struct LicenseState {
 
     /// Optional detail message.
     pub detail: Option<String>,
+
+    /// Vendor-signed management link from license metadata (unverified).
+    #[serde(default)]
+    pub management_link: Option<SignedLink>,
+
+    /// Keygen license ID (if returned).
+    #[serde(default)]
+    pub license_id: Option<String>,
+
+    /// Keygen ID of the license's policy (if returned).
+    #[serde(default)]
+    pub policy_id: Option<String>,
+
+    /// Keygen ID of the license's product (if returned).
+    #[serde(default)]
+    pub product_id: Option<String>,
+
+    /// Whether the license is a trial (license metadata `"trial": true`).
+    #[serde(default)]
+    pub trial: bool,
+
+    /// Renewal and purchase URLs from license metadata.
+    #[serde(default)]
+    pub upgrade: UpgradeLinks,
+
+    /// Newest application version the license covers (license metadata
+    /// `maxVersion`), checked against `GatewardenConfig::app_version`.
+    #[serde(default)]
+    pub max_version: Option<String>,
+
+    /// The group (e.g. a customer team) the license belongs to, if any.
+    /// Only the ID comes with validation; see
+    /// `LicenseManager::fetch_group_and_owner`.
+    #[serde(default)]
+    pub group: Option<LicenseGroup>,
+
+    /// The user who owns the license, if any. Only the ID comes with
+    /// validation, as for `group`.
+    #[serde(default)]
+    pub owner: Option<LicenseOwner>,
+
+    /// User (email or ID) the validation was scoped to, as echoed by
+    /// Keygen; see `LicenseManager::with_user`.
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// The group a license belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseGroup {
+    /// Keygen group ID.
+    pub id: String,
+
+    /// Group name (e.g. the customer's company), if fetched and set.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Machines (seats) allowed across the group, if fetched and set.
+    #[serde(default)]
+    pub max_machines: Option<u64>,
+
+    /// Users allowed in the group, if fetched and set.
+    #[serde(default)]
+    pub max_users: Option<u64>,
+
+    /// Licenses allowed in the group, if fetched and set.
+    #[serde(default)]
+    pub max_licenses: Option<u64>,
+}
+
+/// The user who owns a license.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseOwner {
+    /// Keygen user ID.
+    pub id: String,
+
+    /// Full name, if fetched and set.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Email address, if fetched.
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+impl LicenseGroup {
+    fn with_id(id: String) -> Self {
+        Self {
+            id,
+            name: None,
+            max_machines: None,
+            max_users: None,
+            max_licenses: None,
+        }
+    }
+}
+
+impl From<KeygenGroupData> for LicenseGroup {
+    fn from(data: KeygenGroupData) -> Self {
+        Self {
+            id: data.id,
+            name: data.attributes.name,
+            max_machines: data.attributes.max_machines,
+            max_users: data.attributes.max_users,
+            max_licenses: data.attributes.max_licenses,
+        }
+    }
+}
+
+impl From<KeygenUserData> for LicenseOwner {
+    fn from(data: KeygenUserData) -> Self {
+        Self {
+            id: data.id,
+            name: data.attributes.full_name,
+            email: data.attributes.email,
+        }
+    }
 }
 
 impl LicenseState {
@@ -118,6 +565,48 @@ impl LicenseState {
 
         let current_uses = response.data.as_ref().and_then(|d| d.attributes.uses);
 
+        // Signed management link, if the vendor attached one. Malformed
+        // metadata is ignored rather than failing validation.
+        let metadata = response
+            .data
+            .as_ref()
+            .and_then(|d| d.attributes.metadata.as_ref());
+        let management_link = metadata
+            .and_then(|m| m.get("managementLink"))
+            .and_then(|v| serde_json::from_value::<SignedLink>(v.clone()).ok());
+
+        // Keygen has no trial flag of its own; trial policies mark their
+        // licenses in metadata (as `start_trial` does)
+        let trial = metadata
+            .and_then(|m| m.get("trial"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+
+        let max_version = metadata
+            .and_then(|m| m.get("maxVersion"))
+            .and_then(serde_json::Value::as_str)
+            .map(String::from);
+
+        let relationships = response
+            .data
+            .as_ref()
+            .and_then(|d| d.relationships.as_ref());
+        let related_id = |relationship: Option<&KeygenRelationship>| {
+            relationship
+                .and_then(|r| r.data.as_ref())
+                .map(|r| r.id.clone())
+        };
+        let policy_id = related_id(relationships.and_then(|r| r.policy.as_ref()));
+        let product_id = related_id(relationships.and_then(|r| r.product.as_ref()));
+        let group =
+            related_id(relationships.and_then(|r| r.group.as_ref())).map(LicenseGroup::with_id);
+        let owner =
+            related_id(relationships.and_then(|r| r.owner.as_ref())).map(|id| LicenseOwner {
+                id,
+                name: None,
+                email: None,
+            });
+
         Ok(Self {
             valid: response.meta.valid,
             entitlements,
@@ -126,14 +615,190 @@ impl LicenseState {
             current_uses,
             code: response.meta.code.clone(),
             detail: response.meta.detail.clone(),
+            management_link,
+            license_id: response.data.as_ref().map(|d| d.id.clone()),
+            policy_id,
+            product_id,
+            trial,
+            upgrade: UpgradeLinks::from_metadata(metadata),
+            max_version,
+            group,
+            owner,
+            user: response.meta.scope.as_ref().and_then(|s| s.user.clone()),
         })
     }
+
+    /// Who the license is for, e.g. `"ACME Corp (12 seats)"`.
+    ///
+    /// The group's name (with its machine limit, if any), else the
+    /// owner's name or email; `None` until `fetch_group_and_owner` has
+    /// filled in either.
+    pub fn licensed_to(&self) -> Option<String> {
+        if let Some(group) = &self.group {
+            if let Some(name) = &group.name {
+                return Some(match group.max_machines {
+                    Some(1) => format!("{} (1 seat)", name),
+                    Some(seats) => format!("{} ({} seats)", name, seats),
+                    None => name.clone(),
+                });
+            }
+        }
+        let owner = self.owner.as_ref()?;
+        owner.name.clone().or_else(|| owner.email.clone())
+    }
+
+    /// Whole days until the license expires (negative once expired).
+    ///
+    /// `None` for licenses without an expiry.
+    pub fn days_until_expiry(&self, clock: &dyn Clock) -> Option<i64> {
+        self.expires_at
+            .map(|expires_at| expires_at.signed_duration_since(clock.now_utc()).num_days())
+    }
+
+    /// Whether the license is past its expiry.
+    pub fn is_expired(&self, clock: &dyn Clock) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= clock.now_utc())
+    }
+
+    /// Whether the license is a trial.
+    pub fn is_trial(&self) -> bool {
+        self.trial
+    }
+
+    /// Whole days left in a trial, never negative.
+    ///
+    /// `None` for licenses that are not trials or have no expiry.
+    pub fn trial_days_remaining(&self, clock: &dyn Clock) -> Option<i64> {
+        if !self.trial {
+            return None;
+        }
+        self.days_until_expiry(clock).map(|days| days.max(0))
+    }
+
+    /// Uses left before `max_uses` (`None` when uses are unlimited).
+    pub fn usage_remaining(&self) -> Option<u64> {
+        self.max_uses
+            .map(|max_uses| max_uses.saturating_sub(self.current_uses.unwrap_or(0)))
+    }
+}
+
+/// An entitlement attached to a license.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entitlement {
+    /// Keygen entitlement ID.
+    pub id: String,
+
+    /// Entitlement code (as used in `required_entitlements`).
+    pub code: String,
+
+    /// Human-readable name (if set).
+    pub name: Option<String>,
+
+    /// Vendor-defined metadata (if set).
+    pub metadata: Option<serde_json::Value>,
+
+    /// When the entitlement was created (if returned).
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<KeygenEntitlementData> for Entitlement {
+    fn from(data: KeygenEntitlementData) -> Self {
+        let created_at = data
+            .attributes
+            .created
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+
+        Self {
+            id: data.id,
+            code: data.attributes.code,
+            name: data.attributes.name,
+            metadata: data.attributes.metadata,
+            created_at,
+        }
+    }
+}
+
+/// A license token obtained in exchange for a license key.
+///
+/// `Debug` output redacts the token itself, and the token is zeroized
+/// when dropped.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseToken {
+    /// Keygen token ID.
+    pub id: String,
+
+    /// The bearer token.
+    pub token: String,
+
+    /// Token kind reported by Keygen (if returned).
+    pub kind: Option<String>,
+
+    /// When the token expires (`None` if it never does).
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// ID of the license the token acts as (if returned).
+    pub license_id: Option<String>,
+}
+
+impl LicenseToken {
+    /// Whether the token is past its expiry.
+    pub fn is_expired(&self, clock: &dyn Clock) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= clock.now_utc())
+    }
+}
+
+impl Drop for LicenseToken {
+    fn drop(&mut self) {
+        self.token.zeroize();
+    }
+}
+
+impl fmt::Debug for LicenseToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LicenseToken")
+            .field("id", &self.id)
+            .field("token", &"[REDACTED]")
+            .field("kind", &self.kind)
+            .field("expires_at", &self.expires_at)
+            .field("license_id", &self.license_id)
+            .finish()
+    }
+}
+
+impl From<KeygenTokenData> for LicenseToken {
+    fn from(data: KeygenTokenData) -> Self {
+        let expires_at = data
+            .attributes
+            .expiry
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+        let license_id = data
+            .relationships
+            .and_then(|r| r.bearer)
+            .and_then(|b| b.data)
+            .filter(|bearer| bearer.data_type == "licenses")
+            .map(|bearer| bearer.id);
+
+        Self {
+            id: data.id,
+            token: data.attributes.token,
+            kind: data.attributes.kind,
+            expires_at,
+            license_id,
+        }
+    }
 }
 
 /// Parse raw JSON body into Keygen response.
 pub fn parse_keygen_response(body: &[u8]) -> Result<KeygenValidateResponse, GatewardenError> {
-    serde_json::from_slice(body).map_err(|e| {
-        GatewardenError::ProtocolError(format!("Failed to parse Keygen response: {}", e))
+    serde_json::from_slice(body).map_err(|e| GatewardenError::ProtocolError {
+        message: "Failed to parse Keygen response".to_string(),
+        source: Some(e.into()),
     })
 }
 
@@ -205,7 +870,39 @@ mod tests {
     #[test]
     fn test_parse_malformed_json() {
         let result = parse_keygen_response(b"not json");
-        assert!(matches!(result, Err(GatewardenError::ProtocolError(_))));
+        assert!(matches!(result, Err(GatewardenError::ProtocolError { .. })));
+    }
+
+    #[test]
+    fn test_management_link_extraction() {
+        let body = r#"{
+            "meta": { "valid": false, "code": "EXPIRED" },
+            "data": {
+                "id": "test-license-id",
+                "type": "licenses",
+                "attributes": {
+                    "metadata": {
+                        "managementLink": { "payload": "{}", "signature": "c2ln" },
+                        "renewalUrl": "https://store.example.com/renew"
+                    }
+                }
+            }
+        }"#;
+        let response = parse_keygen_response(body.as_bytes()).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+        assert_eq!(state.management_link.unwrap().signature, "c2ln");
+        assert_eq!(
+            state.upgrade.renewal_url.as_deref(),
+            Some("https://store.example.com/renew")
+        );
+
+        let malformed = body.replace(
+            r#"{ "payload": "{}", "signature": "c2ln" }"#,
+            r#""https://phish.example""#,
+        );
+        let response = parse_keygen_response(malformed.as_bytes()).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+        assert!(state.management_link.is_none());
     }
 
     #[test]
@@ -218,9 +915,81 @@ mod tests {
         assert!(state.expires_at.is_some());
         assert_eq!(state.max_uses, Some(1000));
         assert_eq!(state.current_uses, Some(42));
+        assert_eq!(state.license_id.as_deref(), Some("test-license-id"));
         assert_eq!(state.code, "VALID");
     }
 
+    #[test]
+    fn test_trial_license_state() {
+        use crate::clock::MockClock;
+        use chrono::TimeZone;
+
+        let body = r#"{
+            "meta": { "valid": true, "code": "VALID" },
+            "data": {
+                "id": "lic-trial",
+                "type": "licenses",
+                "attributes": {
+                    "expiry": "2025-01-29T12:00:00Z",
+                    "metadata": { "trial": true }
+                },
+                "relationships": {
+                    "policy": { "data": { "type": "policies", "id": "pol-trial" } },
+                    "product": { "data": { "type": "products", "id": "prod-1" } }
+                }
+            }
+        }"#;
+        let response = parse_keygen_response(body.as_bytes()).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+        assert!(state.is_trial());
+        assert_eq!(state.policy_id.as_deref(), Some("pol-trial"));
+        assert_eq!(state.product_id.as_deref(), Some("prod-1"));
+        assert!(state.upgrade.is_empty());
+
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap());
+        assert_eq!(state.trial_days_remaining(&clock), Some(14));
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 2, 15, 12, 0, 0).unwrap());
+        assert_eq!(state.trial_days_remaining(&clock), Some(0));
+
+        let response = parse_keygen_response(VALID_RESPONSE.as_bytes()).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+        assert!(!state.is_trial());
+        assert_eq!(state.trial_days_remaining(&clock), None);
+    }
+
+    #[test]
+    fn test_group_and_owner_ids() {
+        let body = r#"{
+            "meta": { "valid": true, "code": "VALID" },
+            "data": {
+                "id": "lic-team",
+                "type": "licenses",
+                "attributes": {},
+                "relationships": {
+                    "group": { "data": { "type": "groups", "id": "grp-acme" } },
+                    "owner": { "data": null }
+                }
+            }
+        }"#;
+        let response = parse_keygen_response(body.as_bytes()).unwrap();
+        let mut state = LicenseState::from_keygen_response(&response).unwrap();
+        assert_eq!(state.group.as_ref().unwrap().id, "grp-acme");
+        assert!(state.owner.is_none());
+        assert_eq!(state.licensed_to(), None);
+
+        state.owner = Some(LicenseOwner {
+            id: "usr-1".to_string(),
+            name: None,
+            email: Some("jo@acme.example".to_string()),
+        });
+        assert_eq!(state.licensed_to().as_deref(), Some("jo@acme.example"));
+
+        let group = state.group.as_mut().unwrap();
+        group.name = Some("ACME Corp".to_string());
+        group.max_machines = Some(12);
+        assert_eq!(state.licensed_to().as_deref(), Some("ACME Corp (12 seats)"));
+    }
+
     #[test]
     fn test_license_state_minimal() {
         let response = parse_keygen_response(MINIMAL_RESPONSE.as_bytes()).unwrap();
@@ -241,4 +1010,28 @@ mod tests {
         assert_eq!(state.code, "EXPIRED");
         assert_eq!(state.detail, Some("License has expired".to_string()));
     }
+
+    #[test]
+    fn test_license_state_helpers() {
+        use crate::clock::MockClock;
+        use chrono::TimeZone;
+
+        let response = parse_keygen_response(VALID_RESPONSE.as_bytes()).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 12, 21, 12, 0, 0).unwrap());
+        assert_eq!(state.days_until_expiry(&clock), Some(10));
+        assert!(!state.is_expired(&clock));
+        assert_eq!(state.usage_remaining(), Some(958));
+
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 1, 3, 0, 0, 0).unwrap());
+        assert_eq!(state.days_until_expiry(&clock), Some(-2));
+        assert!(state.is_expired(&clock));
+
+        let response = parse_keygen_response(MINIMAL_RESPONSE.as_bytes()).unwrap();
+        let state = LicenseState::from_keygen_response(&response).unwrap();
+        assert_eq!(state.days_until_expiry(&clock), None);
+        assert!(!state.is_expired(&clock));
+        assert_eq!(state.usage_remaining(), None);
+    }
 }
@@ -0,0 +1,96 @@
+//! License keys that cannot leak into logs.
+//!
+//! A [`RedactedKey`] wraps a raw license key so that formatting it, whether
+//! in an error message, a tracing field, or the `Debug` output of a struct
+//! holding it, shows only a short prefix of the key's SHA-256 hash. That
+//! is enough to tell keys apart in logs without revealing them. The raw
+//! key is reachable only through [`RedactedKey::expose`], which is called
+//! where the key actually goes on the wire or into the key store.
+//!
+//! The key's memory is zeroized when a `RedactedKey` is dropped, so a
+//! process memory dump taken later does not contain it.
+
+use crate::cache::backend::hash_license_key;
+use serde::Deserialize;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Hex characters of the key hash shown when a key is formatted.
+const SHOWN_HASH_CHARS: usize = 8;
+
+/// A license key whose `Debug` and `Display` output is a hash prefix.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub struct RedactedKey(String);
+
+impl RedactedKey {
+    /// Wrap a raw license key.
+    pub fn new(license_key: impl Into<String>) -> Self {
+        Self(license_key.into())
+    }
+
+    /// The raw key, for sending to Keygen or storing in a key store.
+    ///
+    /// Never log the returned value.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// SHA-256 hash of the key, as used for cache records.
+    pub fn hash(&self) -> String {
+        hash_license_key(&self.0)
+    }
+
+    /// Whether the key is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Drop for RedactedKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<String> for RedactedKey {
+    fn from(license_key: String) -> Self {
+        Self(license_key)
+    }
+}
+
+impl From<&str> for RedactedKey {
+    fn from(license_key: &str) -> Self {
+        Self(license_key.to_string())
+    }
+}
+
+impl fmt::Display for RedactedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hash = self.hash();
+        write!(f, "{}…", hash.get(..SHOWN_HASH_CHARS).unwrap_or(&hash))
+    }
+}
+
+impl fmt::Debug for RedactedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RedactedKey({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formatting_hides_key() {
+        let key = RedactedKey::new("SECRET-LICENSE-KEY");
+        let prefix = &key.hash()[..SHOWN_HASH_CHARS];
+
+        assert_eq!(key.to_string(), format!("{}…", prefix));
+        assert_eq!(format!("{:?}", key), format!("RedactedKey({}…)", prefix));
+        assert!(!format!("{:?} {}", key, key).contains("SECRET"));
+        assert_eq!(key.expose(), "SECRET-LICENSE-KEY");
+        assert_eq!(key.hash(), hash_license_key("SECRET-LICENSE-KEY"));
+    }
+}
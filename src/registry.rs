@@ -0,0 +1,327 @@
+//! Several license managers addressed by feature name.
+//!
+//! Suites that gate many modules separately (a `vision` add-on, an
+//! `export` add-on, ...) need one [`LicenseManager`] per feature. Building
+//! them through a registry shares one HTTP connection pool and one clock
+//! between them instead of constructing each ad hoc:
+//!
+//! ```ignore
+//! use gatewarden::{registry::ManagerRegistry, EntitlementPolicy};
+//!
+//! let registry = ManagerRegistry::builder()
+//!     .manager(vision_config)
+//!     .manager_with(export_config, |manager| {
+//!         manager.with_entitlement_policy(EntitlementPolicy::any_of(["PRO", "EXPORT"]))
+//!     })
+//!     .build()?;
+//!
+//! registry.check_access("vision", &license_key)?;
+//! ```
+//!
+//! Each manager is addressed by its config's `feature_name` and keeps its
+//! own cache namespace, skew record and high-water mark. Configs must
+//! agree on timeouts, proxy and TLS pins, since those belong to the shared
+//! pool; they may use different accounts, public keys and policies.
+
+use crate::cache::high_water::HighWaterMark;
+use crate::client::http::KeygenClient;
+use crate::clock::{Clock, SystemClock};
+#[cfg(any(test, feature = "test-seams"))]
+use crate::mock::MockKeygen;
+use crate::skew::ClockSkew;
+use crate::{GatewardenConfig, GatewardenError, LicenseManager, ValidationResult};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+type Customize = Box<dyn FnOnce(LicenseManager) -> LicenseManager>;
+
+/// License managers addressed by feature name.
+///
+/// Like [`LicenseManager`], the registry is `Send + Sync` and cheap to
+/// clone.
+#[derive(Clone)]
+pub struct ManagerRegistry {
+    managers: Arc<HashMap<&'static str, LicenseManager>>,
+}
+
+/// Builder for [`ManagerRegistry`].
+pub struct ManagerRegistryBuilder {
+    entries: Vec<(GatewardenConfig, Option<Customize>)>,
+    clock: Option<Arc<dyn Clock>>,
+    #[cfg(any(test, feature = "test-seams"))]
+    mock: Option<Arc<MockKeygen>>,
+}
+
+impl ManagerRegistry {
+    /// Start adding managers.
+    pub fn builder() -> ManagerRegistryBuilder {
+        ManagerRegistryBuilder {
+            entries: Vec::new(),
+            clock: None,
+            #[cfg(any(test, feature = "test-seams"))]
+            mock: None,
+        }
+    }
+
+    /// The manager for `feature`, if one is registered.
+    pub fn get(&self, feature: &str) -> Option<&LicenseManager> {
+        self.managers.get(feature)
+    }
+
+    /// Registered feature names, sorted.
+    pub fn features(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.managers.keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Validate `license_key` with the manager for `feature`.
+    ///
+    /// # Errors
+    /// - `ConfigError` - No manager is registered for `feature`
+    /// - Any error from `LicenseManager::validate_key`
+    pub fn validate_key(
+        &self,
+        feature: &str,
+        license_key: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        self.require(feature)?.validate_key(license_key)
+    }
+
+    /// Check access with the manager for `feature`.
+    ///
+    /// # Errors
+    /// - `ConfigError` - No manager is registered for `feature`
+    /// - Any error from `LicenseManager::check_access`
+    pub fn check_access(
+        &self,
+        feature: &str,
+        license_key: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        self.require(feature)?.check_access(license_key)
+    }
+
+    fn require(&self, feature: &str) -> Result<&LicenseManager, GatewardenError> {
+        self.get(feature).ok_or_else(|| {
+            GatewardenError::ConfigError(format!("No license manager for feature {feature}"))
+        })
+    }
+}
+
+impl ManagerRegistryBuilder {
+    /// Register a manager built from `config`, addressed by its
+    /// `feature_name`.
+    pub fn manager(mut self, config: GatewardenConfig) -> Self {
+        self.entries.push((config, None));
+        self
+    }
+
+    /// Register a manager built from `config` and then passed through
+    /// `customize`, e.g. to attach an entitlement policy or event sink.
+    pub fn manager_with(
+        mut self,
+        config: GatewardenConfig,
+        customize: impl FnOnce(LicenseManager) -> LicenseManager + 'static,
+    ) -> Self {
+        self.entries.push((config, Some(Box::new(customize))));
+        self
+    }
+
+    /// Read time from `clock` in every manager (for testing).
+    ///
+    /// As with `LicenseManager::new_with_clock`, skew and the high-water
+    /// mark then start empty and are never persisted.
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Send every manager's Keygen requests to a mock (for testing).
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_mock_keygen(mut self, mock: Arc<MockKeygen>) -> Self {
+        self.mock = Some(mock);
+        self
+    }
+
+    /// Build every manager around one shared HTTP client and clock.
+    ///
+    /// # Errors
+    /// - `ConfigError` - No managers were registered, a config is invalid
+    ///   or has no `feature_name`, two configs share a feature name or
+    ///   cache namespace, or their transport settings differ
+    pub fn build(self) -> Result<ManagerRegistry, GatewardenError> {
+        let Some((first, _)) = self.entries.first() else {
+            return Err(GatewardenError::ConfigError(
+                "Manager registry has no managers".to_string(),
+            ));
+        };
+        #[allow(unused_mut)]
+        let mut shared = KeygenClient::new(first)?;
+        #[cfg(any(test, feature = "test-seams"))]
+        if let Some(mock) = self.mock {
+            shared.set_mock_keygen(mock);
+        }
+        let persist = self.clock.is_none();
+        let clock = self.clock.unwrap_or_else(|| Arc::new(SystemClock));
+
+        let mut managers = HashMap::new();
+        let mut namespaces = HashSet::new();
+        for (config, customize) in self.entries {
+            config.validate()?;
+            let feature = config.feature_name;
+            if feature.is_empty() {
+                return Err(GatewardenError::ConfigError(
+                    "Registered managers need a feature_name".to_string(),
+                ));
+            }
+            if managers.contains_key(feature) {
+                return Err(GatewardenError::ConfigError(format!(
+                    "Feature {feature} is registered twice"
+                )));
+            }
+            if !namespaces.insert(config.cache_namespace) {
+                return Err(GatewardenError::ConfigError(format!(
+                    "Feature {feature} reuses cache namespace {}",
+                    config.cache_namespace
+                )));
+            }
+
+            let client = shared.for_config(&config)?;
+            let (skew, high_water) = if persist {
+                (
+                    ClockSkew::load(config.cache_namespace),
+                    HighWaterMark::for_namespace(config.cache_namespace),
+                )
+            } else {
+                (ClockSkew::in_memory(), HighWaterMark::with_path(None))
+            };
+            let manager =
+                LicenseManager::with_clock(config, clock.clone(), skew, high_water, client)?;
+            let manager = match customize {
+                Some(customize) => customize(manager),
+                None => manager,
+            };
+            managers.insert(feature, manager);
+        }
+
+        Ok(ManagerRegistry {
+            managers: Arc::new(managers),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::file::FileCache;
+    use crate::mock::MockLicense;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::{EntitlementPolicy, MockClock};
+    use chrono::{TimeZone, Utc};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn config(feature: &'static str) -> GatewardenConfig {
+        GatewardenConfig::builder()
+            .app_name("registry-test")
+            .feature_name(feature)
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap()
+    }
+
+    fn setup() -> (Arc<MockKeygen>, ManagerRegistryBuilder) {
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let builder = ManagerRegistry::builder()
+            .with_clock(clock)
+            .with_mock_keygen(keygen.clone());
+        (keygen, builder)
+    }
+
+    fn gated(dir: PathBuf, code: &'static str) -> impl FnOnce(LicenseManager) -> LicenseManager {
+        move |manager| {
+            manager
+                .with_cache_backend(Box::new(FileCache::with_path(dir).unwrap()))
+                .with_entitlement_policy(EntitlementPolicy::code(code))
+        }
+    }
+
+    #[test]
+    fn test_managers_addressed_by_feature() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, builder) = setup();
+        keygen.add_license(
+            "key",
+            MockLicense::new("lic-1").with_entitlements(&["VISION"]),
+        );
+        let registry = builder
+            .manager_with(
+                config("vision"),
+                gated(temp_dir.path().join("vision"), "VISION"),
+            )
+            .manager_with(
+                config("export"),
+                gated(temp_dir.path().join("export"), "EXPORT"),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(registry.features(), vec!["export", "vision"]);
+        assert!(registry.validate_key("vision", "key").unwrap().valid);
+        assert!(registry.check_access("vision", "key").is_ok());
+        assert!(matches!(
+            registry.validate_key("export", "key"),
+            Err(GatewardenError::EntitlementMissing { code }) if code == "EXPORT"
+        ));
+        assert!(matches!(
+            registry.validate_key("audio", "key"),
+            Err(GatewardenError::ConfigError(_))
+        ));
+        assert!(registry.get("audio").is_none());
+        // Each gated validation lists entitlements, then validates once
+        assert_eq!(keygen.request_count(), 4);
+    }
+
+    #[test]
+    fn test_build_rejects_conflicting_configs() {
+        let (_, builder) = setup();
+        assert!(matches!(
+            builder.build(),
+            Err(GatewardenError::ConfigError(_))
+        ));
+
+        let (_, builder) = setup();
+        let duplicate = builder.manager(config("vision")).manager(config("vision"));
+        assert!(matches!(
+            duplicate.build(),
+            Err(GatewardenError::ConfigError(_))
+        ));
+
+        let (_, builder) = setup();
+        let shared_namespace = builder.manager(config("vision")).manager(GatewardenConfig {
+            cache_namespace: "registry-test-vision",
+            ..config("export")
+        });
+        assert!(matches!(
+            shared_namespace.build(),
+            Err(GatewardenError::ConfigError(_))
+        ));
+
+        let (_, builder) = setup();
+        let transport = builder.manager(config("vision")).manager(GatewardenConfig {
+            request_timeout: Duration::from_secs(99),
+            ..config("export")
+        });
+        assert!(matches!(
+            transport.build(),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+}
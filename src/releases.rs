@@ -0,0 +1,462 @@
+//! Entitlement-aware update checks against Keygen's distribution API.
+//!
+//! Keygen shows a license only the releases of its product whose
+//! entitlement constraints the license satisfies, so asking for an
+//! upgrade as the license answers "the newest version this customer may
+//! install". Releases, artifact lists, and download links are signed
+//! responses and go through the same verification as validation.
+//!
+//! ```ignore
+//! let current = env!("CARGO_PKG_VERSION");
+//! if let Some(release) = manager.latest_release(&key, current, ReleaseChannel::Stable)? {
+//!     if let Some(download) = manager.artifact_url(&key, &release.version, "win32", None)? {
+//!         fetch(&download.url); // expires shortly
+//!     }
+//! }
+//! ```
+
+use crate::client::response::{check_available, KeygenResponse};
+use crate::manager::{license_auth, LicenseManager};
+use crate::policy::links::check_https;
+use crate::protocol::models::{
+    KeygenArtifactData, KeygenArtifactResponse, KeygenArtifactsResponse, KeygenReleaseData,
+    KeygenReleaseResponse,
+};
+use crate::GatewardenError;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Keygen release channel.
+///
+/// Channels are cumulative: asking on `Beta` also considers `Rc` and
+/// `Stable` releases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    /// Stable releases only.
+    #[default]
+    Stable,
+    /// Release candidates and stable releases.
+    Rc,
+    /// Beta releases and later.
+    Beta,
+    /// Alpha releases and later.
+    Alpha,
+    /// Every release, including development builds.
+    Dev,
+}
+
+/// A release the license is entitled to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    /// Keygen release ID.
+    pub id: String,
+
+    /// Release version (e.g. `"2.1.0"`).
+    pub version: String,
+
+    /// Channel the release was published on.
+    pub channel: String,
+
+    /// Human-readable name (if set).
+    pub name: Option<String>,
+
+    /// Release notes (if set).
+    pub description: Option<String>,
+
+    /// Vendor-defined metadata (if set).
+    pub metadata: Option<serde_json::Value>,
+
+    /// When the release was created (if returned).
+    pub created_at: Option<DateTime<Utc>>,
+
+    /// Downloadable files of the release.
+    pub artifacts: Vec<Artifact>,
+}
+
+/// A downloadable file of a release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// Keygen artifact ID.
+    pub id: String,
+
+    /// File name.
+    pub filename: String,
+
+    /// File type (e.g. `dmg`, `exe`), if set.
+    pub filetype: Option<String>,
+
+    /// File size in bytes, if known.
+    pub filesize: Option<u64>,
+
+    /// Target platform (e.g. `darwin`, `win32`), if set.
+    pub platform: Option<String>,
+
+    /// Target architecture (e.g. `arm64`), if set.
+    pub arch: Option<String>,
+
+    /// Vendor-provided checksum of the file, if set.
+    pub checksum: Option<String>,
+
+    /// Vendor-provided signature of the file, if set.
+    pub signature: Option<String>,
+}
+
+/// A time-limited download link for an artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactDownload {
+    /// The artifact the link downloads.
+    pub artifact: Artifact,
+
+    /// Pre-signed `https://` URL from Keygen's signed response. It expires
+    /// within minutes, so fetch it right away rather than storing it.
+    pub url: String,
+}
+
+impl ReleaseChannel {
+    /// Keygen's name for the channel.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Rc => "rc",
+            Self::Beta => "beta",
+            Self::Alpha => "alpha",
+            Self::Dev => "dev",
+        }
+    }
+}
+
+impl Release {
+    /// The artifact for `platform` (and `arch`, if given), if any.
+    pub fn artifact_for(&self, platform: &str, arch: Option<&str>) -> Option<&Artifact> {
+        self.artifacts
+            .iter()
+            .find(|artifact| artifact.is_for(platform, arch))
+    }
+}
+
+impl Artifact {
+    /// Whether the artifact targets `platform` (and `arch`, if given).
+    pub fn is_for(&self, platform: &str, arch: Option<&str>) -> bool {
+        self.platform.as_deref() == Some(platform)
+            && arch.map_or(true, |arch| self.arch.as_deref() == Some(arch))
+    }
+}
+
+impl From<KeygenReleaseData> for Release {
+    fn from(data: KeygenReleaseData) -> Self {
+        let created_at = data
+            .attributes
+            .created
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+
+        Self {
+            id: data.id,
+            version: data.attributes.version,
+            channel: data.attributes.channel,
+            name: data.attributes.name,
+            description: data.attributes.description,
+            metadata: data.attributes.metadata,
+            created_at,
+            artifacts: Vec::new(),
+        }
+    }
+}
+
+impl From<KeygenArtifactData> for Artifact {
+    fn from(data: KeygenArtifactData) -> Self {
+        Self {
+            id: data.id,
+            filename: data.attributes.filename,
+            filetype: data.attributes.filetype,
+            filesize: data.attributes.filesize,
+            platform: data.attributes.platform,
+            arch: data.attributes.arch,
+            checksum: data.attributes.checksum,
+            signature: data.attributes.signature,
+        }
+    }
+}
+
+impl LicenseManager {
+    /// The newest release after `current_version` on `channel` that the
+    /// license is entitled to, with its artifacts.
+    ///
+    /// Authenticates with the stored license token if there is one (see
+    /// `exchange_license_token`), else the key. Only the first 100
+    /// artifacts of the release are listed.
+    ///
+    /// # Returns
+    /// `None` if no newer release is available to the license, or Keygen
+    /// does not know `current_version`.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `ConfigError` - `current_version` is empty
+    /// - `InvalidLicense` - Keygen refused the license (HTTP 401/403)
+    /// - `ProtocolError` - Unparseable release or unexpected status
+    /// - Any transport, availability, or verification error
+    pub fn latest_release(
+        &self,
+        license_key: &str,
+        current_version: &str,
+        channel: ReleaseChannel,
+    ) -> Result<Option<Release>, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        if current_version.is_empty() {
+            return Err(GatewardenError::ConfigError(
+                "current_version cannot be empty".to_string(),
+            ));
+        }
+
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(&license_key, token.as_ref());
+
+        let response = self
+            .client()
+            .release_upgrade_as(auth, current_version, channel.as_str())?;
+        let Some(document) = self.accept_release_response::<KeygenReleaseResponse>(&response)?
+        else {
+            return Ok(None);
+        };
+        let mut release = Release::from(document.data);
+
+        let response = self.client().release_artifacts_as(auth, &release.id)?;
+        if let Some(page) = self.accept_release_response::<KeygenArtifactsResponse>(&response)? {
+            release.artifacts = page.data.into_iter().map(Artifact::from).collect();
+        }
+        Ok(Some(release))
+    }
+
+    /// A time-limited download URL for the artifact of release `version`
+    /// that targets `platform` (and `arch`, if given).
+    ///
+    /// Performs Keygen's authenticated download flow: the artifact request
+    /// answers `303 See Other`, and the URL is taken from the signed body
+    /// rather than the unsigned `Location` header. Authenticates like
+    /// `latest_release`.
+    ///
+    /// # Returns
+    /// `None` if the license is not entitled to `version`, or the release
+    /// has no artifact for the platform.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `ConfigError` - `version` or `platform` is empty
+    /// - `InvalidLicense` - Keygen refused the license (HTTP 401/403)
+    /// - `ProtocolError` - No `https://` download link, unparseable
+    ///   response, or unexpected status
+    /// - Any transport, availability, or verification error
+    pub fn artifact_url(
+        &self,
+        license_key: &str,
+        version: &str,
+        platform: &str,
+        arch: Option<&str>,
+    ) -> Result<Option<ArtifactDownload>, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        if version.is_empty() || platform.is_empty() {
+            return Err(GatewardenError::ConfigError(
+                "version and platform cannot be empty".to_string(),
+            ));
+        }
+
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(&license_key, token.as_ref());
+
+        // Keygen looks releases up by ID or version
+        let response = self.client().release_artifacts_as(auth, version)?;
+        let Some(page) = self.accept_release_response::<KeygenArtifactsResponse>(&response)? else {
+            return Ok(None);
+        };
+        let Some(artifact) = page
+            .data
+            .into_iter()
+            .map(Artifact::from)
+            .find(|artifact| artifact.is_for(platform, arch))
+        else {
+            return Ok(None);
+        };
+
+        let response = self.client().artifact_as(auth, &artifact.id)?;
+        let Some(document) = self.accept_release_response::<KeygenArtifactResponse>(&response)?
+        else {
+            return Ok(None);
+        };
+        let url = document
+            .links
+            .and_then(|links| links.redirect)
+            .filter(|url| check_https(url).is_ok())
+            .ok_or_else(|| GatewardenError::ProtocolError {
+                message: "Artifact download has no https:// link".to_string(),
+                source: None,
+            })?;
+
+        Ok(Some(ArtifactDownload { artifact, url }))
+    }
+
+    /// Verify a distribution API response and parse it; `None` when
+    /// Keygen has nothing to return (HTTP 204 or 404).
+    fn accept_release_response<T: DeserializeOwned>(
+        &self,
+        response: &KeygenResponse,
+    ) -> Result<Option<T>, GatewardenError> {
+        check_available(response)?;
+        self.verify_with_skew(response)?;
+
+        match response.status {
+            204 | 404 => return Ok(None),
+            // Artifact downloads answer 303 See Other with a signed body
+            200..=299 | 303 => {}
+            401 | 403 => return Err(GatewardenError::InvalidLicense),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Release lookup failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        serde_json::from_str(response.body_str()?)
+            .map(Some)
+            .map_err(|e| GatewardenError::ProtocolError {
+                message: "Parse error".to_string(),
+                source: Some(e.into()),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::file::FileCache;
+    use crate::clock::{Clock, MockClock};
+    use crate::mock::{MockKeygen, MockLicense, MockRelease};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::GatewardenConfig;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn setup(temp_dir: &TempDir) -> (Arc<MockKeygen>, LicenseManager) {
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let config = GatewardenConfig::builder()
+            .app_name("releases-test")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+        (keygen, manager)
+    }
+
+    #[test]
+    fn test_latest_release_respects_entitlements_and_channel() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_entitlements(&["PRO"]));
+        keygen.add_release(MockRelease::new("rel-1", "1.0.0"));
+        keygen.add_release(
+            MockRelease::new("rel-2", "1.1.0")
+                .with_artifact("app-1.1.0.dmg", "darwin", "arm64")
+                .with_artifact("app-1.1.0.exe", "win32", "x86_64"),
+        );
+        keygen.add_release(MockRelease::new("rel-3", "2.0.0").with_entitlements(&["ENTERPRISE"]));
+        keygen.add_release(MockRelease::new("rel-4", "1.2.0-beta.1").on_channel("beta"));
+
+        let release = manager
+            .latest_release("key", "1.0.0", ReleaseChannel::Stable)
+            .unwrap()
+            .unwrap();
+        assert_eq!(release.version, "1.1.0");
+        assert_eq!(release.artifacts.len(), 2);
+        assert_eq!(
+            release.artifact_for("win32", None).unwrap().filename,
+            "app-1.1.0.exe"
+        );
+        assert!(release.artifact_for("darwin", Some("x86_64")).is_none());
+
+        let beta = manager
+            .latest_release("key", "1.0.0", ReleaseChannel::Beta)
+            .unwrap()
+            .unwrap();
+        assert_eq!(beta.version, "1.2.0-beta.1");
+
+        // 2.0.0 needs ENTERPRISE, so 1.1.0 is already the latest
+        assert!(manager
+            .latest_release("key", "1.1.0", ReleaseChannel::Stable)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_artifact_url_follows_signed_redirect() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1"));
+        keygen.add_release(
+            MockRelease::new("rel-1", "1.1.0")
+                .with_artifact("app-1.1.0.dmg", "darwin", "arm64")
+                .with_artifact("app-1.1.0.exe", "win32", "x86_64"),
+        );
+        keygen.add_release(
+            MockRelease::new("rel-2", "2.0.0")
+                .with_entitlements(&["ENTERPRISE"])
+                .with_artifact("app-2.0.0.exe", "win32", "x86_64"),
+        );
+
+        let download = manager
+            .artifact_url("key", "1.1.0", "win32", Some("x86_64"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(download.artifact.filename, "app-1.1.0.exe");
+        assert!(download
+            .url
+            .starts_with("https://downloads.example.com/app-1.1.0.exe?expires="));
+
+        assert!(manager
+            .artifact_url("key", "1.1.0", "linux", None)
+            .unwrap()
+            .is_none());
+        // Not entitled to 2.0.0
+        assert!(manager
+            .artifact_url("key", "2.0.0", "win32", None)
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            manager.artifact_url("key", "1.1.0", "", None),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_latest_release_rejects_bad_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let (_keygen, manager) = setup(&temp_dir);
+        assert!(matches!(
+            manager.latest_release("unknown", "1.0.0", ReleaseChannel::Stable),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert!(matches!(
+            manager.latest_release("", "1.0.0", ReleaseChannel::Stable),
+            Err(GatewardenError::MissingLicense)
+        ));
+        assert!(matches!(
+            manager.latest_release("key", "", ReleaseChannel::Stable),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+}
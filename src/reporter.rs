@@ -0,0 +1,284 @@
+//! Opt-in reporting of security events to a vendor endpoint.
+//!
+//! A [`SecurityReporter`] registered as an event sink POSTs a small JSON
+//! report to an HTTPS endpoint the vendor runs whenever a response or
+//! local record fails authentication (signature and digest failures,
+//! replayed or future-dated responses, cache or meter tampering, clock
+//! rollback, pin mismatches), so cracking attempts in the field show up
+//! on the vendor's side:
+//!
+//! ```ignore
+//! let reporter = SecurityReporter::new("https://telemetry.example.com/gatewarden", &config)?;
+//! let manager = LicenseManager::new(config)?.with_event_sink(Arc::new(reporter));
+//! ```
+//!
+//! Reports are anonymized: they carry the error code, app and feature
+//! names, app version, Keygen's request ID, and an installation ID that is
+//! a salted hash of the machine fingerprint, never the license key,
+//! license ID, or raw machine ID. Reporting is rate-limited and
+//! fail-silent: reports are sent from a background thread, and reports
+//! over the hourly limit, or that cannot be queued or delivered, are
+//! dropped without affecting validation.
+
+use crate::client::request::build_user_agent;
+use crate::clock::{Clock, SystemClock};
+use crate::events::EventSink;
+use crate::fingerprint::machine_fingerprint;
+use crate::{GatewardenConfig, GatewardenError};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::header::USER_AGENT;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Reports sent per hour unless changed with `per_hour`.
+pub const DEFAULT_REPORTS_PER_HOUR: u32 = 10;
+
+/// Reports waiting for the background thread before new ones are dropped.
+const QUEUE_CAPACITY: usize = 16;
+
+/// Hex characters of the installation hash sent with reports.
+const INSTALLATION_ID_CHARS: usize = 16;
+
+/// Body of one security report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SecurityReport {
+    /// `GatewardenError::code` of the failure, e.g. `"SIGNATURE_INVALID"`.
+    pub event: &'static str,
+
+    /// Application name from the config.
+    pub app: &'static str,
+
+    /// Feature name from the config.
+    pub feature: &'static str,
+
+    /// Application version from the config, if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_version: Option<&'static str>,
+
+    /// Salted hash of the machine fingerprint, stable per app and machine.
+    pub installation: String,
+
+    /// Keygen's request ID for the failing response, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// When the failure was observed.
+    pub at: DateTime<Utc>,
+}
+
+/// Reports sent in the current hour.
+struct Window {
+    started: DateTime<Utc>,
+    sent: u32,
+}
+
+/// Event sink reporting security errors to a vendor endpoint.
+pub struct SecurityReporter {
+    app: &'static str,
+    feature: &'static str,
+    app_version: Option<&'static str>,
+    installation: String,
+    per_hour: u32,
+    window: Mutex<Option<Window>>,
+    clock: Arc<dyn Clock>,
+    queue: SyncSender<SecurityReport>,
+}
+
+impl SecurityReporter {
+    /// Report to `endpoint` using `config`'s proxy, timeouts, and identity.
+    ///
+    /// Starts the background thread that delivers reports.
+    ///
+    /// # Errors
+    /// - `ConfigError` - `endpoint` is not an `https://` URL, or the HTTP
+    ///   client cannot be built
+    pub fn new(endpoint: &str, config: &GatewardenConfig) -> Result<Self, GatewardenError> {
+        let url = reqwest::Url::parse(endpoint)
+            .ok()
+            .filter(|url| url.scheme() == "https")
+            .ok_or_else(|| {
+                GatewardenError::ConfigError(
+                    "Security report endpoint must be an https:// URL".to_string(),
+                )
+            })?;
+        let client = crate::client::http::build_http_client(
+            config.request_timeout,
+            config.connect_timeout,
+            config.proxy,
+            &[],
+            config.connection_pool,
+            config.ip_strategy,
+        )?;
+        let user_agent = build_user_agent(config);
+
+        let (queue, reports) = mpsc::sync_channel(QUEUE_CAPACITY);
+        thread::Builder::new()
+            .name("gatewarden-reporter".to_string())
+            .spawn(move || deliver(client, url, user_agent, reports))
+            .map_err(|e| {
+                GatewardenError::ConfigError(format!("Failed to start security reporter: {}", e))
+            })?;
+
+        Ok(Self {
+            app: config.app_name,
+            feature: config.feature_name,
+            app_version: config.app_version,
+            installation: installation_id(
+                config.app_name,
+                &machine_fingerprint().unwrap_or_default(),
+            ),
+            per_hour: DEFAULT_REPORTS_PER_HOUR,
+            window: Mutex::new(None),
+            clock: Arc::new(SystemClock),
+            queue,
+        })
+    }
+
+    /// Send at most `per_hour` reports per hour (default 10).
+    pub fn per_hour(mut self, per_hour: u32) -> Self {
+        self.per_hour = per_hour;
+        self
+    }
+
+    /// Read time from `clock` (for testing).
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The report for `error`, or `None` if the hourly limit is reached.
+    fn prepare(&self, error: &GatewardenError) -> Option<SecurityReport> {
+        let now = self.clock.now_utc();
+        let mut window = self.window.lock().ok()?;
+        let current = match window.as_mut() {
+            Some(current) if now - current.started < Duration::hours(1) => current,
+            _ => window.insert(Window {
+                started: now,
+                sent: 0,
+            }),
+        };
+        if current.sent >= self.per_hour {
+            return None;
+        }
+        current.sent += 1;
+
+        Some(SecurityReport {
+            event: error.code(),
+            app: self.app,
+            feature: self.feature,
+            app_version: self.app_version,
+            installation: self.installation.clone(),
+            request_id: error.request_id().map(String::from),
+            at: now,
+        })
+    }
+}
+
+impl EventSink for SecurityReporter {
+    fn on_security_error(&self, error: &GatewardenError) {
+        if let Some(report) = self.prepare(error) {
+            // Dropped if the queue is full: earlier reports already
+            // describe the same burst
+            let _ = self.queue.try_send(report);
+        }
+    }
+}
+
+/// POST queued reports until the reporter is dropped, ignoring failures.
+fn deliver(
+    client: reqwest::blocking::Client,
+    url: reqwest::Url,
+    user_agent: String,
+    reports: Receiver<SecurityReport>,
+) {
+    for report in reports {
+        let sent = client
+            .post(url.clone())
+            .header(USER_AGENT, &user_agent)
+            .json(&report)
+            .send();
+        if let Err(_e) = sent {
+            trace_debug!(error = %_e.without_url(), "security report not delivered");
+        }
+    }
+}
+
+/// Hash of the machine fingerprint salted with the app name, so reports
+/// from one machine correlate without identifying it across vendors.
+fn installation_id(app: &str, machine_fingerprint: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"gatewarden-installation-v1\0");
+    hasher.update(app.as_bytes());
+    hasher.update([0]);
+    hasher.update(machine_fingerprint.as_bytes());
+    let mut id = hex::encode(hasher.finalize());
+    id.truncate(INSTALLATION_ID_CHARS);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::mock::TEST_PUBLIC_KEY_HEX;
+    use chrono::TimeZone;
+
+    fn config() -> GatewardenConfig {
+        GatewardenConfig::builder()
+            .app_name("reporter-test")
+            .feature_name("pro")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .app_version("1.2.0")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_must_be_https() {
+        assert!(matches!(
+            SecurityReporter::new("http://telemetry.example.com", &config()),
+            Err(GatewardenError::ConfigError(_))
+        ));
+        assert!(matches!(
+            SecurityReporter::new("not a url", &config()),
+            Err(GatewardenError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_reports_are_anonymized_and_rate_limited() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+        let reporter = SecurityReporter::new("https://telemetry.example.invalid/r", &config())
+            .unwrap()
+            .per_hour(2)
+            .with_clock(Arc::new(MockClock::new(start)));
+        let error = GatewardenError::SignatureInvalid {
+            request_id: Some("req-1".to_string()),
+        };
+
+        let report = reporter.prepare(&error).unwrap();
+        assert_eq!(report.event, "SIGNATURE_INVALID");
+        assert_eq!(report.app_version, Some("1.2.0"));
+        assert_eq!(report.request_id.as_deref(), Some("req-1"));
+        assert_eq!(report.installation.len(), INSTALLATION_ID_CHARS);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains(&machine_fingerprint().unwrap_or_else(|| "none".into())));
+
+        assert!(reporter.prepare(&GatewardenError::CacheTampered).is_some());
+        assert!(reporter.prepare(&GatewardenError::CacheTampered).is_none());
+
+        let reporter = reporter.with_clock(Arc::new(MockClock::new(start + Duration::minutes(61))));
+        assert!(reporter.prepare(&GatewardenError::CacheTampered).is_some());
+    }
+
+    #[test]
+    fn test_installation_id_depends_on_app() {
+        assert_eq!(installation_id("a", "m"), installation_id("a", "m"));
+        assert_ne!(installation_id("a", "m"), installation_id("b", "m"));
+    }
+}
@@ -0,0 +1,617 @@
+//! Background auto-revalidation.
+//!
+//! Long-running applications validate once at startup and then rely on the
+//! offline cache. Without periodic refreshes the cache silently ages toward
+//! `CacheExpired`. The revalidator refreshes it on a background thread and
+//! reports state changes over a channel.
+//!
+//! UIs that only care about the license's status can instead
+//! [`watch`](RevalidatorHandle::watch) it: each receiver gets the current
+//! [`LicenseStatus`] and then every change, e.g. valid to expired or
+//! offline to online.
+//!
+//! Applications that must not block startup on a flaky network use
+//! [`start_offline_first`](LicenseManager::start_offline_first): a valid
+//! cached result is returned at once and revalidated in the background.
+//!
+//! When several processes revalidate the same license in one cache
+//! namespace, only the one holding that license's revalidator
+//! [`LeaderLock`] refreshes online; the others follow the leader's results
+//! through the shared cache and take over if the leader exits.
+
+use crate::entropy::random_u64;
+use crate::leader::LeaderLock;
+use crate::manager::{LicenseManager, ValidationResult};
+use crate::redact::RedactedKey;
+use crate::{ErrorCategory, GatewardenError};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Leader-election task name prefix for the revalidator; the license key
+/// hash follows, so each license elects its own leader.
+const LEADER_TASK: &str = "revalidator";
+
+/// Maximum jitter applied to each interval, as a fraction (1/10 = 10%).
+const JITTER_DIVISOR: u32 = 10;
+
+/// Event emitted by the background revalidator.
+// One event per interval at most; boxing would only complicate matching.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum RevalidationEvent {
+    /// Online refresh succeeded and the license state is unchanged.
+    Refreshed(ValidationResult),
+
+    /// Online refresh succeeded and validity or status code changed.
+    StateChanged {
+        /// Previous result, if any refresh had succeeded before.
+        previous: Option<ValidationResult>,
+        /// New result.
+        current: ValidationResult,
+    },
+
+    /// Online refresh failed; the cache was left untouched.
+    Failed(GatewardenError),
+}
+
+/// License status as last seen by the background revalidator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseStatus {
+    /// No refresh has completed yet.
+    Unknown,
+
+    /// Keygen confirmed the license is valid.
+    Valid,
+
+    /// Keygen reported the license is not valid.
+    Invalid {
+        /// Keygen's validation code (e.g. `"EXPIRED"`) or the error's
+        /// `GatewardenError::code` (e.g. `"LICENSE_SUSPENDED"`).
+        code: String,
+    },
+
+    /// Keygen gave no usable answer (unreachable, rate-limited, or not
+    /// verifiable); the application runs on its cached result.
+    Offline,
+}
+
+impl LicenseStatus {
+    /// The status after a refresh.
+    fn from_refresh(refresh: &Result<ValidationResult, GatewardenError>) -> Self {
+        match refresh {
+            Ok(result) if result.valid => Self::Valid,
+            Ok(result) => Self::Invalid {
+                code: result.state.code.clone(),
+            },
+            Err(e) if e.category() == ErrorCategory::License => Self::Invalid {
+                code: e.code().to_string(),
+            },
+            Err(_) => Self::Offline,
+        }
+    }
+}
+
+/// Current status and the receivers watching it.
+struct Watchers {
+    current: LicenseStatus,
+    senders: Vec<Sender<LicenseStatus>>,
+}
+
+impl Watchers {
+    /// Record `status`, notifying receivers if it changed and forgetting
+    /// dropped ones.
+    fn publish(&mut self, status: LicenseStatus) {
+        if status == self.current {
+            return;
+        }
+        self.senders.retain(|tx| tx.send(status.clone()).is_ok());
+        self.current = status;
+    }
+}
+
+/// Handle to a running background revalidator.
+///
+/// Dropping the handle stops the background thread.
+pub struct RevalidatorHandle {
+    stop_tx: Option<Sender<()>>,
+    events: Receiver<RevalidationEvent>,
+    watchers: Arc<Mutex<Watchers>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RevalidatorHandle {
+    /// Receiver for revalidation events.
+    pub fn events(&self) -> &Receiver<RevalidationEvent> {
+        &self.events
+    }
+
+    /// Receiver for the license status: the current status first, then
+    /// each change.
+    ///
+    /// Any number of receivers may watch; each sees every change. The
+    /// channel closes when the revalidator stops. In processes that are
+    /// not the license's revalidator leader, the status follows the
+    /// leader's results in the shared cache.
+    pub fn watch(&self) -> Receiver<LicenseStatus> {
+        let (tx, rx) = mpsc::channel();
+        let mut watchers = self
+            .watchers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // The receiver is still in scope, so this cannot fail
+        let _ = tx.send(watchers.current.clone());
+        watchers.senders.push(tx);
+        rx
+    }
+
+    /// Stop the background thread and wait for it to exit.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the sender wakes the worker immediately.
+        self.stop_tx.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        // Close the watch channels
+        if let Ok(mut watchers) = self.watchers.lock() {
+            watchers.senders.clear();
+        }
+    }
+}
+
+impl Drop for RevalidatorHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl LicenseManager {
+    /// Spawn a background thread that re-validates `license_key` online
+    /// every `interval` (plus up to 10% random jitter), refreshing the cache.
+    ///
+    /// The first refresh happens after one interval. Failures never
+    /// touch the cache; they are reported as [`RevalidationEvent::Failed`].
+    /// Only one process per cache namespace refreshes a given license
+    /// online at a time; the others re-read the cache the leader keeps
+    /// fresh on each tick, and take over if it exits.
+    pub fn spawn_revalidator(
+        self: &Arc<Self>,
+        license_key: &str,
+        interval: Duration,
+    ) -> Result<RevalidatorHandle, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        self.spawn_revalidator_after(license_key, interval, None)
+    }
+
+    /// Validate `license_key` at startup without waiting on Keygen when
+    /// the offline cache can answer.
+    ///
+    /// Serves a valid cached result at once (with
+    /// `FallbackReason::Deferred`) and revalidates online in the
+    /// background right away, then every `interval` as
+    /// [`spawn_revalidator`](Self::spawn_revalidator) does; watch the
+    /// handle for the outcome. Without a usable cached record, validates
+    /// online as `validate_key` does before starting the revalidator.
+    ///
+    /// # Errors
+    /// As `validate_key`, when nothing usable is cached.
+    pub fn start_offline_first(
+        self: &Arc<Self>,
+        license_key: &str,
+        interval: Duration,
+    ) -> Result<(ValidationResult, RevalidatorHandle), GatewardenError> {
+        let redacted = self.redact(license_key)?;
+        let result = match self.validate_deferred(&redacted) {
+            Some(cached) => cached,
+            None => self.validate_key(license_key)?,
+        };
+        let served = result.from_cache.then(|| result.clone());
+        let handle = self.spawn_revalidator_after(redacted, interval, served)?;
+        Ok((result, handle))
+    }
+
+    /// Spawn the revalidator; after a `served` cached result, the first
+    /// refresh happens at once and is compared against it.
+    fn spawn_revalidator_after(
+        self: &Arc<Self>,
+        license_key: RedactedKey,
+        interval: Duration,
+        served: Option<ValidationResult>,
+    ) -> Result<RevalidatorHandle, GatewardenError> {
+        let manager = Arc::clone(self);
+        let seed = jitter_seed(self);
+        let key_hash = license_key.hash();
+        let leader_task = format!(
+            "{}-{}",
+            LEADER_TASK,
+            key_hash.get(..16).unwrap_or(&key_hash)
+        );
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let (event_tx, events) = mpsc::channel();
+        let watchers = Arc::new(Mutex::new(Watchers {
+            // Only valid results are served from the cache
+            current: if served.is_some() {
+                LicenseStatus::Valid
+            } else {
+                LicenseStatus::Unknown
+            },
+            senders: Vec::new(),
+        }));
+        let published = Arc::clone(&watchers);
+
+        let thread = std::thread::Builder::new()
+            .name("gatewarden-revalidator".to_string())
+            .spawn(move || {
+                let mut refresh_now = served.is_some();
+                let mut previous: Option<ValidationResult> = served;
+                let mut leader: Option<LeaderLock> = None;
+                let mut seed = seed;
+
+                loop {
+                    let wait = if std::mem::take(&mut refresh_now) {
+                        Duration::ZERO
+                    } else {
+                        // Saturates for intervals near `Duration::MAX`
+                        interval.saturating_add(jitter(interval, &mut seed))
+                    };
+                    match stop_rx.recv_timeout(wait) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        // Stop requested or handle dropped
+                        _ => return,
+                    }
+
+                    let mut follower = false;
+                    if leader.is_none() {
+                        match LeaderLock::try_acquire(
+                            manager.config().cache_namespace,
+                            &leader_task,
+                        ) {
+                            Ok(Some(lock)) => leader = Some(lock),
+                            // Another revalidator is refreshing this license
+                            Ok(None) => follower = true,
+                            // Locking unavailable; refreshing twice is harmless
+                            Err(_) => {}
+                        }
+                    }
+
+                    // Followers pick up the leader's results from the cache
+                    let refresh = if follower {
+                        manager.check_access(license_key.expose())
+                    } else {
+                        manager.force_refresh(license_key.expose())
+                    };
+                    if let Ok(mut watchers) = published.lock() {
+                        watchers.publish(LicenseStatus::from_refresh(&refresh));
+                    }
+                    let event = match refresh {
+                        Ok(current) => {
+                            let changed = previous.as_ref().map_or(true, |p| {
+                                p.valid != current.valid || p.state.code != current.state.code
+                            });
+                            let event = if changed {
+                                RevalidationEvent::StateChanged {
+                                    previous: previous.take(),
+                                    current: current.clone(),
+                                }
+                            } else {
+                                RevalidationEvent::Refreshed(current.clone())
+                            };
+                            previous = Some(current);
+                            event
+                        }
+                        Err(e) => RevalidationEvent::Failed(e),
+                    };
+
+                    if event_tx.send(event).is_err() {
+                        // Nobody is listening any more.
+                        return;
+                    }
+                }
+            })
+            .map_err(|e| {
+                GatewardenError::ConfigError(format!("Failed to spawn revalidator: {}", e))
+            })?;
+
+        Ok(RevalidatorHandle {
+            stop_tx: Some(stop_tx),
+            events,
+            watchers,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Seed the jitter generator from the manager's entropy source, falling
+/// back to the wall clock (jitter is not security-sensitive).
+fn jitter_seed(manager: &LicenseManager) -> u64 {
+    let seed = random_u64(manager.entropy()).unwrap_or_else(|_| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    });
+    // xorshift state must be non-zero
+    seed | 1
+}
+
+/// Random extra delay in `[0, interval / JITTER_DIVISOR]` (xorshift64).
+fn jitter(interval: Duration, seed: &mut u64) -> Duration {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+
+    let max = interval / JITTER_DIVISOR;
+    let max_nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+    if max_nanos == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_nanos(*seed % max_nanos.saturating_add(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{test_config, TEST_SIGNING_SEED};
+    use std::time::Instant;
+
+    #[test]
+    fn test_jitter_bounded() {
+        let mut seed = 12345;
+        let interval = Duration::from_secs(100);
+        for _ in 0..1000 {
+            assert!(jitter(interval, &mut seed) <= Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn test_jitter_zero_interval() {
+        let mut seed = 12345;
+        assert_eq!(jitter(Duration::ZERO, &mut seed), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_jitter_huge_interval() {
+        let mut seed = 12345;
+        for _ in 0..1000 {
+            let extra = jitter(Duration::MAX, &mut seed);
+            assert!(extra <= Duration::MAX / JITTER_DIVISOR);
+            assert_eq!(Duration::MAX.saturating_add(extra), Duration::MAX);
+        }
+    }
+
+    #[test]
+    fn test_spawn_revalidator_empty_key() {
+        let manager = Arc::new(LicenseManager::new(test_config()).unwrap());
+        let result = manager.spawn_revalidator("", Duration::from_secs(60));
+        assert!(matches!(result, Err(GatewardenError::MissingLicense)));
+    }
+
+    #[test]
+    fn test_revalidator_stops_promptly() {
+        let manager = Arc::new(LicenseManager::new(test_config()).unwrap());
+        let handle = manager
+            .spawn_revalidator("test-key", Duration::from_secs(3600))
+            .unwrap();
+
+        let start = Instant::now();
+        handle.stop();
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_watch_follows_status_changes() {
+        use crate::cache::file::FileCache;
+        use crate::chaos::FaultInjector;
+        use crate::clock::{Clock, MockClock};
+        use crate::mock::{MockKeygen, MockLicense};
+        use chrono::{TimeZone, Utc};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("key", MockLicense::new("lic-1"));
+        let faults = FaultInjector::new();
+        let mut config = test_config();
+        config.cache_namespace = "gatewarden-watch-test";
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone())
+            .with_fault_injector(faults.clone());
+
+        let handle = Arc::new(manager)
+            .spawn_revalidator("key", Duration::from_millis(10))
+            .unwrap();
+        let status = handle.watch();
+        let next = || status.recv_timeout(Duration::from_secs(5)).unwrap();
+        let mut current = next();
+        if current == LicenseStatus::Unknown {
+            current = next();
+        }
+        assert_eq!(current, LicenseStatus::Valid);
+
+        faults.fail_next_requests(u32::MAX);
+        assert_eq!(next(), LicenseStatus::Offline);
+        keygen.add_license("key", MockLicense::new("lic-1").suspended());
+        faults.reset();
+        assert_eq!(
+            next(),
+            LicenseStatus::Invalid {
+                code: "LICENSE_SUSPENDED".to_string()
+            }
+        );
+
+        handle.stop();
+        assert!(status.recv_timeout(Duration::from_secs(5)).is_err());
+    }
+
+    #[test]
+    fn test_revalidators_for_different_keys_both_refresh() {
+        use crate::cache::file::FileCache;
+        use crate::clock::{Clock, MockClock};
+        use crate::mock::{MockKeygen, MockLicense};
+        use chrono::{TimeZone, Utc};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("key-a", MockLicense::new("lic-a"));
+        keygen.add_license("key-b", MockLicense::new("lic-b"));
+        let mut config = test_config();
+        config.cache_namespace = "gatewarden-two-keys-test";
+        let manager = Arc::new(
+            LicenseManager::new_with_clock(config, clock)
+                .unwrap()
+                .with_cache_backend(Box::new(
+                    FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+                ))
+                .with_mock_keygen(keygen.clone()),
+        );
+
+        // Same namespace, same process: each license elects its own leader
+        let handles: Vec<_> = ["key-a", "key-b"]
+            .into_iter()
+            .map(|key| {
+                manager
+                    .spawn_revalidator(key, Duration::from_millis(10))
+                    .unwrap()
+            })
+            .collect();
+        for handle in &handles {
+            let event = handle
+                .events()
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap();
+            assert!(matches!(
+                event,
+                RevalidationEvent::StateChanged { current, .. } if current.valid && !current.from_cache
+            ));
+        }
+        for handle in handles {
+            handle.stop();
+        }
+    }
+
+    #[test]
+    fn test_follower_reads_leaders_results_from_cache() {
+        use crate::cache::file::FileCache;
+        use crate::clock::{Clock, MockClock};
+        use crate::mock::{MockKeygen, MockLicense};
+        use chrono::{TimeZone, Utc};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("key", MockLicense::new("lic-1"));
+        let mut config = test_config();
+        config.cache_namespace = "gatewarden-follower-test";
+        let manager = Arc::new(
+            LicenseManager::new_with_clock(config, clock)
+                .unwrap()
+                .with_cache_backend(Box::new(
+                    FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+                ))
+                .with_mock_keygen(keygen.clone()),
+        );
+        manager.validate_key("key").unwrap();
+
+        // Hold this license's leader lock, as another process would
+        let task = format!(
+            "{}-{}",
+            LEADER_TASK,
+            &crate::cache::file::hash_license_key("key")[..16]
+        );
+        let _leader = LeaderLock::try_acquire("gatewarden-follower-test", &task)
+            .unwrap()
+            .unwrap();
+
+        let requests = keygen.request_count();
+        let handle = manager
+            .spawn_revalidator("key", Duration::from_millis(10))
+            .unwrap();
+        let status = handle.watch();
+        let mut current = status.recv_timeout(Duration::from_secs(5)).unwrap();
+        if current == LicenseStatus::Unknown {
+            current = status.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+        assert_eq!(current, LicenseStatus::Valid);
+        let event = handle
+            .events()
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap();
+        assert!(matches!(
+            event,
+            RevalidationEvent::StateChanged { current, .. } if current.from_cache
+        ));
+        handle.stop();
+        assert_eq!(keygen.request_count(), requests);
+    }
+
+    #[test]
+    fn test_start_offline_first_serves_cache_then_revalidates() {
+        use crate::cache::file::FileCache;
+        use crate::clock::{Clock, MockClock};
+        use crate::mock::{MockKeygen, MockLicense};
+        use crate::policy::fallback::FallbackReason;
+        use chrono::{TimeZone, Utc};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        keygen.add_license("key", MockLicense::new("lic-1"));
+        let mut config = test_config();
+        config.cache_namespace = "gatewarden-offline-first-test";
+        let manager = Arc::new(
+            LicenseManager::new_with_clock(config, clock)
+                .unwrap()
+                .with_cache_backend(Box::new(
+                    FileCache::with_path(temp_dir.path().to_path_buf()).unwrap(),
+                ))
+                .with_mock_keygen(keygen.clone()),
+        );
+
+        // Nothing cached yet: validated online before returning
+        let (result, handle) = manager
+            .start_offline_first("key", Duration::from_secs(3600))
+            .unwrap();
+        assert!(result.valid && !result.from_cache);
+        handle.stop();
+
+        // Cached: served at once, and the background refresh sees the suspension
+        keygen.add_license("key", MockLicense::new("lic-1").suspended());
+        let requests = keygen.request_count();
+        let (result, handle) = manager
+            .start_offline_first("key", Duration::from_secs(3600))
+            .unwrap();
+        assert!(result.valid);
+        assert_eq!(result.fallback_reason, Some(FallbackReason::Deferred));
+        let status = handle.watch();
+        let mut current = status.recv().unwrap();
+        if current == LicenseStatus::Valid {
+            current = status.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+        assert_eq!(
+            current,
+            LicenseStatus::Invalid {
+                code: "LICENSE_SUSPENDED".to_string()
+            }
+        );
+        assert!(keygen.request_count() > requests);
+        handle.stop();
+    }
+}
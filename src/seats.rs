@@ -0,0 +1,751 @@
+//! Machine activations and concurrent seats of floating licenses.
+//!
+//! [`LicenseManager::activate_machine`] activates a machine for a license
+//! and returns a [`MachineLease`] that deactivates it again when dropped,
+//! so apps that exit normally do not strand activations. The drop waits
+//! for Keygen at most a few seconds, so an unreachable Keygen cannot hold
+//! up shutdown.
+//!
+//! A floating policy limits how many machines a license may have
+//! activated at once. Treating each running instance as a machine turns
+//! that limit into a concurrent-seat count: [`LicenseManager::check_out_seat`]
+//! activates a machine with a fingerprint unique to the check-out, and
+//! the returned [`Seat`] deactivates it again when checked in or dropped.
+//!
+//! ```ignore
+//! let seat = match manager.check_out_seat(&key) {
+//!     Ok(seat) => seat,
+//!     Err(GatewardenError::NoSeatAvailable) => return show_all_seats_in_use(),
+//!     Err(e) => return Err(e.into()),
+//! };
+//! run_app();
+//! drop(seat); // or seat.check_in()?
+//! ```
+//!
+//! Hardware components registered with `with_components` are attached to
+//! each activation, and [`Seat::validate`] checks that the machine still
+//! has them.
+//!
+//! Keep the lease or seat alive for as long as the application runs,
+//! e.g. as a local in `main`. Destructors do not run on
+//! `std::process::exit`, on panics with `panic = "abort"`, or when the
+//! process is killed; give the policy a heartbeat or machine culling so
+//! seats of crashed instances return to the pool.
+//!
+//! With a heartbeat policy, [`Seat::spawn_heartbeat`] pings the seat's
+//! machine in the background. A machine Keygen culled while the heartbeat
+//! was missed (e.g. while a laptop slept) is re-activated with the same
+//! fingerprint, and the callback learns about it:
+//!
+//! ```ignore
+//! let heartbeat = seat.spawn_heartbeat(Duration::from_secs(600), |beat| {
+//!     if let Err(e) = beat {
+//!         log::warn!("seat heartbeat failed: {}", e);
+//!     }
+//! })?;
+//! run_app();
+//! drop(heartbeat); // stops pinging and checks the seat in
+//! ```
+
+use crate::client::response::{check_available, KeygenResponse};
+use crate::manager::{license_auth, LicenseManager, ACTIVATION_CODES};
+use crate::protocol::models::{KeygenMachineResponse, KeygenValidateResponse, LicenseState};
+use crate::redact::RedactedKey;
+use crate::GatewardenError;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Random bytes in a seat's fingerprint.
+const SEAT_NONCE_BYTES: usize = 16;
+
+/// Longest a dropped lease waits for Keygen to deactivate its machine, so
+/// an unreachable Keygen cannot hold up shutdown.
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An activated machine; deactivated when dropped.
+pub struct MachineLease {
+    manager: LicenseManager,
+    license_key: RedactedKey,
+    license_id: String,
+    machine_id: String,
+    fingerprint: String,
+    released: bool,
+}
+
+impl MachineLease {
+    /// Keygen ID of the activated machine.
+    pub fn machine_id(&self) -> &str {
+        &self.machine_id
+    }
+
+    /// Fingerprint the machine was activated with.
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Revalidate the license scoped to this machine and its hardware
+    /// components.
+    ///
+    /// # Errors
+    /// - `ComponentsMismatch` - The machine's components changed since it
+    ///   was activated
+    /// - `InvalidLicense` (or a more specific license error) - Keygen
+    ///   rejected the license, or no longer knows the machine
+    /// - `ProtocolError` - Unparseable response or unexpected status
+    /// - Any transport, availability, verification, or policy error
+    pub fn validate(&self) -> Result<LicenseState, GatewardenError> {
+        let response = self.manager.client().validate_fingerprint(
+            &self.license_key,
+            &self.fingerprint,
+            self.manager.components(),
+            self.manager.user(),
+        )?;
+        let document: KeygenValidateResponse = self
+            .manager
+            .accept_seat_response(&response, "Machine validation")?;
+        let state = LicenseState::from_keygen_response(&document)?;
+        self.manager.check_state(&state, 0)?;
+        Ok(state)
+    }
+
+    /// Ping the machine's heartbeat, re-activating the machine if Keygen
+    /// culled it.
+    ///
+    /// A culled machine (HTTP 404), or one whose heartbeat died and is not
+    /// resurrected (HTTP 422), is deactivated if still present and
+    /// activated again with the same fingerprint and components.
+    ///
+    /// # Errors
+    /// - `NoSeatAvailable` - The machine was culled and another instance
+    ///   took its place in the meantime
+    /// - `InvalidLicense` - Keygen refused the license (HTTP 401/403)
+    /// - `ProtocolError` - Unparseable response or unexpected status
+    /// - Any transport, availability, or verification error
+    pub fn heartbeat(&mut self) -> Result<HeartbeatStatus, GatewardenError> {
+        let Some(document) = self
+            .manager
+            .ping_machine(&self.license_key, &self.machine_id)?
+        else {
+            // A dead machine kept by the policy still holds the fingerprint
+            self.manager
+                .deactivate(&self.license_key, &self.machine_id, None)?;
+            let machine_id = self.manager.activate_fingerprint(
+                &self.license_key,
+                &self.license_id,
+                &self.fingerprint,
+            )?;
+            trace_info!(machine = %machine_id, "culled machine re-activated");
+            let previous_machine_id = std::mem::replace(&mut self.machine_id, machine_id);
+            return Ok(HeartbeatStatus::Reactivated {
+                previous_machine_id,
+            });
+        };
+
+        match document.data.attributes.heartbeat_status.as_deref() {
+            Some("RESURRECTED") => Ok(HeartbeatStatus::Resurrected),
+            _ => Ok(HeartbeatStatus::Alive),
+        }
+    }
+
+    /// Deactivate the machine, reporting failures that dropping the lease
+    /// would only log.
+    ///
+    /// # Errors
+    /// - `InvalidLicense` - Keygen refused the license (HTTP 401/403)
+    /// - `ProtocolError` - Unexpected status
+    /// - Any transport, availability, or verification error
+    pub fn release(mut self) -> Result<(), GatewardenError> {
+        self.released = true;
+        self.manager
+            .deactivate(&self.license_key, &self.machine_id, None)
+    }
+}
+
+impl Drop for MachineLease {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        if let Err(_e) =
+            self.manager
+                .deactivate(&self.license_key, &self.machine_id, Some(RELEASE_TIMEOUT))
+        {
+            trace_warn!(error = %_e, machine = %self.machine_id, "machine not deactivated");
+        }
+    }
+}
+
+impl fmt::Debug for MachineLease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MachineLease")
+            .field("license_key", &self.license_key)
+            .field("machine_id", &self.machine_id)
+            .field("fingerprint", &self.fingerprint)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A checked-out seat; checked back in when dropped.
+#[derive(Debug)]
+pub struct Seat {
+    lease: MachineLease,
+}
+
+impl Seat {
+    /// Keygen ID of the machine holding the seat.
+    pub fn machine_id(&self) -> &str {
+        self.lease.machine_id()
+    }
+
+    /// Fingerprint the seat's machine was activated with.
+    pub fn fingerprint(&self) -> &str {
+        self.lease.fingerprint()
+    }
+
+    /// Revalidate the license scoped to this seat's machine and hardware
+    /// components; see [`MachineLease::validate`].
+    ///
+    /// # Errors
+    /// - Any error from `MachineLease::validate`
+    pub fn validate(&self) -> Result<LicenseState, GatewardenError> {
+        self.lease.validate()
+    }
+
+    /// Ping the heartbeat of the seat's machine, re-activating the machine
+    /// if Keygen culled it; see [`MachineLease::heartbeat`].
+    ///
+    /// # Errors
+    /// - Any error from `MachineLease::heartbeat`
+    pub fn heartbeat(&mut self) -> Result<HeartbeatStatus, GatewardenError> {
+        self.lease.heartbeat()
+    }
+
+    /// Ping the seat's heartbeat now and then every `interval` on a
+    /// background thread, passing each outcome to `on_beat`.
+    ///
+    /// Failed pings are retried on the next tick. Dropping the returned
+    /// handle stops the thread and checks the seat in.
+    ///
+    /// # Errors
+    /// - `ConfigError` - The thread cannot be started
+    pub fn spawn_heartbeat<F>(
+        self,
+        interval: Duration,
+        mut on_beat: F,
+    ) -> Result<HeartbeatHandle, GatewardenError>
+    where
+        F: FnMut(Result<HeartbeatStatus, GatewardenError>) + Send + 'static,
+    {
+        let mut seat = self;
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let thread = std::thread::Builder::new()
+            .name("gatewarden-heartbeat".to_string())
+            .spawn(move || loop {
+                on_beat(seat.heartbeat());
+                match stop_rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // Stop requested or handle dropped
+                    _ => return seat,
+                }
+            })
+            .map_err(|e| {
+                GatewardenError::ConfigError(format!("Failed to spawn seat heartbeat: {}", e))
+            })?;
+
+        Ok(HeartbeatHandle {
+            stop_tx: Some(stop_tx),
+            thread: Some(thread),
+        })
+    }
+
+    /// Check the seat back in, reporting failures that dropping the seat
+    /// would only log.
+    ///
+    /// # Errors
+    /// - Any error from `MachineLease::release`
+    pub fn check_in(self) -> Result<(), GatewardenError> {
+        self.lease.release()
+    }
+}
+
+/// Outcome of a machine heartbeat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeartbeatStatus {
+    /// The machine is alive.
+    Alive,
+
+    /// The machine's heartbeat had died and Keygen resurrected it (for
+    /// policies with a resurrection period).
+    Resurrected,
+
+    /// The machine had been culled and was activated again.
+    Reactivated {
+        /// ID of the culled machine; `machine_id()` returns the new one.
+        previous_machine_id: String,
+    },
+}
+
+/// Handle to a running seat heartbeat.
+///
+/// Dropping the handle stops the background thread and checks the seat in.
+pub struct HeartbeatHandle {
+    stop_tx: Option<Sender<()>>,
+    thread: Option<JoinHandle<Seat>>,
+}
+
+impl HeartbeatHandle {
+    /// Stop the background thread and take back the seat.
+    ///
+    /// Returns `None` only if the heartbeat callback panicked, in which
+    /// case the seat was dropped (and checked in) with the thread.
+    pub fn stop(mut self) -> Option<Seat> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Option<Seat> {
+        // Dropping the sender wakes the worker immediately.
+        self.stop_tx.take();
+        self.thread.take().and_then(|thread| thread.join().ok())
+    }
+}
+
+impl Drop for HeartbeatHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl fmt::Debug for HeartbeatHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeartbeatHandle").finish_non_exhaustive()
+    }
+}
+
+impl LicenseManager {
+    /// Check out a seat of a floating license.
+    ///
+    /// Activates a machine with a fresh fingerprint (see
+    /// `activate_machine`), so every running instance takes its own seat.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `NoSeatAvailable` - Keygen reported `TOO_MANY_MACHINES`, or
+    ///   refused the activation (HTTP 422)
+    /// - `InvalidLicense` (or a more specific license error) - Keygen
+    ///   rejected the key
+    /// - `ProtocolError` - Unparseable response or unexpected status
+    /// - Any entropy, transport, availability, verification, or policy
+    ///   error
+    pub fn check_out_seat(&self, license_key: &str) -> Result<Seat, GatewardenError> {
+        let mut nonce = [0u8; SEAT_NONCE_BYTES];
+        self.entropy().fill_bytes(&mut nonce)?;
+        let fingerprint = format!("seat-{}", hex::encode(nonce));
+
+        let lease = self.activate_machine(license_key, &fingerprint)?;
+        trace_info!(machine = %lease.machine_id, "seat checked out");
+        Ok(Seat { lease })
+    }
+
+    /// Activate a machine with `fingerprint` for a license, returning a
+    /// lease that deactivates it when dropped.
+    ///
+    /// Validates the key scoped to the fingerprint, then activates a
+    /// machine with that fingerprint and the registered hardware
+    /// components, authenticating with the stored license token if there
+    /// is one, else the key. Keygen's `NO_MACHINES` and `NO_MACHINE` codes
+    /// just mean the fingerprint is not activated yet. Both responses are
+    /// verified like any other; the cache is neither read nor updated.
+    ///
+    /// Dropping the lease deactivates the machine, waiting at most five
+    /// seconds for Keygen; call [`MachineLease::release`] to wait for the
+    /// full request timeout and see failures.
+    ///
+    /// # Errors
+    /// - `MissingLicense` - `license_key` is empty
+    /// - `NoSeatAvailable` - Keygen reported `TOO_MANY_MACHINES`, or
+    ///   refused the activation (HTTP 422, e.g. the machine limit is
+    ///   reached or the fingerprint is already activated)
+    /// - `InvalidLicense` (or a more specific license error) - Keygen
+    ///   rejected the key
+    /// - `ProtocolError` - Unparseable response or unexpected status
+    /// - Any transport, availability, verification, or policy error
+    pub fn activate_machine(
+        &self,
+        license_key: &str,
+        fingerprint: &str,
+    ) -> Result<MachineLease, GatewardenError> {
+        let license_key = self.redact(license_key)?;
+        let response = self.client().validate_fingerprint(
+            &license_key,
+            fingerprint,
+            self.components(),
+            self.user(),
+        )?;
+        let document: KeygenValidateResponse =
+            self.accept_seat_response(&response, "Machine validation")?;
+        let state = LicenseState::from_keygen_response(&document)?;
+        if state.code == "TOO_MANY_MACHINES" {
+            return Err(GatewardenError::NoSeatAvailable);
+        }
+        if !ACTIVATION_CODES.contains(&state.code.as_str()) {
+            self.check_state(&state, 0)?;
+        }
+        let license_id = state
+            .license_id
+            .ok_or_else(|| GatewardenError::ProtocolError {
+                message: "Validation response has no license".to_string(),
+                source: None,
+            })?;
+
+        let machine_id = self.activate_fingerprint(&license_key, &license_id, fingerprint)?;
+        Ok(MachineLease {
+            manager: self.clone(),
+            license_key,
+            license_id,
+            machine_id,
+            fingerprint: fingerprint.to_string(),
+            released: false,
+        })
+    }
+
+    /// Activate a machine with `fingerprint` and the registered hardware
+    /// components for license `license_id`, returning its ID.
+    fn activate_fingerprint(
+        &self,
+        license_key: &RedactedKey,
+        license_id: &str,
+        fingerprint: &str,
+    ) -> Result<String, GatewardenError> {
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(license_key, token.as_ref());
+        let response =
+            self.client()
+                .activate_machine_as(auth, license_id, fingerprint, self.components())?;
+        let document: KeygenMachineResponse =
+            self.accept_seat_response(&response, "Machine activation")?;
+        if document.data.attributes.fingerprint != fingerprint {
+            return Err(GatewardenError::ProtocolError {
+                message: "Activated machine has another fingerprint".to_string(),
+                source: None,
+            });
+        }
+        Ok(document.data.id)
+    }
+
+    /// Ping the heartbeat of machine `machine_id`; `None` if Keygen culled
+    /// the machine (HTTP 404) or its heartbeat is dead (HTTP 422).
+    fn ping_machine(
+        &self,
+        license_key: &RedactedKey,
+        machine_id: &str,
+    ) -> Result<Option<KeygenMachineResponse>, GatewardenError> {
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(license_key, token.as_ref());
+        let response = self.client().ping_machine_as(auth, machine_id)?;
+        check_available(&response)?;
+        self.verify_with_skew(&response)?;
+
+        match response.status {
+            200..=299 => {}
+            404 | 422 => return Ok(None),
+            401 | 403 => return Err(GatewardenError::InvalidLicense),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("Machine heartbeat failed with HTTP {}", status),
+                    source: None,
+                })
+            }
+        }
+
+        serde_json::from_str(response.body_str()?)
+            .map(Some)
+            .map_err(|e| GatewardenError::ProtocolError {
+                message: "Parse error".to_string(),
+                source: Some(e.into()),
+            })
+    }
+
+    /// Deactivate machine `machine_id`, giving up after `deadline`; a
+    /// machine Keygen no longer knows (HTTP 404) counts as deactivated.
+    fn deactivate(
+        &self,
+        license_key: &RedactedKey,
+        machine_id: &str,
+        deadline: Option<Duration>,
+    ) -> Result<(), GatewardenError> {
+        let token = self.license_token(license_key.expose())?;
+        let auth = license_auth(license_key, token.as_ref());
+        let response = self
+            .client()
+            .deactivate_machine_as(auth, machine_id, deadline)?;
+        check_available(&response)?;
+        self.verify_with_skew(&response)?;
+
+        match response.status {
+            200..=299 | 404 => {
+                trace_info!(machine = %machine_id, "machine deactivated");
+                Ok(())
+            }
+            401 | 403 => Err(GatewardenError::InvalidLicense),
+            status => Err(GatewardenError::ProtocolError {
+                message: format!("Machine deactivation failed with HTTP {}", status),
+                source: None,
+            }),
+        }
+    }
+
+    /// Verify a seat response and parse it.
+    fn accept_seat_response<T: DeserializeOwned>(
+        &self,
+        response: &KeygenResponse,
+        action: &str,
+    ) -> Result<T, GatewardenError> {
+        check_available(response)?;
+        self.verify_with_skew(response)?;
+
+        match response.status {
+            200..=299 => {}
+            422 => return Err(GatewardenError::NoSeatAvailable),
+            401 | 403 | 404 => return Err(GatewardenError::InvalidLicense),
+            status => {
+                return Err(GatewardenError::ProtocolError {
+                    message: format!("{} failed with HTTP {}", action, status),
+                    source: None,
+                })
+            }
+        }
+
+        serde_json::from_str(response.body_str()?).map_err(|e| GatewardenError::ProtocolError {
+            message: "Parse error".to_string(),
+            source: Some(e.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::file::FileCache;
+    use crate::clock::{Clock, MockClock};
+    use crate::fingerprint::Component;
+    use crate::mock::{MockKeygen, MockLicense};
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+    use crate::GatewardenConfig;
+    use chrono::{TimeZone, Utc};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn setup(temp_dir: &TempDir) -> (Arc<MockKeygen>, LicenseManager) {
+        let clock: Arc<dyn Clock> = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap(),
+        ));
+        let keygen = MockKeygen::with_clock(&TEST_SIGNING_SEED, clock.clone());
+        let config = GatewardenConfig::builder()
+            .app_name("seats-test")
+            .account_id("test-account")
+            .public_key_hex(TEST_PUBLIC_KEY_HEX)
+            .memo_ttl(Duration::ZERO)
+            .build()
+            .unwrap();
+        let manager = LicenseManager::new_with_clock(config, clock)
+            .unwrap()
+            .with_cache_backend(Box::new(
+                FileCache::with_path(temp_dir.path().join("cache")).unwrap(),
+            ))
+            .with_mock_keygen(keygen.clone());
+        (keygen, manager)
+    }
+
+    #[test]
+    fn test_seats_are_limited_and_released() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_max_machines(2));
+
+        let first = manager.check_out_seat("key").unwrap();
+        let second = manager.check_out_seat("key").unwrap();
+        assert_ne!(first.fingerprint(), second.fingerprint());
+        assert_eq!(keygen.machines("key"), Some(2));
+        assert!(matches!(
+            manager.check_out_seat("key"),
+            Err(GatewardenError::NoSeatAvailable)
+        ));
+
+        first.check_in().unwrap();
+        assert_eq!(keygen.machines("key"), Some(1));
+        let third = manager.check_out_seat("key").unwrap();
+
+        drop(second);
+        drop(third);
+        assert_eq!(keygen.machines("key"), Some(0));
+    }
+
+    #[test]
+    fn test_over_allocated_license_has_no_seat() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_max_machines(1));
+        keygen.add_machine("key", "old-1");
+        keygen.add_machine("key", "old-2");
+
+        assert!(matches!(
+            manager.check_out_seat("key"),
+            Err(GatewardenError::NoSeatAvailable)
+        ));
+        assert_eq!(keygen.machines("key"), Some(2));
+    }
+
+    #[test]
+    fn test_seat_validation_matches_components() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_max_machines(1));
+        let gpu = Component::new("gpu", "GPU-1");
+        let disk = Component::new("disk", "WD-123");
+
+        let seat = manager
+            .clone()
+            .with_components(vec![gpu.clone(), disk])
+            .check_out_seat("key")
+            .unwrap();
+        assert_eq!(
+            keygen
+                .machine_components("key", seat.machine_id())
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(seat.validate().unwrap().valid);
+
+        // The same seat seen from a machine with another GPU
+        let moved = MachineLease {
+            manager: manager.with_components(vec![Component::new("gpu", "GPU-2")]),
+            license_key: RedactedKey::new("key"),
+            license_id: seat.lease.license_id.clone(),
+            machine_id: seat.lease.machine_id.clone(),
+            fingerprint: seat.lease.fingerprint.clone(),
+            released: true,
+        };
+        assert!(matches!(
+            moved.validate(),
+            Err(GatewardenError::ComponentsMismatch)
+        ));
+        assert!(seat.validate().is_ok());
+    }
+
+    #[test]
+    fn test_check_out_rejects_bad_license() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license(
+            "suspended",
+            MockLicense::new("lic-2").with_max_machines(1).suspended(),
+        );
+
+        assert!(matches!(
+            manager.check_out_seat(""),
+            Err(GatewardenError::MissingLicense)
+        ));
+        assert!(matches!(
+            manager.check_out_seat("unknown"),
+            Err(GatewardenError::InvalidLicense)
+        ));
+        assert!(matches!(
+            manager.check_out_seat("suspended"),
+            Err(GatewardenError::LicenseSuspended)
+        ));
+        assert_eq!(keygen.machines("suspended"), Some(0));
+    }
+
+    #[test]
+    fn test_machine_lease_deactivates_on_drop() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_max_machines(1));
+
+        let lease = manager.activate_machine("key", "workstation-1").unwrap();
+        assert_eq!(lease.fingerprint(), "workstation-1");
+        assert!(lease.validate().unwrap().valid);
+        assert!(matches!(
+            manager.activate_machine("key", "workstation-1"),
+            Err(GatewardenError::NoSeatAvailable)
+        ));
+
+        drop(lease);
+        assert_eq!(keygen.machines("key"), Some(0));
+        let lease = manager.activate_machine("key", "workstation-1").unwrap();
+        lease.release().unwrap();
+        assert_eq!(keygen.machines("key"), Some(0));
+    }
+
+    #[test]
+    fn test_heartbeat_recovers_culled_seat() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_max_machines(1));
+        keygen.add_license(
+            "revivable",
+            MockLicense::new("lic-2")
+                .with_max_machines(1)
+                .resurrects_machines(),
+        );
+
+        let mut seat = manager.check_out_seat("key").unwrap();
+        assert_eq!(seat.heartbeat().unwrap(), HeartbeatStatus::Alive);
+
+        let culled = seat.machine_id().to_string();
+        keygen.cull_machines("key");
+        assert_eq!(
+            seat.heartbeat().unwrap(),
+            HeartbeatStatus::Reactivated {
+                previous_machine_id: culled.clone()
+            }
+        );
+        assert_ne!(seat.machine_id(), culled);
+        assert_eq!(keygen.machines("key"), Some(1));
+
+        // A dead machine the policy keeps is replaced too
+        keygen.kill_heartbeats("key");
+        assert!(matches!(
+            seat.heartbeat().unwrap(),
+            HeartbeatStatus::Reactivated { .. }
+        ));
+        assert_eq!(keygen.machines("key"), Some(1));
+
+        let mut revivable = manager.check_out_seat("revivable").unwrap();
+        keygen.kill_heartbeats("revivable");
+        assert_eq!(revivable.heartbeat().unwrap(), HeartbeatStatus::Resurrected);
+        assert_eq!(revivable.heartbeat().unwrap(), HeartbeatStatus::Alive);
+    }
+
+    #[test]
+    fn test_background_heartbeat() {
+        let temp_dir = TempDir::new().unwrap();
+        let (keygen, manager) = setup(&temp_dir);
+        keygen.add_license("key", MockLicense::new("lic-1").with_max_machines(1));
+
+        let (beats_tx, beats) = std::sync::mpsc::channel();
+        let seat = manager.check_out_seat("key").unwrap();
+        let handle = seat
+            .spawn_heartbeat(Duration::from_secs(3600), move |beat| {
+                let _ = beats_tx.send(beat.ok());
+            })
+            .unwrap();
+        assert_eq!(
+            beats.recv_timeout(Duration::from_secs(5)).unwrap(),
+            Some(HeartbeatStatus::Alive)
+        );
+
+        let seat = handle.stop().unwrap();
+        assert_eq!(keygen.machines("key"), Some(1));
+        let handle = seat
+            .spawn_heartbeat(Duration::from_secs(3600), |_| {})
+            .unwrap();
+        drop(handle);
+        assert_eq!(keygen.machines("key"), Some(0));
+    }
+}
@@ -0,0 +1,181 @@
+//! Coalescing of concurrent identical calls.
+//!
+//! When several threads validate the same key at once, only the first
+//! (the leader) calls Keygen; the others wait and receive a copy of its
+//! result. If the leader panics, waiting callers run the call themselves.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Progress of one in-flight call.
+enum CallState<T> {
+    Running,
+    Done(T),
+    Abandoned,
+}
+
+/// An in-flight call that followers wait on.
+struct Call<T> {
+    state: Mutex<CallState<T>>,
+    done: Condvar,
+}
+
+/// Deduplicates concurrent calls by key.
+pub(crate) struct SingleFlight<T> {
+    calls: Mutex<HashMap<String, Arc<Call<T>>>>,
+}
+
+impl<T> SingleFlight<T> {
+    /// Create an empty group.
+    pub(crate) fn new() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `call` for `key`, or wait for the call already in flight for
+    /// `key` and return `share` of its result.
+    ///
+    /// Returns the result and whether this caller made the call.
+    pub(crate) fn run(
+        &self,
+        key: &str,
+        call: impl FnOnce() -> T,
+        share: impl Fn(&T) -> T,
+    ) -> (T, bool) {
+        let (flight, leader) = match self.calls.lock() {
+            Ok(mut calls) => match calls.get(key) {
+                Some(flight) => (flight.clone(), false),
+                None => {
+                    let flight = Arc::new(Call {
+                        state: Mutex::new(CallState::Running),
+                        done: Condvar::new(),
+                    });
+                    calls.insert(key.to_string(), flight.clone());
+                    (flight, true)
+                }
+            },
+            // Coalescing is an optimization; never block on a poisoned map
+            Err(_) => return (call(), true),
+        };
+
+        if !leader {
+            return match wait(&flight, &share) {
+                Some(result) => (result, false),
+                None => (call(), true),
+            };
+        }
+
+        let guard = LeaderGuard {
+            group: self,
+            key,
+            flight: &flight,
+        };
+        let result = call();
+        if let Ok(mut state) = flight.state.lock() {
+            *state = CallState::Done(share(&result));
+        }
+        drop(guard);
+        (result, true)
+    }
+}
+
+/// Wait for the leader; `None` if it gave up without a result.
+fn wait<T>(flight: &Call<T>, share: &impl Fn(&T) -> T) -> Option<T> {
+    let mut state = flight.state.lock().ok()?;
+    loop {
+        match &*state {
+            CallState::Running => state = flight.done.wait(state).ok()?,
+            CallState::Done(result) => return Some(share(result)),
+            CallState::Abandoned => return None,
+        }
+    }
+}
+
+/// Retires the leader's call on completion or unwind, waking followers.
+struct LeaderGuard<'a, T> {
+    group: &'a SingleFlight<T>,
+    key: &'a str,
+    flight: &'a Call<T>,
+}
+
+impl<T> Drop for LeaderGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Ok(mut calls) = self.group.calls.lock() {
+            calls.remove(self.key);
+        }
+        if let Ok(mut state) = self.flight.state.lock() {
+            if matches!(*state, CallState::Running) {
+                *state = CallState::Abandoned;
+            }
+        }
+        self.flight.done.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_concurrent_calls_coalesce() {
+        let group = Arc::new(SingleFlight::new());
+        let calls = Arc::new(AtomicU32::new(0));
+        let barrier = Arc::new(Barrier::new(4));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (group, calls, barrier) = (group.clone(), calls.clone(), barrier.clone());
+                thread::spawn(move || {
+                    barrier.wait();
+                    group.run(
+                        "key",
+                        || {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            thread::sleep(Duration::from_millis(100));
+                            42
+                        },
+                        |result| *result,
+                    )
+                })
+            })
+            .collect();
+
+        let results: Vec<(u32, bool)> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert!(results.iter().all(|(result, _)| *result == 42));
+        assert_eq!(results.iter().filter(|(_, leader)| *leader).count(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Later calls are not served from the finished flight
+        assert_eq!(group.run("key", || 7, |result| *result), (7, true));
+    }
+
+    #[test]
+    fn test_follower_retries_after_leader_panics() {
+        let group = Arc::new(SingleFlight::new());
+        let started = Arc::new(Barrier::new(2));
+
+        let leader = {
+            let (group, started) = (group.clone(), started.clone());
+            thread::spawn(move || {
+                group.run(
+                    "key",
+                    || {
+                        started.wait();
+                        thread::sleep(Duration::from_millis(100));
+                        panic!("leader failed");
+                    },
+                    |result: &u32| *result,
+                )
+            })
+        };
+
+        started.wait();
+        assert_eq!(group.run("key", || 7, |result| *result), (7, true));
+        assert!(leader.join().is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Clock-skew measurement and correction.
+//!
+//! A verified Keygen `Date` header is a lower bound on the true time: the
+//! server produced the response before we received it. When the local
+//! clock is behind that bound, responses look future-dated and fail with
+//! `ResponseFromFuture`. [`ClockSkew`] records how far behind the local
+//! clock is, and [`SkewCorrectedClock`] adds it to every reading.
+//!
+//! The offset is only ever positive (clock moved forward), so the
+//! corrected clock always lies between the local clock and the true time:
+//! freshness and grace checks are never looser than without correction.
+//! A local clock that runs *ahead* is not corrected, since a replayed old
+//! response would be indistinguishable from it.
+
+use crate::clock::Clock;
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+/// Largest offset that will be applied (24 hours). Larger measurements are ignored.
+pub const MAX_CLOCK_SKEW_SECONDS: i64 = 24 * 60 * 60;
+
+/// Offsets within this many seconds of the stored value are not rewritten.
+const PERSIST_THRESHOLD_SECONDS: i64 = 2;
+
+/// File name for the persisted offset within the cache namespace.
+const SKEW_FILE: &str = "clock_skew";
+
+/// Measured offset of the local clock behind server time.
+#[derive(Debug)]
+pub struct ClockSkew {
+    offset_secs: AtomicI64,
+    path: Option<PathBuf>,
+}
+
+impl ClockSkew {
+    /// Load the offset persisted in a cache namespace (zero if none).
+    ///
+    /// Stored at `dirs::data_dir()/<namespace>/clock_skew`. Missing or
+    /// malformed files are treated as zero.
+    pub fn load(namespace: &str) -> Self {
+        let path = dirs::data_dir().map(|d| d.join(namespace).join(SKEW_FILE));
+        let offset = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|s| s.trim().parse::<i64>().ok())
+            .filter(|o| (0..=MAX_CLOCK_SKEW_SECONDS).contains(o))
+            .unwrap_or(0);
+
+        Self {
+            offset_secs: AtomicI64::new(offset),
+            path,
+        }
+    }
+
+    /// An offset that is measured but never persisted.
+    pub fn in_memory() -> Self {
+        Self {
+            offset_secs: AtomicI64::new(0),
+            path: None,
+        }
+    }
+
+    /// Current offset added to local time.
+    pub fn offset(&self) -> Duration {
+        Duration::seconds(self.offset_secs.load(Ordering::SeqCst))
+    }
+
+    /// Record a verified server date observed at local time `local_now`.
+    ///
+    /// Returns `true` if the offset changed.
+    pub(crate) fn observe(&self, server_date: DateTime<Utc>, local_now: DateTime<Utc>) -> bool {
+        let measured = (server_date - local_now).num_seconds().max(0);
+        if measured > MAX_CLOCK_SKEW_SECONDS {
+            return false;
+        }
+
+        let previous = self.offset_secs.swap(measured, Ordering::SeqCst);
+        if previous == measured {
+            return false;
+        }
+
+        if (measured - previous).abs() > PERSIST_THRESHOLD_SECONDS {
+            self.persist(measured);
+        }
+        true
+    }
+
+    /// Best-effort write of the offset; a lost write only means re-measuring.
+    fn persist(&self, offset: i64) {
+        if let Some(path) = &self.path {
+            let _ = fs::write(path, offset.to_string());
+        }
+    }
+}
+
+/// Clock that applies a [`ClockSkew`] offset to an inner clock.
+pub struct SkewCorrectedClock {
+    inner: Arc<dyn Clock>,
+    skew: ClockSkew,
+}
+
+impl SkewCorrectedClock {
+    /// Wrap `inner` with the given skew.
+    pub fn new(inner: Arc<dyn Clock>, skew: ClockSkew) -> Self {
+        Self { inner, skew }
+    }
+
+    /// Uncorrected local time.
+    pub fn local_now(&self) -> DateTime<Utc> {
+        self.inner.now_utc()
+    }
+
+    /// The skew being applied.
+    pub fn skew(&self) -> &ClockSkew {
+        &self.skew
+    }
+}
+
+impl Clock for SkewCorrectedClock {
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.inner.now_utc() + self.skew.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::TimeZone;
+
+    fn local() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_behind_clock_is_corrected() {
+        let clock =
+            SkewCorrectedClock::new(Arc::new(MockClock::new(local())), ClockSkew::in_memory());
+        let server = local() + Duration::minutes(10);
+
+        assert!(clock.skew().observe(server, clock.local_now()));
+        assert_eq!(clock.now_utc(), server);
+    }
+
+    #[test]
+    fn test_ahead_clock_is_not_corrected() {
+        let skew = ClockSkew::in_memory();
+        assert!(!skew.observe(local() - Duration::minutes(10), local()));
+        assert_eq!(skew.offset(), Duration::zero());
+    }
+
+    #[test]
+    fn test_implausible_skew_ignored() {
+        let skew = ClockSkew::in_memory();
+        assert!(!skew.observe(local() + Duration::days(3), local()));
+        assert_eq!(skew.offset(), Duration::zero());
+    }
+
+    #[test]
+    fn test_fixed_clock_resets_offset() {
+        let skew = ClockSkew::in_memory();
+        skew.observe(local() + Duration::minutes(10), local());
+        skew.observe(local(), local());
+        assert_eq!(skew.offset(), Duration::zero());
+    }
+}
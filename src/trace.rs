@@ -0,0 +1,41 @@
+//! Internal tracing macros.
+//!
+//! With the `tracing` feature enabled these forward to the `tracing` crate;
+//! otherwise they expand to nothing. Never pass license keys (or anything
+//! derived from them) as fields.
+
+macro_rules! trace_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::debug!($($arg)*);
+        }
+    };
+}
+
+macro_rules! trace_info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::info!($($arg)*);
+        }
+    };
+}
+
+macro_rules! trace_warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::warn!($($arg)*);
+        }
+    };
+}
+
+macro_rules! trace_error {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::error!($($arg)*);
+        }
+    };
+}
@@ -0,0 +1,278 @@
+//! License validation results, shared by `LicenseManager` and the
+//! fetch-based `WebValidator`.
+
+use crate::policy::access::UsageCaps;
+use crate::policy::fallback::FallbackReason;
+use crate::policy::upgrade::UpgradeReason;
+use crate::protocol::models::LicenseState;
+use crate::{ErrorCategory, GatewardenError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// License validation result.
+///
+/// Serializes to JSON for IPC (Tauri commands, Electron sidecars, gRPC
+/// bridges). Field names are snake_case and stable: fields are only ever
+/// added, and readers should ignore unknown ones.
+///
+/// A result answered from cache after Keygen could not be reached:
+///
+/// ```json
+/// {
+///   "valid": true,
+///   "state": {
+///     "valid": true, "entitlements": ["PRO"], "expires_at": "2026-01-01T00:00:00Z",
+///     "max_uses": 1000, "current_uses": 42, "code": "VALID", "detail": null,
+///     "management_link": null, "license_id": "…", "policy_id": "…",
+///     "product_id": "…", "trial": false,
+///     "upgrade": { "renewal_url": null, "purchase_url": null },
+///     "max_version": null, "group": { "id": "…", "name": null, "max_machines": null,
+///     "max_users": null }, "owner": null, "user": null
+///   },
+///   "caps": { "monthly_limit": 1000, "current_uses": 42 },
+///   "from_cache": true,
+///   "fallback_reason": { "kind": "network", "failure": "dns" },
+///   "cached_at": "2025-06-01T09:00:00Z",
+///   "cache_age_secs": 10800,
+///   "warnings": [{ "kind": "expires_soon", "days": 3 }]
+/// }
+/// ```
+///
+/// Online results have `from_cache: false` and null `fallback_reason`,
+/// `cached_at`, and `cache_age_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationResult {
+    /// Whether the license is valid.
+    ///
+    /// Always `true` in results returned by `LicenseManager` validation,
+    /// which reports a rejected license as an error instead.
+    pub valid: bool,
+
+    /// The license state from Keygen.
+    pub state: LicenseState,
+
+    /// Usage cap information.
+    pub caps: UsageCaps,
+
+    /// Whether this result came from cache.
+    pub from_cache: bool,
+
+    /// Why the online check failed, for results answered from cache
+    /// after one. `None` for online results and for `check_access` hits,
+    /// which consult the cache without trying Keygen.
+    #[serde(default)]
+    pub fallback_reason: Option<FallbackReason>,
+
+    /// When the cached response was verified online, for cached results.
+    #[serde(default)]
+    pub cached_at: Option<DateTime<Utc>>,
+
+    /// Age of the cached response when this result was produced, for
+    /// cached results; e.g. "offline mode, last verified 3 hours ago".
+    /// Serialized as whole seconds (`cache_age_secs`).
+    #[serde(default, rename = "cache_age_secs", with = "duration_secs")]
+    pub cache_age: Option<Duration>,
+
+    /// Advisory notices, e.g. to prompt the user to renew.
+    #[serde(default)]
+    pub warnings: Vec<ValidationWarning>,
+}
+
+/// Serde for an optional `Duration` as whole seconds, so IPC readers in
+/// other languages get a plain number.
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::Duration;
+
+    pub(super) fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.map(|age| age.as_secs()).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+    }
+}
+
+/// Advisory notice attached to a successful validation.
+///
+/// Serialized with a `kind` tag, e.g. `{"kind": "nearing_usage_cap", "remaining": 5}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationWarning {
+    /// The license expires within 14 days (`days` whole days remain).
+    ExpiresSoon {
+        /// Whole days until expiry.
+        days: u64,
+    },
+
+    /// At most 10% of the license's `maxUses` remain.
+    NearingUsageCap {
+        /// Uses remaining before the cap.
+        remaining: u64,
+    },
+
+    /// The cached result is in the last quarter of its offline grace;
+    /// Keygen must be reached soon.
+    CacheNearExpiry,
+}
+
+/// Warn when the license expires within this many days.
+const EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// Warn when at most this percentage of `maxUses` remains.
+const USAGE_WARNING_PERCENT: u64 = 10;
+
+impl ValidationResult {
+    /// Whether the license has entitlement `code`.
+    ///
+    /// Keygen only reports entitlements that were scoped on the request,
+    /// so `code` must be in `required_entitlements` or the entitlement
+    /// policy to ever be present. Use `LicenseManager::list_entitlements`
+    /// for the full list.
+    pub fn has_entitlement(&self, code: &str) -> bool {
+        self.state.entitlements.iter().any(|e| e == code)
+    }
+
+    /// Prefilled renewal or purchase URL for `reason`, from license
+    /// metadata (see `UpgradeLinks::url_for`).
+    ///
+    /// Keep the last valid result: a later `LicenseExpired` or
+    /// `UsageLimitExceeded` error carries no links of its own, and
+    /// `UpgradeReason::from_error` turns it into a reason.
+    pub fn upgrade_url(&self, reason: &UpgradeReason) -> Option<String> {
+        self.state
+            .upgrade
+            .url_for(reason, self.state.license_id.as_deref())
+    }
+}
+
+/// What a validation concluded about the license.
+///
+/// `validate_key` reports a rejected license as an error
+/// (`InvalidLicense`, `LicenseSuspended`, ...) alongside errors that say
+/// nothing about the license; an outcome turns the license errors into
+/// `Invalid`, and a missing entitlement into `Denied`. Failures to reach
+/// a trustworthy answer (transport, verification, cache, configuration)
+/// stay errors.
+// Outcomes are returned once per validation; boxing would only
+// complicate matching.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum ValidationOutcome {
+    /// Keygen confirmed the license online.
+    Valid(ValidationResult),
+
+    /// Keygen could not be asked; a verified cached result was used.
+    ValidFromCache {
+        /// Age of the cached response.
+        age: Duration,
+        /// The cached result.
+        result: ValidationResult,
+    },
+
+    /// The license is not valid.
+    Invalid {
+        /// Keygen's code for a `valid: false` result (e.g. `"EXPIRED"`),
+        /// otherwise the error's `GatewardenError::code` (e.g.
+        /// `"LICENSE_SUSPENDED"`).
+        code: String,
+    },
+
+    /// The license is valid but lacks an entitlement.
+    Denied {
+        /// The missing entitlement code (or entitlement policy).
+        missing_entitlement: String,
+    },
+}
+
+impl ValidationOutcome {
+    /// The outcome of a `validate_key`, `check_access`, or
+    /// `force_refresh` result.
+    ///
+    /// # Errors
+    /// Errors that say nothing about the license itself, and the input
+    /// errors `MissingLicense` and `MalformedLicenseKey`, are returned
+    /// unchanged.
+    pub fn from_result(
+        result: Result<ValidationResult, GatewardenError>,
+    ) -> Result<Self, GatewardenError> {
+        match result {
+            Ok(result) if !result.valid => Ok(Self::Invalid {
+                code: result.state.code,
+            }),
+            Ok(result) if result.from_cache => Ok(Self::ValidFromCache {
+                age: result.cache_age.unwrap_or_default(),
+                result,
+            }),
+            Ok(result) => Ok(Self::Valid(result)),
+            Err(GatewardenError::EntitlementMissing { code }) => Ok(Self::Denied {
+                missing_entitlement: code,
+            }),
+            Err(
+                e @ (GatewardenError::MissingLicense | GatewardenError::MalformedLicenseKey { .. }),
+            ) => Err(e),
+            Err(e) if e.category() == ErrorCategory::License => Ok(Self::Invalid {
+                code: e.code().to_string(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the license may be used (`Valid` or `ValidFromCache`).
+    pub fn is_valid(&self) -> bool {
+        self.result().is_some()
+    }
+
+    /// The validation result, for `Valid` and `ValidFromCache`.
+    pub fn result(&self) -> Option<&ValidationResult> {
+        match self {
+            Self::Valid(result) | Self::ValidFromCache { result, .. } => Some(result),
+            Self::Invalid { .. } | Self::Denied { .. } => None,
+        }
+    }
+}
+
+/// Renewal warnings for an accepted state.
+///
+/// `cached` is the record's `cached_at` and the grace it was checked
+/// against, for results answered from cache.
+pub(crate) fn compute_warnings(
+    state: &LicenseState,
+    caps: &UsageCaps,
+    cached: Option<(DateTime<Utc>, Duration)>,
+    now: DateTime<Utc>,
+) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+
+    if let Some(expires_at) = state.expires_at {
+        let days = expires_at.signed_duration_since(now).num_days();
+        if (0..EXPIRY_WARNING_DAYS).contains(&days) {
+            warnings.push(ValidationWarning::ExpiresSoon { days: days as u64 });
+        }
+    }
+
+    if let (Some(limit), Some(used)) = (caps.monthly_limit, caps.current_uses) {
+        let remaining = limit.saturating_sub(used);
+        if remaining.saturating_mul(100) <= limit.saturating_mul(USAGE_WARNING_PERCENT) {
+            warnings.push(ValidationWarning::NearingUsageCap { remaining });
+        }
+    }
+
+    if let Some((cached_at, grace)) = cached {
+        let age = now
+            .signed_duration_since(cached_at)
+            .to_std()
+            .unwrap_or_default();
+        if age.saturating_mul(4) >= grace.saturating_mul(3) {
+            warnings.push(ValidationWarning::CacheNearExpiry);
+        }
+    }
+
+    warnings
+}
@@ -0,0 +1,370 @@
+//! License validation for web-delivered tools (`wasm32-unknown-unknown`).
+//!
+//! [`WebValidator`] validates keys through the fetch-based client with the
+//! same verification pipeline as `LicenseManager`: signature, digest, and
+//! freshness checks online, and an authenticated cache that is verified
+//! again on every load when Keygen cannot be reached. In the browser,
+//! `LocalStorageCache` keeps the cache in `localStorage`; any other
+//! [`CacheBackend`] (e.g. one over IndexedDB) can be used instead.
+//!
+//! ```rust,ignore
+//! let cache = LocalStorageCache::new(config.cache_namespace);
+//! let validator = WebValidator::new(config, Box::new(cache))?;
+//! let result = validator.validate_key(&license_key).await?;
+//! ```
+//!
+//! Only `validate_key` is available: the meter, key store, clock-skew
+//! correction, and clock-rollback detection need the native filesystem.
+//! The validator compiles on every target; outside the browser it needs a
+//! Tokio runtime.
+
+use crate::cache::backend::CacheBackend;
+use crate::cache::format::CacheRecord;
+use crate::client::fetch::FetchClient;
+use crate::client::response::check_available;
+use crate::clock::{Clock, SystemClock};
+use crate::config::GatewardenConfig;
+use crate::crypto::pipeline::verify_response;
+#[cfg(any(test, feature = "test-seams"))]
+use crate::mock::MockKeygen;
+use crate::policy::access::{check_access_with_usage, check_not_expired, check_version};
+use crate::policy::fallback::FallbackReason;
+use crate::protocol::models::{KeygenValidateResponse, LicenseState};
+use crate::redact::RedactedKey;
+use crate::validation::{compute_warnings, ValidationResult};
+use crate::GatewardenError;
+#[cfg(any(test, feature = "test-seams"))]
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Async license validator over the fetch-based client.
+pub struct WebValidator {
+    config: GatewardenConfig,
+    client: FetchClient,
+    cache: Box<dyn CacheBackend>,
+}
+
+impl WebValidator {
+    /// Create a validator caching verified responses in `cache`.
+    ///
+    /// # Errors
+    /// - `ConfigError` - The config is invalid or the client cannot be built
+    pub fn new(
+        config: GatewardenConfig,
+        cache: Box<dyn CacheBackend>,
+    ) -> Result<Self, GatewardenError> {
+        config.validate()?;
+        Ok(Self {
+            client: FetchClient::new(&config)?,
+            config,
+            cache,
+        })
+    }
+
+    /// Answer requests from a mock Keygen instead of the network.
+    #[cfg(any(test, feature = "test-seams"))]
+    pub fn with_mock_keygen(mut self, mock: Arc<MockKeygen>) -> Self {
+        self.client.set_mock_keygen(mock);
+        self
+    }
+
+    /// Validate a license key online, falling back to the authenticated
+    /// cache when the fallback policy allows it.
+    ///
+    /// # Errors
+    /// As `LicenseManager::validate_key`, except that `ClockRollback` is
+    /// never reported.
+    pub async fn validate_key(
+        &self,
+        license_key: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        let license_key = RedactedKey::new(self.config.key_format.normalize(license_key)?);
+        let key_hash = license_key.hash();
+
+        let online_error = match self.validate_online(&license_key, &key_hash).await {
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+        let Some(reason) = self.config.fallback_policy.reason(&online_error) else {
+            return Err(online_error);
+        };
+        match self.validate_offline(&key_hash, reason)? {
+            Some(result) => Ok(result),
+            None => Err(online_error),
+        }
+    }
+
+    /// Fetch and verify a validation, apply access policy, and cache it.
+    async fn validate_online(
+        &self,
+        license_key: &RedactedKey,
+        key_hash: &str,
+    ) -> Result<ValidationResult, GatewardenError> {
+        let response = self
+            .client
+            .validate_key(license_key, self.config.required_entitlements)
+            .await?;
+        check_available(&response)?;
+
+        self.check_digest_present(response.digest.as_deref())
+            .map_err(|e| e.with_request_id(response.request_id.as_deref()))?;
+        verify_response(&response, self.config.key_ring(), &SystemClock)?;
+        if matches!(response.status, 401 | 403 | 404) {
+            return Err(GatewardenError::InvalidLicense);
+        }
+
+        let body = response.body_str()?;
+        let state = parse_state(body)?;
+        let caps = check_access_with_usage(&state, self.config.required_entitlements, 0)?;
+        check_version(&state, self.config.app_version)?;
+
+        let record = CacheRecord::new(
+            response.date.clone().unwrap_or_default(),
+            response.signature.clone().unwrap_or_default(),
+            response.digest.clone(),
+            body.to_string(),
+            response.request_path.clone(),
+            response.host.clone(),
+            &SystemClock,
+        )
+        .with_summary(state.clone())
+        .with_request(response.request_id.clone(), response.rate_limit);
+        self.cache.save(key_hash, &record)?;
+
+        let warnings = compute_warnings(&state, &caps, None, SystemClock.now_utc());
+        Ok(ValidationResult {
+            valid: state.valid,
+            state,
+            caps,
+            from_cache: false,
+            fallback_reason: None,
+            cached_at: None,
+            cache_age: None,
+            warnings,
+        })
+    }
+
+    /// Answer from the authenticated cache in place of Keygen.
+    ///
+    /// Returns `None` if there is no cached record for the key.
+    fn validate_offline(
+        &self,
+        key_hash: &str,
+        reason: FallbackReason,
+    ) -> Result<Option<ValidationResult>, GatewardenError> {
+        let Some(record) = self.cache.load(key_hash)? else {
+            return Ok(None);
+        };
+        if record.is_older_than(self.config.max_cache_age, &SystemClock) {
+            // Best-effort purge; the record is rejected either way
+            let _ = self.cache.delete(key_hash);
+            return Err(GatewardenError::CacheExpired);
+        }
+
+        let grace = self.config.offline_grace;
+        self.check_digest_present(record.digest.as_deref())?;
+        record.verify(self.config.key_ring(), grace, &SystemClock)?;
+
+        let state = parse_state(record.body())?;
+        if record
+            .summary
+            .as_ref()
+            .is_some_and(|summary| *summary != state)
+        {
+            return Err(GatewardenError::CacheTampered);
+        }
+
+        // Cached state never outlives the license itself
+        let now = SystemClock.now_utc();
+        check_not_expired(&state, now)?;
+        let caps = check_access_with_usage(&state, self.config.required_entitlements, 0)?;
+        check_version(&state, self.config.app_version)?;
+
+        let warnings = compute_warnings(&state, &caps, Some((record.cached_at, grace)), now);
+        let cache_age = now
+            .signed_duration_since(record.cached_at)
+            .to_std()
+            .unwrap_or(Duration::ZERO);
+        Ok(Some(ValidationResult {
+            valid: state.valid,
+            state,
+            caps,
+            from_cache: true,
+            fallback_reason: Some(reason),
+            cached_at: Some(record.cached_at),
+            cache_age: Some(cache_age),
+            warnings,
+        }))
+    }
+
+    /// Fail closed on a missing Digest header when `require_digest` is set.
+    fn check_digest_present(&self, digest: Option<&str>) -> Result<(), GatewardenError> {
+        if self.config.require_digest && digest.is_none() {
+            return Err(GatewardenError::SignatureMissing { request_id: None });
+        }
+        Ok(())
+    }
+}
+
+/// License state of a verified validation body.
+fn parse_state(body: &str) -> Result<LicenseState, GatewardenError> {
+    let response: KeygenValidateResponse =
+        serde_json::from_str(body).map_err(|e| GatewardenError::ProtocolError {
+            message: "Parse error".to_string(),
+            source: Some(e.into()),
+        })?;
+    LicenseState::from_keygen_response(&response)
+}
+
+/// Cache backend storing records in the browser's `localStorage`.
+///
+/// Records are stored as JSON under `gatewarden:<namespace>:<key hash>`
+/// and verified on load like any other backend's. They are not encrypted.
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorageCache {
+    prefix: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorageCache {
+    /// Cache for `namespace`, usually the config's `cache_namespace`.
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            prefix: format!("gatewarden:{}:", namespace),
+        }
+    }
+
+    /// The page's `localStorage`, looked up per call: `web_sys` handles
+    /// cannot be shared between threads.
+    fn storage() -> Result<web_sys::Storage, GatewardenError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| GatewardenError::CacheIO {
+                message: "localStorage is not available".to_string(),
+                source: None,
+            })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_error(message: &str) -> GatewardenError {
+    GatewardenError::CacheIO {
+        message: message.to_string(),
+        source: None,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CacheBackend for LocalStorageCache {
+    fn save(&self, license_key_hash: &str, record: &CacheRecord) -> Result<(), GatewardenError> {
+        Self::storage()?
+            .set_item(
+                &format!("{}{}", self.prefix, license_key_hash),
+                &record.to_json()?,
+            )
+            .map_err(|_| storage_error("Failed to write localStorage (quota exceeded?)"))
+    }
+
+    fn load(&self, license_key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        let json = Self::storage()?
+            .get_item(&format!("{}{}", self.prefix, license_key_hash))
+            .map_err(|_| storage_error("Failed to read localStorage"))?;
+        json.as_deref().map(CacheRecord::from_json).transpose()
+    }
+
+    fn delete(&self, license_key_hash: &str) -> Result<(), GatewardenError> {
+        Self::storage()?
+            .remove_item(&format!("{}{}", self.prefix, license_key_hash))
+            .map_err(|_| storage_error("Failed to write localStorage"))
+    }
+
+    fn clear(&self) -> Result<(), GatewardenError> {
+        let storage = Self::storage()?;
+        let length = storage
+            .length()
+            .map_err(|_| storage_error("Failed to read localStorage"))?;
+        // Collect first: removing items renumbers the rest
+        let keys: Vec<String> = (0..length)
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .filter(|key| key.starts_with(&self.prefix))
+            .collect();
+        for key in keys {
+            storage
+                .remove_item(&key)
+                .map_err(|_| storage_error("Failed to write localStorage"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::backend::hash_license_key;
+    use crate::cache::file::FileCache;
+    use crate::mock::MockLicense;
+    use crate::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+
+    fn validator(public_key_hex: &'static str, cache_dir: &std::path::Path) -> WebValidator {
+        let config = GatewardenConfig::builder()
+            .app_name("test")
+            .account_id("test-account")
+            .public_key_hex(public_key_hex)
+            .cache_namespace("test-web")
+            .build()
+            .unwrap();
+        let cache = FileCache::with_path(cache_dir.to_path_buf()).unwrap();
+        WebValidator::new(config, Box::new(cache)).unwrap()
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_validates_and_serves_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let validator =
+            validator(TEST_PUBLIC_KEY_HEX, temp_dir.path()).with_mock_keygen(keygen.clone());
+
+        let result = block_on(validator.validate_key("test-key")).unwrap();
+        assert!(result.valid);
+        assert!(!result.from_cache);
+        assert_eq!(result.state.license_id.as_deref(), Some("lic-1"));
+
+        let cached = validator
+            .validate_offline(&hash_license_key("test-key"), FallbackReason::RateLimited)
+            .unwrap()
+            .unwrap();
+        assert!(cached.from_cache);
+        assert_eq!(cached.fallback_reason, Some(FallbackReason::RateLimited));
+
+        keygen.add_license("test-key", MockLicense::new("lic-1").suspended());
+        let err = block_on(validator.validate_key("test-key")).unwrap_err();
+        assert!(matches!(err, GatewardenError::LicenseSuspended));
+        assert!(matches!(
+            block_on(validator.validate_key("")),
+            Err(GatewardenError::MissingLicense)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_response_signed_by_other_key() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+        keygen.add_license("test-key", MockLicense::new("lic-1"));
+        let validator = validator(
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            temp_dir.path(),
+        )
+        .with_mock_keygen(keygen);
+
+        let err = block_on(validator.validate_key("test-key")).unwrap_err();
+        assert!(matches!(err, GatewardenError::SignatureInvalid { .. }));
+    }
+}
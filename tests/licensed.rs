@@ -0,0 +1,118 @@
+//! `#[licensed]` functions against the process-wide default manager.
+
+use gatewarden::cache::format::CacheRecord;
+use gatewarden::mock::{TEST_PUBLIC_KEY_HEX, TEST_SIGNING_SEED};
+use gatewarden::{
+    global, licensed, CacheBackend, GatewardenConfig, GatewardenError, LicenseManager,
+};
+use gatewarden::{MockKeygen, MockLicense};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+    Unlicensed,
+}
+
+#[licensed]
+fn open_project(name: &str) -> Result<String, GatewardenError> {
+    Ok(format!("opened {}", name))
+}
+
+#[licensed(entitlement = "PRO")]
+fn export_pdf() -> Result<&'static str, GatewardenError> {
+    Ok("exported")
+}
+
+#[licensed(entitlement = "TEAM", error = |_| AppError::Unlicensed)]
+fn share_project() -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Keeps the offline cache out of the user's data directory.
+#[derive(Default)]
+struct MemoryCache(Mutex<HashMap<String, CacheRecord>>);
+
+impl CacheBackend for MemoryCache {
+    fn save(&self, key_hash: &str, record: &CacheRecord) -> Result<(), GatewardenError> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key_hash.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn load(&self, key_hash: &str) -> Result<Option<CacheRecord>, GatewardenError> {
+        Ok(self.0.lock().unwrap().get(key_hash).cloned())
+    }
+
+    fn delete(&self, key_hash: &str) -> Result<(), GatewardenError> {
+        self.0.lock().unwrap().remove(key_hash);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), GatewardenError> {
+        self.0.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+fn manager(temp_dir: &TempDir, keygen: Arc<MockKeygen>) -> LicenseManager {
+    let config = GatewardenConfig::builder()
+        .app_name("licensed-test")
+        .account_id("test-account")
+        .public_key_hex(TEST_PUBLIC_KEY_HEX)
+        .required_entitlements(&["PRO"])
+        .build()
+        .unwrap();
+    LicenseManager::new(config)
+        .unwrap()
+        .with_cache_backend(Box::<MemoryCache>::default())
+        .with_meter_dir(temp_dir.path().join("meter"))
+        .with_mock_keygen(keygen)
+}
+
+// One test: the default manager and registration are process-wide
+#[test]
+fn licensed_functions_follow_registration() {
+    let temp_dir = TempDir::new().unwrap();
+    let keygen = MockKeygen::new(&TEST_SIGNING_SEED);
+    keygen.add_license(
+        "pro-key",
+        MockLicense::new("lic-1").with_entitlements(&["PRO"]),
+    );
+    keygen.add_license("bad-key", MockLicense::new("lic-2").suspended());
+
+    assert!(!global::is_registered());
+    assert!(matches!(
+        open_project("a"),
+        Err(GatewardenError::ConfigError(_))
+    ));
+
+    // A key can only be registered once the default manager exists
+    assert!(matches!(
+        global::register("pro-key"),
+        Err(GatewardenError::ConfigError(_))
+    ));
+    gatewarden::init_with(manager(&temp_dir, keygen)).unwrap();
+
+    global::register("pro-key").unwrap();
+    assert_eq!(open_project("a").unwrap(), "opened a");
+    assert_eq!(export_pdf().unwrap(), "exported");
+    // TEAM is not scoped, so Keygen never reports it
+    assert_eq!(share_project(), Err(AppError::Unlicensed));
+    assert!(matches!(
+        global::require_entitlement("TEAM"),
+        Err(GatewardenError::EntitlementMissing { code }) if code == "TEAM"
+    ));
+
+    global::register("bad-key").unwrap();
+    assert!(matches!(
+        export_pdf(),
+        Err(GatewardenError::LicenseSuspended)
+    ));
+
+    global::unregister();
+    assert!(!global::is_registered());
+}